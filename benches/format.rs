@@ -0,0 +1,49 @@
+//! Formatting of the placeholders compared to `dynfmt::PythonFormat`, the formatter used before
+//!
+//! The messages are rendered from their keyword arguments, like `dynfmt` formats a map of
+//! strings.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dynfmt::{Format, PythonFormat};
+use serde_gettext::SerdeGetText;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+const TEMPLATE: &str = "%(greeting)s %(name)s, you have %(count)s new messages from %(sender)s \
+                        in %(folder)s (%(size)s, last one %(date)s)";
+
+fn args() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("greeting", "Hello"),
+        ("name", "Grace"),
+        ("count", "42"),
+        ("sender", "Marie"),
+        ("folder", "Inbox"),
+        ("size", "12 MB"),
+        ("date", "yesterday"),
+    ]
+}
+
+fn format(c: &mut Criterion) {
+    let message: SerdeGetText = serde_json::from_value(serde_json::json!({
+        "text": TEMPLATE,
+        "args": args().into_iter().collect::<HashMap<_, _>>(),
+    }))
+    .unwrap();
+    let map: HashMap<&str, &str> = args().into_iter().collect();
+
+    c.bench_function("format serde-gettext", |b| {
+        b.iter(|| String::try_from(black_box(message.clone())).unwrap())
+    });
+    c.bench_function("format dynfmt", |b| {
+        b.iter(|| {
+            PythonFormat
+                .format(black_box(TEMPLATE), black_box(&map))
+                .unwrap()
+                .into_owned()
+        })
+    });
+}
+
+criterion_group!(benches, format);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+//! Rendering throughput of the messages whose cost grows with their size: deep nesting, large
+//! argument maps and big arrays (see "Performance" in the documentation of the crate)
+//!
+//! Compare to a baseline to detect the regressions:
+//!
+//! ```bash
+//! cargo bench --bench render -- --save-baseline main
+//! cargo bench --bench render -- --baseline main
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_gettext::{Interner, SerdeGetText};
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+fn message(j: Value) -> SerdeGetText {
+    serde_json::from_value(j).unwrap()
+}
+
+fn render(message: &SerdeGetText) -> String {
+    String::try_from(black_box(message.clone())).unwrap()
+}
+
+/// Messages nested in the keyword arguments of their parent, up to the maximum nesting
+fn nesting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nesting");
+    for depth in [1, 16, 64, 127] {
+        let mut j = json!({"text": "%(name)s", "args": {"name": "Grace"}});
+        for _ in 1..depth {
+            j = json!({"text": "(%(inner)s)", "args": {"inner": j}});
+        }
+        let message = message(j);
+
+        group.throughput(Throughput::Elements(depth));
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &message, |b, x| {
+            b.iter(|| render(x))
+        });
+    }
+    group.finish();
+}
+
+/// Messages with many keyword arguments, all of them used by the format string
+fn args(c: &mut Criterion) {
+    let mut group = c.benchmark_group("args");
+    for len in [10, 100, 1000] {
+        let args: serde_json::Map<String, Value> =
+            (0..len).map(|i| (format!("arg{}", i), json!(i))).collect();
+        let text: String = (0..len).map(|i| format!("%(arg{})s ", i)).collect();
+        let message = message(json!({"text": text, "args": args}));
+
+        group.throughput(Throughput::Elements(len));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &message, |b, x| {
+            b.iter(|| render(x))
+        });
+    }
+    group.finish();
+}
+
+/// Arrays, sorted joins and `foreach` of many items
+fn arrays(c: &mut Criterion) {
+    let mut group = c.benchmark_group("arrays");
+    for len in [10, 1000, 10000] {
+        // the first item is the separator
+        let items: Vec<Value> = std::iter::once(json!(", "))
+            .chain((0..len).map(|i| json!(format!("item {}", i))))
+            .collect();
+        let array = message(json!({"text": "%(items)s", "args": {"items": items}}));
+        let sorted = message(json!({"join": items, "sort": true}));
+        let foreach = message(json!({
+            "foreach": {
+                "items": (0..len).map(|i| json!({"i": i})).collect::<Vec<_>>(),
+                "template": {"text": "<%(i)s>"},
+                "sep": " ",
+            },
+        }));
+
+        group.throughput(Throughput::Elements(len));
+        group.bench_with_input(BenchmarkId::new("array", len), &array, |b, x| {
+            b.iter(|| render(x))
+        });
+        group.bench_with_input(BenchmarkId::new("sorted", len), &sorted, |b, x| {
+            b.iter(|| render(x))
+        });
+        group.bench_with_input(BenchmarkId::new("foreach", len), &foreach, |b, x| {
+            b.iter(|| render(x))
+        });
+    }
+    group.finish();
+}
+
+/// Batch of messages translated by the catalogs of the system, with and without an interner
+fn lookups(c: &mut Criterion) {
+    let interner = Arc::new(Interner::new());
+    let batch: Vec<SerdeGetText> = (0..1000)
+        .map(|i| {
+            message(json!({
+                "gettext": format!("Message {}", i % 100),
+                "args": {"n": i},
+            }))
+        })
+        .collect();
+    let interned: Vec<SerdeGetText> = batch
+        .iter()
+        .cloned()
+        .map(|mut message| {
+            message.interner = Some(interner.clone());
+            message
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("lookups");
+    group.throughput(Throughput::Elements(batch.len() as u64));
+    group.bench_function("batch", |b| b.iter(|| batch.iter().map(render).count()));
+    group.bench_function("batch interned", |b| {
+        b.iter(|| interned.iter().map(render).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, nesting, args, arrays, lookups);
+criterion_main!(benches);
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use serde_gettext::SerdeGetText;
+use std::convert::TryFrom;
+
+fuzz_target!(|message: SerdeGetText| {
+    let _ = String::try_from(message);
+});
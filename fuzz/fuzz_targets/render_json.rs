@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use serde_gettext::SerdeGetText;
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = serde_json::from_slice::<SerdeGetText>(data) {
+        let _ = String::try_from(message);
+    }
+});
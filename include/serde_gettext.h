@@ -0,0 +1,40 @@
+/* C API of serde-gettext (feature `ffi`) */
+
+#ifndef SERDE_GETTEXT_H
+#define SERDE_GETTEXT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SG_OK 0
+#define SG_NULL_POINTER 1
+#define SG_INVALID_UTF8 2
+#define SG_INVALID_MESSAGE 3
+#define SG_RENDER_ERROR 4
+#define SG_INVALID_CATALOG 5
+#define SG_INTERNAL_ERROR 6
+
+/* Render a JSON message in a locale (NULL for the locale of the environment).
+ * Returns the rendered message, to free with sg_free, or NULL on error. */
+char *sg_translate(const char *json, const char *locale);
+
+/* Add a compiled catalog (MO file) of a text domain (NULL for the messages
+ * without domain) to the catalog of a locale. Returns SG_OK or an error code. */
+int sg_load_catalog(const char *locale, const char *domain, const uint8_t *data, size_t len);
+
+/* Free a string returned by sg_translate */
+void sg_free(char *s);
+
+/* Error code and message of the last call on this thread */
+int sg_last_error(void);
+const char *sg_last_error_message(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,374 @@
+//! Procedural macros of serde-gettext, re-exported by `serde_gettext` with the feature `macros`
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use std::collections::BTreeSet;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitStr, Token, Type};
+
+/// Build a `SerdeGetText` translated by `gettext` (one literal) or `ngettext` (the singular and
+/// plural forms, with the count `n`) with the arguments of its keyword placeholders
+///
+/// Every placeholder (`%(name)s`) must have an argument and every argument must be used, or
+/// the macro fails to compile. The keys that are not identifiers (`%(user.name)s`) are written
+/// as literals.
+///
+/// ```rust,ignore
+/// let message = translate!("Hello %(name)s!", name = user.name);
+/// let message = translate!("One file", "%(n)s files", n = files.len() as u64);
+/// let message = translate!("Welcome %(user.name)s", "user.name" = user.name);
+/// ```
+#[proc_macro]
+pub fn translate(input: TokenStream) -> TokenStream {
+    let translate = parse_macro_input!(input as Translate);
+
+    match translate.check() {
+        Ok(()) => translate.expand().into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Implement `ToGettextArgs` for a struct with named fields
+///
+/// The fields accept the attributes `#[gettext(rename = "key")]` (key of the argument instead of
+/// the name of the field), `#[gettext(skip)]`, `#[gettext(nested)]` (the arguments of the field,
+/// prefixed with its key: `%(customer.name)s`) and `#[gettext(flatten)]` (the arguments of the
+/// field, without prefix).
+#[proc_macro_derive(ToGettextArgs, attributes(gettext))]
+pub fn derive_to_gettext_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match derive_args(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn derive_args(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unit => return Ok(impl_args(input, Vec::new())),
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "ToGettextArgs can't be derived for the tuple structs",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "ToGettextArgs can only be derived for the structs",
+            ))
+        }
+    };
+
+    let mut statements = Vec::new();
+    for field in fields.iter() {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut key = ident.unraw().to_string();
+        let mut mode = Mode::Display;
+        let mut renamed = false;
+
+        for attr in field.attrs.iter().filter(|x| x.path().is_ident("gettext")) {
+            attr.parse_nested_meta(|meta| {
+                let other = match mode {
+                    Mode::Display => None,
+                    Mode::Skip => Some("skip"),
+                    Mode::Nested => Some("nested"),
+                    Mode::Flatten => Some("flatten"),
+                };
+                let set = |new: Mode, name: &str| match other {
+                    Some(other) => Err(meta.error(format!("{} conflicts with {}", name, other))),
+                    None => Ok(new),
+                };
+
+                if meta.path.is_ident("rename") {
+                    key = meta.value()?.parse::<LitStr>()?.value();
+                    renamed = true;
+                } else if meta.path.is_ident("skip") {
+                    mode = set(Mode::Skip, "skip")?;
+                } else if meta.path.is_ident("nested") {
+                    mode = set(Mode::Nested, "nested")?;
+                } else if meta.path.is_ident("flatten") {
+                    mode = set(Mode::Flatten, "flatten")?;
+                } else {
+                    return Err(meta.error("unknown gettext attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        if renamed && matches!(mode, Mode::Flatten) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "the flatten fields have no key to rename",
+            ));
+        }
+
+        let (binding, wrap) = if is_option(&field.ty) {
+            (
+                quote!(value),
+                Some(quote!(if let ::std::option::Option::Some(value) = &self.#ident)),
+            )
+        } else {
+            (quote!((&self.#ident)), None)
+        };
+        let statement = match mode {
+            Mode::Skip => continue,
+            Mode::Display => quote! {
+                args.insert(
+                    ::std::format!("{}{}", prefix, #key),
+                    ::std::string::ToString::to_string(#binding),
+                );
+            },
+            Mode::Nested => quote! {
+                ::serde_gettext::ToGettextArgs::add_gettext_args(
+                    #binding,
+                    &::std::format!("{}{}.", prefix, #key),
+                    args,
+                );
+            },
+            Mode::Flatten => quote! {
+                ::serde_gettext::ToGettextArgs::add_gettext_args(#binding, prefix, args);
+            },
+        };
+        statements.push(match wrap {
+            Some(wrap) => quote!(#wrap { #statement }),
+            None => statement,
+        });
+    }
+
+    Ok(impl_args(input, statements))
+}
+
+fn impl_args(
+    input: &DeriveInput,
+    statements: Vec<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::serde_gettext::ToGettextArgs for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn add_gettext_args(
+                &self,
+                prefix: &str,
+                args: &mut ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+            ) {
+                #(#statements)*
+            }
+        }
+    }
+}
+
+/// Conversion of a field into arguments
+#[derive(Clone, Copy)]
+enum Mode {
+    Display,
+    Skip,
+    Nested,
+    Flatten,
+}
+
+/// Check if the type of a field is an `Option`, whose `None` adds no argument
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|x| x.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+struct Translate {
+    msgid: LitStr,
+    plural: Option<LitStr>,
+    args: Vec<Arg>,
+}
+
+struct Arg {
+    key: String,
+    span: Span,
+    value: Expr,
+}
+
+impl Parse for Translate {
+    fn parse(input: ParseStream) -> syn::Result<Translate> {
+        let msgid = input.parse()?;
+        let mut plural = None;
+        let mut args = Vec::new();
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            if args.is_empty() && plural.is_none() && input.peek(LitStr) && !input.peek2(Token![=])
+            {
+                plural = Some(input.parse()?);
+                continue;
+            }
+
+            let (key, span) = if input.peek(LitStr) {
+                let key: LitStr = input.parse()?;
+                (key.value(), key.span())
+            } else {
+                let key = input.call(Ident::parse_any)?;
+                (key.unraw().to_string(), key.span())
+            };
+            input.parse::<Token![=]>()?;
+            args.push(Arg {
+                key,
+                span,
+                value: input.parse()?,
+            });
+        }
+
+        Ok(Translate {
+            msgid,
+            plural,
+            args,
+        })
+    }
+}
+
+impl Translate {
+    /// Check that the placeholders and the arguments match
+    fn check(&self) -> syn::Result<()> {
+        let mut errors = Vec::new();
+        let mut keys = BTreeSet::new();
+
+        for arg in self.args.iter() {
+            if !keys.insert(arg.key.as_str()) {
+                errors.push(syn::Error::new(
+                    arg.span,
+                    format!("duplicate argument: {}", arg.key),
+                ));
+            }
+        }
+        if self.plural.is_some() && !keys.contains("n") {
+            errors.push(syn::Error::new(
+                self.msgid.span(),
+                "missing count of the plural message: n = ...",
+            ));
+        }
+
+        let mut used = BTreeSet::new();
+        for literal in Some(&self.msgid).into_iter().chain(self.plural.as_ref()) {
+            let found = placeholders(&literal.value());
+            if found.positional {
+                errors.push(syn::Error::new(
+                    literal.span(),
+                    "positional placeholders are not supported, use %(name)s",
+                ));
+            }
+            for name in found.named {
+                let count = self.plural.is_some() && name == "n";
+                if !count && !keys.contains(name.as_str()) {
+                    errors.push(syn::Error::new(
+                        literal.span(),
+                        format!("missing argument: {}", name),
+                    ));
+                }
+                used.insert(name);
+            }
+        }
+        for arg in self.args.iter() {
+            let count = self.plural.is_some() && arg.key == "n";
+            if !count && !used.contains(&arg.key) {
+                errors.push(syn::Error::new(
+                    arg.span,
+                    format!("unused argument: {}", arg.key),
+                ));
+            }
+        }
+
+        match errors.into_iter().reduce(|mut a, b| {
+            a.combine(b);
+            a
+        }) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn expand(&self) -> proc_macro2::TokenStream {
+        let msgid = &self.msgid;
+        let plural = self.plural.is_some();
+        let args = self
+            .args
+            .iter()
+            .filter(|x| !(plural && x.key == "n"))
+            .map(|Arg { key, value, .. }| quote!(.arg(#key, #value)));
+
+        match &self.plural {
+            Some(plural) => {
+                let n = self.args.iter().find(|x| x.key == "n").map(|x| &x.value);
+                quote!(::serde_gettext::SerdeGetText::ngettext(#msgid, #plural, #n) #(#args)*)
+            }
+            None => quote!(::serde_gettext::SerdeGetText::gettext(#msgid) #(#args)*),
+        }
+    }
+}
+
+/// Placeholders of a format string
+#[derive(Default)]
+struct Placeholders {
+    named: BTreeSet<String>,
+    positional: bool,
+}
+
+fn placeholders(s: &str) -> Placeholders {
+    let mut res = Placeholders::default();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+
+        match chars.peek() {
+            Some('%') => {
+                chars.next();
+                continue;
+            }
+            Some('(') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != ')').collect();
+                res.named.insert(name);
+            }
+            Some(_) => {
+                // a `%` that doesn't start a conversion is rendered as is
+                let spec: String = chars
+                    .clone()
+                    .take_while(|c| !c.is_ascii_alphabetic() || matches!(c, 'h' | 'l' | 'L'))
+                    .collect();
+                let conversion = chars.clone().nth(spec.chars().count());
+                if spec.chars().all(|c| "#0- +.*0123456789hlL".contains(c))
+                    && conversion.is_some_and(|c| "diouxXeEfFgGcrs".contains(c))
+                {
+                    res.positional = true;
+                } else {
+                    continue;
+                }
+            }
+            None => break,
+        }
+
+        // flags, width, precision and length modifier until the conversion type
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() && !matches!(c, 'h' | 'l' | 'L') {
+                break;
+            }
+        }
+    }
+
+    res
+}
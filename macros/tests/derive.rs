@@ -0,0 +1,68 @@
+use serde_gettext::{SerdeGetText, ToGettextArgs};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[derive(ToGettextArgs)]
+struct Customer {
+    name: String,
+    #[gettext(rename = "mail")]
+    email: Option<String>,
+}
+
+#[derive(ToGettextArgs)]
+struct Totals {
+    total: f64,
+}
+
+#[derive(ToGettextArgs)]
+struct Order<'a> {
+    id: u64,
+    r#type: &'a str,
+    #[gettext(nested)]
+    customer: Customer,
+    #[gettext(nested, rename = "shipping")]
+    shipping_address: Option<Customer>,
+    #[gettext(flatten)]
+    totals: Totals,
+    #[gettext(skip)]
+    #[allow(dead_code)]
+    internal_note: String,
+}
+
+#[test]
+fn derive() {
+    let order = Order {
+        id: 42,
+        r#type: "express",
+        customer: Customer {
+            name: "Grace".to_string(),
+            email: None,
+        },
+        shipping_address: Some(Customer {
+            name: "Ada".to_string(),
+            email: Some("ada@example.com".to_string()),
+        }),
+        totals: Totals { total: 9.5 },
+        internal_note: "secret".to_string(),
+    };
+
+    let expected: HashMap<String, String> = [
+        ("id", "42"),
+        ("type", "express"),
+        ("customer.name", "Grace"),
+        ("shipping.name", "Ada"),
+        ("shipping.mail", "ada@example.com"),
+        ("total", "9.5"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    assert_eq!(order.to_gettext_args(), expected);
+
+    let message =
+        SerdeGetText::gettext("Order %(id)s for %(customer.name)s: %(total).2f").with_args(&order);
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "Order 42 for Grace: 9.50"
+    );
+}
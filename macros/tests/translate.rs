@@ -0,0 +1,40 @@
+use serde_gettext::{translate, SerdeGetText};
+use std::convert::TryFrom;
+
+fn render(message: SerdeGetText) -> String {
+    String::try_from(message).unwrap()
+}
+
+#[test]
+fn translate() {
+    let name = "Grace";
+    let files = ["a.txt", "b.txt"];
+
+    assert_eq!(render(translate!("Hello!")), "Hello!");
+    assert_eq!(
+        render(translate!("Hello %(name)s, 100%% done!", name = name)),
+        "Hello Grace, 100% done!"
+    );
+    assert_eq!(
+        render(translate!(
+            "One file in %(dir)s",
+            "%(n)s files in %(dir)s",
+            n = files.len() as u64,
+            dir = "/tmp",
+        )),
+        "2 files in /tmp"
+    );
+    assert_eq!(
+        render(translate!(
+            "%(user.name)s (%(type)s)",
+            "user.name" = name,
+            r#type = 1
+        )),
+        "Grace (1)"
+    );
+}
+
+#[test]
+fn translate_compile_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
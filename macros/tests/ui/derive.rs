@@ -0,0 +1,23 @@
+use serde_gettext::ToGettextArgs;
+
+#[derive(ToGettextArgs)]
+struct Tuple(String);
+
+#[derive(ToGettextArgs)]
+enum Enum {
+    A,
+}
+
+#[derive(ToGettextArgs)]
+struct Unknown {
+    #[gettext(default)]
+    name: String,
+}
+
+#[derive(ToGettextArgs)]
+struct Conflict {
+    #[gettext(skip, nested)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use serde_gettext::translate;
+
+fn main() {
+    translate!("Hello %(name)s!");
+    translate!("Hello!", name = "Grace");
+    translate!("Hello %(name)s!", name = "Grace", name = "Ada");
+    translate!("One file", "%(n)s files");
+    translate!("Hello %s!");
+}
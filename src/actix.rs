@@ -0,0 +1,93 @@
+//! Integration with [actix-web](https://docs.rs/actix-web/): extractor of the messages rendered
+//! in the locale negotiated from `Accept-Language`
+//!
+//! The catalogs are taken from the `web::Data<Translator>` of the application, the catalogs of
+//! the system are used if there is none.
+//!
+//! ```rust,no_run
+//! use actix_web::{web, App};
+//! use serde_gettext::actix::Localized;
+//! use serde_gettext::server::Translator;
+//! use serde_gettext::SerdeGetText;
+//!
+//! async fn translate(message: Localized<SerdeGetText>) -> Result<String, serde_gettext::Error> {
+//!     message.render()
+//! }
+//!
+//! let app = App::new()
+//!     .app_data(web::Data::new(Translator::new()))
+//!     .route("/translate", web::post().to(translate));
+//! ```
+
+use crate::server::{TranslateResult, Translator};
+use crate::{Backend, Error, SerdeGetText};
+use ::actix_web::dev::Payload;
+use ::actix_web::http::{header, StatusCode};
+use ::actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use std::convert::TryFrom;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Value of the request with the locale and the catalog negotiated from `Accept-Language`
+#[derive(Clone, Debug)]
+pub struct Localized<T> {
+    /// Value extracted from the request
+    pub inner: T,
+    /// Negotiated locale, `None` if no catalog matches
+    pub locale: Option<String>,
+    catalog: Arc<dyn Backend + Send + Sync>,
+}
+
+impl Localized<SerdeGetText> {
+    /// Render the message with the negotiated catalog
+    pub fn render(self) -> Result<String, Error> {
+        let mut message = self.inner;
+        message.backend = Some(self.catalog);
+        message.locale = self.locale;
+
+        String::try_from(message)
+    }
+}
+
+impl FromRequest for Localized<SerdeGetText> {
+    type Error = ::actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<SerdeGetText>::from_request(req, payload);
+        let accept_language = req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|x| x.to_str().ok());
+        let default = Translator::default();
+        let translator = req
+            .app_data::<web::Data<Translator>>()
+            .map(|x| x.get_ref())
+            .unwrap_or(&default);
+        let (locale, catalog) = translator.negotiate(accept_language);
+        let locale = locale.map(ToString::to_string);
+
+        Box::pin(async move {
+            let web::Json(inner) = json.await?;
+
+            Ok(Localized {
+                inner,
+                locale,
+                catalog,
+            })
+        })
+    }
+}
+
+/// The rendering errors are responses `422 Unprocessable Entity` with the body
+/// `{"error": "..."}`
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNPROCESSABLE_ENTITY
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(TranslateResult::Error(self.to_string()))
+    }
+}
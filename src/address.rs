@@ -0,0 +1,206 @@
+//! Postal addresses laid out following the conventions of their region (`LC_ADDRESS`)
+
+use crate::format::format_into;
+use crate::format::Arg;
+use crate::{Context, Error, UnionMap, Value};
+use std::fmt;
+
+/// Lines of the regions writing the number before the street
+const NUMBER_FIRST: &[&str] = &[
+    "%(name)s",
+    "%(company)s",
+    "%(building)s",
+    "%(number)s %(street)s",
+    "%(postcode)s %(city)s",
+    "%(state)s",
+    "%(country)s",
+];
+
+/// Lines of the regions writing the street before the number
+const STREET_FIRST: &[&str] = &[
+    "%(name)s",
+    "%(company)s",
+    "%(building)s",
+    "%(street)s %(number)s",
+    "%(postcode)s %(city)s",
+    "%(state)s",
+    "%(country)s",
+];
+
+const NORTH_AMERICA: &[&str] = &[
+    "%(name)s",
+    "%(company)s",
+    "%(building)s",
+    "%(number)s %(street)s",
+    "%(city)s, %(state)s %(postcode)s",
+    "%(country)s",
+];
+
+const AUSTRALIA: &[&str] = &[
+    "%(name)s",
+    "%(company)s",
+    "%(building)s",
+    "%(number)s %(street)s",
+    "%(city)s %(state)s %(postcode)s",
+    "%(country)s",
+];
+
+const BRITISH_ISLES: &[&str] = &[
+    "%(name)s",
+    "%(company)s",
+    "%(building)s",
+    "%(number)s %(street)s",
+    "%(city)s",
+    "%(state)s",
+    "%(postcode)s",
+    "%(country)s",
+];
+
+const RUSSIA: &[&str] = &[
+    "%(name)s",
+    "%(company)s",
+    "%(street)s, %(number)s",
+    "%(building)s",
+    "%(city)s",
+    "%(state)s",
+    "%(country)s",
+    "%(postcode)s",
+];
+
+/// Lines of the regions writing the addresses from the largest to the smallest part
+const EAST_ASIA: &[&str] = &[
+    "%(country)s",
+    "%(postcode)s",
+    "%(state)s%(city)s%(street)s%(number)s",
+    "%(building)s",
+    "%(company)s",
+    "%(name)s",
+];
+
+/// Postal address, its fields are values (e.g. the country name can be translated)
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub(crate) struct ValueAddress {
+    pub(crate) name: Option<Box<Value>>,
+    pub(crate) company: Option<Box<Value>>,
+    pub(crate) building: Option<Box<Value>>,
+    pub(crate) street: Option<Box<Value>>,
+    pub(crate) number: Option<Box<Value>>,
+    pub(crate) postcode: Option<Box<Value>>,
+    pub(crate) city: Option<Box<Value>>,
+    pub(crate) state: Option<Box<Value>>,
+    pub(crate) country: Option<Box<Value>>,
+    /// Region whose conventions are followed (e.g. `FR`), the territory of the locale
+    /// (`LC_ADDRESS`) by default
+    pub(crate) region: Option<String>,
+    /// Separator of the lines
+    pub(crate) sep: Option<String>,
+}
+
+impl ValueAddress {
+    /// Values of the fields of the address
+    pub(crate) fn fields(&self) -> Vec<&Value> {
+        [
+            &self.name,
+            &self.company,
+            &self.building,
+            &self.street,
+            &self.number,
+            &self.postcode,
+            &self.city,
+            &self.state,
+            &self.country,
+        ]
+        .iter()
+        .filter_map(|x| x.as_deref())
+        .collect()
+    }
+
+    pub(crate) fn write_to(self, out: &mut dyn fmt::Write, ctx: &Context) -> Result<(), Error> {
+        let region = self
+            .region
+            .or_else(|| {
+                ctx.locale("LC_ADDRESS")
+                    .as_deref()
+                    .and_then(territory)
+                    .map(String::from)
+            })
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let sep = self.sep.unwrap_or_else(|| "\n".to_string());
+
+        let fields = [
+            ("name", self.name),
+            ("company", self.company),
+            ("building", self.building),
+            ("street", self.street),
+            ("number", self.number),
+            ("postcode", self.postcode),
+            ("city", self.city),
+            ("state", self.state),
+            ("country", self.country),
+        ];
+        let mut args = Vec::with_capacity(fields.len());
+        for (name, value) in fields {
+            let value = match value {
+                Some(value) => value.try_into_arg(ctx)?,
+                None => Arg::default(),
+            };
+            args.push((name.to_string(), value));
+        }
+
+        let mut first = true;
+        for template in lines(&region) {
+            let mut line = String::new();
+            format_into(
+                &mut line,
+                template,
+                &UnionMap::new(&args, ctx.scope),
+                ctx.format,
+            )?;
+
+            // the separators of the missing fields are left out
+            let line = line
+                .split(' ')
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>();
+            let line = line.join(" ");
+            let line = line.trim_matches(|c| c == ',' || c == ' ');
+            if line.is_empty() {
+                continue;
+            }
+
+            if !first {
+                out.write_str(&sep)?;
+            }
+            out.write_str(line)?;
+            first = false;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lines of the addresses of a region (ISO 3166 code)
+fn lines(region: &str) -> &'static [&'static str] {
+    match region {
+        "US" | "CA" => NORTH_AMERICA,
+        "AU" => AUSTRALIA,
+        "GB" | "IE" => BRITISH_ISLES,
+        "RU" | "BY" | "UA" | "KZ" => RUSSIA,
+        "JP" | "CN" | "TW" => EAST_ASIA,
+        "DE" | "AT" | "CH" | "LI" | "NL" | "BE" | "IT" | "ES" | "PT" | "PL" | "CZ" | "SK"
+        | "HU" | "HR" | "SI" | "SE" | "NO" | "DK" | "FI" | "IS" | "GR" | "TR" | "BR" | "AR"
+        | "MX" => STREET_FIRST,
+        _ => NUMBER_FIRST,
+    }
+}
+
+/// Territory of a locale (e.g. `FR` for `fr_FR.UTF-8`)
+pub(crate) fn territory(locale: &str) -> Option<&str> {
+    locale
+        .split(['.', '@'])
+        .next()
+        .and_then(|x| x.split_once('_'))
+        .map(|(_, territory)| territory)
+}
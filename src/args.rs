@@ -0,0 +1,55 @@
+//! Conversion of the user types into the base arguments of the messages
+
+use crate::SerdeGetText;
+use std::collections::HashMap;
+
+/// Value converted into the base arguments of a message (`SerdeGetText::args`), usually
+/// derived with the feature `macros`:
+///
+/// ```rust,ignore
+/// #[derive(ToGettextArgs)]
+/// struct Order {
+///     id: u64,
+///     #[gettext(rename = "total")]
+///     amount: String,
+///     #[gettext(nested)]
+///     customer: Customer,
+///     #[gettext(skip)]
+///     internal_note: String,
+/// }
+/// ```
+///
+/// The fields are formatted with `Display` (`Option` fields only when they are `Some`). The
+/// `nested` fields add their own fields with their name as prefix (`%(customer.name)s`), the
+/// `flatten` fields add them without prefix.
+pub trait ToGettextArgs {
+    /// Add the arguments, their keys prefixed by `prefix` (e.g. `customer.`)
+    fn add_gettext_args(&self, prefix: &str, args: &mut HashMap<String, String>);
+
+    /// Arguments of the value
+    fn to_gettext_args(&self) -> HashMap<String, String> {
+        let mut args = HashMap::new();
+        self.add_gettext_args("", &mut args);
+        args
+    }
+}
+
+impl<T: ToGettextArgs + ?Sized> ToGettextArgs for &T {
+    fn add_gettext_args(&self, prefix: &str, args: &mut HashMap<String, String>) {
+        (**self).add_gettext_args(prefix, args)
+    }
+}
+
+impl<T: ToGettextArgs + ?Sized> ToGettextArgs for Box<T> {
+    fn add_gettext_args(&self, prefix: &str, args: &mut HashMap<String, String>) {
+        (**self).add_gettext_args(prefix, args)
+    }
+}
+
+impl SerdeGetText {
+    /// Add the base arguments (`args`) of a value, e.g. a struct deriving `ToGettextArgs`
+    pub fn with_args<T: ToGettextArgs + ?Sized>(mut self, value: &T) -> SerdeGetText {
+        value.add_gettext_args("", &mut self.args);
+        self
+    }
+}
@@ -0,0 +1,84 @@
+//! Integration with [axum](https://docs.rs/axum/): extractor of the translation requests and
+//! responses for the rendered messages and the errors
+//!
+//! ```rust,no_run
+//! use axum::extract::State;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use serde_gettext::axum::Translate;
+//! use serde_gettext::server::{TranslateResponse, Translator};
+//!
+//! async fn translate(
+//!     State(translator): State<Translator>,
+//!     request: Translate,
+//! ) -> TranslateResponse {
+//!     request.render(&translator)
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/translate", post(translate))
+//!     .with_state(Translator::new());
+//! ```
+
+use crate::server::{TranslateRequest, TranslateResponse, TranslateResult, Translator};
+use crate::Error;
+use ::axum::extract::{FromRequest, Request};
+use ::axum::http::{header, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use ::axum::Json;
+
+/// Extractor of the messages of the JSON body (a message or an array of messages) and of the
+/// `Accept-Language` header
+#[derive(Clone, Debug)]
+pub struct Translate {
+    /// Messages of the body
+    pub request: TranslateRequest,
+    /// Value of the `Accept-Language` header
+    pub accept_language: Option<String>,
+}
+
+impl Translate {
+    /// Render the messages with the catalog of the locale negotiated from `Accept-Language`
+    pub fn render(self, translator: &Translator) -> TranslateResponse {
+        translator.translate(self.request, self.accept_language.as_deref())
+    }
+}
+
+#[::axum::async_trait]
+impl<S: Send + Sync> FromRequest<S> for Translate {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Translate, Response> {
+        let accept_language = req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|x| x.to_str().ok())
+            .map(ToString::to_string);
+        let Json(request) = Json::<TranslateRequest>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        Ok(Translate {
+            request,
+            accept_language,
+        })
+    }
+}
+
+impl IntoResponse for TranslateResponse {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
+/// The rendering errors are responses `422 Unprocessable Entity` with the body
+/// `{"error": "..."}`
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(TranslateResult::Error(self.to_string())),
+        )
+            .into_response()
+    }
+}
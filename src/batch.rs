@@ -0,0 +1,61 @@
+//! Documents of several messages rendered at once (e.g. all the strings of a screen)
+
+use crate::{Error, SerdeGetText};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Messages by key, rendered into a map with the same keys
+///
+/// ```rust
+/// use serde_gettext::TranslatedMap;
+/// use std::collections::HashMap;
+/// use std::convert::TryFrom;
+///
+/// let screen: TranslatedMap = serde_json::from_str(
+///     r#"{
+///         "title": {"gettext": "Settings"},
+///         "body": {"text": "Version %(version)s", "args": {"version": "1.2"}}
+///     }"#,
+/// )?;
+/// let strings = HashMap::try_from(screen)?;
+/// assert_eq!(strings["title"], "Settings");
+/// assert_eq!(strings["body"], "Version 1.2");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TranslatedMap(pub HashMap<String, SerdeGetText>);
+
+/// Render the messages, it fails with the error of the first message that can't be rendered
+impl TryFrom<TranslatedMap> for HashMap<String, String> {
+    type Error = Error;
+
+    fn try_from(x: TranslatedMap) -> Result<HashMap<String, String>, Error> {
+        x.0.into_iter()
+            .map(|(key, message)| Ok((key, String::try_from(message)?)))
+            .collect()
+    }
+}
+
+/// Messages rendered into a list of strings in the same order, unlike the arrays of values in
+/// the arguments of a message which are joined by their separator
+///
+/// ```rust
+/// use serde_gettext::TranslatedList;
+/// use std::convert::TryFrom;
+///
+/// let labels: TranslatedList =
+///     serde_json::from_str(r#"[{"gettext": "Yes"}, {"gettext": "No"}]"#)?;
+/// assert_eq!(Vec::try_from(labels)?, vec!["Yes", "No"]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TranslatedList(pub Vec<SerdeGetText>);
+
+/// Render the messages, it fails with the error of the first message that can't be rendered
+impl TryFrom<TranslatedList> for Vec<String> {
+    type Error = Error;
+
+    fn try_from(x: TranslatedList) -> Result<Vec<String>, Error> {
+        x.0.into_iter().map(String::try_from).collect()
+    }
+}
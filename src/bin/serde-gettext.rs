@@ -0,0 +1,223 @@
+use gettextrs::LocaleCategory;
+use serde_gettext::extract::{self, ExtractedMsgid};
+use serde_gettext::validate::{self, LintWarning};
+use serde_gettext::{pot, GettextBackend, SerdeGetText};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use structopt::StructOpt;
+
+/// Translate and format messages, extract and check their msgids
+#[derive(StructOpt, Debug)]
+#[structopt(name = "serde-gettext")]
+enum Command {
+    /// Translate and format a message document (JSON or YAML)
+    Render {
+        /// Locale of the translations (e.g. fr_FR.UTF-8), the locale of the environment by
+        /// default
+        #[structopt(long)]
+        locale: Option<String>,
+        #[structopt(flatten)]
+        domain: DomainOpt,
+        /// Message document, read from the standard input if omitted
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+    },
+    /// Extract the msgids of template files (or directories) to a POT file
+    Extract {
+        /// Text domain of the msgids to extract, the msgids without domain by default
+        #[structopt(long)]
+        domain: Option<String>,
+        /// POT file to write, the standard output by default
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Template files or directories
+        #[structopt(parse(from_os_str), required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Check the placeholders of template files (or directories) and the coverage of their
+    /// msgids by the catalogs
+    Check {
+        /// Locale whose catalog must translate all the msgids (can be repeated)
+        #[structopt(long = "locale")]
+        locales: Vec<String>,
+        #[structopt(flatten)]
+        domain: DomainOpt,
+        /// Template files or directories
+        #[structopt(parse(from_os_str), required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+struct DomainOpt {
+    /// Text domain of the messages
+    #[structopt(long)]
+    domain: Option<String>,
+    /// Directory of the catalogs of the text domain
+    #[structopt(long, parse(from_os_str))]
+    localedir: Option<PathBuf>,
+}
+
+impl DomainOpt {
+    fn bind(&self) {
+        if let Some(domain) = self.domain.as_deref() {
+            if let Some(localedir) = self.localedir.as_ref() {
+                gettextrs::bindtextdomain(domain, localedir.to_string_lossy().as_ref());
+            }
+            gettextrs::textdomain(domain);
+        }
+    }
+}
+
+fn main() {
+    let res = match Command::from_args() {
+        Command::Render {
+            locale,
+            domain,
+            file,
+        } => render(locale.as_deref(), &domain, file.as_deref()),
+        Command::Extract {
+            domain,
+            output,
+            paths,
+        } => extract(domain.as_deref(), output.as_deref(), &paths),
+        Command::Check {
+            locales,
+            domain,
+            paths,
+        } => check(&locales, &domain, &paths),
+    };
+
+    if let Err(err) = res {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Activate the locale for the messages and the dates
+fn set_locale(locale: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let Some(locale) = locale {
+        env::set_var("LC_ALL", locale);
+    }
+    if gettextrs::setlocale(LocaleCategory::LcAll, "").is_none() {
+        return Err(format!("locale not available: {}", locale.unwrap_or("")).into());
+    }
+    libc_strftime::tz_set();
+    libc_strftime::set_locale();
+
+    Ok(())
+}
+
+fn render(
+    locale: Option<&str>,
+    domain: &DomainOpt,
+    file: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    set_locale(locale)?;
+    domain.bind();
+
+    let document = match file {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut document = String::new();
+            io::stdin().read_to_string(&mut document)?;
+            document
+        }
+    };
+    println!("{}", String::try_from(parse(&document)?)?);
+
+    Ok(())
+}
+
+/// Parse a JSON document, or a YAML document if it isn't valid JSON
+fn parse(document: &str) -> Result<SerdeGetText, Box<dyn Error>> {
+    match serde_json::from_str(document) {
+        Ok(message) => Ok(message),
+        Err(_) => Ok(serde_yaml::from_str(document)?),
+    }
+}
+
+/// Template files of the paths, the directories are searched recursively
+fn template_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            files.extend(extract::template_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    Ok(files)
+}
+
+fn extract(
+    domain: Option<&str>,
+    output: Option<&Path>,
+    paths: &[PathBuf],
+) -> Result<(), Box<dyn Error>> {
+    let mut msgids: BTreeMap<ExtractedMsgid, Vec<PathBuf>> = BTreeMap::new();
+
+    for path in template_files(paths)? {
+        for msgid in extract::extract_file(&path)? {
+            msgids.entry(msgid).or_default().push(path.clone());
+        }
+    }
+
+    match output {
+        Some(output) => pot::write(&mut fs::File::create(output)?, &msgids, domain)?,
+        None => pot::write(&mut io::stdout(), &msgids, domain)?,
+    }
+
+    Ok(())
+}
+
+fn check(locales: &[String], domain: &DomainOpt, paths: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    let mut messages = Vec::new();
+    let mut problems = 0;
+    let mut stderr = io::stderr();
+
+    for path in template_files(paths)? {
+        for message in extract::read_template(&path)? {
+            for warning in validate::lint(&message, None) {
+                writeln!(stderr, "{}: {}", path.display(), warning)?;
+                problems += 1;
+            }
+            messages.push((path.clone(), message));
+        }
+    }
+
+    for locale in locales {
+        set_locale(Some(locale.as_str()))?;
+        domain.bind();
+
+        for (path, message) in messages.iter() {
+            for warning in validate::lint(message, Some(&GettextBackend)) {
+                if let LintWarning::TranslationMismatch { .. } = warning {
+                    writeln!(stderr, "{}: {}: {}", path.display(), locale, warning)?;
+                    problems += 1;
+                }
+            }
+        }
+
+        let messages: Vec<SerdeGetText> = messages.iter().map(|(_, x)| x.clone()).collect();
+        let report = validate::verify(&messages, &[(locale.as_str(), &GettextBackend)]);
+        for msgid in report.missing.values().flatten() {
+            writeln!(stderr, "{}: missing translation: {:?}", locale, msgid.msgid)?;
+            problems += 1;
+        }
+    }
+
+    if problems > 0 {
+        return Err(format!("{} problem(s) found", problems).into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Identifiers and binary blobs given as values: the UUIDs are rendered in their canonical form
+//! and the bytes in hexadecimal or in base64
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encoding of the bytes rendered as text
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BytesEncoding {
+    /// Lower case hexadecimal (`deadbeef`)
+    #[default]
+    Hex,
+    /// Standard base64 with padding (`3q2+7w==`)
+    Base64,
+    /// URL-safe base64 without padding (`3q2-7w`)
+    Base64Url,
+}
+
+impl BytesEncoding {
+    pub(crate) fn write(self, bytes: &[u8], out: &mut dyn Write) -> fmt::Result {
+        match self {
+            BytesEncoding::Hex => bytes.iter().try_for_each(|b| write!(out, "{:02x}", b)),
+            BytesEncoding::Base64 => base64(bytes, BASE64, true, out),
+            BytesEncoding::Base64Url => base64(bytes, BASE64_URL, false, out),
+        }
+    }
+}
+
+fn base64(bytes: &[u8], alphabet: &[u8; 64], padding: bool, out: &mut dyn Write) -> fmt::Result {
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+
+        // a chunk of n bytes is encoded by n + 1 characters
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.write_char(alphabet[(n >> (18 - 6 * i)) as usize & 63] as char)?;
+            } else if padding {
+                out.write_char('=')?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Bytes given as binary data (MessagePack, CBOR, ...) or as an array of integers
+#[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub(crate) struct ValueBytes(pub(crate) Vec<u8>);
+
+impl<'de> Deserialize<'de> for ValueBytes {
+    fn deserialize<D>(deserializer: D) -> Result<ValueBytes, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = ValueBytes;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("bytes or an array of integers between 0 and 255")
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<ValueBytes, E> {
+                Ok(ValueBytes(value.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<ValueBytes, E> {
+                Ok(ValueBytes(value))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ValueBytes, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                while let Some(b) = seq.next_element()? {
+                    bytes.push(b);
+                }
+
+                Ok(ValueBytes(bytes))
+            }
+        }
+
+        // the formats that are not self-describing can only give the bytes
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(BytesVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+}
+
+/// UUID given as a string (hyphenated or not, braced, with the `urn:uuid:` prefix, in any case)
+/// or as its 16 bytes
+#[derive(Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub(crate) struct ValueUuid([u8; 16]);
+
+impl ValueUuid {
+    fn parse(s: &str) -> Option<ValueUuid> {
+        let s = s.trim();
+        let s = match s.get(..9) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("urn:uuid:") => &s[9..],
+            _ => s,
+        };
+        let s = s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .unwrap_or(s);
+        let hex: Vec<u8> = match s.len() {
+            32 => s.bytes().collect(),
+            36 if [8, 13, 18, 23].iter().all(|&i| s.as_bytes()[i] == b'-') => {
+                s.bytes().filter(|&b| b != b'-').collect()
+            }
+            _ => return None,
+        };
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+
+        let mut bytes = [0; 16];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+
+        Some(ValueUuid(bytes))
+    }
+}
+
+impl fmt::Display for ValueUuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if let 4 | 6 | 8 | 10 = i {
+                f.write_char('-')?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueUuid {
+    fn deserialize<D>(deserializer: D) -> Result<ValueUuid, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct UuidVisitor;
+
+        impl<'de> Visitor<'de> for UuidVisitor {
+            type Value = ValueUuid;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a UUID")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ValueUuid, E> {
+                ValueUuid::parse(value)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<ValueUuid, E> {
+                <[u8; 16]>::try_from(value)
+                    .map(ValueUuid)
+                    .map_err(|_| E::invalid_length(value.len(), &self))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<ValueUuid, A::Error> {
+                let bytes = ValueBytes::deserialize(de::value::SeqAccessDeserializer::new(seq))?;
+                self.visit_bytes(&bytes.0)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(UuidVisitor)
+        } else {
+            deserializer.deserialize_bytes(UuidVisitor)
+        }
+    }
+}
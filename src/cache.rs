@@ -0,0 +1,511 @@
+//! Cache of the rendered messages, for the services rendering the same messages over and over
+//! (menus, notification templates, ...)
+
+use crate::default_args::default_args;
+use crate::environment;
+use crate::langinfo::langinfo;
+use crate::{
+    Backend, Error, Formatter, KeywordArg, LocaleContext, SerdeGetText, Value, ValueForeach,
+    ValueSelectPlural,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+/// Cache of the rendered messages, the least recently used are evicted when it is full
+///
+/// The messages are identified by their content, their arguments and all their options,
+/// including the locale and the backend: the same cache can be shared by all the locales. The
+/// environment variables and the facts about the locale of the process (`env`, `langinfo`) are
+/// identified by their current values. The errors are not cached, and the observers are only
+/// notified when a message is rendered (not when it is found in the cache). The messages without
+/// `locale` are cached with the locale of the environment at the time they are rendered.
+///
+/// ```rust
+/// use serde_gettext::{MockCatalog, RenderCache, SerdeGetText};
+/// use std::sync::Arc;
+///
+/// let mut catalog = MockCatalog::new();
+/// catalog.insert("Hello!", "Bonjour !");
+/// let catalog = Arc::new(catalog);
+/// let cache = RenderCache::new(1000);
+///
+/// for _ in 0..3 {
+///     let mut message: SerdeGetText = serde_json::from_str(r#"{"gettext": "Hello!"}"#)?;
+///     message.backend = Some(catalog.clone());
+///     assert_eq!(cache.render(message)?, "Bonjour !");
+/// }
+/// assert_eq!(cache.len(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct RenderCache {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Entries by hash of their key
+    entries: HashMap<u64, Entry>,
+    /// Keys of the entries by last use
+    used: BTreeMap<u64, u64>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// Full key of the message, the messages with the same hash are told apart
+    key: Box<[u8]>,
+    text: String,
+    used: u64,
+    /// Kept alive so its address can't identify another backend while the entry exists
+    _backend: Option<Arc<dyn Backend + Send + Sync>>,
+}
+
+impl RenderCache {
+    /// Create a cache of a number of rendered messages, nothing is cached if it is 0
+    pub fn new(capacity: usize) -> RenderCache {
+        RenderCache {
+            capacity,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Render a message, or get it from the cache if an identical message has been rendered
+    pub fn render(&self, message: SerdeGetText) -> Result<String, Error> {
+        if self.capacity == 0 || message.uses_current_time() {
+            return String::try_from(message);
+        }
+
+        let key = message.cache_key();
+        if let Some(text) = self.lock().get(&key) {
+            return Ok(text);
+        }
+
+        let backend = message.backend.clone();
+        let text = String::try_from(message)?;
+        self.lock()
+            .insert(key, text.clone(), backend, self.capacity);
+
+        Ok(text)
+    }
+
+    /// Number of rendered messages in the cache
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the rendered messages, e.g. when the catalogs have been reloaded
+    pub fn clear(&self) {
+        *self.lock() = State::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // the state is always consistent, even if a thread panicked while holding it
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl State {
+    fn get(&mut self, key: &CacheKey) -> Option<String> {
+        self.tick += 1;
+        let hash = key.finish();
+        let entry = self
+            .entries
+            .get_mut(&hash)
+            .filter(|entry| *entry.key == *key.0)?;
+        self.used.remove(&entry.used);
+        self.used.insert(self.tick, hash);
+        entry.used = self.tick;
+
+        Some(entry.text.clone())
+    }
+
+    fn insert(
+        &mut self,
+        key: CacheKey,
+        text: String,
+        backend: Option<Arc<dyn Backend + Send + Sync>>,
+        capacity: usize,
+    ) {
+        self.tick += 1;
+        let hash = key.finish();
+        // a message with the same hash is replaced
+        if let Some(previous) = self.entries.remove(&hash) {
+            self.used.remove(&previous.used);
+        }
+        while self.entries.len() >= capacity {
+            match self.used.pop_first() {
+                Some((_, oldest)) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        self.used.insert(self.tick, hash);
+        self.entries.insert(
+            hash,
+            Entry {
+                key: key.0.into_boxed_slice(),
+                text,
+                used: self.tick,
+                _backend: backend,
+            },
+        );
+    }
+}
+
+/// Everything that changes the rendering of a message, as written to a hasher: the `Hash`
+/// implementations write their values without ambiguity
+#[derive(Debug, Default)]
+pub(crate) struct CacheKey(Vec<u8>);
+
+impl Hasher for CacheKey {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        state.write(&self.0);
+        state.finish()
+    }
+}
+
+impl SerdeGetText {
+    /// Key of everything that changes the rendering of the message
+    pub(crate) fn cache_key(&self) -> CacheKey {
+        let mut state = CacheKey::default();
+        self.value.hash(&mut state);
+        hash_map(&self.vars, &mut state);
+        hash_map(&self.args, &mut state);
+        hash_map(&default_args(), &mut state);
+        crate::domain::current().hash(&mut state);
+        self.merge.hash(&mut state);
+        self.empty_msgid.hash(&mut state);
+        self.pseudo.hash(&mut state);
+        self.backend
+            .as_ref()
+            .map(|x| Arc::as_ptr(x) as *const () as usize)
+            .hash(&mut state);
+        self.now.hash(&mut state);
+        self.timezone.hash(&mut state);
+        self.locale
+            .clone()
+            .or_else(LocaleContext::current)
+            .hash(&mut state);
+        self.decoding.hash(&mut state);
+        self.placeholders.hash(&mut state);
+        self.percent.hash(&mut state);
+        self.control.hash(&mut state);
+        self.escape.hash(&mut state);
+
+        state
+    }
+
+    /// Check if the message renders a date at the current time of the system
+    fn uses_current_time(&self) -> bool {
+        self.now.is_none()
+            && (self.value.uses_current_time() || self.vars.values().any(Value::uses_current_time))
+    }
+}
+
+impl Value {
+    fn uses_current_time(&self) -> bool {
+        let uses_args = |args: &Option<Formatter>, defaults: &HashMap<String, Value>| {
+            args.as_ref()
+                .map(Formatter::uses_current_time)
+                .unwrap_or(false)
+                || defaults.values().any(Value::uses_current_time)
+        };
+
+        match self {
+            Value::Datetime(x) => x.epoch.is_none(),
+            Value::Text(_)
+            | Value::Integer(_)
+            | Value::Float(_)
+            | Value::Bool(_)
+            | Value::Unit(())
+            | Value::Ref { .. }
+            | Value::Env { .. }
+            | Value::Uuid { .. }
+            | Value::Bytes { .. }
+            | Value::Fragment(_) => false,
+            #[cfg(feature = "decimal")]
+            Value::Decimal { .. } => false,
+            #[cfg(feature = "phone")]
+            Value::Phone { .. } => false,
+            Value::Measure { .. } => false,
+            Value::LangInfo { .. } => false,
+            Value::Array(xs) | Value::Join { join: xs, .. } | Value::FirstOf { first_of: xs } => {
+                xs.iter().any(Value::uses_current_time)
+            }
+            Value::Table { table, .. } => table.iter().flatten().any(Value::uses_current_time),
+            Value::Filtered { value, .. } => value.uses_current_time(),
+            Value::IfTranslated { if_translated } => if_translated
+                .then
+                .iter()
+                .chain(&if_translated.otherwise)
+                .any(|x| x.uses_current_time()),
+            Value::Choice { choice, args } => {
+                choice
+                    .cases
+                    .values()
+                    .chain(choice.default.as_deref())
+                    .any(Value::uses_current_time)
+                    || args.as_ref().is_some_and(Formatter::uses_current_time)
+            }
+            Value::Map { map, .. } => map.values().any(Value::uses_current_time),
+            Value::Address { address } => {
+                address.fields().into_iter().any(Value::uses_current_time)
+            }
+            Value::PersonName { person_name } => person_name
+                .fields()
+                .into_iter()
+                .any(Value::uses_current_time),
+            Value::Foreach { foreach } => {
+                foreach.template.uses_current_time()
+                    || foreach
+                        .items
+                        .iter()
+                        .any(|x| x.values().any(KeywordArg::uses_current_time))
+            }
+            Value::FormattedText { args, defaults, .. }
+            | Value::Noop { args, defaults, .. }
+            | Value::GetText { args, defaults, .. }
+            | Value::NGetText { args, defaults, .. }
+            | Value::PGetText { args, defaults, .. }
+            | Value::DGetText { args, defaults, .. }
+            | Value::DNGetText { args, defaults, .. }
+            | Value::NPGetText { args, defaults, .. }
+            | Value::DCNGetText { args, defaults, .. }
+            | Value::SelectPlural { args, defaults, .. } => uses_args(args, defaults),
+        }
+    }
+}
+
+impl Formatter {
+    fn uses_current_time(&self) -> bool {
+        match self {
+            Formatter::KeywordArgs(args) => args.values().any(KeywordArg::uses_current_time),
+            Formatter::PositionalArgs(args) => args.iter().any(Value::uses_current_time),
+        }
+    }
+}
+
+impl KeywordArg {
+    fn uses_current_time(&self) -> bool {
+        match self {
+            KeywordArg::Value(value) => value.uses_current_time(),
+            KeywordArg::Object(map) => map.values().any(KeywordArg::uses_current_time),
+        }
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Text(x) => x.hash(state),
+            Value::Integer(x) => x.hash(state),
+            Value::Float(x) => x.to_bits().hash(state),
+            Value::Uuid { uuid } => uuid.hash(state),
+            Value::Bytes { bytes, encoding } => (bytes, encoding).hash(state),
+            #[cfg(feature = "decimal")]
+            Value::Decimal { decimal } => decimal.hash(state),
+            #[cfg(feature = "phone")]
+            Value::Phone {
+                phone,
+                format,
+                region,
+            } => (phone, format, region).hash(state),
+            Value::Measure {
+                measure,
+                unit,
+                system,
+                precision,
+            } => (measure.to_bits(), unit, system, precision).hash(state),
+            Value::Bool(x) => x.hash(state),
+            Value::Unit(()) => {}
+            Value::Datetime(x) => x.hash(state),
+            Value::Array(xs) => xs.hash(state),
+            Value::Join {
+                join,
+                sort,
+                collate,
+                unique,
+                skip_empty,
+            } => (join, sort, collate, unique, skip_empty).hash(state),
+            Value::Ref { name } => name.hash(state),
+            Value::Table { table, align, sep } => (table, align, sep).hash(state),
+            Value::Filtered {
+                value,
+                filters,
+                precision,
+                rounding,
+            } => (value, filters, precision, rounding).hash(state),
+            Value::Map { map, entry, sep } => (map, entry, sep).hash(state),
+            Value::Foreach { foreach } => foreach.hash(state),
+            Value::FirstOf { first_of } => first_of.hash(state),
+            Value::IfTranslated { if_translated } => if_translated.hash(state),
+            Value::Choice { choice, args } => (choice, args).hash(state),
+            // the values can change between two renderings
+            Value::Env { env } => (env, environment::var(env).ok()).hash(state),
+            Value::LangInfo {
+                langinfo: item,
+                index,
+            } => (item, index, langinfo(*item, *index).ok()).hash(state),
+            Value::Address { address } => address.hash(state),
+            Value::PersonName { person_name } => person_name.hash(state),
+            Value::Fragment(x) => x.hash(state),
+            Value::FormattedText {
+                text,
+                args,
+                defaults,
+            } => {
+                (text, args).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::Noop {
+                noop,
+                args,
+                defaults,
+                ..
+            } => {
+                (noop, args).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::GetText {
+                gettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (gettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::NGetText {
+                ngettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (ngettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::PGetText {
+                pgettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (pgettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::DGetText {
+                dgettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (dgettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::DNGetText {
+                dngettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (dngettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::NPGetText {
+                npgettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (npgettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::DCNGetText {
+                dcngettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (dcngettext, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+            Value::SelectPlural {
+                selectplural,
+                args,
+                defaults,
+                default,
+                ..
+            } => {
+                (selectplural, args, default).hash(state);
+                hash_map(defaults, state);
+            }
+        }
+    }
+}
+
+impl Hash for Formatter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Formatter::KeywordArgs(args) => hash_map(args, state),
+            Formatter::PositionalArgs(args) => args.hash(state),
+        }
+    }
+}
+
+impl Hash for KeywordArg {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            KeywordArg::Value(value) => value.hash(state),
+            KeywordArg::Object(map) => hash_map(map, state),
+        }
+    }
+}
+
+impl Hash for ValueForeach {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.items.len().hash(state);
+        for item in self.items.iter() {
+            hash_map(item, state);
+        }
+        (&self.template, &self.sep).hash(state);
+    }
+}
+
+impl Hash for ValueSelectPlural {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (&self.select, self.n).hash(state);
+        hash_map(&self.forms, state);
+    }
+}
+
+/// Hash the entries of a map sorted by key: the order of a `HashMap` changes between instances
+fn hash_map<V: Hash, H: Hasher>(map: &HashMap<String, V>, state: &mut H) {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|(key, _)| *key);
+    entries.hash(state);
+}
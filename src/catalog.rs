@@ -0,0 +1,723 @@
+use crate::locale_context::ThreadLocale;
+use crate::plural::Expr;
+use gettextrs::LocaleCategory;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::raw::{c_char, c_int, c_ulong};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::str;
+
+/// Message looked up in a catalog
+#[derive(Clone, Copy, Debug)]
+pub struct Lookup<'a> {
+    /// Text domain, `None` for the current one
+    pub domain: Option<&'a str>,
+    /// Context of the message (`msgctxt`)
+    pub context: Option<&'a str>,
+    /// Message identifier (the singular form for the plural messages)
+    pub msgid: &'a str,
+    /// Plural form and count of the plural messages
+    pub plural: Option<(&'a str, u64)>,
+    /// Locale category, `None` for `LC_MESSAGES`
+    pub category: Option<LocaleCategory>,
+    /// Locale of the message (e.g. `fr_FR.UTF-8`), `None` for the locale of the process
+    pub locale: Option<&'a str>,
+}
+
+impl<'a> Lookup<'a> {
+    pub(crate) fn new(msgid: &'a str) -> Lookup<'a> {
+        Lookup {
+            domain: None,
+            context: None,
+            msgid,
+            plural: None,
+            category: None,
+            locale: None,
+        }
+    }
+
+    /// Message returned by gettext when there is no translation
+    pub fn untranslated(&self) -> &'a str {
+        match self.plural {
+            Some((plural, n)) if n != 1 => plural,
+            _ => self.msgid,
+        }
+    }
+}
+
+/// Source of the translations
+pub trait Backend {
+    /// Translation of the message, `None` if the catalog has no translation for it
+    fn translate(&self, lookup: &Lookup) -> Option<String>;
+
+    /// Translation of the message, `Err` with the translation decoded lossily (the invalid
+    /// sequences replaced by `U+FFFD`) if the catalog returned invalid UTF-8
+    fn translate_checked(&self, lookup: &Lookup) -> Option<Result<String, String>> {
+        self.translate(lookup).map(Ok)
+    }
+
+    /// Index of the plural form selected for the count of a plural message (`msgstr[i]`),
+    /// `None` if the backend doesn't know it
+    fn plural_form(&self, _lookup: &Lookup) -> Option<usize> {
+        None
+    }
+}
+
+impl fmt::Debug for dyn Backend + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Backend")
+    }
+}
+
+/// Backend using the catalogs installed on the system through gettext (the default)
+///
+/// gettext returns the msgid when there is no translation: the translations identical to their
+/// msgid are taken for missing translations (by the observers, the metrics and the coverage).
+///
+/// The text domains without codeset (`bind_textdomain_codeset`) are bound to UTF-8 on their first
+/// lookup: gettext converts the legacy catalogs instead of returning the codeset of the locale.
+/// The translations that are still not valid UTF-8 (e.g. with another codeset bound explicitly)
+/// are decoded lossily by `translate` and reported by `translate_checked`.
+///
+/// The lookups with a locale (`Lookup::locale`) use the catalogs of that locale on the current
+/// thread only (`uselocale`), provided the locale is installed on the system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GettextBackend;
+
+impl Backend for GettextBackend {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        self.translate_checked(lookup)
+            .map(|x| x.unwrap_or_else(|lossy| lossy))
+    }
+
+    fn translate_checked(&self, lookup: &Lookup) -> Option<Result<String, String>> {
+        // gettext can't look up the strings containing NUL characters
+        let plural = lookup.plural.map(|(plural, _)| plural);
+        if [lookup.domain, lookup.context, Some(lookup.msgid), plural]
+            .iter()
+            .flatten()
+            .any(|x| x.contains('\0'))
+        {
+            return None;
+        }
+
+        // gettext stores the context and the msgid separated by EOT
+        let key = match lookup.context {
+            Some(context) => format!("{}\u{4}{}", context, lookup.msgid),
+            None => lookup.msgid.to_string(),
+        };
+        let c_key = CString::new(key.as_str()).ok()?;
+        let c_domain = lookup.domain.map(CString::new).transpose().ok()?;
+        let c_plural = plural.map(CString::new).transpose().ok()?;
+        bind_utf8(c_domain.as_deref());
+        // the catalogs of the locale of the message are used by this thread during the lookup,
+        // or the ones of the locale of the process if it isn't installed
+        let _locale = lookup
+            .locale
+            .and_then(|x| CString::new(x).ok())
+            .and_then(|x| ThreadLocale::enter(libc::LC_ALL_MASK, &x));
+
+        // the category is only used with a text domain, like dcgettext does
+        let translated = unsafe {
+            let ptr = match (
+                c_domain.as_deref(),
+                lookup.plural.zip(c_plural.as_deref()),
+                lookup.category,
+            ) {
+                (None, None, _) => gettext_sys::gettext(c_key.as_ptr()),
+                (None, Some(((_, n), plural)), _) => {
+                    gettext_sys::ngettext(c_key.as_ptr(), plural.as_ptr(), gettext_count(n))
+                }
+                (Some(domain), None, None) => {
+                    gettext_sys::dgettext(domain.as_ptr(), c_key.as_ptr())
+                }
+                (Some(domain), None, Some(category)) => {
+                    gettext_sys::dcgettext(domain.as_ptr(), c_key.as_ptr(), category as c_int)
+                }
+                (Some(domain), Some(((_, n), plural)), None) => gettext_sys::dngettext(
+                    domain.as_ptr(),
+                    c_key.as_ptr(),
+                    plural.as_ptr(),
+                    gettext_count(n),
+                ),
+                (Some(domain), Some(((_, n), plural)), Some(category)) => gettext_sys::dcngettext(
+                    domain.as_ptr(),
+                    c_key.as_ptr(),
+                    plural.as_ptr(),
+                    gettext_count(n),
+                    category as c_int,
+                ),
+            };
+            if ptr.is_null() {
+                return None;
+            }
+            CStr::from_ptr(ptr).to_bytes()
+        };
+
+        // gettext returns the msgid (or the plural form) unchanged when there is no translation,
+        // a translation identical to the msgid can't be told apart
+        if translated == key.as_bytes() || plural.map(str::as_bytes) == Some(translated) {
+            return None;
+        }
+
+        Some(match str::from_utf8(translated) {
+            Ok(translated) => Ok(translated.to_string()),
+            Err(_) => Err(String::from_utf8_lossy(translated).into_owned()),
+        })
+    }
+}
+
+/// Count given to ngettext: `unsigned long` has 32 bits on some platforms, the bigger counts are
+/// reduced to a number with the same last 6 digits like the counts of the messages
+fn gettext_count(n: u64) -> c_ulong {
+    c_ulong::try_from(n).unwrap_or_else(|_| (n % 1_000_000 + 1_000_000) as c_ulong)
+}
+
+/// Bind a text domain (`None` for the current one) to UTF-8 if it has no codeset yet
+fn bind_utf8(domain: Option<&CStr>) {
+    unsafe {
+        let current;
+        let domain = match domain {
+            Some(domain) => domain,
+            None => {
+                // the current text domain is returned when it is not changed
+                let ptr = gettext_sys::textdomain(ptr::null());
+                if ptr.is_null() {
+                    return;
+                }
+                current = CStr::from_ptr(ptr).to_owned();
+                &current
+            }
+        };
+        // the codeset is returned when it is not changed, NULL if there is none
+        if gettext_sys::bind_textdomain_codeset(domain.as_ptr(), ptr::null()).is_null() {
+            gettext_sys::bind_textdomain_codeset(domain.as_ptr(), UTF_8.as_ptr() as *const c_char);
+        }
+    }
+}
+
+const UTF_8: &[u8] = b"UTF-8\0";
+
+/// In-memory catalog to get deterministic translations in the tests, without installing
+/// catalogs on the system
+///
+/// ```rust
+/// use serde_gettext::MockCatalog;
+///
+/// let mut catalog = MockCatalog::new();
+/// catalog
+///     .insert("Hello %(name)s!", "Bonjour %(name)s !")
+///     .insert_plural("%(n)s apple", &["%(n)s pomme", "%(n)s pommes"])
+///     .plural_rule(|n| usize::from(n > 1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MockCatalog {
+    messages: HashMap<(Option<String>, Option<String>, String), Vec<String>>,
+    plural_rule: fn(u64) -> usize,
+}
+
+impl Default for MockCatalog {
+    fn default() -> MockCatalog {
+        MockCatalog {
+            messages: HashMap::new(),
+            plural_rule: |n| usize::from(n != 1),
+        }
+    }
+}
+
+impl MockCatalog {
+    /// Create an empty catalog using the plural rule of English
+    pub fn new() -> MockCatalog {
+        MockCatalog::default()
+    }
+
+    /// Add the translation of a message of the current text domain
+    pub fn insert(&mut self, msgid: &str, msgstr: &str) -> &mut MockCatalog {
+        self.insert_with(None, None, msgid, &[msgstr])
+    }
+
+    /// Add the plural forms (`msgstr[0]`, `msgstr[1]`, ...) of a message of the current text
+    /// domain
+    pub fn insert_plural(&mut self, msgid: &str, msgstrs: &[&str]) -> &mut MockCatalog {
+        self.insert_with(None, None, msgid, msgstrs)
+    }
+
+    /// Add the translation (or the plural forms) of a message of any text domain and context
+    pub fn insert_with(
+        &mut self,
+        domain: Option<&str>,
+        context: Option<&str>,
+        msgid: &str,
+        msgstrs: &[&str],
+    ) -> &mut MockCatalog {
+        self.messages.insert(
+            (
+                domain.map(ToString::to_string),
+                context.map(ToString::to_string),
+                msgid.to_string(),
+            ),
+            msgstrs.iter().map(ToString::to_string).collect(),
+        );
+        self
+    }
+
+    /// Set the plural rule: the index of the plural form to use for a count
+    pub fn plural_rule(&mut self, rule: fn(u64) -> usize) -> &mut MockCatalog {
+        self.plural_rule = rule;
+        self
+    }
+}
+
+impl Backend for MockCatalog {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        let msgstrs = self.messages.get(&(
+            lookup.domain.map(ToString::to_string),
+            lookup.context.map(ToString::to_string),
+            lookup.msgid.to_string(),
+        ))?;
+        let index = self.plural_form(lookup).unwrap_or(0);
+
+        msgstrs.get(index).cloned()
+    }
+
+    fn plural_form(&self, lookup: &Lookup) -> Option<usize> {
+        lookup.plural.map(|(_, n)| (self.plural_rule)(n))
+    }
+}
+
+/// Error that occurs when a catalog cannot be loaded
+#[derive(Debug, Error)]
+pub enum CatalogError {
+    /// The data is not a valid catalog
+    #[error(msg_embedded, no_from, non_std)]
+    Invalid(String),
+    /// The file of the catalog could not be read or watched
+    #[error(msg_embedded, no_from, non_std)]
+    Io(String),
+    /// The catalog could not be downloaded from a remote source
+    #[error(msg_embedded, no_from, non_std)]
+    Fetch(String),
+}
+
+/// Catalog loaded from compiled catalogs (MO files) without gettext: the catalogs can be
+/// embedded in the binary or loaded in the browsers
+///
+/// ```rust,no_run
+/// use serde_gettext::Catalog;
+///
+/// let mut catalog = Catalog::new();
+/// catalog
+///     .load_mo(None, &std::fs::read("locale/fr/LC_MESSAGES/app.mo")?)?
+///     .load_mo(Some("errors"), &std::fs::read("locale/fr/LC_MESSAGES/errors.mo")?)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    messages: HashMap<(Option<String>, Option<String>, String), Vec<String>>,
+    /// Plural rules of the text domains, from the headers of their catalogs
+    plurals: HashMap<Option<String>, Expr>,
+}
+
+impl Catalog {
+    /// Create an empty catalog
+    pub fn new() -> Catalog {
+        Catalog::default()
+    }
+
+    /// Add the messages of a compiled catalog (MO file) of a text domain (`None` for the current
+    /// one). The plural rule of the text domain is taken from its `Plural-Forms` header.
+    pub fn load_mo(
+        &mut self,
+        domain: Option<&str>,
+        data: &[u8],
+    ) -> Result<&mut Catalog, CatalogError> {
+        for (key, msgstrs) in parse_mo(data)? {
+            // the msgid of the header is empty
+            if key.is_empty() {
+                let forms = msgstrs
+                    .first()
+                    .and_then(|x| x.lines().find_map(|x| x.strip_prefix("Plural-Forms:")));
+                if let Some(plural) = forms.and_then(plural_expression) {
+                    self.plurals.insert(
+                        domain.map(ToString::to_string),
+                        Expr::parse(plural)
+                            .ok_or_else(|| invalid(format!("plural expression: {}", plural)))?,
+                    );
+                }
+                continue;
+            }
+
+            // the plural form of the msgid is not part of the key
+            let key = key.split('\0').next().unwrap_or("");
+            let (context, msgid) = match key.find('\u{4}') {
+                Some(i) => (Some(key[..i].to_string()), key[i + 1..].to_string()),
+                None => (None, key.to_string()),
+            };
+            self.messages
+                .insert((domain.map(ToString::to_string), context, msgid), msgstrs);
+        }
+
+        Ok(self)
+    }
+
+    /// Add the messages of a JSON catalog made by [po2json](https://github.com/mikeedwards/po2json)
+    /// (the raw format and the Jed formats) or for
+    /// [gettext.js](https://github.com/guillaumepotier/gettext.js). The messages of the text
+    /// domain of a Jed catalog are added to `domain`, the messages of its other text domains to
+    /// their own text domain.
+    ///
+    /// ```rust
+    /// use serde_gettext::Catalog;
+    ///
+    /// let mut catalog = Catalog::new();
+    /// catalog.load_json(
+    ///     None,
+    ///     br#"{
+    ///         "": {"language": "fr", "plural-forms": "nplurals=2; plural=n>1;"},
+    ///         "Hello %(name)s!": "Bonjour %(name)s !",
+    ///         "%(n)s apple": ["%(n)s pomme", "%(n)s pommes"]
+    ///     }"#,
+    /// )?;
+    /// # Ok::<(), serde_gettext::CatalogError>(())
+    /// ```
+    #[cfg(feature = "po2json")]
+    pub fn load_json(
+        &mut self,
+        domain: Option<&str>,
+        data: &[u8],
+    ) -> Result<&mut Catalog, CatalogError> {
+        use serde_json::Value;
+
+        let json: Value = serde_json::from_slice(data)
+            .map_err(|err| invalid(format!("invalid JSON: {}", err)))?;
+        let catalog = json
+            .as_object()
+            .ok_or_else(|| invalid("not a JSON catalog".to_string()))?;
+
+        match catalog.get("locale_data") {
+            // Jed: {"domain": "app", "locale_data": {"app": {...}, "errors": {...}}}
+            Some(locale_data) => {
+                let default = catalog
+                    .get("domain")
+                    .and_then(Value::as_str)
+                    .unwrap_or("messages");
+                let domains = locale_data
+                    .as_object()
+                    .ok_or_else(|| invalid("locale_data is not an object".to_string()))?;
+                for (name, messages) in domains {
+                    let target = if name == default {
+                        domain
+                    } else {
+                        Some(name.as_str())
+                    };
+                    self.load_json_messages(target, messages)?;
+                }
+            }
+            None => self.load_json_messages(domain, &json)?,
+        }
+
+        Ok(self)
+    }
+
+    /// Add the messages of a text domain of a JSON catalog: the header is the empty key and the
+    /// translations are strings or arrays of plural forms, which may start with the plural form of
+    /// the msgid (`null` for the messages without plural form)
+    #[cfg(feature = "po2json")]
+    fn load_json_messages(
+        &mut self,
+        domain: Option<&str>,
+        messages: &serde_json::Value,
+    ) -> Result<(), CatalogError> {
+        use serde_json::Value;
+
+        let messages = messages
+            .as_object()
+            .ok_or_else(|| invalid("messages are not an object".to_string()))?;
+        // po2json writes `plural-forms`, Jed `plural_forms`
+        let forms = messages.get("").and_then(Value::as_object).and_then(|x| {
+            x.iter()
+                .find(|(key, _)| key.to_lowercase().replace('_', "-") == "plural-forms")
+                .and_then(|(_, value)| value.as_str())
+        });
+        let nplurals = forms
+            .and_then(|x| {
+                x.split(';')
+                    .find_map(|x| x.trim().strip_prefix("nplurals="))
+            })
+            .and_then(|x| x.trim().parse::<usize>().ok())
+            .unwrap_or(2);
+        if let Some(plural) = forms.and_then(plural_expression) {
+            self.plurals.insert(
+                domain.map(ToString::to_string),
+                Expr::parse(plural)
+                    .ok_or_else(|| invalid(format!("plural expression: {}", plural)))?,
+            );
+        }
+
+        for (key, value) in messages.iter().filter(|(key, _)| !key.is_empty()) {
+            let mut msgstrs = match value {
+                Value::String(x) => vec![Value::String(x.clone())],
+                Value::Array(x) => x.clone(),
+                _ => return Err(invalid(format!("invalid translation of: {}", key))),
+            };
+            if msgstrs.first().map(Value::is_null).unwrap_or(false) || msgstrs.len() == nplurals + 1
+            {
+                msgstrs.remove(0);
+            }
+            let msgstrs = msgstrs
+                .into_iter()
+                .map(|x| match x {
+                    Value::String(x) => Ok(x),
+                    _ => Err(invalid(format!("invalid translation of: {}", key))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let (context, msgid) = match key.find('\u{4}') {
+                Some(i) => (Some(key[..i].to_string()), key[i + 1..].to_string()),
+                None => (None, key.to_string()),
+            };
+            self.messages
+                .insert((domain.map(ToString::to_string), context, msgid), msgstrs);
+        }
+
+        Ok(())
+    }
+
+    /// Text domains of the loaded messages (`None` for the current one)
+    pub fn available_domains(&self) -> Vec<Option<&str>> {
+        let mut domains: Vec<_> = self
+            .messages
+            .keys()
+            .map(|(domain, _, _)| domain.as_deref())
+            .collect();
+        domains.sort_unstable();
+        domains.dedup();
+        domains
+    }
+
+    /// Check if a message (without context) of a text domain has a translation
+    pub fn has_translation(&self, domain: Option<&str>, msgid: &str) -> bool {
+        self.translate(&Lookup {
+            domain,
+            ..Lookup::new(msgid)
+        })
+        .is_some()
+    }
+
+    /// Plural rule of a text domain, `None` if its catalog has no `Plural-Forms`
+    fn plural(&self, domain: Option<&str>) -> Option<&Expr> {
+        self.plurals
+            .iter()
+            .find(|(x, _)| x.as_deref() == domain)
+            .map(|(_, plural)| plural)
+    }
+}
+
+impl Backend for Catalog {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        let msgstrs = self.messages.get(&(
+            lookup.domain.map(ToString::to_string),
+            lookup.context.map(ToString::to_string),
+            lookup.msgid.to_string(),
+        ))?;
+        let index = match lookup.plural {
+            Some(_) => self.plural_form(lookup)?,
+            None => 0,
+        };
+
+        // gettext ignores the empty translations (fuzzy or untranslated entries)
+        msgstrs.get(index).filter(|x| !x.is_empty()).cloned()
+    }
+
+    fn plural_form(&self, lookup: &Lookup) -> Option<usize> {
+        let (_, n) = lookup.plural?;
+        match self.plural(lookup.domain) {
+            Some(plural) => usize::try_from(plural.eval(n)).ok(),
+            None => Some(usize::from(n != 1)),
+        }
+    }
+}
+
+fn invalid(message: String) -> CatalogError {
+    CatalogError::Invalid(message)
+}
+
+/// Value of `plural=` in the value of the `Plural-Forms` header, the fields may come in any order
+fn plural_expression(forms: &str) -> Option<&str> {
+    let i = forms.find("plural=")?;
+
+    forms[i + "plural=".len()..].split(';').next()
+}
+
+/// Original strings and translations of a MO file, the plural forms are separated by NUL
+/// characters. They are decoded from the charset of the header (UTF-8 or ISO-8859-1).
+fn parse_mo(data: &[u8]) -> Result<Vec<(String, Vec<String>)>, CatalogError> {
+    let big_endian = match data.get(..4) {
+        Some([0xde, 0x12, 0x04, 0x95]) => false,
+        Some([0x95, 0x04, 0x12, 0xde]) => true,
+        _ => return Err(invalid("not a MO file".to_string())),
+    };
+    let read_u32 = |offset: usize| -> Result<usize, CatalogError> {
+        let bytes = offset
+            .checked_add(4)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| invalid("truncated file".to_string()))?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        let value = if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+
+        usize::try_from(value).map_err(|_| invalid("offset out of range".to_string()))
+    };
+    // a table entry is the length and the offset of a string
+    let read_string = |table: usize, i: usize| -> Result<&[u8], CatalogError> {
+        let entry = i
+            .checked_mul(8)
+            .and_then(|x| x.checked_add(table))
+            .ok_or_else(|| invalid("offset out of range".to_string()))?;
+        let (len, offset) = (read_u32(entry)?, read_u32(entry.saturating_add(4))?);
+
+        offset
+            .checked_add(len)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| invalid("truncated file".to_string()))
+    };
+
+    let count = read_u32(8)?;
+    let (originals, translations) = (read_u32(12)?, read_u32(16)?);
+    let mut strings = Vec::new();
+    for i in 0..count {
+        strings.push((read_string(originals, i)?, read_string(translations, i)?));
+    }
+    // the msgid of the header is empty
+    let charset = strings
+        .iter()
+        .find(|(original, _)| original.is_empty())
+        .and_then(|(_, header)| charset(&String::from_utf8_lossy(header)));
+
+    let mut messages = Vec::new();
+    for (original, translation) in strings {
+        let msgstrs = decode(translation, charset.as_deref())?
+            .split('\0')
+            .map(ToString::to_string)
+            .collect();
+        messages.push((decode(original, charset.as_deref())?, msgstrs));
+    }
+
+    Ok(messages)
+}
+
+/// Value of `charset=` in the `Content-Type` header, lower case
+fn charset(header: &str) -> Option<String> {
+    let content_type = header
+        .lines()
+        .find_map(|x| x.strip_prefix("Content-Type:"))?;
+    let i = content_type.find("charset=")?;
+
+    Some(content_type[i + "charset=".len()..].trim().to_lowercase())
+}
+
+fn decode(bytes: &[u8], charset: Option<&str>) -> Result<String, CatalogError> {
+    match charset {
+        // `CHARSET` is the placeholder of the templates
+        None | Some("utf-8") | Some("utf8") | Some("ascii") | Some("us-ascii")
+        | Some("charset") => str::from_utf8(bytes)
+            .map(ToString::to_string)
+            .map_err(|err| invalid(format!("invalid UTF-8: {}", err))),
+        // the code points of ISO-8859-1 are the first ones of Unicode
+        Some("iso-8859-1") | Some("iso8859-1") | Some("latin1") | Some("latin-1") => {
+            Ok(bytes.iter().map(|x| char::from(*x)).collect())
+        }
+        Some(charset) => Err(invalid(format!("unsupported charset: {}", charset))),
+    }
+}
+
+/// Directory of the compiled catalogs installed for gettext (`<dir>/<locale>/LC_MESSAGES/<domain>.mo`),
+/// e.g. to report the locales of a service or to fail fast when a locale is not installed
+///
+/// ```rust,no_run
+/// use serde_gettext::LocaleDir;
+///
+/// let dir = LocaleDir::new("/usr/share/locale");
+/// if !dir.available_locales()?.iter().any(|x| x == "fr") {
+///     eprintln!("the French catalogs are not installed");
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct LocaleDir {
+    path: PathBuf,
+}
+
+impl LocaleDir {
+    /// Directory given to `bindtextdomain`
+    pub fn new<P: AsRef<Path>>(path: P) -> LocaleDir {
+        LocaleDir {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Locales having at least one compiled catalog, sorted
+    pub fn available_locales(&self) -> io::Result<Vec<String>> {
+        let mut locales = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let locale = entry.file_name().to_string_lossy().to_string();
+            if !self.available_domains(&locale)?.is_empty() {
+                locales.push(locale);
+            }
+        }
+        locales.sort();
+
+        Ok(locales)
+    }
+
+    /// Text domains having a compiled catalog for a locale, sorted
+    pub fn available_domains(&self, locale: &str) -> io::Result<Vec<String>> {
+        let dir = self.path.join(locale).join("LC_MESSAGES");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            // not a directory: a file of the locale directory
+            Err(_) if !dir.is_dir() => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut domains = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map(|x| x == "mo").unwrap_or(false) {
+                if let Some(domain) = path.file_stem() {
+                    domains.push(domain.to_string_lossy().to_string());
+                }
+            }
+        }
+        domains.sort();
+
+        Ok(domains)
+    }
+
+    /// Check if the compiled catalog of a text domain and a locale has a translation for a
+    /// message (without context), `false` if there is no valid catalog
+    pub fn has_translation(&self, domain: &str, msgid: &str, locale: &str) -> bool {
+        let path = self
+            .path
+            .join(locale)
+            .join("LC_MESSAGES")
+            .join(format!("{}.mo", domain));
+
+        match fs::read(path) {
+            Ok(data) => Catalog::new()
+                .load_mo(None, &data)
+                .map(|x| x.has_translation(None, msgid))
+                .unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+}
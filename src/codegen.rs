@@ -0,0 +1,261 @@
+//! Typed constructors of the messages of a POT or PO file, for the services authoring their
+//! messages in Rust
+//!
+//! Every msgid becomes a function named after the msgid (or the context of the message) taking
+//! its keyword placeholders as arguments, and the count `n` for the plural messages. A build
+//! script generates them:
+//!
+//! ```rust,no_run
+//! // build.rs
+//! serde_gettext::codegen::build("po/app.pot", None, "messages.rs").unwrap();
+//! ```
+//!
+//! and the crate includes them in a module:
+//!
+//! ```rust,ignore
+//! mod messages {
+//!     include!(concat!(env!("OUT_DIR"), "/messages.rs"));
+//! }
+//!
+//! let message = messages::one_item_has_been_deleted(3);
+//! let message = messages::hello_name("Grace");
+//! ```
+//!
+//! A msgid that is renamed or whose placeholders change in the POT file breaks the build of the
+//! code using it. The messages with positional placeholders (`%s`) and the messages with both
+//! a context and a text domain have no constructor.
+
+use crate::extract::ExtractedMsgid;
+use crate::pot;
+use crate::validate::placeholders;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Error that occurs when the constructors cannot be generated
+#[derive(Debug, Error)]
+pub enum CodegenError {
+    /// The POT file cannot be read or the code cannot be written
+    #[error(msg_embedded, no_from, non_std)]
+    Io(String),
+    /// The POT file is not valid
+    #[error(msg_embedded, no_from, non_std)]
+    Parse(String),
+}
+
+/// Generate the constructors of a POT or PO file in `$OUT_DIR/<out>` from a build script, for
+/// a text domain (`None` for the current one). The build script runs again when the file
+/// changes.
+pub fn build<P: AsRef<Path>>(
+    pot: P,
+    domain: Option<&str>,
+    out: &str,
+) -> Result<PathBuf, CodegenError> {
+    let pot = pot.as_ref();
+    let out_dir = env::var_os("OUT_DIR")
+        .ok_or_else(|| CodegenError::Io("OUT_DIR is not set: not in a build script".to_string()))?;
+    let path = Path::new(&out_dir).join(out);
+
+    let content = fs::read_to_string(pot)
+        .map_err(|err| CodegenError::Io(format!("{}: {}", pot.display(), err)))?;
+    let msgids = pot::read(&content, domain)
+        .map_err(|err| CodegenError::Parse(format!("{}: {}", pot.display(), err)))?;
+    let mut code = Vec::new();
+    generate(&mut code, &msgids)
+        .and_then(|()| fs::write(&path, code))
+        .map_err(|err| CodegenError::Io(format!("{}: {}", path.display(), err)))?;
+    println!("cargo:rerun-if-changed={}", pot.display());
+
+    Ok(path)
+}
+
+/// Write the constructors of the msgids
+pub fn generate<W: Write>(out: &mut W, msgids: &[ExtractedMsgid]) -> io::Result<()> {
+    let mut names = BTreeSet::new();
+
+    writeln!(out, "// Generated by serde_gettext::codegen, do not edit")?;
+    for msgid in msgids {
+        writeln!(out)?;
+        let params = match Params::new(msgid) {
+            Some(params) => params,
+            None => {
+                writeln!(out, "// No constructor: {:?}", msgid.msgid)?;
+                continue;
+            }
+        };
+        let name = unique_name(&mut names, msgid);
+
+        for line in msgid.msgid.lines() {
+            writeln!(out, "/// {}", line)?;
+        }
+        let mut signature: Vec<String> = params
+            .named
+            .iter()
+            .map(|(param, _)| format!("{}: impl ::std::fmt::Display", param))
+            .collect();
+        if msgid.plural.is_some() {
+            signature.insert(0, "n: u64".to_string());
+        }
+        writeln!(
+            out,
+            "pub fn {}({}) -> ::serde_gettext::SerdeGetText {{",
+            name,
+            signature.join(", ")
+        )?;
+        write!(
+            out,
+            "    ::serde_gettext::SerdeGetText::{}",
+            params.function
+        )?;
+        let args: Vec<String> = msgid
+            .domain
+            .iter()
+            .chain(msgid.context.iter())
+            .chain(Some(&msgid.msgid))
+            .chain(msgid.plural.iter())
+            .map(|x| format!("{:?}", x))
+            .chain(msgid.plural.as_ref().map(|_| "n".to_string()))
+            .collect();
+        write!(out, "({})", args.join(", "))?;
+        for (param, key) in params.named.iter() {
+            write!(out, "\n        .arg({:?}, {})", key, param)?;
+        }
+        writeln!(out)?;
+        writeln!(out, "}}")?;
+    }
+
+    Ok(())
+}
+
+/// Constructor of the message and its parameters with the keys of their arguments
+struct Params {
+    function: &'static str,
+    named: Vec<(String, String)>,
+}
+
+impl Params {
+    /// `None` if the message has no constructor
+    fn new(msgid: &ExtractedMsgid) -> Option<Params> {
+        let function = match (
+            msgid.domain.is_some(),
+            msgid.context.is_some(),
+            msgid.plural.is_some(),
+        ) {
+            (false, false, false) => "gettext",
+            (false, false, true) => "ngettext",
+            (false, true, false) => "pgettext",
+            (false, true, true) => "npgettext",
+            (true, false, false) => "dgettext",
+            (true, false, true) => "dngettext",
+            (true, true, _) => return None,
+        };
+
+        let plural = msgid.plural.is_some();
+        let mut found = placeholders(&msgid.msgid);
+        if let Some(plural) = msgid.plural.as_deref() {
+            found.extend(placeholders(plural));
+        }
+        if found.positional > 0 {
+            return None;
+        }
+        // the count of the plural messages is the parameter `n`
+        let mut params = BTreeSet::new();
+        let named = found
+            .named
+            .into_iter()
+            .filter(|key| !(plural && key == "n"))
+            .map(|key| {
+                let mut param = identifier(&key);
+                while (plural && param == "n") || !params.insert(param.clone()) {
+                    param.push('_');
+                }
+                (param, key)
+            })
+            .collect();
+
+        Some(Params { function, named })
+    }
+}
+
+/// Name of the constructor: the first words of the context or the msgid, unique in the file
+fn unique_name(names: &mut BTreeSet<String>, msgid: &ExtractedMsgid) -> String {
+    let source = msgid.context.as_deref().unwrap_or(&msgid.msgid);
+    let words: Vec<String> = without_conversions(source)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .take(6)
+        .map(str::to_lowercase)
+        .collect();
+    let name = identifier(&words.join("_"));
+
+    let mut unique = name.clone();
+    let mut i = 1;
+    while !names.insert(unique.clone()) {
+        i += 1;
+        unique = format!("{}_{}", name, i);
+    }
+
+    unique
+}
+
+/// Text with the names of its keyword placeholders instead of the placeholders (`%(name)s`
+/// becomes `name`), without the other placeholders
+fn without_conversions(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            res.push(c);
+            continue;
+        }
+        if chars.next_if_eq(&'(').is_some() {
+            res.push(' ');
+            res.extend(chars.by_ref().take_while(|c| *c != ')'));
+            res.push(' ');
+        }
+        // flags, width, precision and length modifier until the conversion type
+        for c in chars.by_ref() {
+            if c == '%' || (c.is_ascii_alphabetic() && !matches!(c, 'h' | 'l' | 'L')) {
+                break;
+            }
+        }
+    }
+
+    res
+}
+
+/// Valid Rust identifier made of the ASCII alphanumeric characters of a string
+fn identifier(s: &str) -> String {
+    let mut res: String = s
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if res.trim_matches('_').is_empty() {
+        res = "message".to_string();
+    }
+    if res.starts_with(|c: char| c.is_ascii_digit()) {
+        res.insert(0, '_');
+    }
+    if KEYWORDS.contains(&res.as_str()) {
+        res.push('_');
+    }
+
+    res
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override",
+    "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
@@ -0,0 +1,58 @@
+use crate::locale_context::ThreadLocale;
+use std::cmp::Ordering;
+use std::ffi::CString;
+
+/// Order of strings following the collation rules of a locale (e.g. `fr_FR.UTF-8`): the indexes
+/// of the strings from the first to the last, the equal strings keep their order
+///
+/// The locale of the process (`LC_COLLATE`) is used if the locale isn't installed, and the byte
+/// order for the strings containing a nul byte.
+///
+/// ```rust
+/// let labels = ["pear", "banana", "apple"];
+/// let order = serde_gettext::collate("en_US.UTF-8", &labels);
+/// assert_eq!(order, vec![2, 1, 0]);
+/// ```
+pub fn collate(locale: &str, items: &[&str]) -> Vec<usize> {
+    let _locale = CString::new(locale)
+        .ok()
+        .and_then(|x| ThreadLocale::enter(libc::LC_COLLATE_MASK, &x));
+
+    order(items)
+}
+
+/// Sort the strings using the collation rules of a locale, or of the locale of the process
+/// (`LC_COLLATE`)
+pub(crate) fn sort(items: &mut Vec<String>, locale: Option<&str>) {
+    let order = {
+        let strs = items.iter().map(String::as_str).collect::<Vec<_>>();
+        match locale {
+            Some(locale) => collate(locale, &strs),
+            None => order(&strs),
+        }
+    };
+
+    let mut taken = items.drain(..).map(Some).collect::<Vec<_>>();
+    items.extend(order.into_iter().filter_map(|i| taken[i].take()));
+}
+
+/// Order of strings using the collation rules of the locale of the current thread
+fn order(items: &[&str]) -> Vec<usize> {
+    let keys = items
+        .iter()
+        .map(|x| CString::new(*x).ok())
+        .collect::<Vec<_>>();
+
+    let mut order = (0..items.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| match (&keys[a], &keys[b]) {
+        (Some(a_key), Some(b_key)) => strcoll(a_key, b_key),
+        // strings containing a nul byte cannot be collated
+        _ => items[a].cmp(items[b]),
+    });
+
+    order
+}
+
+fn strcoll(a: &CString, b: &CString) -> Ordering {
+    unsafe { libc::strcoll(a.as_ptr(), b.as_ptr()) }.cmp(&0)
+}
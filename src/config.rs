@@ -0,0 +1,239 @@
+//! Configuration of gettext (`textdomain`, `bindtextdomain` and `bind_textdomain_codeset`),
+//! written in the same format as the messages or built in Rust
+//!
+//!  *  Example in JSON
+//!
+//!     ```json
+//!     {
+//!         "configure": {
+//!             "textdomain": "myapp",
+//!             "bindtextdomain": {"domain": "myapp", "dir": "/usr/share/locale"},
+//!             "codeset": "UTF-8"
+//!         }
+//!     }
+//!     ```
+//!
+//!  *  Example in Rust
+//!
+//!     ```rust,no_run
+//!     serde_gettext::init()
+//!         .textdomain("myapp")
+//!         .bindtextdomain("myapp", "/usr/share/locale")
+//!         .codeset("UTF-8")
+//!         .apply()?;
+//!     # Ok::<(), serde_gettext::config::ConfigError>(())
+//!     ```
+//!
+//! `bindtextdomain` can also be a list to bind many text domains. The codeset is used by all the
+//! bound text domains and the current one.
+//!
+//! `bare_string` (`text` or `gettext`) sets the message of the documents that are a bare string
+//! instead of an object: they are rendered as is by default, or translated.
+//!
+//! `env` is the list of the environment variables that the messages can read with
+//! `{"env": "APP_NAME"}`, none by default: the messages can't read the secrets of the process.
+//!
+//! `default_args` are added to the base arguments of all the messages, like `set_default_arg`.
+
+use serde::de::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error that occurs when the configuration can't be applied
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// A text domain, a directory, a codeset or an environment variable is empty or contains a
+    /// NUL character
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidValue(String),
+}
+
+/// Document configuring gettext: `{"configure": {...}}`
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Configure {
+    /// Configuration to apply
+    pub configure: Config,
+}
+
+/// Configuration of gettext, applied with `apply`
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    textdomain: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    bindtextdomain: Vec<BindTextDomain>,
+    codeset: Option<String>,
+    bare_string: Option<BareString>,
+    #[serde(default)]
+    env: Vec<String>,
+    #[serde(default)]
+    default_args: BTreeMap<String, String>,
+}
+
+/// Message of the documents that are a bare string instead of an object
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BareString {
+    /// Rendered as is, like a value
+    #[default]
+    Text,
+    /// Translated, like `{"gettext": ...}`
+    Gettext,
+}
+
+/// Directory of the compiled catalogs of a text domain
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct BindTextDomain {
+    domain: String,
+    dir: PathBuf,
+}
+
+/// Create an empty configuration of gettext
+pub fn init() -> Config {
+    Config::default()
+}
+
+impl Config {
+    /// Set the current text domain
+    pub fn textdomain(mut self, domain: &str) -> Config {
+        self.textdomain = Some(domain.to_string());
+        self
+    }
+
+    /// Set the directory of the compiled catalogs (`<dir>/<locale>/LC_MESSAGES/<domain>.mo`) of a
+    /// text domain
+    pub fn bindtextdomain<P: AsRef<Path>>(mut self, domain: &str, dir: P) -> Config {
+        self.bindtextdomain.push(BindTextDomain {
+            domain: domain.to_string(),
+            dir: dir.as_ref().to_path_buf(),
+        });
+        self
+    }
+
+    /// Set the codeset of the translations (e.g. `UTF-8`)
+    pub fn codeset(mut self, codeset: &str) -> Config {
+        self.codeset = Some(codeset.to_string());
+        self
+    }
+
+    /// Set the message of the documents deserialized into a `SerdeGetText` that are a bare
+    /// string (rendered as is by default)
+    pub fn bare_string(mut self, mode: BareString) -> Config {
+        self.bare_string = Some(mode);
+        self
+    }
+
+    /// Allow the messages to read an environment variable with `{"env": name}` (e.g. the name
+    /// of the product or the URL of the support)
+    pub fn allow_env(mut self, name: &str) -> Config {
+        self.env.push(name.to_string());
+        self
+    }
+
+    /// Add a base argument to all the messages (`set_default_arg`)
+    pub fn default_arg(mut self, name: &str, value: impl fmt::Display) -> Config {
+        self.default_args
+            .insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Configure gettext for the whole process. Nothing is applied if a value is invalid.
+    pub fn apply(&self) -> Result<(), ConfigError> {
+        let dirs = self
+            .bindtextdomain
+            .iter()
+            .map(|x| {
+                x.dir
+                    .to_str()
+                    .map(|dir| (x.domain.as_str(), dir))
+                    .ok_or_else(|| {
+                        ConfigError::InvalidValue(format!("invalid directory: {}", x.dir.display()))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // gettext can't receive the strings containing NUL characters
+        let check = |name: &str, value: &str| {
+            if value.is_empty() || value.contains('\0') {
+                Err(ConfigError::InvalidValue(format!(
+                    "invalid {}: {:?}",
+                    name, value
+                )))
+            } else {
+                Ok(())
+            }
+        };
+        for (domain, dir) in dirs.iter() {
+            check("text domain", domain)?;
+            check("directory", dir)?;
+        }
+        if let Some(domain) = self.textdomain.as_deref() {
+            check("text domain", domain)?;
+        }
+        if let Some(codeset) = self.codeset.as_deref() {
+            check("codeset", codeset)?;
+        }
+        for name in self.env.iter() {
+            if name.contains('=') {
+                return Err(ConfigError::InvalidValue(format!(
+                    "invalid environment variable: {:?}",
+                    name
+                )));
+            }
+            check("environment variable", name)?;
+        }
+
+        for (domain, dir) in dirs.iter() {
+            gettextrs::bindtextdomain(*domain, *dir);
+        }
+        if let Some(codeset) = self.codeset.as_deref() {
+            for domain in dirs
+                .iter()
+                .map(|(domain, _)| *domain)
+                .chain(self.textdomain.as_deref())
+            {
+                gettextrs::bind_textdomain_codeset(domain, codeset);
+            }
+        }
+        if let Some(domain) = self.textdomain.as_deref() {
+            gettextrs::textdomain(domain);
+        }
+        if let Some(mode) = self.bare_string {
+            crate::shape::set_bare_string(mode);
+        }
+        if !self.env.is_empty() {
+            crate::environment::set_allowed(&self.env);
+        }
+        for (name, value) in self.default_args.iter() {
+            crate::set_default_arg(name, value);
+        }
+
+        Ok(())
+    }
+}
+
+impl Configure {
+    /// Configure gettext for the whole process
+    pub fn apply(&self) -> Result<(), ConfigError> {
+        self.configure.apply()
+    }
+}
+
+/// A single text domain or a list
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<BindTextDomain>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(BindTextDomain),
+        Many(Vec<BindTextDomain>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(x) => vec![x],
+        OneOrMany::Many(x) => x,
+    })
+}
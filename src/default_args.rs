@@ -0,0 +1,47 @@
+//! Default arguments of all the messages of the process (the name of the product, the URL of
+//! the support, ...), set at the startup of the services
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+static DEFAULT_ARGS: RwLock<Option<Arc<HashMap<String, String>>>> = RwLock::new(None);
+
+/// Add a base argument to all the messages rendered afterwards, the arguments of the messages
+/// take precedence. Setting an argument again replaces its value.
+///
+/// ```rust
+/// use serde_gettext::{set_default_arg, SerdeGetText};
+/// use std::convert::TryFrom;
+///
+/// set_default_arg("product", "Acme");
+/// let message: SerdeGetText = serde_json::from_str(r#"{"text": "Welcome to %(product)s!"}"#)?;
+/// assert_eq!(String::try_from(message)?, "Welcome to Acme!");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn set_default_arg(name: &str, value: impl fmt::Display) {
+    let mut args = DEFAULT_ARGS.write().unwrap_or_else(|err| err.into_inner());
+    // the messages being rendered keep the arguments they started with
+    let mut map = args.as_deref().cloned().unwrap_or_default();
+    map.insert(name.to_string(), value.to_string());
+    *args = Some(Arc::new(map));
+}
+
+/// Remove a default argument, returns `false` if there was none
+pub fn remove_default_arg(name: &str) -> bool {
+    let mut args = DEFAULT_ARGS.write().unwrap_or_else(|err| err.into_inner());
+    let mut map = args.as_deref().cloned().unwrap_or_default();
+    let removed = map.remove(name).is_some();
+    *args = Some(Arc::new(map));
+
+    removed
+}
+
+/// Default arguments currently set
+pub(crate) fn default_args() -> Arc<HashMap<String, String>> {
+    DEFAULT_ARGS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
@@ -0,0 +1,49 @@
+//! Text domain of the messages rendered by a closure, for the services rendering the messages
+//! of a request in the text domain of a component without mutating the global `textdomain`
+
+use std::cell::RefCell;
+
+thread_local! {
+    static DOMAIN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Render the messages of a closure in a text domain: the messages without text domain (all
+/// but `dgettext`, `dngettext` and `dcngettext`) are looked up in `domain` instead of the current
+/// one. The calls can be nested, the domain is restored when the closure returns or panics.
+///
+/// The text domain is set for the current thread only, the messages rendered by the other
+/// threads (e.g. spawned tasks) don't use it.
+///
+/// ```rust
+/// use serde_gettext::{with_domain, MockCatalog, SerdeGetText};
+/// use std::convert::TryFrom;
+/// use std::sync::Arc;
+///
+/// let mut catalog = MockCatalog::new();
+/// catalog.insert_with(Some("billing"), None, "Pay", &["Payer"]);
+/// let mut message: SerdeGetText = serde_json::from_str(r#"{"gettext": "Pay"}"#)?;
+/// message.backend = Some(Arc::new(catalog));
+///
+/// assert_eq!(with_domain("billing", || String::try_from(message.clone()))?, "Payer");
+/// assert_eq!(String::try_from(message)?, "Pay");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn with_domain<T>(domain: &str, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<String>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            DOMAIN.with(|x| *x.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(DOMAIN.with(|x| x.borrow_mut().replace(domain.to_string())));
+
+    f()
+}
+
+/// Text domain set by `with_domain` on the current thread
+pub(crate) fn current() -> Option<String> {
+    DOMAIN.with(|x| x.borrow().clone())
+}
@@ -0,0 +1,27 @@
+//! Environment variables available to the messages, only the ones allowed by the configuration
+
+use crate::Error;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+static ALLOWED: RwLock<BTreeSet<String>> = RwLock::new(BTreeSet::new());
+
+/// Replace the environment variables that the messages can read
+pub(crate) fn set_allowed(names: &[String]) {
+    let mut allowed = ALLOWED.write().unwrap_or_else(|err| err.into_inner());
+    *allowed = names.iter().cloned().collect();
+}
+
+/// Value of an environment variable, if it is allowed
+pub(crate) fn var(name: &str) -> Result<String, Error> {
+    let allowed = ALLOWED.read().unwrap_or_else(|err| err.into_inner());
+    if !allowed.contains(name) {
+        return Err(Error::EnvNotAllowed(format!(
+            "environment variable not allowed: {}",
+            name
+        )));
+    }
+
+    std::env::var(name)
+        .map_err(|_| Error::UndefinedVariable(format!("undefined environment variable: {}", name)))
+}
@@ -0,0 +1,621 @@
+//! Extraction of the msgids of the messages, like `xgettext` does for source code
+
+use crate::measure;
+use crate::{
+    ExplicitCounts, Formatter, KeywordArg, SerdeGetText, Value, ValueDCNGetText, ValueDGetText,
+    ValueDNGetText, ValueGetText, ValueNGetText, ValueNPGetText, ValuePGetText, ValueSelectPlural,
+    ValueSource,
+};
+use std::cmp::Ordering;
+#[cfg(feature = "extract")]
+use std::collections::BTreeMap;
+use std::collections::{BTreeSet, HashMap};
+#[cfg(feature = "extract")]
+use std::fs;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "extract")]
+use std::path::{Path, PathBuf};
+
+/// Message to translate found in a message payload
+///
+/// The comments and the references are not compared: the msgids used with different comments
+/// or in different places are the same entry of the catalog, with all their comments and
+/// references.
+#[derive(Clone, Debug)]
+pub struct ExtractedMsgid {
+    /// Text domain, `None` for the current one
+    pub domain: Option<String>,
+    /// Context of the message (`msgctxt`)
+    pub context: Option<String>,
+    /// Message identifier (the singular form for the plural messages)
+    pub msgid: String,
+    /// Plural form of the plural messages (`msgid_plural`)
+    pub plural: Option<String>,
+    /// Comments for the translators (`comment` or `translators` on the message)
+    pub comments: Vec<String>,
+    /// Locations of the message in the code it is generated from (`source` on the message),
+    /// `file:line` or `file`
+    pub references: Vec<String>,
+}
+
+impl ExtractedMsgid {
+    fn key(&self) -> (&Option<String>, &Option<String>, &str, &Option<String>) {
+        (&self.domain, &self.context, &self.msgid, &self.plural)
+    }
+
+    /// Add the comments and the references of another use of the msgid that it doesn't have yet
+    pub(crate) fn merge(&mut self, other: ExtractedMsgid) {
+        push_new(&mut self.comments, other.comments);
+        push_new(&mut self.references, other.references);
+    }
+}
+
+impl PartialEq for ExtractedMsgid {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for ExtractedMsgid {}
+
+impl Hash for ExtractedMsgid {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for ExtractedMsgid {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ExtractedMsgid {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl ExtractedMsgid {
+    pub(crate) fn new(msgid: &str) -> ExtractedMsgid {
+        ExtractedMsgid {
+            domain: None,
+            context: None,
+            msgid: msgid.to_string(),
+            plural: None,
+            comments: Vec::new(),
+            references: Vec::new(),
+        }
+    }
+
+    fn with_plural(msgid: &str, plural: &str) -> ExtractedMsgid {
+        ExtractedMsgid {
+            plural: Some(plural.to_string()),
+            ..ExtractedMsgid::new(msgid)
+        }
+    }
+}
+
+impl SerdeGetText {
+    /// Msgids of the message, of all its nested messages and of its variables, sorted and
+    /// without duplicates
+    pub fn extract_msgids(&self) -> Vec<ExtractedMsgid> {
+        let mut msgids = Msgids::new();
+        self.value.collect_msgids(&mut msgids);
+        for value in self.vars.values() {
+            value.collect_msgids(&mut msgids);
+        }
+
+        msgids.into_iter().collect()
+    }
+}
+
+pub(crate) type Msgids = BTreeSet<ExtractedMsgid>;
+
+pub(crate) trait CollectMsgids {
+    fn collect_msgids(&self, msgids: &mut Msgids);
+}
+
+impl CollectMsgids for Value {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        match self {
+            Value::Text(_)
+            | Value::Integer(_)
+            | Value::Float(_)
+            | Value::Datetime(_)
+            | Value::Ref { .. }
+            | Value::Env { .. }
+            | Value::LangInfo { .. }
+            | Value::Uuid { .. }
+            | Value::Bytes { .. }
+            | Value::Fragment(_) => {}
+            #[cfg(feature = "decimal")]
+            Value::Decimal { .. } => {}
+            #[cfg(feature = "phone")]
+            Value::Phone { .. } => {}
+            Value::Bool(_) => {
+                msgids.insert(ExtractedMsgid::new("yes"));
+                msgids.insert(ExtractedMsgid::new("no"));
+            }
+            Value::Unit(()) => {
+                msgids.insert(ExtractedMsgid::new("n/a"));
+            }
+            Value::Measure { unit, .. } => {
+                for unit in measure::units(unit) {
+                    msgids.insert(ExtractedMsgid {
+                        context: Some(measure::UNIT_CONTEXT.to_string()),
+                        ..ExtractedMsgid::new(unit)
+                    });
+                }
+            }
+            Value::Array(xs) | Value::Join { join: xs, .. } | Value::FirstOf { first_of: xs } => {
+                for x in xs {
+                    x.collect_msgids(msgids);
+                }
+            }
+            Value::Table { table, .. } => {
+                for x in table.iter().flatten() {
+                    x.collect_msgids(msgids);
+                }
+            }
+            Value::Filtered { value, .. } => value.collect_msgids(msgids),
+            Value::IfTranslated { if_translated } => {
+                insert(
+                    msgids,
+                    ExtractedMsgid {
+                        domain: if_translated.domain.clone(),
+                        context: if_translated.ctx.clone(),
+                        ..ExtractedMsgid::new(&if_translated.msgid)
+                    },
+                );
+                let branches = if_translated.then.iter().chain(&if_translated.otherwise);
+                for x in branches {
+                    x.collect_msgids(msgids);
+                }
+            }
+            Value::Choice { choice, args } => {
+                let cases = choice.cases.values().chain(choice.default.as_deref());
+                for x in cases {
+                    x.collect_msgids(msgids);
+                }
+                collect_args(args, &HashMap::new(), msgids);
+            }
+            Value::Address { address } => {
+                for value in address.fields() {
+                    value.collect_msgids(msgids);
+                }
+            }
+            Value::PersonName { person_name } => {
+                for value in person_name.fields() {
+                    value.collect_msgids(msgids);
+                }
+            }
+            Value::Map { map, .. } => {
+                for value in map.values() {
+                    value.collect_msgids(msgids);
+                }
+            }
+            Value::Foreach { foreach } => {
+                for item in foreach.items.iter() {
+                    for arg in item.values() {
+                        arg.collect_msgids(msgids);
+                    }
+                }
+                foreach.template.collect_msgids(msgids);
+            }
+            Value::FormattedText { args, defaults, .. } => {
+                collect_args(args, defaults, msgids);
+            }
+            Value::Noop {
+                noop,
+                args,
+                defaults,
+                comment,
+                source,
+            } => {
+                insert(
+                    msgids,
+                    ExtractedMsgid {
+                        comments: comment.iter().cloned().collect(),
+                        references: source.iter().map(|x| x.reference()).collect(),
+                        ..ExtractedMsgid::new(noop)
+                    },
+                );
+                collect_args(args, defaults, msgids);
+            }
+            Value::GetText {
+                gettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(gettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::NGetText {
+                ngettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(&**ngettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::PGetText {
+                pgettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(pgettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::DGetText {
+                dgettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(dgettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::DNGetText {
+                dngettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(&**dngettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::NPGetText {
+                npgettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(&**npgettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::DCNGetText {
+                dcngettext,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(&**dcngettext, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+            Value::SelectPlural {
+                selectplural,
+                args,
+                defaults,
+                comment,
+                source,
+                ..
+            } => {
+                collect_annotated(&**selectplural, comment, source.as_deref(), msgids);
+                collect_args(args, defaults, msgids);
+            }
+        }
+    }
+}
+
+fn collect_args(args: &Option<Formatter>, defaults: &HashMap<String, Value>, msgids: &mut Msgids) {
+    match args {
+        Some(Formatter::KeywordArgs(args)) => {
+            for arg in args.values() {
+                arg.collect_msgids(msgids);
+            }
+        }
+        Some(Formatter::PositionalArgs(args)) => {
+            for arg in args {
+                arg.collect_msgids(msgids);
+            }
+        }
+        None => {}
+    }
+
+    for value in defaults.values() {
+        value.collect_msgids(msgids);
+    }
+}
+
+impl CollectMsgids for KeywordArg {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        match self {
+            KeywordArg::Value(value) => value.collect_msgids(msgids),
+            KeywordArg::Object(map) => {
+                for arg in map.values() {
+                    arg.collect_msgids(msgids);
+                }
+            }
+        }
+    }
+}
+
+/// Insert the msgid unless it is empty: it is the header of the catalog. The comments and the
+/// references are merged into the msgid if it is already there.
+fn insert(msgids: &mut Msgids, msgid: ExtractedMsgid) {
+    if msgid.msgid.is_empty() {
+        return;
+    }
+
+    match msgids.take(&msgid) {
+        Some(mut existing) => {
+            existing.merge(msgid);
+            msgids.insert(existing);
+        }
+        None => {
+            msgids.insert(msgid);
+        }
+    }
+}
+
+/// Collect the msgids of a function with the comment and the source of the message
+fn collect_annotated<T: CollectMsgids>(
+    x: &T,
+    comment: &Option<String>,
+    source: Option<&ValueSource>,
+    msgids: &mut Msgids,
+) {
+    if comment.is_none() && source.is_none() {
+        return x.collect_msgids(msgids);
+    }
+
+    let mut annotated = Msgids::new();
+    x.collect_msgids(&mut annotated);
+    for mut msgid in annotated {
+        msgid.comments.extend(comment.iter().cloned());
+        msgid.references.extend(source.map(ValueSource::reference));
+        insert(msgids, msgid);
+    }
+}
+
+/// Append the items that the list doesn't have yet
+fn push_new(list: &mut Vec<String>, items: Vec<String>) {
+    for item in items {
+        if !list.contains(&item) {
+            list.push(item);
+        }
+    }
+}
+
+impl ValueSource {
+    fn reference(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.file, line),
+            None => self.file.clone(),
+        }
+    }
+}
+
+/// Insert the plural forms and the messages of the explicit counts
+fn insert_plural(msgids: &mut Msgids, msgid: ExtractedMsgid, counts: &ExplicitCounts) {
+    for count in [&counts.zero, &counts.one, &counts.two]
+        .iter()
+        .copied()
+        .flatten()
+    {
+        insert(
+            msgids,
+            ExtractedMsgid {
+                msgid: count.clone(),
+                plural: None,
+                ..msgid.clone()
+            },
+        );
+    }
+    insert(msgids, msgid);
+}
+
+impl CollectMsgids for ValueGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert(msgids, ExtractedMsgid::new(&self.0));
+    }
+}
+
+impl CollectMsgids for ValueNGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert_plural(
+            msgids,
+            ExtractedMsgid::with_plural(&self.singular, &self.plural),
+            &self.counts,
+        );
+    }
+}
+
+impl CollectMsgids for ValuePGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert(
+            msgids,
+            ExtractedMsgid {
+                context: Some(self.ctx.clone()),
+                ..ExtractedMsgid::new(&self.msgid)
+            },
+        );
+    }
+}
+
+impl CollectMsgids for ValueDGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert(
+            msgids,
+            ExtractedMsgid {
+                domain: Some(self.domain.clone()),
+                ..ExtractedMsgid::new(&self.msgid)
+            },
+        );
+    }
+}
+
+impl CollectMsgids for ValueDNGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert_plural(
+            msgids,
+            ExtractedMsgid {
+                domain: Some(self.domain.clone()),
+                ..ExtractedMsgid::with_plural(&self.singular, &self.plural)
+            },
+            &self.counts,
+        );
+    }
+}
+
+impl CollectMsgids for ValueNPGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert_plural(
+            msgids,
+            ExtractedMsgid {
+                context: Some(self.ctx.clone()),
+                ..ExtractedMsgid::with_plural(&self.singular, &self.plural)
+            },
+            &self.counts,
+        );
+    }
+}
+
+impl CollectMsgids for ValueDCNGetText {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        insert_plural(
+            msgids,
+            ExtractedMsgid {
+                domain: Some(self.domain.clone()),
+                ..ExtractedMsgid::with_plural(&self.singular, &self.plural)
+            },
+            &self.counts,
+        );
+    }
+}
+
+impl CollectMsgids for ValueSelectPlural {
+    fn collect_msgids(&self, msgids: &mut Msgids) {
+        for forms in self.forms.values() {
+            insert(
+                msgids,
+                ExtractedMsgid::with_plural(&forms.singular, &forms.plural),
+            );
+        }
+    }
+}
+
+/// Error that occurs when the templates cannot be read
+#[cfg(feature = "extract")]
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    /// The file or the directory cannot be read
+    #[error(msg_embedded, no_from, non_std)]
+    Io(String),
+    /// The file is not a valid template
+    #[error(msg_embedded, no_from, non_std)]
+    Parse(String),
+}
+
+/// A template file: a message, a list of messages or messages by name
+#[cfg(feature = "extract")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Template {
+    List(Vec<SerdeGetText>),
+    Message(Box<SerdeGetText>),
+    Named(BTreeMap<String, SerdeGetText>),
+}
+
+#[cfg(feature = "extract")]
+impl Template {
+    fn into_messages(self) -> Vec<SerdeGetText> {
+        match self {
+            Template::List(messages) => messages,
+            Template::Message(message) => vec![*message],
+            Template::Named(messages) => messages.into_values().collect(),
+        }
+    }
+}
+
+/// Messages of a JSON (`.json`) or YAML (`.yaml`, `.yml`) template file
+#[cfg(feature = "extract")]
+pub fn read_template(path: &Path) -> Result<Vec<SerdeGetText>, ExtractError> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| ExtractError::Io(format!("{}: {}", path.display(), err)))?;
+    let template: Template = match path.extension().and_then(|x| x.to_str()) {
+        Some("json") => serde_json::from_str(&content)
+            .map_err(|err| ExtractError::Parse(format!("{}: {}", path.display(), err)))?,
+        _ => serde_yaml::from_str(&content)
+            .map_err(|err| ExtractError::Parse(format!("{}: {}", path.display(), err)))?,
+    };
+
+    Ok(template.into_messages())
+}
+
+/// Msgids of a JSON (`.json`) or YAML (`.yaml`, `.yml`) template file, sorted and without
+/// duplicates
+#[cfg(feature = "extract")]
+pub fn extract_file(path: &Path) -> Result<Vec<ExtractedMsgid>, ExtractError> {
+    let mut msgids = Msgids::new();
+    for message in read_template(path)? {
+        for msgid in message.extract_msgids() {
+            insert(&mut msgids, msgid);
+        }
+    }
+
+    Ok(msgids.into_iter().collect())
+}
+
+/// Msgids of all the template files of a directory and its subdirectories, with the files
+/// that use them
+#[cfg(feature = "extract")]
+pub fn extract_dir(dir: &Path) -> Result<BTreeMap<ExtractedMsgid, Vec<PathBuf>>, ExtractError> {
+    let mut res: BTreeMap<ExtractedMsgid, Vec<PathBuf>> = BTreeMap::new();
+
+    for path in template_files(dir)? {
+        for msgid in extract_file(&path)? {
+            // the key keeps the comments and the references of all the files
+            let (mut key, mut paths) = match res.remove_entry(&msgid) {
+                Some(entry) => entry,
+                None => (msgid.clone(), Vec::new()),
+            };
+            key.merge(msgid);
+            paths.push(path.clone());
+            res.insert(key, paths);
+        }
+    }
+
+    Ok(res)
+}
+
+/// Template files of a directory and its subdirectories, sorted by path
+#[cfg(feature = "extract")]
+pub fn template_files(dir: &Path) -> Result<Vec<PathBuf>, ExtractError> {
+    let io_error = |err| ExtractError::Io(format!("{}: {}", dir.display(), err));
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+
+        if path.is_dir() {
+            files.extend(template_files(&path)?);
+        } else if matches!(
+            path.extension().and_then(|x| x.to_str()),
+            Some("json") | Some("yaml") | Some("yml")
+        ) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
@@ -0,0 +1,195 @@
+//! C API to render the messages from other languages (`include/serde_gettext.h`)
+//!
+//! The functions return `NULL` (or an error code) on failure, the error code and its message are
+//! then available from `sg_last_error` and `sg_last_error_message` on the same thread.
+//!
+//! ```c
+//! const char *message = "{\"gettext\": \"Hello %(name)s!\", \"args\": {\"name\": \"Grace\"}}";
+//! char *text = sg_translate(message, "fr_FR");
+//! if (text == NULL) {
+//!     fprintf(stderr, "error %d: %s\n", sg_last_error(), sg_last_error_message());
+//! } else {
+//!     puts(text);
+//!     sg_free(text);
+//! }
+//! ```
+//!
+//! The C library is built with `cargo rustc --release --features ffi --crate-type cdylib`.
+
+use crate::server::Translator;
+use crate::{Catalog, SerdeGetText};
+use libc::{c_char, c_int};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::sync::{Arc, Mutex};
+
+/// No error
+pub const SG_OK: c_int = 0;
+/// A required pointer is `NULL`
+pub const SG_NULL_POINTER: c_int = 1;
+/// A string is not valid UTF-8
+pub const SG_INVALID_UTF8: c_int = 2;
+/// The message is not valid JSON or not a valid message
+pub const SG_INVALID_MESSAGE: c_int = 3;
+/// The message cannot be rendered
+pub const SG_RENDER_ERROR: c_int = 4;
+/// The catalog is not a valid MO file
+pub const SG_INVALID_CATALOG: c_int = 5;
+/// Unexpected internal error
+pub const SG_INTERNAL_ERROR: c_int = 6;
+
+/// Catalogs loaded by locale, the catalogs of the system are used for the other locales
+static CATALOGS: Mutex<BTreeMap<String, Arc<Catalog>>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static LAST_ERROR: RefCell<(c_int, CString)> = RefCell::new((SG_OK, CString::default()));
+}
+
+struct FfiError(c_int, String);
+
+fn set_last_error(code: c_int, message: &str) {
+    // the messages can't contain NUL characters in C
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|x| *x.borrow_mut() = (code, message));
+}
+
+/// Run a function, record its error and never unwind into C
+fn guard<T>(default: T, f: impl FnOnce() -> Result<T, FfiError>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            set_last_error(SG_OK, "");
+            value
+        }
+        Ok(Err(FfiError(code, message))) => {
+            set_last_error(code, &message);
+            default
+        }
+        Err(_) => {
+            set_last_error(SG_INTERNAL_ERROR, "internal error");
+            default
+        }
+    }
+}
+
+/// String of a C string, `None` for `NULL`
+unsafe fn to_str<'a>(s: *const c_char) -> Result<Option<&'a str>, FfiError> {
+    if s.is_null() {
+        return Ok(None);
+    }
+
+    CStr::from_ptr(s)
+        .to_str()
+        .map(Some)
+        .map_err(|err| FfiError(SG_INVALID_UTF8, err.to_string()))
+}
+
+/// Render a JSON message in a locale (e.g. `fr_FR`, `NULL` for the locale of the environment)
+///
+/// Returns the rendered message, to free with `sg_free`, or `NULL` on error.
+///
+/// # Safety
+///
+/// `json` must be a valid C string and `locale` a valid C string or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn sg_translate(json: *const c_char, locale: *const c_char) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let json =
+            to_str(json)?.ok_or_else(|| FfiError(SG_NULL_POINTER, "json is NULL".to_string()))?;
+        let locale = to_str(locale)?;
+        let mut message: SerdeGetText = serde_json::from_str(json)
+            .map_err(|err| FfiError(SG_INVALID_MESSAGE, err.to_string()))?;
+
+        if let Some(locale) = locale {
+            let catalogs = CATALOGS.lock().unwrap_or_else(|err| err.into_inner());
+            let translator = catalogs
+                .iter()
+                .fold(Translator::new(), |translator, (locale, catalog)| {
+                    translator.catalog(locale, catalog.clone())
+                });
+            let (_, backend) = translator.negotiate(Some(locale));
+            message.backend = Some(backend);
+            message.locale = Some(locale.to_string());
+        }
+
+        let text =
+            String::try_from(message).map_err(|err| FfiError(SG_RENDER_ERROR, err.to_string()))?;
+        let text = CString::new(text).map_err(|_| {
+            FfiError(
+                SG_RENDER_ERROR,
+                "the message contains a NUL character".to_string(),
+            )
+        })?;
+
+        Ok(text.into_raw())
+    })
+}
+
+/// Add the messages of a compiled catalog (MO file) of a text domain (`NULL` for the messages
+/// without domain) to the catalog of a locale
+///
+/// Returns `SG_OK` or an error code.
+///
+/// # Safety
+///
+/// `locale` must be a valid C string, `domain` a valid C string or `NULL` and `data` must point
+/// to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sg_load_catalog(
+    locale: *const c_char,
+    domain: *const c_char,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    guard((), || {
+        let locale = to_str(locale)?
+            .ok_or_else(|| FfiError(SG_NULL_POINTER, "locale is NULL".to_string()))?;
+        let domain = to_str(domain)?;
+        if data.is_null() {
+            return Err(FfiError(SG_NULL_POINTER, "data is NULL".to_string()));
+        }
+        let data = slice::from_raw_parts(data, len);
+
+        let mut catalogs = CATALOGS.lock().unwrap_or_else(|err| err.into_inner());
+        let mut catalog = catalogs
+            .get(locale)
+            .map(|x| Catalog::clone(x))
+            .unwrap_or_default();
+        catalog
+            .load_mo(domain, data)
+            .map_err(|err| FfiError(SG_INVALID_CATALOG, err.to_string()))?;
+        catalogs.insert(locale.to_string(), Arc::new(catalog));
+
+        Ok(())
+    });
+
+    sg_last_error()
+}
+
+/// Free a string returned by `sg_translate`
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by `sg_translate` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sg_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Error code of the last call on this thread, `SG_OK` if it succeeded
+#[no_mangle]
+pub extern "C" fn sg_last_error() -> c_int {
+    LAST_ERROR.with(|x| x.borrow().0)
+}
+
+/// Message of the error of the last call on this thread, valid until the next call
+#[no_mangle]
+pub extern "C" fn sg_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|x| x.borrow().1.as_ptr())
+}
@@ -0,0 +1,311 @@
+use crate::Error;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Maximum width of the padding filters and of the placeholders
+pub(crate) const MAX_WIDTH: usize = 4096;
+
+/// Transformation applied on a rendered value
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Filter {
+    Upper,
+    Lower,
+    #[serde(alias = "titlecase")]
+    Title,
+    Trim,
+    Truncate {
+        len: usize,
+        #[serde(default)]
+        ellipsis: String,
+    },
+    PadLeft {
+        width: usize,
+        #[serde(default = "default_fill")]
+        fill: char,
+    },
+    PadRight {
+        width: usize,
+        #[serde(default = "default_fill")]
+        fill: char,
+    },
+    Center {
+        width: usize,
+        #[serde(default = "default_fill")]
+        fill: char,
+    },
+}
+
+fn default_fill() -> char {
+    ' '
+}
+
+/// Rounding of the numbers to a `precision` (`{"value": 3.14159, "precision": 2}`)
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Rounding {
+    /// Half away from zero (`2.5` becomes `3`, `-2.5` becomes `-3`)
+    #[default]
+    HalfUp,
+    /// Half to the even digit (`2.5` becomes `2`, `3.5` becomes `4`)
+    HalfEven,
+    /// Half toward zero (`2.5` becomes `2`, `-2.5` becomes `-2`)
+    HalfDown,
+    /// Away from zero
+    Up,
+    /// Toward zero
+    Down,
+    /// Toward positive infinity
+    Ceiling,
+    /// Toward negative infinity
+    Floor,
+}
+
+impl Filter {
+    /// Apply the filter, `locale` is the locale of the character classification (`LC_CTYPE`)
+    pub(crate) fn apply(&self, s: String, locale: Option<&str>) -> Result<String, Error> {
+        match self {
+            Filter::PadLeft { width, .. }
+            | Filter::PadRight { width, .. }
+            | Filter::Center { width, .. }
+                if *width > MAX_WIDTH =>
+            {
+                return Err(Error::LimitExceeded(format!(
+                    "padding width larger than {}: {}",
+                    MAX_WIDTH, width
+                )));
+            }
+            _ => {}
+        }
+
+        Ok(match self {
+            Filter::Upper => to_upper(&s, has_dotted_i(locale)),
+            Filter::Lower => to_lower(&s, has_dotted_i(locale)),
+            Filter::Title => to_title(&s, has_dotted_i(locale)),
+            Filter::Trim => s.trim().to_string(),
+            Filter::Truncate { len, ellipsis } => truncate(s, *len, ellipsis),
+            Filter::PadLeft { width, fill } => {
+                let missing = missing_width(&s, *width);
+                pad(&s, missing, 0, *fill)
+            }
+            Filter::PadRight { width, fill } => {
+                let missing = missing_width(&s, *width);
+                pad(&s, 0, missing, *fill)
+            }
+            Filter::Center { width, fill } => {
+                let missing = missing_width(&s, *width);
+                pad(&s, missing / 2, missing - missing / 2, *fill)
+            }
+        })
+    }
+}
+
+/// Round a number to `precision` decimals, the values that are not numbers are kept as is. The
+/// digits of the number are rounded, not its binary approximation (`2.675` becomes `2.68`).
+pub(crate) fn round(s: String, precision: usize, rounding: Rounding) -> Result<String, Error> {
+    if precision > MAX_WIDTH {
+        return Err(Error::LimitExceeded(format!(
+            "precision larger than {}: {}",
+            MAX_WIDTH, precision
+        )));
+    }
+
+    let trimmed = s.trim();
+    let negative = trimmed.starts_with('-');
+    let unsigned = trimmed.strip_prefix(['-', '+']).unwrap_or(trimmed);
+    let plain = if is_plain_number(unsigned) {
+        unsigned.to_string()
+    } else {
+        // the shortest representation of the float, never in exponent notation
+        match trimmed.parse::<f64>() {
+            Ok(x) if x.is_finite() => x.abs().to_string(),
+            _ => return Ok(s),
+        }
+    };
+
+    let (int, frac) = plain.split_once('.').unwrap_or((&plain, ""));
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+    let mut digits: Vec<u8> = int
+        .bytes()
+        .chain(frac.bytes().chain(std::iter::repeat(b'0')).take(precision))
+        .collect();
+    let rest = frac.get(precision..).unwrap_or("");
+    let first = rest.bytes().next().unwrap_or(b'0');
+    let tail = rest.bytes().skip(1).any(|b| b != b'0');
+    let inexact = first != b'0' || tail;
+    let odd = digits.last().is_some_and(|d| (d - b'0') % 2 == 1);
+
+    let increment = match rounding {
+        Rounding::HalfUp => first >= b'5',
+        Rounding::HalfEven => first > b'5' || (first == b'5' && (tail || odd)),
+        Rounding::HalfDown => first > b'5' || (first == b'5' && tail),
+        Rounding::Up => inexact,
+        Rounding::Down => false,
+        Rounding::Ceiling => inexact && !negative,
+        Rounding::Floor => inexact && negative,
+    };
+    if increment {
+        match digits.iter().rposition(|&d| d != b'9') {
+            Some(i) => {
+                digits[i] += 1;
+                digits[i + 1..].iter_mut().for_each(|d| *d = b'0');
+            }
+            None => {
+                digits.iter_mut().for_each(|d| *d = b'0');
+                digits.insert(0, b'1');
+            }
+        }
+    }
+
+    let (int, frac) = digits.split_at(digits.len() - precision);
+    let mut res = String::with_capacity(digits.len() + 2);
+    // a number rounded to zero has no sign
+    if negative && digits.iter().any(|&d| d != b'0') {
+        res.push('-');
+    }
+    res.extend(int.iter().map(|&d| d as char));
+    if precision > 0 {
+        res.push('.');
+        res.extend(frac.iter().map(|&d| d as char));
+    }
+
+    Ok(res)
+}
+
+/// Digits with an optional fractional part (`12.30`)
+fn is_plain_number(s: &str) -> bool {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    !int.is_empty()
+        && int.bytes().all(|b| b.is_ascii_digit())
+        && frac.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Cut the string on a grapheme cluster boundary so it doesn't exceed `len` grapheme clusters,
+/// including the ellipsis. The ellipsis is left out when it is longer than `len`.
+fn truncate(s: String, len: usize, ellipsis: &str) -> String {
+    if s.graphemes(true).nth(len).is_none() {
+        return s;
+    }
+
+    let ellipsis_len = ellipsis.graphemes(true).count();
+    let (keep, ellipsis) = if ellipsis_len <= len {
+        (len - ellipsis_len, ellipsis)
+    } else {
+        (len, "")
+    };
+    let end = s
+        .grapheme_indices(true)
+        .nth(keep)
+        .map(|(i, _)| i)
+        .unwrap_or(s.len());
+    let mut res = String::with_capacity(end + ellipsis.len());
+    res.push_str(&s[..end]);
+    res.push_str(ellipsis);
+
+    res
+}
+
+/// Number of grapheme clusters missing to reach `width`
+fn missing_width(s: &str, width: usize) -> usize {
+    width.saturating_sub(s.graphemes(true).count())
+}
+
+pub(crate) fn pad(s: &str, left: usize, right: usize, fill: char) -> String {
+    let mut res = String::with_capacity(s.len() + (left + right) * fill.len_utf8());
+    for _ in 0..left {
+        res.push(fill);
+    }
+    res.push_str(s);
+    for _ in 0..right {
+        res.push(fill);
+    }
+
+    res
+}
+
+/// Language of a locale (e.g. `tr` for `tr_TR.UTF-8`)
+fn language(locale: &str) -> Option<String> {
+    locale
+        .split(['_', '.', '@'])
+        .next()
+        .map(|x| x.to_lowercase())
+}
+
+/// Turkish and Azerbaijani distinguish the dotted and the dotless i in both cases
+fn has_dotted_i(locale: Option<&str>) -> bool {
+    matches!(
+        locale.and_then(language).as_deref(),
+        Some("tr") | Some("az")
+    )
+}
+
+fn to_upper(s: &str, dotted_i: bool) -> String {
+    if !dotted_i {
+        return s.to_uppercase();
+    }
+
+    let mut res = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            'i' => res.push('\u{130}'),
+            c => res.extend(c.to_uppercase()),
+        }
+    }
+
+    res
+}
+
+fn to_lower(s: &str, dotted_i: bool) -> String {
+    if !dotted_i {
+        return s.to_lowercase();
+    }
+
+    // the special cases are replaced first so the final sigma is still handled by to_lowercase
+    s.replace('I', "\u{131}")
+        .replace('\u{130}', "i")
+        .to_lowercase()
+}
+
+/// Title case mapping of the first letter of a word (it differs from the upper case mapping for
+/// the digraphs)
+fn push_title(c: char, dotted_i: bool, res: &mut String) {
+    match c {
+        'i' if dotted_i => res.push('\u{130}'),
+        '\u{1c4}'..='\u{1c6}' => res.push('\u{1c5}'),
+        '\u{1c7}'..='\u{1c9}' => res.push('\u{1c8}'),
+        '\u{1ca}'..='\u{1cc}' => res.push('\u{1cb}'),
+        '\u{1f1}'..='\u{1f3}' => res.push('\u{1f2}'),
+        c => res.extend(c.to_uppercase()),
+    }
+}
+
+/// Title case the first letter of every word and lower case the others
+fn to_title(s: &str, dotted_i: bool) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find(char::is_alphanumeric) {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len());
+        let mut chars = rest[..end].chars();
+
+        if let Some(c) = chars.next() {
+            push_title(c, dotted_i, &mut res);
+        }
+        res.push_str(&to_lower(chars.as_str(), dotted_i));
+
+        rest = &rest[end..];
+    }
+
+    res.push_str(rest);
+    res
+}
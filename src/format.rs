@@ -0,0 +1,737 @@
+//! Formatting of the printf-style placeholders (`%(name)s`, `%s`, `%05.2f`, ...), like the `%`
+//! operator of Python
+//!
+//! The arguments are rendered strings: the numeric conversions parse them back (the integers and
+//! the floats of the messages are rendered losslessly). An argument that is not a number is
+//! rendered as is by all the conversions.
+
+use crate::filters::MAX_WIDTH;
+use crate::{ControlChars, Error, EscapeMode, PercentMode, Segment};
+#[cfg(feature = "decimal")]
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+use std::mem;
+
+/// Options of the formatting of the messages
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FormatOptions {
+    /// Handling of the `%` that are not a placeholder
+    pub(crate) percent: PercentMode,
+    /// Handling of the control characters of the arguments
+    pub(crate) control: ControlChars,
+    /// Escaping of the arguments
+    pub(crate) escape: EscapeMode,
+}
+
+/// Rendered argument of a message
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Arg {
+    pub(crate) text: String,
+    /// Output of a nested message: its own arguments are already sanitized and escaped, and its
+    /// markup is kept as is
+    pub(crate) nested: bool,
+}
+
+impl Arg {
+    /// Argument given by the caller or rendered from a scalar, sanitized and escaped
+    pub(crate) fn leaf(text: String) -> Arg {
+        Arg {
+            text,
+            nested: false,
+        }
+    }
+
+    fn as_ref(&self) -> ArgRef<'_> {
+        ArgRef {
+            text: &self.text,
+            nested: self.nested,
+        }
+    }
+}
+
+/// Argument of a placeholder
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ArgRef<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) nested: bool,
+}
+
+/// Arguments of a message, `Err` if they can't be accessed this way
+pub(crate) trait FormatArgs {
+    /// Keyword argument of a mapping key (`%(name)s`)
+    fn get_key(&self, key: &str) -> Result<Option<ArgRef<'_>>, ()>;
+
+    /// Positional argument (`%s`)
+    fn get_index(&self, index: usize) -> Result<Option<ArgRef<'_>>, ()>;
+}
+
+impl FormatArgs for [Arg] {
+    fn get_key(&self, _key: &str) -> Result<Option<ArgRef<'_>>, ()> {
+        Err(())
+    }
+
+    fn get_index(&self, index: usize) -> Result<Option<ArgRef<'_>>, ()> {
+        Ok(self.get(index).map(Arg::as_ref))
+    }
+}
+
+/// Replace the placeholders of a template by the arguments at the end of a writer
+pub(crate) fn format_into<A: FormatArgs + ?Sized>(
+    out: &mut dyn fmt::Write,
+    template: &str,
+    args: &A,
+    options: FormatOptions,
+) -> Result<(), Error> {
+    let mut next = 0;
+    let mut rest = template;
+
+    while let Some(i) = rest.find('%') {
+        out.write_str(&rest[..i])?;
+        rest = &rest[i + 1..];
+        // a `%` that doesn't start a placeholder is kept as is
+        match Spec::next(rest, options.percent)? {
+            Some((spec, len)) => {
+                if !spec.write_escaped(out, args, &mut next, options)? {
+                    write!(out, "%{}", &rest[..len])?;
+                }
+                rest = &rest[len..];
+            }
+            None => out.write_char('%')?,
+        }
+    }
+    out.write_str(rest)?;
+
+    Ok(())
+}
+
+/// Replace the placeholders of a template by the arguments, the text and the formatted arguments
+/// in separate segments
+pub(crate) fn format_segments<A: FormatArgs + ?Sized>(
+    template: &str,
+    args: &A,
+    options: FormatOptions,
+) -> Result<Vec<Segment>, Error> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut next = 0;
+    let mut rest = template;
+
+    while let Some(i) = rest.find('%') {
+        text.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        match Spec::next(rest, options.percent)? {
+            Some((spec, len)) if spec.conversion != '%' => {
+                let mut value = String::new();
+                if !spec.write_escaped(&mut value, args, &mut next, options)? {
+                    text.push('%');
+                    text.push_str(&rest[..len]);
+                    rest = &rest[len..];
+                    continue;
+                }
+                if !text.is_empty() {
+                    segments.push(Segment::Text(mem::take(&mut text)));
+                }
+                segments.push(match spec.key {
+                    Some(key) => Segment::Arg {
+                        key: key.to_string(),
+                        value,
+                    },
+                    // the argument is the last positional one consumed by the placeholder
+                    None => Segment::Positional {
+                        index: next - 1,
+                        value,
+                    },
+                });
+                rest = &rest[len..];
+            }
+            Some((_, len)) => {
+                text.push('%');
+                rest = &rest[len..];
+            }
+            None => text.push('%'),
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    Ok(segments)
+}
+
+/// Replace the placeholders of a template by tokens at the end of a writer: `{name}` for a
+/// mapping key, `{0}`, `{1}`, ... for the positional arguments. The braces of the text are
+/// doubled so the tokens can be told apart.
+pub(crate) fn format_tokens(out: &mut dyn fmt::Write, template: &str) -> Result<(), Error> {
+    let escaped = |out: &mut dyn fmt::Write, text: &str| -> fmt::Result {
+        for c in text.chars() {
+            if matches!(c, '{' | '}') {
+                out.write_char(c)?;
+            }
+            out.write_char(c)?;
+        }
+        Ok(())
+    };
+    let mut next = 0;
+    let mut rest = template;
+
+    while let Some(i) = rest.find('%') {
+        escaped(out, &rest[..i])?;
+        rest = &rest[i + 1..];
+        match Spec::parse(rest) {
+            Some((spec, len)) => {
+                match (spec.conversion, spec.key) {
+                    ('%', _) => out.write_char('%')?,
+                    (_, Some(key)) => write!(out, "{{{}}}", key)?,
+                    (_, None) => {
+                        // the width and the precision `*` are positional arguments too
+                        next += [spec.width, spec.precision]
+                            .iter()
+                            .filter(|x| matches!(x, Some(Count::Next)))
+                            .count();
+                        write!(out, "{{{}}}", next)?;
+                        next += 1;
+                    }
+                }
+                rest = &rest[len..];
+            }
+            None => out.write_char('%')?,
+        }
+    }
+    escaped(out, rest)?;
+
+    Ok(())
+}
+
+/// Conversion specifier: `%`, mapping key, flags, width, precision, length modifier (ignored)
+/// and conversion type
+#[derive(Debug, Default)]
+struct Spec<'a> {
+    key: Option<&'a str>,
+    alternate: bool,
+    zero: bool,
+    left: bool,
+    sign: Option<char>,
+    width: Option<Count>,
+    precision: Option<Count>,
+    conversion: char,
+}
+
+/// Width or precision: a number or `*` (the next positional argument)
+#[derive(Debug, Clone, Copy)]
+enum Count {
+    Value(usize),
+    Next,
+}
+
+/// Formatted argument: the zeros of the padding go between the prefix and the digits
+struct Formatted<'a> {
+    prefix: Cow<'static, str>,
+    body: Cow<'a, str>,
+    numeric: bool,
+}
+
+impl<'a> Spec<'a> {
+    /// Parse the specifier following a `%` with the rules of the literal percents, with its
+    /// length. `None` if the `%` is literal.
+    fn next(s: &'a str, percent: PercentMode) -> Result<Option<(Spec<'a>, usize)>, Error> {
+        match (Spec::parse(s), percent) {
+            (None, PercentMode::Strict) => {
+                Err(format_error("unescaped %, use %% for a literal percent"))
+            }
+            // only `%%` is a literal percent, `% %` is a `%` followed by a placeholder
+            (Some((spec, len)), PercentMode::Escape) if spec.conversion == '%' && len > 1 => {
+                Ok(None)
+            }
+            (spec, _) => Ok(spec),
+        }
+    }
+
+    /// Write the argument of the placeholder, `false` if the placeholder has no argument and is
+    /// written as is (`PercentMode::Escape`): nothing is written when the formatting fails
+    fn write_escaped<A: FormatArgs + ?Sized>(
+        &self,
+        out: &mut dyn fmt::Write,
+        args: &A,
+        next: &mut usize,
+        options: FormatOptions,
+    ) -> Result<bool, Error> {
+        let start = *next;
+        match self.write(out, args, next, options) {
+            Err(Error::FormatError(_)) if options.percent == PercentMode::Escape => {
+                *next = start;
+                Ok(false)
+            }
+            res => res.map(|()| true),
+        }
+    }
+
+    /// Parse the specifier following a `%`, with its length
+    fn parse(s: &'a str) -> Option<(Spec<'a>, usize)> {
+        let mut spec = Spec::default();
+        let mut i = 0;
+        let bytes = s.as_bytes();
+
+        if bytes.first() == Some(&b'(') {
+            let len = s[1..].find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))?;
+            if len == 0 || bytes.get(1 + len) != Some(&b')') {
+                return None;
+            }
+            spec.key = Some(&s[1..1 + len]);
+            i = len + 2;
+        }
+
+        while let Some(flag) = bytes.get(i) {
+            match flag {
+                b'#' => spec.alternate = true,
+                b'0' => spec.zero = true,
+                b'-' => spec.left = true,
+                b'+' => spec.sign = Some('+'),
+                b' ' => spec.sign = spec.sign.or(Some(' ')),
+                _ => break,
+            }
+            i += 1;
+        }
+
+        let (width, len) = Count::parse(&s[i..]);
+        spec.width = width;
+        i += len;
+        if bytes.get(i) == Some(&b'.') {
+            let (precision, len) = Count::parse(&s[i + 1..]);
+            spec.precision = Some(precision.unwrap_or(Count::Value(0)));
+            i += len + 1;
+        }
+        while matches!(bytes.get(i), Some(b'h' | b'l' | b'L')) {
+            i += 1;
+        }
+
+        match bytes.get(i) {
+            Some(&c) if b"diouxXeEfFgGcrs%".contains(&c) => {
+                spec.conversion = c as char;
+                Some((spec, i + 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn write<A: FormatArgs + ?Sized>(
+        &self,
+        out: &mut dyn fmt::Write,
+        args: &A,
+        next: &mut usize,
+        options: FormatOptions,
+    ) -> Result<(), Error> {
+        if self.conversion == '%' {
+            out.write_char('%')?;
+            return Ok(());
+        }
+
+        let width = self.count(self.width, "field width", args, next)?;
+        let precision = self.count(self.precision, "precision", args, next)?;
+        let arg = match self.key {
+            Some(key) => args
+                .get_key(key)
+                .map_err(|()| format_error("format requires an argument map"))?
+                .ok_or_else(|| format_error(&format!("missing argument: {}", key)))?,
+            None => positional(args, next)?,
+        };
+
+        // the nested messages escaped their own arguments
+        let sanitized;
+        let arg = match arg.nested {
+            true => Cow::Borrowed(arg.text),
+            false => {
+                sanitized = sanitize(arg.text, options.control);
+                escape(&sanitized, options.escape)
+            }
+        };
+        let formatted = self.convert(&arg, precision);
+        let len = formatted.prefix.len() + formatted.body.chars().count();
+        let padding = width.unwrap_or(0).saturating_sub(len);
+
+        if self.left {
+            out.write_str(&formatted.prefix)?;
+            out.write_str(&formatted.body)?;
+            pad(out, ' ', padding)?;
+        } else if self.zero && formatted.numeric {
+            out.write_str(&formatted.prefix)?;
+            pad(out, '0', padding)?;
+            out.write_str(&formatted.body)?;
+        } else {
+            pad(out, ' ', padding)?;
+            out.write_str(&formatted.prefix)?;
+            out.write_str(&formatted.body)?;
+        }
+
+        Ok(())
+    }
+
+    /// Value of the width or the precision
+    fn count<A: FormatArgs + ?Sized>(
+        &self,
+        count: Option<Count>,
+        name: &str,
+        args: &A,
+        next: &mut usize,
+    ) -> Result<Option<usize>, Error> {
+        let value = match count {
+            None => return Ok(None),
+            Some(Count::Value(value)) => value,
+            Some(Count::Next) => {
+                let arg = positional(args, next)?.text;
+                arg.parse().map_err(|_| {
+                    format_error(&format!("{} is not a positive integer: {}", name, arg))
+                })?
+            }
+        };
+
+        // the placeholders can't allocate unbounded amounts of memory
+        if value > MAX_WIDTH {
+            return Err(Error::LimitExceeded(format!(
+                "{} larger than {}: {}",
+                name, MAX_WIDTH, value
+            )));
+        }
+
+        Ok(Some(value))
+    }
+
+    fn convert<'b>(&self, arg: &'b str, precision: Option<usize>) -> Formatted<'b> {
+        let text = |body: Cow<'b, str>| Formatted {
+            prefix: Cow::Borrowed(""),
+            body,
+            numeric: false,
+        };
+
+        match self.conversion {
+            'd' | 'i' | 'u' => match parse_integer(arg) {
+                Some((negative, digits)) => {
+                    let zeros = precision.unwrap_or(0).saturating_sub(digits.len());
+                    Formatted {
+                        prefix: Cow::Borrowed(self.sign(negative)),
+                        body: Cow::Owned("0".repeat(zeros) + &digits),
+                        numeric: true,
+                    }
+                }
+                None => text(Cow::Borrowed(arg)),
+            },
+            'o' | 'x' | 'X' => match arg.parse::<i128>() {
+                Ok(n) => {
+                    let (digits, prefix) = match self.conversion {
+                        'o' => (format!("{:o}", n.unsigned_abs()), "0o"),
+                        'x' => (format!("{:x}", n.unsigned_abs()), "0x"),
+                        _ => (format!("{:X}", n.unsigned_abs()), "0X"),
+                    };
+                    let sign = self.sign(n < 0);
+                    Formatted {
+                        prefix: match self.alternate {
+                            true => Cow::Owned(format!("{}{}", sign, prefix)),
+                            false => Cow::Borrowed(sign),
+                        },
+                        body: Cow::Owned(digits),
+                        numeric: true,
+                    }
+                }
+                Err(_) => text(Cow::Borrowed(arg)),
+            },
+            'e' | 'E' | 'f' | 'F' | 'g' | 'G' => {
+                // the decimals are written exactly instead of rounded through a float
+                #[cfg(feature = "decimal")]
+                if let ('f' | 'F', Some(x)) = (self.conversion, parse_decimal(arg)) {
+                    return self.decimal(x, precision.unwrap_or(6));
+                }
+
+                match arg.trim().parse::<f64>() {
+                    Ok(x) if x.is_finite() => Formatted {
+                        prefix: Cow::Borrowed(self.sign(x.is_sign_negative())),
+                        body: Cow::Owned(self.float(x.abs(), precision.unwrap_or(6))),
+                        numeric: true,
+                    },
+                    Ok(x) => {
+                        let body = match (x.is_nan(), self.conversion.is_ascii_uppercase()) {
+                            (true, false) => "nan",
+                            (true, true) => "NAN",
+                            (false, false) => "inf",
+                            (false, true) => "INF",
+                        };
+                        Formatted {
+                            prefix: Cow::Borrowed(self.sign(x.is_sign_negative() && !x.is_nan())),
+                            body: Cow::Borrowed(body),
+                            numeric: false,
+                        }
+                    }
+                    Err(_) => text(Cow::Borrowed(arg)),
+                }
+            }
+            'c' => match arg.parse::<u32>().ok().and_then(char::from_u32) {
+                Some(c) if arg.chars().count() != 1 => text(Cow::Owned(c.to_string())),
+                _ => text(Cow::Borrowed(arg)),
+            },
+            // `s` and `r`
+            _ => match precision {
+                Some(precision) => match arg.char_indices().nth(precision) {
+                    Some((i, _)) => text(Cow::Borrowed(&arg[..i])),
+                    None => text(Cow::Borrowed(arg)),
+                },
+                None => text(Cow::Borrowed(arg)),
+            },
+        }
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        match (negative, self.sign) {
+            (true, _) => "-",
+            (false, Some('+')) => "+",
+            (false, Some(_)) => " ",
+            (false, None) => "",
+        }
+    }
+
+    /// Format a decimal exactly, rounded half away from zero like the amounts of money
+    #[cfg(feature = "decimal")]
+    fn decimal<'b>(&self, x: Decimal, precision: usize) -> Formatted<'b> {
+        let scale = precision.min(Decimal::MAX_SCALE as usize) as u32;
+        let rounded = x.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+        let mut body = format!("{:.*}", precision, rounded.abs());
+        if self.alternate && precision == 0 {
+            body.push('.');
+        }
+
+        Formatted {
+            prefix: Cow::Borrowed(self.sign(x.is_sign_negative())),
+            body: Cow::Owned(body),
+            numeric: true,
+        }
+    }
+
+    /// Format a positive float
+    fn float(&self, x: f64, precision: usize) -> String {
+        let upper = self.conversion.is_ascii_uppercase();
+
+        match self.conversion {
+            'e' | 'E' => exponent(x, precision, self.alternate, upper),
+            'f' | 'F' => {
+                let mut res = format!("{:.*}", precision, x);
+                if self.alternate && precision == 0 {
+                    res.push('.');
+                }
+                res
+            }
+            _ => {
+                // the exponent after rounding to the significant digits decides the notation
+                let precision = precision.max(1);
+                let rounded = format!("{:.*e}", precision - 1, x);
+                let exp: i64 = rounded
+                    .split('e')
+                    .nth(1)
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or(0);
+                let res = if exp < -4 || exp >= precision as i64 {
+                    exponent(x, precision - 1, self.alternate, upper)
+                } else {
+                    let decimals = (precision as i64 - 1 - exp) as usize;
+                    let mut res = format!("{:.*}", decimals, x);
+                    if self.alternate && decimals == 0 {
+                        res.push('.');
+                    }
+                    res
+                };
+
+                if self.alternate {
+                    res
+                } else {
+                    strip_zeros(&res)
+                }
+            }
+        }
+    }
+}
+
+impl Count {
+    fn parse(s: &str) -> (Option<Count>, usize) {
+        if s.starts_with('*') {
+            return (Some(Count::Next), 1);
+        }
+
+        let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        match len {
+            0 => (None, 0),
+            // too big anyway
+            len => (
+                Some(Count::Value(s[..len].parse().unwrap_or(usize::MAX))),
+                len,
+            ),
+        }
+    }
+}
+
+/// Strip or escape the control characters and the bidi controls of an argument
+fn sanitize(arg: &str, control: ControlChars) -> Cow<'_, str> {
+    let is_control = |c: char| {
+        c.is_control()
+            || matches!(c, '\u{061c}' | '\u{200e}' | '\u{200f}')
+            || ('\u{202a}'..='\u{202e}').contains(&c)
+            || ('\u{2066}'..='\u{2069}').contains(&c)
+    };
+
+    if control == ControlChars::Keep || !arg.contains(is_control) {
+        return Cow::Borrowed(arg);
+    }
+
+    let mut sanitized = String::with_capacity(arg.len());
+    for c in arg.chars() {
+        match control {
+            _ if !is_control(c) => sanitized.push(c),
+            ControlChars::Escape => sanitized.extend(c.escape_unicode()),
+            _ => {}
+        }
+    }
+
+    Cow::Owned(sanitized)
+}
+
+/// Escape an argument for an HTML document, a URL, a POSIX shell or a Markdown text
+fn escape(arg: &str, mode: EscapeMode) -> Cow<'_, str> {
+    match mode {
+        EscapeMode::None => Cow::Borrowed(arg),
+        EscapeMode::Html if !arg.contains(['&', '<', '>', '"', '\'']) => Cow::Borrowed(arg),
+        EscapeMode::Html => {
+            let mut escaped = String::with_capacity(arg.len() + 8);
+            for c in arg.chars() {
+                match c {
+                    '&' => escaped.push_str("&amp;"),
+                    '<' => escaped.push_str("&lt;"),
+                    '>' => escaped.push_str("&gt;"),
+                    '"' => escaped.push_str("&quot;"),
+                    '\'' => escaped.push_str("&#39;"),
+                    c => escaped.push(c),
+                }
+            }
+            Cow::Owned(escaped)
+        }
+        // the unreserved characters of RFC 3986 are kept, the argument can be a path segment or
+        // the value of a query parameter
+        EscapeMode::Url => {
+            let unreserved = |b: u8| b.is_ascii_alphanumeric() || b"-._~".contains(&b);
+            if arg.bytes().all(unreserved) {
+                return Cow::Borrowed(arg);
+            }
+            let mut escaped = String::with_capacity(arg.len() * 3);
+            for b in arg.bytes() {
+                if unreserved(b) {
+                    escaped.push(b as char);
+                } else {
+                    let _ = write!(escaped, "%{:02X}", b);
+                }
+            }
+            Cow::Owned(escaped)
+        }
+        EscapeMode::Markdown => {
+            let meta = |c: char| "\\`*_{}[]<>()#+-.!|~".contains(c);
+            if !arg.contains(meta) {
+                return Cow::Borrowed(arg);
+            }
+            let mut escaped = String::with_capacity(arg.len() * 2);
+            for c in arg.chars() {
+                if meta(c) {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            Cow::Owned(escaped)
+        }
+        // quoted like `shlex.quote` of Python: the words of safe characters are kept as is
+        EscapeMode::Shell => {
+            let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c);
+            if !arg.is_empty() && arg.chars().all(safe) {
+                return Cow::Borrowed(arg);
+            }
+            Cow::Owned(format!("'{}'", arg.replace('\'', "'\"'\"'")))
+        }
+    }
+}
+
+fn pad(out: &mut dyn fmt::Write, fill: char, width: usize) -> fmt::Result {
+    (0..width).try_for_each(|_| out.write_char(fill))
+}
+
+fn positional<'b, A: FormatArgs + ?Sized>(
+    args: &'b A,
+    next: &mut usize,
+) -> Result<ArgRef<'b>, Error> {
+    let index = *next;
+    *next += 1;
+
+    args.get_index(index)
+        .map_err(|()| format_error("format requires an argument list"))?
+        .ok_or_else(|| format_error(&format!("missing argument: {}", index)))
+}
+
+/// Sign and digits of an integer argument of any length, the floats are truncated
+fn parse_integer(arg: &str) -> Option<(bool, Cow<'_, str>)> {
+    let (negative, digits) = match arg.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, arg.strip_prefix('+').unwrap_or(arg)),
+    };
+
+    // the big numbers are given as strings, they are written exactly
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        let digits = digits.trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+        return Some((negative && digits != "0", Cow::Borrowed(digits)));
+    }
+
+    arg.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|x| x.is_finite() && x.abs() < i128::MAX as f64)
+        .map(|x| x.trunc() as i128)
+        .map(|n| (n < 0, Cow::Owned(n.unsigned_abs().to_string())))
+}
+
+/// Decimal of an argument written in plain notation (`-12.30`), `None` beyond the 28 digits
+/// of `Decimal`
+#[cfg(feature = "decimal")]
+fn parse_decimal(arg: &str) -> Option<Decimal> {
+    let digits = arg.strip_prefix(|c| c == '-' || c == '+').unwrap_or(arg);
+    if !digits.bytes().any(|b| b.is_ascii_digit())
+        || !digits.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+    {
+        return None;
+    }
+
+    arg.parse().ok()
+}
+
+/// Exponent notation with a sign and at least 2 digits in the exponent (e.g. `1.5e+03`)
+fn exponent(x: f64, precision: usize, alternate: bool, upper: bool) -> String {
+    let formatted = format!("{:.*e}", precision, x);
+    let (mantissa, exp) = formatted.split_at(formatted.find('e').unwrap_or(formatted.len()));
+    let exp: i64 = exp.get(1..).and_then(|x| x.parse().ok()).unwrap_or(0);
+
+    format!(
+        "{}{}{}{}{:02}",
+        mantissa,
+        if alternate && precision == 0 { "." } else { "" },
+        if upper { 'E' } else { 'e' },
+        if exp < 0 { '-' } else { '+' },
+        exp.abs()
+    )
+}
+
+/// Remove the trailing zeros of the fractional part (and the point if nothing is left)
+fn strip_zeros(s: &str) -> String {
+    let (mantissa, exp) = s.split_at(s.find(['e', 'E']).unwrap_or(s.len()));
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+
+    format!("{}{}", mantissa, exp)
+}
+
+fn format_error(message: &str) -> Error {
+    Error::FormatError(message.to_string())
+}
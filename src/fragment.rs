@@ -0,0 +1,173 @@
+//! Values of user-defined types in the arguments of the messages
+
+use crate::{Context, Error, Lookup, Value};
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+/// Value of a user-defined type rendered in a message, anywhere a value is accepted (`args`,
+/// `defaults`, `vars`, the items of `join`, ...)
+///
+/// The types are registered with `register_fragment` under the name of a field: the objects
+/// with that field and that are not a value of serde-gettext are deserialized into that type.
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use serde_gettext::{register_fragment, Error, FragmentContext, RenderFragment, SerdeGetText};
+/// use std::convert::TryFrom;
+/// use std::fmt::Write;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Money {
+///     money: i64,
+///     currency: String,
+/// }
+///
+/// impl RenderFragment for Money {
+///     fn render(&self, out: &mut dyn Write, _ctx: &FragmentContext) -> Result<(), Error> {
+///         write!(out, "{}.{:02} {}", self.money / 100, self.money % 100, self.currency)?;
+///         Ok(())
+///     }
+/// }
+///
+/// register_fragment::<Money>("money");
+///
+/// let message: SerdeGetText = serde_json::from_str(r#"{
+///     "text": "Total: %(total)s",
+///     "args": {"total": {"money": 1250, "currency": "EUR"}}
+/// }"#)?;
+/// assert_eq!(String::try_from(message)?, "Total: 12.50 EUR");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// The rendering must depend only on the value and on the context: the messages are cached by
+/// `RenderCache` like the others.
+pub trait RenderFragment: fmt::Debug + Send + Sync {
+    /// Render the value at the end of a writer
+    fn render(&self, out: &mut dyn fmt::Write, ctx: &FragmentContext) -> Result<(), Error>;
+}
+
+/// Options of the message rendering a fragment
+pub struct FragmentContext<'a> {
+    ctx: &'a Context<'a>,
+}
+
+impl FragmentContext<'_> {
+    /// Translate a message of the current text domain with the backend of the message (the
+    /// observer is not notified of the missing translations)
+    pub fn gettext(&self, msgid: &str) -> Result<String, Error> {
+        Value::translate_word(msgid, self.ctx)
+    }
+
+    /// Translate a message of the current text domain, `fallback` is used instead of the msgid
+    /// when there is no translation
+    pub fn gettext_or(&self, msgid: &str, fallback: &str) -> Result<String, Error> {
+        let translated = self
+            .ctx
+            .decode(msgid, self.ctx.lookup(&Lookup::new(msgid)))?;
+
+        Ok(self
+            .ctx
+            .localized(translated.unwrap_or_else(|| fallback.to_string())))
+    }
+
+    /// Locale of a category (e.g. `LC_NUMERIC`): the locale of the message if there is one, the
+    /// locale of the environment otherwise
+    pub fn locale(&self, category: &str) -> Option<String> {
+        self.ctx.locale(category)
+    }
+
+    /// Keyword argument visible by the value: the arguments of the messages it is nested in,
+    /// then the base arguments
+    pub fn arg(&self, key: &str) -> Option<&str> {
+        self.ctx.scope.get(key).map(|x| x.text)
+    }
+}
+
+type Constructor = fn(serde_value::Value) -> Result<Arc<dyn RenderFragment>, String>;
+
+static REGISTRY: RwLock<BTreeMap<String, Constructor>> = RwLock::new(BTreeMap::new());
+
+/// Deserialize the objects having the field `name` into `T` in the messages deserialized
+/// afterwards. Registering another type under the same name replaces it.
+pub fn register_fragment<T: RenderFragment + DeserializeOwned + 'static>(name: &str) {
+    fn construct<T: RenderFragment + DeserializeOwned + 'static>(
+        value: serde_value::Value,
+    ) -> Result<Arc<dyn RenderFragment>, String> {
+        match value.deserialize_into::<T>() {
+            Ok(x) => Ok(Arc::new(x)),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    REGISTRY
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(name.to_string(), construct::<T>);
+}
+
+/// Remove the type registered under the name of a field, returns `false` if there was none
+pub fn unregister_fragment(name: &str) -> bool {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .remove(name)
+        .is_some()
+}
+
+/// Check if a type is registered under the name of a field
+pub(crate) fn is_registered(name: &str) -> bool {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .contains_key(name)
+}
+
+/// Value of a registered type, with the data it was deserialized from
+#[derive(Clone)]
+pub(crate) struct Fragment {
+    raw: serde_value::Value,
+    value: Arc<dyn RenderFragment>,
+}
+
+impl Fragment {
+    pub(crate) fn write_to(&self, out: &mut dyn fmt::Write, ctx: &Context) -> Result<(), Error> {
+        self.value.render(out, &FragmentContext { ctx })
+    }
+}
+
+impl fmt::Debug for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl Hash for Fragment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl<'de> Deserialize<'de> for Fragment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Fragment, D::Error> {
+        let raw = serde_value::Value::deserialize(deserializer)?;
+        let constructor = match &raw {
+            serde_value::Value::Map(map) => {
+                let registry = REGISTRY.read().unwrap_or_else(|err| err.into_inner());
+                map.keys()
+                    .find_map(|key| match key {
+                        serde_value::Value::String(key) => registry.get(key).copied(),
+                        _ => None,
+                    })
+                    .ok_or_else(|| de::Error::custom("no registered fragment"))?
+            }
+            _ => return Err(de::Error::custom("a fragment is an object")),
+        };
+        let value = constructor(raw.clone()).map_err(de::Error::custom)?;
+
+        Ok(Fragment { raw, value })
+    }
+}
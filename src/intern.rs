@@ -0,0 +1,150 @@
+//! Interning of the msgids and of their translations, for the services translating the same
+//! strings over and over
+
+use crate::{Backend, Lookup};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Msgids and translations shared by all the messages rendered with it
+///
+/// Every string (text domain, context, msgid, plural form and translation) is allocated once
+/// and the translations are asked to the backend only the first time: a batch of messages
+/// repeating the same strings only hashes them. Like `RenderCache`, it can be shared by all the
+/// locales (the translations are kept by locale) and the backends. It grows with the number of
+/// distinct msgids: `clear` it when the catalogs are reloaded.
+///
+/// ```rust
+/// use serde_gettext::{Interner, MockCatalog, SerdeGetText};
+/// use std::convert::TryFrom;
+/// use std::sync::Arc;
+///
+/// let mut catalog = MockCatalog::new();
+/// catalog.insert("Hello!", "Bonjour !");
+/// let catalog = Arc::new(catalog);
+/// let interner = Arc::new(Interner::new());
+///
+/// for _ in 0..3 {
+///     let mut message: SerdeGetText = serde_json::from_str(r#"{"gettext": "Hello!"}"#)?;
+///     message.backend = Some(catalog.clone());
+///     message.interner = Some(interner.clone());
+///     assert_eq!(String::try_from(message)?, "Bonjour !");
+/// }
+/// assert_eq!(interner.len(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Interner {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    strings: HashSet<Arc<str>>,
+    translations: HashMap<Key, Option<Translation>>,
+    /// Kept alive so their addresses can't identify another backend while the interner exists
+    backends: HashMap<usize, Arc<dyn Backend + Send + Sync>>,
+}
+
+/// Translation, `Err` if it was not valid UTF-8 (decoded lossily)
+type Translation = Result<Arc<str>, Arc<str>>;
+
+/// Backend, text domain, context, msgid, plural form and count, locale category and locale of a
+/// lookup
+type Key = (
+    usize,
+    Option<Arc<str>>,
+    Option<Arc<str>>,
+    Arc<str>,
+    Option<(Arc<str>, u64)>,
+    Option<i32>,
+    Option<Arc<str>>,
+);
+
+impl Interner {
+    /// Create an empty interner
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Number of translations interned
+    pub fn len(&self) -> usize {
+        self.lock().translations.len()
+    }
+
+    /// Check if the interner is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the strings, e.g. when the catalogs have been reloaded
+    pub fn clear(&self) {
+        *self.lock() = State::default();
+    }
+
+    /// Keep a backend alive while its translations are interned
+    pub(crate) fn retain(&self, backend: &Arc<dyn Backend + Send + Sync>) {
+        self.lock()
+            .backends
+            .entry(backend_id(backend.as_ref()))
+            .or_insert_with(|| backend.clone());
+    }
+
+    /// Translation given by a backend, looked up only the first time
+    pub(crate) fn translate(
+        &self,
+        backend: &dyn Backend,
+        lookup: &Lookup,
+    ) -> Option<Result<String, String>> {
+        let key = {
+            let mut state = self.lock();
+            let key = (
+                backend_id(backend),
+                lookup.domain.map(|x| state.intern(x)),
+                lookup.context.map(|x| state.intern(x)),
+                state.intern(lookup.msgid),
+                lookup.plural.map(|(plural, n)| (state.intern(plural), n)),
+                lookup.category.map(|x| x as i32),
+                lookup.locale.map(|x| state.intern(x)),
+            );
+            if let Some(translated) = state.translations.get(&key) {
+                return translated.as_ref().map(|x| {
+                    x.as_ref()
+                        .map(ToString::to_string)
+                        .map_err(ToString::to_string)
+                });
+            }
+            key
+        };
+
+        // the lock is not held while the backend looks up the translation
+        let translated = backend.translate_checked(lookup);
+        let mut state = self.lock();
+        let interned = translated.as_ref().map(|x| match x {
+            Ok(x) => Ok(state.intern(x)),
+            Err(x) => Err(state.intern(x)),
+        });
+        state.translations.insert(key, interned);
+
+        translated
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // the state is always consistent, even if a thread panicked while holding it
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl State {
+    fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(x) = self.strings.get(s) {
+            return x.clone();
+        }
+        let x: Arc<str> = Arc::from(s);
+        self.strings.insert(x.clone());
+        x
+    }
+}
+
+fn backend_id(backend: &dyn Backend) -> usize {
+    backend as *const dyn Backend as *const () as usize
+}
@@ -0,0 +1,76 @@
+//! Handler for [AWS Lambda](https://docs.rs/lambda_runtime/): the API Gateway events (or the
+//! messages themselves when the function is invoked directly) are rendered with the catalog of
+//! the locale negotiated from `Accept-Language`
+
+use crate::server::{TranslateRequest, TranslateResult, Translator};
+use ::lambda_runtime::{Error, LambdaEvent};
+use serde_json::{json, Value};
+
+/// Handler rendering the messages of the events with the catalogs of the system
+///
+/// ```rust,no_run
+/// use serde_gettext::lambda::translate_handler;
+///
+/// # async fn run() -> Result<(), lambda_runtime::Error> {
+/// lambda_runtime::run(lambda_runtime::service_fn(translate_handler)).await
+/// # }
+/// ```
+pub async fn translate_handler(event: LambdaEvent<Value>) -> Result<Value, Error> {
+    Ok(handle_event(&Translator::default(), event.payload))
+}
+
+/// Render the messages of an event with the catalogs of a translator
+///
+/// An API Gateway proxy event (with a `body`) gets a proxy response with the status code and the
+/// JSON body of [`Translator::handle`]. Any other payload is a message (or an array of messages)
+/// and gets the results directly, or `{"error": "..."}` if it is not a message.
+pub fn handle_event(translator: &Translator, event: Value) -> Value {
+    if event.get("body").is_some() {
+        return proxy_response(translator, &event);
+    }
+
+    match serde_json::from_value::<TranslateRequest>(event) {
+        Ok(request) => to_value(&translator.translate(request, None)),
+        Err(err) => to_value(&TranslateResult::Error(err.to_string())),
+    }
+}
+
+fn proxy_response(translator: &Translator, event: &Value) -> Value {
+    // the header names are lower case in the payloads 2.0 but not in the payloads 1.0
+    let accept_language = event
+        .get("headers")
+        .and_then(Value::as_object)
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("accept-language"))
+        })
+        .and_then(|(_, value)| value.as_str());
+    let body = event.get("body").and_then(Value::as_str).unwrap_or("");
+
+    let (status, body) = if event.get("isBase64Encoded").and_then(Value::as_bool) == Some(true) {
+        (
+            400,
+            to_json(&TranslateResult::Error(
+                "base64 encoded bodies are not supported".to_string(),
+            )),
+        )
+    } else {
+        translator.handle(body.as_bytes(), accept_language)
+    };
+
+    json!({
+        "statusCode": status,
+        "headers": { "Content-Type": "application/json" },
+        "isBase64Encoded": false,
+        "body": body,
+    })
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
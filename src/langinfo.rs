@@ -0,0 +1,125 @@
+//! Facts about the locale of the process (`nl_langinfo`): currency symbol, names of the days and
+//! of the months, ...
+
+use crate::Error;
+use std::ffi::CStr;
+
+const DAYS: [libc::nl_item; 7] = [
+    libc::DAY_1,
+    libc::DAY_2,
+    libc::DAY_3,
+    libc::DAY_4,
+    libc::DAY_5,
+    libc::DAY_6,
+    libc::DAY_7,
+];
+
+const ABBREVIATED_DAYS: [libc::nl_item; 7] = [
+    libc::ABDAY_1,
+    libc::ABDAY_2,
+    libc::ABDAY_3,
+    libc::ABDAY_4,
+    libc::ABDAY_5,
+    libc::ABDAY_6,
+    libc::ABDAY_7,
+];
+
+const MONTHS: [libc::nl_item; 12] = [
+    libc::MON_1,
+    libc::MON_2,
+    libc::MON_3,
+    libc::MON_4,
+    libc::MON_5,
+    libc::MON_6,
+    libc::MON_7,
+    libc::MON_8,
+    libc::MON_9,
+    libc::MON_10,
+    libc::MON_11,
+    libc::MON_12,
+];
+
+const ABBREVIATED_MONTHS: [libc::nl_item; 12] = [
+    libc::ABMON_1,
+    libc::ABMON_2,
+    libc::ABMON_3,
+    libc::ABMON_4,
+    libc::ABMON_5,
+    libc::ABMON_6,
+    libc::ABMON_7,
+    libc::ABMON_8,
+    libc::ABMON_9,
+    libc::ABMON_10,
+    libc::ABMON_11,
+    libc::ABMON_12,
+];
+
+/// Fact about the locale, the days and the months are selected by their number (`index`)
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LangInfoItem {
+    /// Decimal separator (`LC_NUMERIC`), `radixchar` like the name of the item in C
+    #[serde(alias = "radixchar")]
+    RadixChar,
+    /// Thousands separator (`LC_NUMERIC`)
+    ThousandsSep,
+    /// Symbol of the local currency, without its position (`LC_MONETARY`)
+    CurrencySymbol,
+    /// Name of a day of the week, from 1 (Sunday) to 7 (`LC_TIME`)
+    Day,
+    /// Abbreviated name of a day of the week (`LC_TIME`)
+    AbbreviatedDay,
+    /// Name of a month, from 1 (January) to 12 (`LC_TIME`)
+    Month,
+    /// Abbreviated name of a month (`LC_TIME`)
+    AbbreviatedMonth,
+    /// `strftime` format of the dates (`LC_TIME`)
+    DateFormat,
+    /// `strftime` format of the times (`LC_TIME`)
+    TimeFormat,
+    /// `strftime` format of the dates and times (`LC_TIME`)
+    DateTimeFormat,
+}
+
+/// Value of a fact about the locale of the process, `index` selects the day or the month
+pub(crate) fn langinfo(item: LangInfoItem, index: Option<usize>) -> Result<String, Error> {
+    let select = |items: &[libc::nl_item]| {
+        index
+            .and_then(|x| x.checked_sub(1))
+            .and_then(|x| items.get(x).copied())
+            .ok_or_else(|| {
+                Error::FormatError(format!(
+                    "langinfo index must be between 1 and {}",
+                    items.len()
+                ))
+            })
+    };
+
+    let nl_item = match item {
+        LangInfoItem::RadixChar => libc::RADIXCHAR,
+        LangInfoItem::ThousandsSep => libc::THOUSEP,
+        LangInfoItem::CurrencySymbol => libc::CRNCYSTR,
+        LangInfoItem::Day => select(&DAYS)?,
+        LangInfoItem::AbbreviatedDay => select(&ABBREVIATED_DAYS)?,
+        LangInfoItem::Month => select(&MONTHS)?,
+        LangInfoItem::AbbreviatedMonth => select(&ABBREVIATED_MONTHS)?,
+        LangInfoItem::DateFormat => libc::D_FMT,
+        LangInfoItem::TimeFormat => libc::T_FMT,
+        LangInfoItem::DateTimeFormat => libc::D_T_FMT,
+    };
+
+    let value = unsafe { CStr::from_ptr(libc::nl_langinfo(nl_item)) }
+        .to_string_lossy()
+        .into_owned();
+
+    Ok(match item {
+        // the first character tells where the symbol goes: before (`-`), after (`+`) or
+        // instead of the radix character (`.`)
+        LangInfoItem::CurrencySymbol => value
+            .strip_prefix(|c| matches!(c, '-' | '+' | '.'))
+            .unwrap_or(&value)
+            .to_string(),
+        _ => value,
+    })
+}
@@ -47,6 +47,42 @@
 //! assert_eq!(String::try_from(s).unwrap(), "5 items have been deleted");
 //! ```
 //!
+//! The strings of a whole screen can be rendered at once: a `TranslatedMap` is
+//! deserialized from an object of messages by key and converted to a
+//! `HashMap<String, String>` with the same keys:
+//!
+//! ```yaml
+//! title:
+//!     gettext: Settings
+//! body:
+//!     gettext: Choose the language of the application
+//! ```
+//!
+//! Likewise a `TranslatedList` is deserialized from an array of messages and
+//! converted to a `Vec<String>` with one string per message (the arrays of values in
+//! the arguments of a message are joined by their separator instead).
+//!
+//! The messages can also be written in TOML, for example in configuration files.
+//! TOML has no null: `{ none = true }` is the unit value (rendered as "n/a"), and
+//! the counts of the plural functions can be written as floats (`n = 5.0`):
+//!
+//! ```toml
+//! text = "%s %s"
+//! args = [true, { none = true }]
+//! ```
+//!
+//! The configuration of gettext can be written in the same format as the messages and
+//! applied with `config::Configure::apply`, or built in Rust with `init()`:
+//!
+//! ```yaml
+//! configure:
+//!     textdomain: myapp
+//!     bindtextdomain:
+//!         domain: myapp
+//!         dir: /usr/share/locale
+//!     codeset: UTF-8
+//! ```
+//!
 //! Formatting
 //! ==========
 //!
@@ -82,6 +118,54 @@
 //!
 //! Output: "yes 3.14 n/a"
 //!
+//! The placeholders accept the flags, width, precision and conversion types of the
+//! `%` operator of Python (e.g. `%05d`, `%-10s`, `%.2f`, `%#x`, `%e`, `%g`). The
+//! numeric conversions parse the rendered argument, the arguments that are not
+//! numbers (e.g. translated booleans) are rendered as is. The width and the
+//! precision are limited to 4096 (`Error::LimitExceeded`):
+//!
+//! ```yaml
+//! text: "%(name)-8s %(score)6.2f"
+//! args:
+//!     name: Grace
+//!     score: 9.5
+//! ```
+//!
+//! Output: "Grace      9.50"
+//!
+//! The integers are kept exactly up to the range of `u64` and `i128` (IDs, amounts in
+//! cents, ...). The bigger numbers can be given as strings: `%d` writes their digits
+//! as they are instead of rounding them through a float.
+//!
+//! The UUIDs are rendered in their canonical form (lower case, hyphenated) with
+//! `uuid`, from a string in any form or from their 16 bytes. The bytes (the binary data
+//! of MessagePack or CBOR, or an array of integers with `bytes`) are rendered in
+//! hexadecimal, or in base64 with `encoding` set to `base64` or `base64_url`:
+//!
+//! ```yaml
+//! text: "Order %(id)s, signature %(signature)s"
+//! args:
+//!     id:
+//!         uuid: "{67E55044-10B1-426F-9247-BB680E5FE0C8}"
+//!     signature:
+//!         bytes: [222, 173, 190, 239]
+//!         encoding: base64
+//! ```
+//!
+//! Output: "Order 67e55044-10b1-426f-9247-bb680e5fe0c8, signature 3q2+7w=="
+//!
+//! `%%` is a literal percent. The other `%` that don't start a placeholder are kept
+//! as is (`100%!`), which `percent` on the message changes:
+//! `PercentMode::Strict` rejects them, and `PercentMode::Escape` also keeps the
+//! placeholders without argument as is, so the text of the users rendered as a
+//! message (`50%d off`) never breaks the formatting.
+//!
+//! The arguments are substituted as they are, control characters included: with
+//! `control` set to `ControlChars::Strip` (or `ControlChars::Escape`, `\u{1b}`),
+//! the control characters and the Unicode bidi controls of the arguments are
+//! removed, and the arguments of the users can't forge the lines of a log or the
+//! sequences of a terminal.
+//!
 //! `args` can be added to any function:
 //!
 //! ```yaml
@@ -109,6 +193,54 @@
 //!
 //! Output: "yes, 3.14, n/a"
 //!
+//! The items can be sorted before being joined by using the `join` form. With
+//! `collate` the collation rules of the locale of the message (`LC_COLLATE`) are
+//! used instead of the byte order:
+//!
+//! ```yaml
+//! gettext: "%(fruits)s"
+//! args:
+//!     fruits:
+//!         join:
+//!             - ", "
+//!             - Pear
+//!             - Éclair
+//!             - Apple
+//!         sort: true
+//!         collate: true
+//! ```
+//!
+//! Output: "Apple, Éclair, Pear"
+//!
+//! The separator can also be named with the object form of `join`, which leaves no
+//! doubt when the first item is data:
+//!
+//! ```yaml
+//! join:
+//!     sep: " / "
+//!     items:
+//!         - Home
+//!         - Settings
+//! ```
+//!
+//! Output: "Home / Settings"
+//!
+//! The lists built from user data can leave out the blank items with `skip_empty`
+//! and the repeated ones with `unique` (the first one is kept):
+//!
+//! ```yaml
+//! join:
+//!     sep: ", "
+//!     items: [a, "", a, b]
+//! unique: true
+//! skip_empty: true
+//! ```
+//!
+//! Output: "a, b"
+//!
+//! The labels sorted by the application itself can use the same rules with
+//! `collate(locale, &labels)`, which returns the indexes of the labels in order.
+//!
 //! `args` is recursive and can handle gettext functions:
 //!
 //! ```yaml
@@ -125,6 +257,401 @@
 //!
 //! Output: "Last operation status: 1 element deleted (success: yes)"
 //!
+//! By default the keyword arguments are only used by the message that defines them.
+//! Set `merge` to `deep` on the top-level message to make them available to all the
+//! nested messages as well (the arguments of a nested message take precedence):
+//!
+//! ```yaml
+//! gettext: "%(greetings)s"
+//! merge: deep
+//! args:
+//!     user_name: Grace
+//!     greetings:
+//!         gettext: "Hello %(user_name)s!"
+//! ```
+//!
+//! Output: "Hello Grace!"
+//!
+//! Default values can be provided for the keyword arguments that might be missing:
+//!
+//! ```yaml
+//! gettext: "Hello %(name)s!"
+//! defaults:
+//!     name: stranger
+//! ```
+//!
+//! Output: "Hello stranger!"
+//!
+//! The arguments shared by all the messages of an application (the name of the
+//! product, the URL of the support, ...) can be set once at startup with
+//! `set_default_arg("product", "Acme")` or `default_args` in the configuration:
+//! they are added to the base arguments of every message, which take precedence.
+//!
+//! `args` can handle maps by formatting every entry with `entry` (by default
+//! `"%(key)s: %(value)s"`) and joining them with `sep` (by default `", "`), the
+//! entries are sorted by key:
+//!
+//! ```yaml
+//! gettext: "Totals: %(totals)s"
+//! args:
+//!     totals:
+//!         map:
+//!             apples: 3
+//!             pears: 5
+//!         entry: "%(value)s %(key)s"
+//!         sep: " and "
+//! ```
+//!
+//! Output: "Totals: 3 apples and 5 pears"
+//!
+//! The objects passed in the keyword arguments can be accessed by path:
+//!
+//! ```yaml
+//! gettext: "Hello %(user.name)s (%(user.address.city)s)!"
+//! args:
+//!     user:
+//!         name: Grace
+//!         address:
+//!             city: Arlington
+//! ```
+//!
+//! Output: "Hello Grace (Arlington)!"
+//!
+//! The keys of the values are reserved: an object with a `text`, a `gettext`, a
+//! `ref`, a `strftime`, ... is rendered as that value and its other fields can't be
+//! accessed by path.
+//!
+//! Values of your own types can be used anywhere a value is accepted: a type
+//! implementing `RenderFragment` and `Deserialize` registered with
+//! `register_fragment::<Price>("price")` renders the objects that have a `price`
+//! field (and that are not a value of serde-gettext), e.g. `{price: 1250, currency:
+//! EUR}`. It can translate its words and read the locale and the keyword arguments of
+//! the message with its `FragmentContext`. The objects that can't be deserialized
+//! into the type are kept as objects accessed by path.
+//!
+//! Filters
+//! =======
+//!
+//! Any value can be transformed after being rendered by wrapping it with a list of
+//! filters that are applied in order:
+//!
+//! ```yaml
+//! gettext: "Status: %(status)s"
+//! args:
+//!     status:
+//!         value:
+//!             gettext: "  shipped "
+//!         filters:
+//!             - trim
+//!             - upper
+//! ```
+//!
+//! Output: "Status: SHIPPED"
+//!
+//! Available filters:
+//!
+//!  *  `upper`: upper case
+//!  *  `lower`: lower case
+//!  *  `title` (or `titlecase`): title case the first letter of every word and lower
+//!     case the others
+//!  *  `trim`: remove the leading and trailing whitespaces
+//!  *  `truncate`: keep only the `len` first characters (grapheme clusters), the
+//!     optional `ellipsis` is included in the length and left out if it is longer
+//!
+//!     ```yaml
+//!     filters:
+//!         - truncate:
+//!             len: 40
+//!             ellipsis: "…"
+//!     ```
+//!
+//!  *  `pad_left`, `pad_right` and `center`: fill up to `width` characters (grapheme
+//!     clusters) with `fill` (a space by default) to align the text respectively to
+//!     the right, to the left and to the center
+//!
+//!     ```yaml
+//!     filters:
+//!         - pad_left:
+//!             width: 10
+//!             fill: "."
+//!     ```
+//!
+//! The case filters use the full Unicode case mapping (e.g. "ß" becomes "SS") and
+//! follow the rules of the language of the current locale (`LC_ALL`, `LC_CTYPE` or
+//! `LANG`): in Turkish and Azerbaijani, "i" becomes "İ" and "I" becomes "ı".
+//!
+//! A number can be rounded to `precision` decimals before the filters (the `filters`
+//! are then optional), half away from zero or with `rounding` set to `half_even`,
+//! `half_down`, `up`, `down`, `ceiling` or `floor`. The decimals written are rounded,
+//! not the binary approximation of the float:
+//!
+//! ```yaml
+//! text: "Total: %(total)s"
+//! args:
+//!     total:
+//!         value: 2.675
+//!         precision: 2
+//! ```
+//!
+//! Output: "Total: 2.68"
+//!
+//! A fallback message can be provided with `default` to any gettext function. It is
+//! used instead of the msgid when the catalog has no translation for it:
+//!
+//! ```yaml
+//! gettext: "Welcome to the new dashboard"
+//! default: "Welcome!"
+//! ```
+//!
+//! Output (without translation): "Welcome!"
+//!
+//! An observer implementing `MissingTranslationObserver` can be set on the message to
+//! be notified of all the msgids that have no translation in the catalog, for
+//! example to report them. gettext returns the msgid itself when there is no
+//! translation: with the catalogs of the system, the translations identical to
+//! their msgid (e.g. `OK`) are reported too. A `Catalog` backend tells them apart.
+//!
+//! An implementation of `Metrics` set as `metrics` on the message (or on all the
+//! messages of a `server::Translator`) is called when a message has been rendered
+//! (with its locale and the duration), for each missing translation and for each
+//! format error, to update the counters and the histograms of a service.
+//!
+//! `SerdeGetText::render_with_meta` returns the rendered message with what happened
+//! while rendering it (`RenderMeta`): the locale, every msgid looked up with its text
+//! domain, whether it was found and the plural form selected for its count, and the
+//! keyword arguments used by the format strings. It is meant for debugging and for
+//! the analytics of the translation quality.
+//!
+//! `plural_index("ru", 21)` returns the plural form selected for a count by the rules
+//! of a language (or of a catalog with `plural_index(&catalog, 21)`), for the
+//! previews of the translations without rendering a message.
+//!
+//! `SerdeGetText::render_segments` renders the message into a list of `Segment`: the
+//! translated text and the formatted arguments (with their key) of its format string.
+//! The front-ends can wrap the arguments in markup (e.g. bold the name of the user)
+//! without splitting the rendered string.
+//!
+//! Set `placeholders` to `PlaceholderMode::Tokens` on the message to render the
+//! translations with stable tokens instead of the arguments: `%(name)s` becomes
+//! `{name}` and the positional placeholders `{0}`, `{1}`, ... (the braces of the text
+//! are doubled). The output can be sent to a translation-memory system with its
+//! placeholders still identifiable.
+//!
+//! The messages with an empty msgid are rendered as an empty string instead of the
+//! header of the catalog. Set `empty_msgid` to `EmptyMsgid::Error` on the message to
+//! get an error instead.
+//!
+//! The catalogs of the system are converted to UTF-8: the text domains without codeset
+//! (`bind_textdomain_codeset`) are bound to UTF-8 when they are used, and `Catalog`
+//! reads the MO files in UTF-8 or ISO-8859-1. A translation that is still not valid
+//! UTF-8 fails with `Error::InvalidEncoding`. Set `decoding` to `Decoding::Lossy` on
+//! the message to replace the invalid sequences by "�" instead.
+//!
+//! Set `pseudo` to `true` on the top-level message to pseudo-localize all the
+//! translations (accented letters, longer text and brackets) without needing a
+//! pseudo catalog. The strings that are not translated or that are too long for the
+//! layout become easy to spot:
+//!
+//! ```yaml
+//! gettext: "Hello %(name)s!"
+//! pseudo: true
+//! args:
+//!     name: Grace
+//! ```
+//!
+//! Output: "[Hélló Grace!~~]"
+//!
+//! Set `escape` on the top-level message to `html`, `url`, `shell` or `markdown` to
+//! escape the arguments substituted in the placeholders for an HTML document, a
+//! link, a command or a Markdown text (Slack, Discord, ...), the translations are
+//! kept as is, like the output of the nested messages (their own arguments are
+//! escaped):
+//!
+//! ```yaml
+//! gettext: "Search %(query)s: https://example.com/?q=%(query)s"
+//! escape: url
+//! args:
+//!     query: "R&D"
+//! ```
+//!
+//! Output: "Search R%26D: https://example.com/?q=R%26D"
+//!
+//! The translations are looked up in the catalogs of the system through gettext by
+//! default. Any other source implementing `Backend` can be set as `backend` on the
+//! message:
+//!
+//!  *  `MockCatalog` is an in-memory catalog that gives deterministic translations in
+//!     the tests without installing catalogs in the CI
+//!  *  `Catalog` reads the compiled catalogs (MO files) without gettext, e.g.
+//!     embedded in the binary, and with the feature `po2json` the JSON catalogs of
+//!     po2json, Jed and gettext.js
+//!
+//! ```rust
+//! use serde_gettext::{MockCatalog, SerdeGetText};
+//! use std::convert::TryFrom;
+//! use std::sync::Arc;
+//!
+//! let mut catalog = MockCatalog::new();
+//! catalog.insert("Hello %(name)s!", "Bonjour %(name)s !");
+//!
+//! let yaml = r#"---
+//! gettext: "Hello %(name)s!"
+//! args:
+//!     name: Grace
+//! "#;
+//! let mut message: SerdeGetText = serde_yaml::from_str(yaml).unwrap();
+//! message.backend = Some(Arc::new(catalog));
+//!
+//! assert_eq!(String::try_from(message).unwrap(), "Bonjour Grace !");
+//! ```
+//!
+//! `SerdeGetText::render_to` renders a message at the end of any `fmt::Write`
+//! (e.g. the buffer of a response body) instead of returning a new `String`: the
+//! joined items, the `foreach` items and the formatted messages are written directly
+//! into it.
+//!
+//! Rendering never panics: any input (malformed format strings, absurd nesting,
+//! strings containing NUL characters, ...) yields an `Error` instead. The messages
+//! can't be nested more than 128 levels deep, the padding filters are limited to a
+//! width of 4096 and the dates to the years 1 to 9999 (`Error::LimitExceeded`). The
+//! fuzz targets in `fuzz/` check it with
+//! [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz):
+//!
+//! ```bash
+//! cargo fuzz run render
+//! ```
+//!
+//! `Error` serializes to `{"code": ..., "detail": ...}` for the error responses of the
+//! services: the code (`Error::code`, e.g. `undefined_variable`) is stable and can be
+//! matched by the clients, the detail is the error message.
+//!
+//! Performance: the rendering time grows linearly with the size of the message and of
+//! its output, apart from the sorted joins and the placeholders. The nested messages
+//! share the arguments of their parents instead of copying them, the arrays, joins
+//! and `foreach` are written directly at the end of the output and every msgid is
+//! translated once per rendering (once for all the renderings sharing an `Interner`).
+//! A placeholder is resolved by scanning the keyword arguments of its message: the
+//! messages with hundreds of arguments are faster with the `args` of `SerdeGetText`,
+//! which is a hash map. The benchmarks in `benches/` measure deep nesting, large
+//! argument maps, big arrays and batches of lookups; compare them to a baseline to
+//! detect the regressions:
+//!
+//! ```bash
+//! cargo bench --bench render -- --save-baseline main
+//! cargo bench --bench render -- --baseline main
+//! ```
+//!
+//! `SerdeGetText::extract_msgids` returns the msgids (with their plural form, context
+//! and domain) of a message, its nested messages and its variables, like `xgettext`
+//! does for source code. With the feature `extract`, `extract::extract_dir` does it
+//! for all the JSON and YAML templates of a directory. A template contains a message,
+//! a list of messages or messages by name.
+//!
+//! `pot::write` writes the extracted msgids of a text domain to a POT file, with the
+//! files where they are used, their context and their plural form, ready for the
+//! translators' tools (`msginit`, `msgmerge`, ...).
+//!
+//! The functions accept a `comment` (or `translators`) for the translators, ignored
+//! when rendering: it is extracted with the msgid and written by `pot::write` as an
+//! extracted comment (`#.`):
+//!
+//! ```yaml
+//! gettext: "Open"
+//! comment: "Verb: the button opening a file"
+//! ```
+//!
+//! The templates generated from code can also give the location of the message in
+//! that code, `source` with a `file` and an optional `line`, written with the
+//! templates that use the msgid as a reference (`#: src/editor.rs:42`).
+//!
+//! `codegen::build` generates typed constructors of the msgids of a POT or PO file
+//! (read by `pot::read`) from a build script, e.g. `one_item_deleted(n: u64)`
+//! for a plural message: the code using a msgid that changed in the catalog doesn't
+//! build anymore. The constructors use `SerdeGetText::gettext`, `ngettext`, ... and
+//! `arg`, available to author the messages in Rust.
+//!
+//! `validate::verify` checks that every msgid used by a set of messages has a
+//! translation in the catalog (any `Backend`) of each target locale and returns the
+//! missing msgids by locale, e.g. to block a deployment with incomplete catalogs.
+//!
+//! `LocaleDir` lists the locales and the text domains of the compiled catalogs
+//! installed for gettext and checks if they translate a msgid, like
+//! `Catalog::available_domains`, `Catalog::has_translation` and the same methods
+//! of `server::Translator` for its catalogs: a service can report the locales it
+//! supports and fail fast when a locale is not installed.
+//!
+//! `RenderCache` keeps the most recently rendered messages (up to a capacity) and
+//! renders the identical messages only once: the translation services see the same
+//! payloads over and over (menus, notification templates, ...). The messages are
+//! identified by a hash of their content, their arguments and their options, including
+//! the locale and the backend. `server::Translator::cache` enables it for a service.
+//!
+//! `Interner` shares the msgids and the translations between the messages rendered
+//! with it (`interner`): the strings repeated across a batch, or across requests, are
+//! allocated once and looked up in the backend only the first time, even when the
+//! arguments change. `server::Translator::intern` enables it for a service.
+//!
+//! `validate::lint` checks the placeholders of a message and its nested messages:
+//! the placeholders of the plural form missing in the singular form (or the
+//! opposite, except `n`), the arguments never used, the placeholders without
+//! argument and, if a catalog is provided, the placeholders of the translations
+//! missing in the msgid.
+//!
+//! The formats that are not self-describing (Bincode, Postcard, ...) can't
+//! recognize the values by their shape. They use `tagged::TaggedSerdeGetText`
+//! instead, where every value is an externally tagged enum, and convert it to a
+//! `SerdeGetText`. It also gives compact MessagePack and CBOR payloads:
+//!
+//! ```yaml
+//! value:
+//!     message:
+//!         message:
+//!             ngettext:
+//!                 singular: One item has been deleted
+//!                 plural: "%(n)s items have been deleted"
+//!                 n: 5
+//!         args:
+//!             keyword:
+//!                 user:
+//!                     value:
+//!                         text: Grace
+//! ```
+//!
+//! The objects are recognized by their first field naming a value (`text`,
+//! `gettext`, `join`, ...) and the errors tell which value failed (e.g. ``invalid
+//! `ngettext`: missing field `n` ``). Their unknown fields are ignored: a typo like
+//! `argz` instead of `args` renders the placeholders without their arguments.
+//! `strict::StrictSerdeGetText` fails on the unknown fields instead, with the path
+//! of the object, and converts to a `SerdeGetText`.
+//!
+//! The top-level `version` gives the version of the format of a message (1 if there
+//! is none): the messages of the older versions are upgraded to the current format
+//! when they are deserialized, and `migrate::upgrade` rewrites a stored template in
+//! the current format.
+//!
+//! Variables
+//! =========
+//!
+//! A message can be defined once in the top-level `vars` and used as many times as
+//! needed anywhere with `ref`:
+//!
+//! ```yaml
+//! gettext: "%(summary)s (copy: %(copy)s)"
+//! vars:
+//!     order_summary:
+//!         ngettext:
+//!             singular: "%(n)s item ordered"
+//!             plural: "%(n)s items ordered"
+//!             n: 3
+//! args:
+//!     summary:
+//!         ref: order_summary
+//!     copy:
+//!         ref: order_summary
+//! ```
+//!
+//! Output: "3 items ordered (copy: 3 items ordered)"
+//!
 //! List of All Available Functions
 //! ===============================
 //!
@@ -187,9 +714,226 @@
 //!         singular: "msgid_singular"
 //!         plural: "msgid_singular"
 //!         n: 5
-//!         cateogy: "ctype|numeric|time|collate|monetary|messages|all|paper|name|address|telephone|measurement|identification"
+//!         category: "ctype|numeric|time|collate|monetary|messages|all|paper|name|address|telephone|measurement|identification"
+//!     ```
+//!
+//!  *  selectplural: choose the plural forms by `select` (or `other` if there are no
+//!     forms for it) and then by `n`
+//!
+//!     ```yaml
+//!     selectplural:
+//!         select: "female"
+//!         n: 5
+//!         forms:
+//!             female:
+//!                 singular: "She added %(n)s photo"
+//!                 plural: "She added %(n)s photos"
+//!             other:
+//!                 singular: "They added %(n)s photo"
+//!                 plural: "They added %(n)s photos"
+//!     ```
+//!
+//!  *  noop: a msgid that is extracted (`extract_msgids`) like `gettext` but
+//!     formatted as is, for the messages that must be in the catalog template before
+//!     being translated
+//!
+//!     ```yaml
+//!     noop: "Hello %(name)s!"
+//!     args:
+//!         name: Grace
+//!     ```
+//!
+//!  *  foreach: render `template` once per item of `items` (the fields of the item are
+//!     available as keyword arguments) and join the results with `sep`
+//!
+//!     ```yaml
+//!     foreach:
+//!         items:
+//!             - name: Grace
+//!             - name: Marie
+//!         template:
+//!             gettext: "- %(name)s"
+//!         sep: "\n"
+//!     ```
+//!
+//!  *  first_of: render the first message of the list that has all its translations
+//!     (and whose arguments can be formatted), or the last one otherwise. The observer
+//!     is notified of the missing translations of the messages that have been tried.
+//!
+//!     ```yaml
+//!     first_of:
+//!         - pgettext:
+//!               ctx: "checkout"
+//!               msgid: "Confirm"
+//!         - gettext: "Confirm"
+//!     ```
+//!
+//!  *  if_translated: render `then` if the catalog has a translation of `msgid` (with
+//!     the optional `domain` and `ctx`) and `else` otherwise, e.g. to roll out a new
+//!     copy only in the languages it has been translated to. The observer is not
+//!     notified, a missing branch renders nothing.
+//!
+//!     ```yaml
+//!     if_translated:
+//!         msgid: "Start your free trial"
+//!         then:
+//!             gettext: "Start your free trial"
+//!         else:
+//!             gettext: "Sign up"
+//!     ```
+//!
+//!  *  choice: render the case selected by `key`, a format string of the arguments
+//!     of the choice (`args`) then of the keyword arguments in scope, or `default` if
+//!     there is no case for it. The cases see the keyword arguments of the choice,
+//!     like the fields of the items of `foreach`.
+//!
+//!     ```yaml
+//!     choice:
+//!         key: "%(status)s"
+//!         cases:
+//!             shipped:
+//!                 text: "Your order shipped on %(date)s"
+//!             pending:
+//!                 gettext: "Your order is being prepared"
+//!         default:
+//!             gettext: "Your order has been received"
+//!     args:
+//!         status: "shipped"
+//!         date: "2024-03-01"
+//!     ```
+//!
+//!     Output: "Your order shipped on 2024-03-01"
+//!
+//!  *  env: the value of an environment variable allowed by the configuration
+//!     (`config::Config::allow_env`), e.g. the name of the product in the arguments
+//!
+//!     ```yaml
+//!     gettext: "Welcome to %(product)s!"
+//!     args:
+//!         product:
+//!             env: "APP_NAME"
+//!     ```
+//!
+//!  *  langinfo: a fact about the locale of the process (`nl_langinfo`):
+//!     `radix_char` (or `radixchar`), `thousands_sep`, `currency_symbol`,
+//!     `date_format`, `time_format`, `date_time_format`, or the name of a day
+//!     (`day`, `abbreviated_day`, from 1 for Sunday to 7) or of a month (`month`,
+//!     `abbreviated_month`, from 1 to 12) selected by `index`
+//!
+//!     ```yaml
+//!     gettext: "Prices in %(currency)s"
+//!     args:
+//!         currency:
+//!             langinfo: currency_symbol
+//!     ```
+//!
+//!  *  address: a postal address laid out following the conventions of the region
+//!     (`region`, by default the territory of the locale, `LC_ADDRESS`): the order of
+//!     the number and the street, of the postcode and the city, ... The fields are
+//!     values (e.g. the country can be translated), the missing ones are left out
+//!     and the lines are separated by `sep` (a new line by default)
+//!
+//!     ```yaml
+//!     address:
+//!         name: "Grace Hopper"
+//!         street: "Hauptstraße"
+//!         number: "5"
+//!         postcode: "10115"
+//!         city: "Berlin"
+//!         country:
+//!             gettext: "Germany"
+//!         region: DE
+//!     ```
+//!
+//!     Output: "Grace Hopper\nHauptstraße 5\n10115 Berlin\nGermany"
+//!
+//!  *  person_name: the name of a person ordered following the conventions of the
+//!     region (`region`, by default the territory of the locale, `LC_NAME`): the
+//!     given names or the family name first, the honorific (`title`) before or after
+//!     the name, and the CJK names without spaces. `form` renders the `full` name
+//!     (by default), the `formal` name to address the person (title and family name)
+//!     or the `informal` one (given name)
+//!
+//!     ```yaml
+//!     person_name:
+//!         title: "様"
+//!         given: "太郎"
+//!         family: "田中"
+//!         region: JP
+//!     ```
+//!
+//!     Output: "田中太郎様"
+//!
+//!  *  measure: a quantity with its unit (`unit`), converted to the measurement system
+//!     (`system`: `metric` or `imperial`, by default the one of the territory of the
+//!     locale, `LC_MEASUREMENT`). The converted quantities are rounded to one decimal
+//!     unless `precision` is given. The units are translated in the context `unit`
+//!
+//!     ```yaml
+//!     measure: 5
+//!     unit: km
+//!     system: imperial
+//!     ```
+//!
+//!     Output: "3.1 mi"
+//!
+//!  *  table: rows of values laid out in aligned columns of plain text, separated by
+//!     `sep` (two spaces by default). `align` gives the alignment of each column
+//!     (`left` by default, `right` or `center`). The wide characters (CJK) take two
+//!     columns, and the ambiguous ones too in the Chinese, Japanese and Korean
+//!     locales (`LC_CTYPE`)
+//!
+//!     ```yaml
+//!     table:
+//!         - [Item, Qty]
+//!         - [{gettext: Apples}, 3]
+//!         - [{gettext: Pears}, 12]
+//!     align: [left, right]
 //!     ```
 //!
+//!     Output: "Item    Qty\nApples    3\nPears    12"
+//!
+//! `n` can be any integer, or a string containing an integer for the numbers that
+//! can't be represented in your format.
+//!
+//! The plural functions accept optional `zero`, `one` and `two` messages that are
+//! translated and used instead of the plural forms of the catalog when `n` is
+//! respectively 0, 1 or 2:
+//!
+//! ```yaml
+//! ngettext:
+//!     singular: "%(n)s item has been deleted"
+//!     plural: "%(n)s items have been deleted"
+//!     zero: "No item has been deleted"
+//!     n: 0
+//! ```
+//!
+//! Output: "No item has been deleted"
+//!
+//! The names used by the other gettext tools are accepted as aliases, so their
+//! payloads work unmodified: `msgid` and `id` for `gettext` (if the other fields of
+//! the object are the options of the message: the objects of keyword arguments often
+//! have an `id`), `msgid` for `singular`, `msgid_plural` for `plural`, `count` for
+//! `n` and `context` for `ctx`.
+//!
+//! `gettext` and `ngettext` also have array forms for the compact payloads: the
+//! msgid followed by the positional arguments, and the singular form, the plural
+//! form and `n`:
+//!
+//! ```json
+//! {"gettext": ["Hello %s", "Grace"]}
+//! {"ngettext": ["%(n)s item", "%(n)s items", 5]}
+//! ```
+//!
+//! A message can also be a bare string instead of an object: it is rendered as is,
+//! or translated if `bare_string` is set to `gettext` in the configuration
+//! (`config::Config::bare_string`).
+//!
+//! The messages rendered in a closure given to `with_domain("billing", || ...)` are
+//! looked up in the text domain `billing` unless they have their own (`dgettext`,
+//! ...), so the payloads of a component don't repeat its text domain. The global
+//! text domain of gettext is not changed.
+//!
 //! Date and Time Formatting
 //! ========================
 //!
@@ -209,6 +953,91 @@
 //! If you want to change the locale and timezone for the current process, you
 //! will need to export `TZ` and `LC_ALL` as environment variable first, then call
 //! `set_locale` and `tz_set` again.
+//!
+//! When `epoch` is omitted, the current time is used. The tests can fix it, and
+//! avoid depending on the time zone and the locale of the machine, by setting on
+//! the message `now` (seconds since the epoch), `timezone` (`TimeZone::Utc` instead
+//! of the local time zone) and `locale` (used instead of `LC_ALL`, `LC_*` and `LANG`
+//! for the translations, the case filters and the missing translation observer).
+//!
+//! The locale of all the messages rendered by a thread (e.g. while handling a
+//! request) can be set with a guard instead of the environment variables of the
+//! process: `let _locale = LocaleContext::enter("de_DE");` applies to the messages
+//! without `locale` until the guard is dropped, nested renderings included. The
+//! backend receives it with every lookup (`Lookup::locale`): gettext uses the
+//! catalogs of that locale during the lookup if it is installed on the system.
+//!
+//! Crate Features
+//! ==============
+//!
+//!  *  `extract`: `extract::extract_dir` extracts the msgids of all the templates of a
+//!     directory
+//!  *  `cli`: the `serde-gettext` command line
+//!  *  `server`: `server::serve` runs a ready-made translation service, `POST
+//!     /translate` renders the messages of the JSON body in the locale negotiated
+//!     from the `Accept-Language` header
+//!  *  `axum`, `actix` and `lambda`: the same service in an
+//!     [axum](https://docs.rs/axum/) or [actix-web](https://docs.rs/actix-web/)
+//!     application, or in an [AWS Lambda](https://docs.rs/lambda_runtime/) function
+//!     (the modules `axum`, `actix` and `lambda`)
+//!  *  `wasm`: the messages are rendered in the browsers by the functions exported
+//!     with wasm-bindgen (the module `wasm`)
+//!  *  `ffi`: a C API to render the messages from other languages (the module `ffi`)
+//!  *  `po2json`: `Catalog::load_json` reads the JSON catalogs made by po2json (raw
+//!     and Jed formats) or for gettext.js
+//!  *  `remote`: `remote::HttpSource` downloads the catalogs over HTTP(S) and
+//!     revalidates them with their `ETag` and `Last-Modified` when their time to live
+//!     expires (an implementation of the asynchronous `remote::CatalogSource`)
+//!  *  `watch`: `Catalog::watch` reloads the catalogs when their MO files change on
+//!     disk (the module `watch`)
+//!  *  `query`: `query::from_query` reads a message from a query string or a form-encoded
+//!     body (e.g. `gettext=Hello%20%25(name)s!&args[name]=Grace`) for the translation
+//!     endpoints reached with a simple `GET`
+//!  *  `schema`: `schema::schema` returns the JSON Schema (draft 2020-12) of the
+//!     messages, to validate the payloads in the API gateways and the client SDKs, and
+//!     `schema::openapi_components` their components for an OpenAPI 3.0 or 3.1 document
+//!  *  `macros`: `translate!("Hello %(name)s!", name = user.name)` builds a
+//!     `SerdeGetText` and fails to compile if a placeholder has no argument or an
+//!     argument is not used, and `#[derive(ToGettextArgs)]` converts a struct into
+//!     the arguments of `SerdeGetText::with_args` (the crate `serde-gettext-macros`)
+//!  *  `tracing`: the rendering emits `tracing` spans and events: a `render` span with
+//!     the hash and the locale of the message, the catalog lookups, the translations
+//!     with the plural form selected, and the formatting, with their duration
+//!  *  `tokio`: `task::render_in_locale` sets the locale of the messages rendered by
+//!     a future, carried across its `.await` points (a Tokio task-local)
+//!  *  `decimal`: the `decimal` values (`{"decimal": "19.90"}`) are exact decimals
+//!     ([rust_decimal](https://docs.rs/rust_decimal/)) and `%f` writes the
+//!     decimal arguments exactly, rounded half away from zero, so the amounts of
+//!     money never render as `0.30000000000000004`
+//!  *  `phone`: the `phone` values (`{"phone": "01 23 45 67 89"}`) are formatted
+//!     following the conventions of their region
+//!     ([phonenumber](https://docs.rs/phonenumber/)): in the national format for the
+//!     numbers of `region` (by default the territory of the locale, `LC_TELEPHONE`)
+//!     and in the international format for the others, or with `format` set to
+//!     `national`, `international`, `e164` or `rfc3966`
+//!  *  `arbitrary`: `SerdeGetText` implements `arbitrary::Arbitrary` to generate
+//!     random messages for fuzzing and property testing
+//!
+//! Command Line
+//! ============
+//!
+//! With the feature `cli`, the `serde-gettext` binary renders a message document
+//! (JSON or YAML) read from a file or the standard input:
+//!
+//! ```bash
+//! cargo install serde-gettext --features cli
+//! echo '{"gettext": "Hello!"}' | serde-gettext render --locale fr_FR.UTF-8 --domain app --localedir /usr/share/locale
+//! ```
+//!
+//! `serde-gettext extract` writes a POT file with the msgids of template files (or
+//! directories of templates) and `serde-gettext check` lints their placeholders and
+//! checks that the catalogs of the given locales translate all their msgids. It
+//! fails if there is any problem, so CI pipelines can gate merges on it:
+//!
+//! ```bash
+//! serde-gettext extract --domain app -o po/app.pot templates/
+//! serde-gettext check --domain app --localedir locale --locale fr_FR.UTF-8 --locale de_DE.UTF-8 templates/
+//! ```
 
 #![deny(missing_docs)]
 
@@ -217,13 +1046,117 @@ extern crate serde_derive;
 #[macro_use]
 extern crate derive_error;
 
-use dynfmt::{Argument, Format, FormatArgs, PythonFormat};
-use libc_strftime::strftime_local;
+#[cfg(feature = "actix")]
+pub mod actix;
+mod address;
+mod args;
+#[cfg(feature = "axum")]
+pub mod axum;
+mod batch;
+mod binary;
+mod cache;
+mod catalog;
+pub mod codegen;
+mod collate;
+pub mod config;
+mod default_args;
+mod domain;
+mod environment;
+pub mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filters;
+mod format;
+mod fragment;
+mod intern;
+#[cfg(feature = "lambda")]
+pub mod lambda;
+mod langinfo;
+mod locale_context;
+mod measure;
+mod meta;
+mod metrics;
+pub mod migrate;
+mod person_name;
+#[cfg(feature = "phone")]
+mod phone;
+mod plural;
+pub mod pot;
+mod pseudo;
+#[cfg(feature = "query")]
+pub mod query;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "schema")]
+pub mod schema;
+mod segment;
+#[cfg(any(
+    feature = "server",
+    feature = "axum",
+    feature = "actix",
+    feature = "lambda",
+    feature = "ffi"
+))]
+pub mod server;
+mod shape;
+pub mod strict;
+mod table;
+pub mod tagged;
+#[cfg(feature = "tokio")]
+pub mod task;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use crate::args::ToGettextArgs;
+pub use crate::batch::{TranslatedList, TranslatedMap};
+pub use crate::cache::RenderCache;
+pub use crate::catalog::{
+    Backend, Catalog, CatalogError, GettextBackend, LocaleDir, Lookup, MockCatalog,
+};
+pub use crate::collate::collate;
+pub use crate::config::init;
+pub use crate::default_args::{remove_default_arg, set_default_arg};
+pub use crate::domain::with_domain;
+pub use crate::fragment::{
+    register_fragment, unregister_fragment, FragmentContext, RenderFragment,
+};
+pub use crate::intern::Interner;
+pub use crate::locale_context::LocaleContext;
+pub use crate::meta::{LookupMeta, RenderMeta};
+pub use crate::metrics::Metrics;
+pub use crate::plural::{plural_index, PluralRules};
+pub use crate::segment::Segment;
+#[cfg(feature = "macros")]
+pub use serde_gettext_macros::{translate, ToGettextArgs};
+
+use crate::address::ValueAddress;
+use crate::binary::{BytesEncoding, ValueBytes, ValueUuid};
+use crate::filters::{Filter, Rounding};
+use crate::format::{
+    format_into, format_segments, format_tokens, Arg, ArgRef, FormatArgs, FormatOptions,
+};
+use crate::fragment::Fragment;
+use crate::langinfo::LangInfoItem;
+use crate::measure::MeasureSystem;
+use crate::person_name::ValuePersonName;
+use crate::pseudo::pseudo_localize;
+use crate::table::Align;
+use libc_strftime::{strftime_gmt, strftime_local};
+use serde::de::{self, Deserializer, Visitor};
 #[allow(unused_imports)]
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
+use std::env;
+use std::fmt;
 use std::string::ToString;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 /// Runtime error that occurs when the input cannot be formatted
 #[derive(Debug, Error)]
@@ -234,23 +1167,493 @@ pub enum Error {
     /// Missing join separator
     #[error(non_std, no_from, display = "missing join separator")]
     MissingJoinSeparator,
-}
+    /// Reference to a variable that is not defined in `vars`
+    #[error(msg_embedded, no_from, non_std)]
+    UndefinedVariable(String),
+    /// Variable that references itself directly or indirectly
+    #[error(msg_embedded, no_from, non_std)]
+    CircularReference(String),
+    /// No forms match the selection
+    #[error(msg_embedded, no_from, non_std)]
+    MissingForm(String),
+    /// Empty msgid
+    #[error(non_std, no_from, display = "empty msgid")]
+    EmptyMsgid,
+    /// The message exceeds a limit of the rendering (nesting depth, padding width, date range)
+    #[error(msg_embedded, no_from, non_std)]
+    LimitExceeded(String),
+    /// The catalog returned a translation that is not valid UTF-8
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidEncoding(String),
+    /// The writer given to `SerdeGetText::render_to` failed
+    #[error(non_std, no_from, display = "write error")]
+    WriteError,
+    /// Environment variable not allowed by the configuration (`config::Config::allow_env`)
+    #[error(msg_embedded, no_from, non_std)]
+    EnvNotAllowed(String),
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Error {
+        Error::WriteError
+    }
+}
+
+impl Error {
+    /// Stable identifier of the kind of error (e.g. `undefined_variable`), for the clients of
+    /// the services returning the errors
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::FormatError(_) => "format_error",
+            Error::MissingJoinSeparator => "missing_join_separator",
+            Error::UndefinedVariable(_) => "undefined_variable",
+            Error::CircularReference(_) => "circular_reference",
+            Error::MissingForm(_) => "missing_form",
+            Error::EmptyMsgid => "empty_msgid",
+            Error::LimitExceeded(_) => "limit_exceeded",
+            Error::InvalidEncoding(_) => "invalid_encoding",
+            Error::WriteError => "write_error",
+            Error::EnvNotAllowed(_) => "env_not_allowed",
+        }
+    }
+}
+
+/// The errors are serialized as an object with their `code` and their message (`detail`), e.g.
+/// `{"code": "undefined_variable", "detail": "undefined variable: user"}`
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Error", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("detail", &self.to_string())?;
+        state.end()
+    }
+}
 
 /// A deserializable struct to translate and format
-#[derive(Deserialize, Clone, Debug)]
+// deserialized in `shape`: the fields of its value, `merge`, `vars`, `pseudo` and `escape`, or a
+// bare string
+#[derive(Clone, Debug)]
 pub struct SerdeGetText {
-    #[serde(flatten)]
     value: Value,
     /// Base arguments that can be provided for keywords format
-    #[serde(skip)]
     pub args: HashMap<String, String>,
+    /// How the keyword arguments of a message are made available to its nested messages
+    pub merge: ArgsMerge,
+    vars: HashMap<String, Value>,
+    /// Observer notified of the messages that have no translation
+    pub observer: Option<Arc<dyn MissingTranslationObserver + Send + Sync>>,
+    /// How the messages with an empty msgid are rendered
+    pub empty_msgid: EmptyMsgid,
+    /// Pseudo-localize the translations to spot the unlocalized or overflowing strings
+    pub pseudo: bool,
+    /// Source of the translations, the catalogs of the system if `None`
+    pub backend: Option<Arc<dyn Backend + Send + Sync>>,
+    /// Current time (seconds since the epoch) used by the dates without `epoch`, the time of the
+    /// system if `None`
+    pub now: Option<i64>,
+    /// Time zone of the dates
+    pub timezone: TimeZone,
+    /// Locale used instead of the locale of the thread (`LocaleContext`) or of the environment
+    /// (`LC_ALL`, `LC_*` or `LANG`)
+    pub locale: Option<String>,
+    /// Handling of the translations that are not valid UTF-8
+    pub decoding: Decoding,
+    /// Msgids and translations shared with the other messages rendered with it
+    pub interner: Option<Arc<Interner>>,
+    /// Rendering of the placeholders: their arguments, or tokens for a translation memory
+    pub placeholders: PlaceholderMode,
+    /// Handling of the `%` that are not a placeholder
+    pub percent: PercentMode,
+    /// Handling of the control characters of the arguments
+    pub control: ControlChars,
+    /// Escaping of the arguments for an HTML document, a URL or a shell command
+    pub escape: EscapeMode,
+    /// Callbacks updating the metrics of the service
+    pub metrics: Option<Arc<dyn Metrics + Send + Sync>>,
 }
 
+/// Time zone used to format the dates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum TimeZone {
+    /// Time zone of the system (`TZ`)
+    #[default]
+    Local,
+    /// Coordinated Universal Time
+    Utc,
+}
+
+/// Rendering of the messages with an empty msgid: gettext would return the header of the catalog
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum EmptyMsgid {
+    /// Render an empty string
+    #[default]
+    Empty,
+    /// Fail with `Error::EmptyMsgid`
+    Error,
+}
+
+/// Handling of the translations that are not valid UTF-8 (legacy catalogs in another codeset)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Decoding {
+    /// Fail with `Error::InvalidEncoding`
+    #[default]
+    Strict,
+    /// Replace the invalid sequences by `U+FFFD`
+    Lossy,
+}
+
+/// Rendering of the placeholders of the messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum PlaceholderMode {
+    /// Replace the placeholders by their arguments
+    #[default]
+    Substitute,
+    /// Replace the placeholders by stable tokens (`%(name)s` by `{name}`, the first `%s` by
+    /// `{0}`) without rendering the arguments, and double the braces of the text: the
+    /// translations can be sent to a translation-memory system with their placeholders
+    Tokens,
+}
+
+/// Handling of the `%` of the format strings that are not a placeholder, e.g. in the text of the
+/// users rendered as a message (`{"text": comment}`). `%%` is a literal percent in all the modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum PercentMode {
+    /// Keep the `%` that don't start a placeholder (`100% sure`), the placeholders without
+    /// argument fail with `Error::FormatError`
+    #[default]
+    Lenient,
+    /// Fail with `Error::FormatError` on the `%` that don't start a placeholder, the literal
+    /// percents must be written `%%`
+    Strict,
+    /// Write as is the `%` that don't start a placeholder and the placeholders without argument
+    /// (`50%d off`, `100% %(name)s` keeps `% `): the text of the users never breaks the formatting
+    Escape,
+}
+
+/// Handling of the control characters (`\n`, `\x1b`, ...) and of the Unicode bidi controls
+/// (`U+202E`, ...) of the arguments substituted in the placeholders: the arguments coming from
+/// the users can't forge the lines of a log or the sequences of a terminal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ControlChars {
+    /// Substitute the arguments as they are
+    #[default]
+    Keep,
+    /// Remove the control characters from the arguments
+    Strip,
+    /// Replace the control characters of the arguments by their escape (`\u{1b}`)
+    Escape,
+}
+
+/// Escaping of the arguments substituted in the placeholders, for the messages that are a part of
+/// an HTML document, of a link, of a command or of a Markdown text (`escape` on the top-level
+/// message)
+///
+/// Only the arguments given as values are escaped: the output of the nested messages is
+/// substituted as is, their own arguments are escaped when they are formatted.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum EscapeMode {
+    /// Substitute the arguments as they are
+    #[default]
+    None,
+    /// Escape the special characters of HTML (`&`, `<`, `>`, `"` and `'`)
+    Html,
+    /// Percent-encode all the characters but the unreserved characters of the URLs
+    /// (`a-z`, `A-Z`, `0-9`, `-`, `.`, `_` and `~`)
+    Url,
+    /// Quote the arguments for a POSIX shell, except the words of safe characters
+    /// (`'it'"'"'s'` for `it's`)
+    Shell,
+    /// Escape the metacharacters of Markdown with a backslash (`\*admin\*` for `*admin*`)
+    Markdown,
+}
+
+/// Observer notified of the messages that have no translation in the catalog
+///
+/// With the catalogs of the system (`GettextBackend`), gettext returns the msgid when there is
+/// no translation: the translations identical to their msgid (e.g. `OK`) are reported as missing
+/// too. A `Catalog` tells them apart.
+pub trait MissingTranslationObserver {
+    /// Called with the text domain (`None` for the current one), the msgid (the singular for
+    /// the plural functions) and the locale of the messages (`LC_ALL`, `LC_MESSAGES` or `LANG`)
+    fn on_missing(&self, domain: Option<&str>, msgid: &str, locale: Option<&str>);
+}
+
+impl fmt::Debug for dyn MissingTranslationObserver + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("MissingTranslationObserver")
+    }
+}
+
+/// Render the message. It never panics: any input (malformed format strings, absurd nesting,
+/// strings containing NUL characters, ...) yields an `Error` instead.
 impl TryFrom<SerdeGetText> for String {
     type Error = Error;
 
     fn try_from(x: SerdeGetText) -> Result<String, Error> {
-        x.value.try_into_string(&x.args)
+        let mut res = String::new();
+        x.render_to(&mut res)?;
+
+        Ok(res)
+    }
+}
+
+impl SerdeGetText {
+    /// Render the message at the end of a writer (e.g. the buffer of a response body) instead of
+    /// a new `String`. The output is incomplete if it fails.
+    pub fn render_to<W: fmt::Write>(self, out: &mut W) -> Result<(), Error> {
+        self.render_into(out, None, None)
+    }
+
+    /// Render the message with what happened while rendering it: the locale, the messages
+    /// looked up in the catalog (found or not, plural form selected) and the arguments used
+    pub fn render_with_meta(self) -> Result<(String, RenderMeta), Error> {
+        let meta = RefCell::new(RenderMeta::default());
+        let mut res = String::new();
+        self.render_into(&mut res, Some(&meta), None)?;
+
+        Ok((res, meta.into_inner()))
+    }
+
+    /// Render the message, recording its metadata and splitting its format string into
+    /// segments if requested
+    fn render_into(
+        mut self,
+        out: &mut dyn fmt::Write,
+        meta: Option<&RefCell<RenderMeta>>,
+        segments: Option<&RefCell<Vec<Segment>>>,
+    ) -> Result<(), Error> {
+        if self.locale.is_none() {
+            self.locale = LocaleContext::current();
+        }
+        let lookups = Lookups::default();
+        if let (Some(interner), Some(backend)) = (self.interner.as_deref(), self.backend.as_ref()) {
+            interner.retain(backend);
+        }
+        if let Some(meta) = meta {
+            meta.borrow_mut().locale = self.locale.clone().or_else(|| env_locale("LC_MESSAGES"));
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "render",
+            hash = std::hash::Hasher::finish(&self.cache_key()),
+            locale = self.locale.as_deref(),
+        )
+        .entered();
+        let start = Instant::now();
+        let default_args = default_args::default_args();
+        let domain = domain::current();
+
+        let res = self.value.write_to(
+            out,
+            &Context {
+                scope: Scope::Base(&self.args, &default_args),
+                merge: self.merge,
+                vars: &self.vars,
+                refs: None,
+                observer: self
+                    .observer
+                    .as_deref()
+                    .map(|x| x as &dyn MissingTranslationObserver),
+                empty_msgid: self.empty_msgid,
+                pseudo: self.pseudo,
+                backend: self
+                    .backend
+                    .as_deref()
+                    .map(|x| x as &dyn Backend)
+                    .unwrap_or(&GettextBackend),
+                now: self.now,
+                timezone: self.timezone,
+                locale: self.locale.as_deref(),
+                decoding: self.decoding,
+                interner: self.interner.as_deref(),
+                placeholders: self.placeholders,
+                format: FormatOptions {
+                    percent: self.percent,
+                    control: self.control,
+                    escape: self.escape,
+                },
+                metrics: self.metrics.as_deref().map(|x| x as &dyn Metrics),
+                lookups: &lookups,
+                meta,
+                segments,
+                missing: None,
+                domain: domain.as_deref(),
+                depth: 0,
+            },
+        );
+        #[cfg(feature = "tracing")]
+        match &res {
+            Ok(()) => tracing::debug!(duration_us = micros(start), "rendered"),
+            Err(err) => {
+                tracing::debug!(duration_us = micros(start), error = %err, "rendering failed")
+            }
+        }
+        if let Some(metrics) = self.metrics.as_deref() {
+            let locale = self.locale.or_else(|| env_locale("LC_MESSAGES"));
+            metrics.on_render(locale.as_deref(), start.elapsed(), res.is_ok());
+        }
+
+        res
+    }
+
+    /// Message translated by `gettext`, for the messages authored in Rust
+    pub fn gettext(msgid: &str) -> SerdeGetText {
+        SerdeGetText::new(Value::GetText {
+            gettext: ValueGetText(msgid.to_string()),
+            args: None,
+            defaults: HashMap::new(),
+            default: None,
+            comment: None,
+            source: None,
+        })
+    }
+
+    /// Message translated by `ngettext`, `n` is available as `%(n)s`
+    pub fn ngettext(singular: &str, plural: &str, n: u64) -> SerdeGetText {
+        SerdeGetText::new(Value::NGetText {
+            ngettext: Box::new(ValueNGetText {
+                singular: singular.to_string(),
+                plural: plural.to_string(),
+                n: Count(n.into()),
+                counts: ExplicitCounts::default(),
+            }),
+            args: None,
+            defaults: HashMap::new(),
+            default: None,
+            comment: None,
+            source: None,
+        })
+    }
+
+    /// Message translated by `pgettext`
+    pub fn pgettext(ctx: &str, msgid: &str) -> SerdeGetText {
+        SerdeGetText::new(Value::PGetText {
+            pgettext: ValuePGetText {
+                ctx: ctx.to_string(),
+                msgid: msgid.to_string(),
+            },
+            args: None,
+            defaults: HashMap::new(),
+            default: None,
+            comment: None,
+            source: None,
+        })
+    }
+
+    /// Message translated by `dgettext`
+    pub fn dgettext(domain: &str, msgid: &str) -> SerdeGetText {
+        SerdeGetText::new(Value::DGetText {
+            dgettext: ValueDGetText {
+                domain: domain.to_string(),
+                msgid: msgid.to_string(),
+            },
+            args: None,
+            defaults: HashMap::new(),
+            default: None,
+            comment: None,
+            source: None,
+        })
+    }
+
+    /// Message translated by `dngettext`, `n` is available as `%(n)s`
+    pub fn dngettext(domain: &str, singular: &str, plural: &str, n: u64) -> SerdeGetText {
+        SerdeGetText::new(Value::DNGetText {
+            dngettext: Box::new(ValueDNGetText {
+                domain: domain.to_string(),
+                singular: singular.to_string(),
+                plural: plural.to_string(),
+                n: Count(n.into()),
+                counts: ExplicitCounts::default(),
+            }),
+            args: None,
+            defaults: HashMap::new(),
+            default: None,
+            comment: None,
+            source: None,
+        })
+    }
+
+    /// Message translated by `npgettext`, `n` is available as `%(n)s`
+    pub fn npgettext(ctx: &str, singular: &str, plural: &str, n: u64) -> SerdeGetText {
+        SerdeGetText::new(Value::NPGetText {
+            npgettext: Box::new(ValueNPGetText {
+                ctx: ctx.to_string(),
+                singular: singular.to_string(),
+                plural: plural.to_string(),
+                n: Count(n.into()),
+                counts: ExplicitCounts::default(),
+            }),
+            args: None,
+            defaults: HashMap::new(),
+            default: None,
+            comment: None,
+            source: None,
+        })
+    }
+
+    /// Add a base argument (`args`) for the keyword placeholders (`%(name)s`)
+    pub fn arg(mut self, name: &str, value: impl fmt::Display) -> SerdeGetText {
+        self.args.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Message with the default options
+    pub(crate) fn new(value: Value) -> SerdeGetText {
+        SerdeGetText {
+            value,
+            args: HashMap::new(),
+            merge: ArgsMerge::default(),
+            vars: HashMap::new(),
+            observer: None,
+            empty_msgid: EmptyMsgid::default(),
+            pseudo: false,
+            backend: None,
+            now: None,
+            timezone: TimeZone::default(),
+            locale: None,
+            decoding: Decoding::default(),
+            interner: None,
+            placeholders: PlaceholderMode::default(),
+            percent: PercentMode::default(),
+            control: ControlChars::default(),
+            escape: EscapeMode::default(),
+            metrics: None,
+        }
+    }
+}
+
+/// Random messages for fuzzing and property testing, rendered with the catalogs of the system
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SerdeGetText {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<SerdeGetText> {
+        Ok(SerdeGetText {
+            value: u.arbitrary()?,
+            args: u.arbitrary()?,
+            merge: u.arbitrary()?,
+            vars: u.arbitrary()?,
+            observer: None,
+            empty_msgid: u.arbitrary()?,
+            pseudo: u.arbitrary()?,
+            backend: None,
+            now: u.arbitrary()?,
+            timezone: u.arbitrary()?,
+            locale: u.arbitrary()?,
+            decoding: u.arbitrary()?,
+            interner: None,
+            placeholders: u.arbitrary()?,
+            percent: u.arbitrary()?,
+            control: u.arbitrary()?,
+            escape: u.arbitrary()?,
+            metrics: None,
+        })
     }
 }
 
@@ -262,276 +1665,1749 @@ impl TryFrom<Box<SerdeGetText>> for String {
     }
 }
 
+/// Merging strategy of the keyword arguments into the nested messages
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum ArgsMerge {
+    /// The keyword arguments are only used to format the message that defines them
+    #[default]
+    Leaf,
+    /// The keyword arguments are also used to format all the nested messages (the arguments
+    /// of the nested messages take precedence)
+    Deep,
+}
+
+// recognized by its shape in `shape`, the variants are deserialized by the derived implementation
 #[derive(Deserialize, Clone, Debug)]
-#[serde(untagged)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(remote = "Self", rename_all = "snake_case")]
 enum Value {
     Text(String),
-    Integer(i64),
+    // wide enough for the unsigned integers (IDs, amounts in cents, ...) of all the formats
+    Integer(i128),
     Float(f64),
+    #[cfg(feature = "decimal")]
+    Decimal {
+        decimal: rust_decimal::Decimal,
+    },
+    Uuid {
+        uuid: ValueUuid,
+    },
+    Bytes {
+        bytes: ValueBytes,
+        #[serde(default)]
+        encoding: BytesEncoding,
+    },
+    #[cfg(feature = "phone")]
+    Phone {
+        phone: String,
+        #[serde(default)]
+        format: phone::PhoneFormat,
+        region: Option<String>,
+    },
+    Measure {
+        measure: f64,
+        unit: String,
+        system: Option<MeasureSystem>,
+        precision: Option<usize>,
+    },
     Bool(bool),
-    Unit(()),
+    Unit(#[serde(deserialize_with = "deserialize_unit")] ()),
     Datetime(DatetimeValue),
     Array(Vec<Value>),
+    Join {
+        #[serde(deserialize_with = "deserialize_join")]
+        join: Vec<Value>,
+        #[serde(default)]
+        sort: bool,
+        #[serde(default)]
+        collate: bool,
+        #[serde(default)]
+        unique: bool,
+        #[serde(default)]
+        skip_empty: bool,
+    },
+    Ref {
+        #[serde(rename = "ref")]
+        name: String,
+    },
+    Table {
+        table: Vec<Vec<Value>>,
+        #[serde(default)]
+        align: Vec<Align>,
+        sep: Option<String>,
+    },
+    Filtered {
+        value: Box<Value>,
+        #[serde(default)]
+        filters: Vec<Filter>,
+        precision: Option<usize>,
+        #[serde(default)]
+        rounding: Rounding,
+    },
+    Map {
+        map: BTreeMap<String, Value>,
+        #[serde(default = "default_map_entry")]
+        entry: String,
+        #[serde(default = "default_map_sep")]
+        sep: String,
+    },
+    Foreach {
+        foreach: ValueForeach,
+    },
+    FirstOf {
+        #[serde(deserialize_with = "deserialize_first_of")]
+        first_of: Vec<Value>,
+    },
+    IfTranslated {
+        if_translated: ValueIfTranslated,
+    },
+    Choice {
+        choice: ValueChoice,
+        args: Option<Formatter>,
+    },
+    Env {
+        env: String,
+    },
+    LangInfo {
+        langinfo: LangInfoItem,
+        index: Option<usize>,
+    },
+    Address {
+        address: Box<ValueAddress>,
+    },
+    PersonName {
+        person_name: Box<ValuePersonName>,
+    },
     FormattedText {
         text: String,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+    },
+    Noop {
+        noop: String,
+        args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
     },
     GetText {
+        #[serde(alias = "msgid", alias = "id")]
         gettext: ValueGetText,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
     NGetText {
-        ngettext: ValueNGetText,
+        ngettext: Box<ValueNGetText>,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
     PGetText {
         pgettext: ValuePGetText,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
     DGetText {
         dgettext: ValueDGetText,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
     DNGetText {
-        dngettext: ValueDNGetText,
+        dngettext: Box<ValueDNGetText>,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
     NPGetText {
-        npgettext: ValueNPGetText,
+        npgettext: Box<ValueNPGetText>,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
     DCNGetText {
-        dcngettext: ValueDCNGetText,
+        dcngettext: Box<ValueDCNGetText>,
         args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
     },
+    SelectPlural {
+        selectplural: Box<ValueSelectPlural>,
+        args: Option<Formatter>,
+        #[serde(default)]
+        defaults: HashMap<String, Value>,
+        #[serde(default, alias = "translators")]
+        comment: Option<String>,
+        source: Option<Box<ValueSource>>,
+        default: Option<String>,
+    },
+    // the objects that are not a value of serde-gettext, with the field of a registered type
+    #[cfg_attr(feature = "arbitrary", arbitrary(skip))]
+    Fragment(Fragment),
 }
 
-macro_rules! handle_gettext {
-    ($s:expr, $args:expr, $map:expr, $base_map:expr) => {{
-        Self::format(&$s.to_string(), $args, $map, $base_map)
-    }};
+#[derive(Default)]
+struct JoinOptions {
+    sort: bool,
+    collate: bool,
+    /// Leave out the items rendered like a previous one
+    unique: bool,
+    /// Leave out the items rendered as an empty or blank string
+    skip_empty: bool,
 }
 
-macro_rules! handle_plural {
-    ($s:expr, $args:expr, $map:expr, $base_map:expr) => {{
-        $map.reserve(match $args.as_ref() {
-            Some(Formatter::KeywordArgs(args)) => args.len() + 1,
-            _ => 1,
-        });
-        $map.insert("n".to_string(), $s.n.to_string());
+/// Unit value (`null`), or `{none: true}` for the formats that have no null (TOML)
+fn deserialize_unit<'de, D>(deserializer: D) -> Result<(), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct UnitVisitor;
+
+    impl<'de> Visitor<'de> for UnitVisitor {
+        type Value = ();
 
-        Self::format(&$s.to_string(), $args, $map, $base_map)
-    }};
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("null or {none: true}")
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<(), E> {
+            Ok(())
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            match (map.next_entry::<String, bool>()?, map.next_key::<String>()?) {
+                (Some((key, true)), None) if key == "none" => Ok(()),
+                _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+            }
+        }
+    }
+
+    deserializer.deserialize_any(UnitVisitor)
+}
+
+/// Messages of `first_of`, there must be at least one
+fn deserialize_first_of<'de, D>(deserializer: D) -> Result<Vec<Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let messages = Vec::<Value>::deserialize(deserializer)?;
+    if messages.is_empty() {
+        return Err(de::Error::invalid_length(0, &"at least one message"));
+    }
+
+    Ok(messages)
+}
+
+/// Items of `join` with their separator first: an array starting with the separator, or an
+/// object with the separator (`sep`) and the items
+fn deserialize_join<'de, D>(deserializer: D) -> Result<Vec<Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Join {
+        sep: Value,
+        items: Vec<Value>,
+    }
+
+    match serde_value::Value::deserialize(deserializer)? {
+        raw @ serde_value::Value::Map(_) => {
+            let join = Join::deserialize(serde_value::ValueDeserializer::<D::Error>::new(raw))?;
+            Ok(std::iter::once(join.sep).chain(join.items).collect())
+        }
+        raw => Vec::<Value>::deserialize(serde_value::ValueDeserializer::<D::Error>::new(raw)),
+    }
+}
+
+fn default_map_entry() -> String {
+    "%(key)s: %(value)s".to_string()
+}
+
+fn default_map_sep() -> String {
+    ", ".to_string()
+}
+
+/// State shared by all the values of a message while rendering
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    scope: Scope<'a>,
+    merge: ArgsMerge,
+    vars: &'a HashMap<String, Value>,
+    refs: Option<&'a Refs<'a>>,
+    observer: Option<&'a dyn MissingTranslationObserver>,
+    empty_msgid: EmptyMsgid,
+    pseudo: bool,
+    backend: &'a dyn Backend,
+    now: Option<i64>,
+    timezone: TimeZone,
+    locale: Option<&'a str>,
+    decoding: Decoding,
+    interner: Option<&'a Interner>,
+    placeholders: PlaceholderMode,
+    format: FormatOptions,
+    metrics: Option<&'a dyn Metrics>,
+    lookups: &'a Lookups,
+    meta: Option<&'a RefCell<RenderMeta>>,
+    segments: Option<&'a RefCell<Vec<Segment>>>,
+    missing: Option<&'a Cell<bool>>,
+    domain: Option<&'a str>,
+    depth: usize,
+}
+
+/// Translations already given by the backend while rendering a message: the repeated messages
+/// (e.g. in `foreach`) are looked up only once
+type Lookups = RefCell<HashMap<LookupKey, Option<Result<String, String>>>>;
+
+/// Text domain, context, msgid, plural form and count, locale category and locale of a lookup
+type LookupKey = (
+    Option<String>,
+    Option<String>,
+    String,
+    Option<(String, u64)>,
+    Option<i32>,
+    Option<String>,
+);
+
+impl<'a> Context<'a> {
+    fn with_scope<'b>(&self, scope: Scope<'b>) -> Context<'b>
+    where
+        'a: 'b,
+    {
+        Context {
+            scope,
+            merge: self.merge,
+            vars: self.vars,
+            refs: self.refs,
+            observer: self.observer,
+            empty_msgid: self.empty_msgid,
+            pseudo: self.pseudo,
+            backend: self.backend,
+            now: self.now,
+            timezone: self.timezone,
+            locale: self.locale,
+            decoding: self.decoding,
+            interner: self.interner,
+            placeholders: self.placeholders,
+            format: self.format,
+            metrics: self.metrics,
+            lookups: self.lookups,
+            meta: self.meta,
+            segments: self.segments,
+            missing: self.missing,
+            domain: self.domain,
+            depth: self.depth,
+        }
+    }
+
+    /// Current time in seconds since the epoch
+    fn now(&self) -> i64 {
+        self.now.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs() as i64)
+                .unwrap_or(0)
+        })
+    }
+
+    /// Locale of a category: the locale of the message if there is one, the locale of the
+    /// environment otherwise
+    fn locale(&self, category: &str) -> Option<String> {
+        self.locale
+            .map(ToString::to_string)
+            .or_else(|| env_locale(category))
+    }
+
+    /// Lookup in the text domain of `with_domain` if it has no text domain
+    fn scoped<'l>(&self, lookup: Lookup<'l>) -> Lookup<'l>
+    where
+        'a: 'l,
+    {
+        Lookup {
+            domain: lookup.domain.or(self.domain),
+            locale: lookup.locale.or(self.locale),
+            ..lookup
+        }
+    }
+
+    /// Translation given by the backend, looked up only once per rendering (or once for all the
+    /// renderings sharing an interner)
+    fn lookup(&self, lookup: &Lookup) -> Option<Result<String, String>> {
+        let lookup = &self.scoped(*lookup);
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+        let translated = self.cached_lookup(lookup);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            domain = lookup.domain,
+            context = lookup.context,
+            msgid = lookup.msgid,
+            found = translated.is_some(),
+            duration_us = micros(start),
+            "catalog lookup",
+        );
+
+        translated
+    }
+
+    fn cached_lookup(&self, lookup: &Lookup) -> Option<Result<String, String>> {
+        if let Some(interner) = self.interner {
+            return interner.translate(self.backend, lookup);
+        }
+
+        let key = (
+            lookup.domain.map(ToString::to_string),
+            lookup.context.map(ToString::to_string),
+            lookup.msgid.to_string(),
+            lookup.plural.map(|(plural, n)| (plural.to_string(), n)),
+            lookup.category.map(|x| x as i32),
+            lookup.locale.map(ToString::to_string),
+        );
+        if let Some(translated) = self.lookups.borrow().get(&key) {
+            return translated.clone();
+        }
+
+        let translated = self.backend.translate_checked(lookup);
+        self.lookups.borrow_mut().insert(key, translated.clone());
+        translated
+    }
+
+    /// Translation given by the backend, decoded as requested if it is not valid UTF-8
+    fn decode(
+        &self,
+        msgid: &str,
+        translated: Option<Result<String, String>>,
+    ) -> Result<Option<String>, Error> {
+        match (translated, self.decoding) {
+            (Some(Ok(translated)), _) | (Some(Err(translated)), Decoding::Lossy) => {
+                Ok(Some(translated))
+            }
+            (Some(Err(_)), Decoding::Strict) => Err(Error::InvalidEncoding(format!(
+                "translation is not valid UTF-8: {}",
+                msgid
+            ))),
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// Pseudo-localize the translated message if requested
+    fn localized(&self, message: String) -> String {
+        if self.pseudo {
+            pseudo_localize(&message)
+        } else {
+            message
+        }
+    }
+}
+
+/// Maximum nesting of the messages, the rendering is recursive
+const MAX_DEPTH: usize = 128;
+
+/// Chain of the variables being resolved, used to detect circular references
+struct Refs<'a> {
+    name: &'a str,
+    parent: Option<&'a Refs<'a>>,
+}
+
+impl Refs<'_> {
+    fn contains(&self, name: &str) -> bool {
+        self.name == name || self.parent.map(|x| x.contains(name)).unwrap_or(false)
+    }
+}
+
+/// Rendered keyword arguments of a message, the last ones take precedence
+type Args = Vec<(String, Arg)>;
+
+fn get_arg<'a>(args: &'a [(String, Arg)], key: &str) -> Option<ArgRef<'a>> {
+    args.iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| ArgRef {
+            text: &v.text,
+            nested: v.nested,
+        })
+}
+
+/// Keyword arguments visible by a message without its own: the arguments of the messages it is
+/// nested in (merged with `deep` or the fields of a `foreach` item), then the base arguments and
+/// the default arguments of the process
+#[derive(Clone, Copy)]
+enum Scope<'a> {
+    Base(&'a HashMap<String, String>, &'a HashMap<String, String>),
+    Nested {
+        args: &'a [(String, Arg)],
+        parent: &'a Scope<'a>,
+    },
+}
+
+impl<'a> Scope<'a> {
+    fn get(&self, key: &str) -> Option<ArgRef<'a>> {
+        match self {
+            Scope::Base(args, defaults) => {
+                args.get(key).or_else(|| defaults.get(key)).map(|x| ArgRef {
+                    text: x,
+                    nested: false,
+                })
+            }
+            Scope::Nested { args, parent } => get_arg(args, key).or_else(|| parent.get(key)),
+        }
+    }
 }
 
 impl Value {
-    fn try_into_string(self, base_map: &HashMap<String, String>) -> Result<String, Error> {
-        let mut map = HashMap::new();
+    fn try_into_string(self, ctx: &Context) -> Result<String, Error> {
+        let mut res = String::new();
+        self.write_to(&mut res, ctx)?;
+
+        Ok(res)
+    }
+
+    /// Render the value as an argument of a message: the nested messages escaped their own
+    /// arguments and their markup is kept, the other values are escaped by the message
+    fn try_into_arg(self, ctx: &Context) -> Result<Arg, Error> {
+        let nested = self.is_formatted();
+
+        Ok(Arg {
+            text: self.try_into_string(ctx)?,
+            nested,
+        })
+    }
+
+    /// Render the value at the end of a writer, the output is incomplete if it fails
+    fn write_to(self, out: &mut dyn fmt::Write, ctx: &Context) -> Result<(), Error> {
+        if ctx.depth >= MAX_DEPTH {
+            return Err(Error::LimitExceeded(format!(
+                "messages nested deeper than {} levels",
+                MAX_DEPTH
+            )));
+        }
+        let ctx = &Context {
+            depth: ctx.depth + 1,
+            ..*ctx
+        };
 
         match self {
-            Value::Text(x) => Ok(x.to_string()),
-            Value::Integer(x) => Ok(x.to_string()),
-            Value::Float(x) => Ok(x.to_string()),
-            Value::Bool(x) => Ok(if x {
-                gettextrs::gettext(b"yes" as &[u8])
-            } else {
-                gettextrs::gettext(b"no" as &[u8])
-            }),
-            Value::Unit(()) => Ok(gettextrs::gettext(b"n/a" as &[u8])),
-            Value::Datetime(x) => Ok(x.to_string()),
-            Value::Array(xs) => Ok({
-                let mut it = xs.into_iter();
-                let sep: String = match it.next() {
-                    Some(x) => x.try_into_string(base_map),
-                    None => Err(Error::MissingJoinSeparator),
-                }?;
-
-                let mut vec: Vec<String> = Vec::new();
-
-                for value in it {
-                    vec.push(value.try_into_string(base_map)?);
+            Value::Text(x) => Ok(out.write_str(&x)?),
+            Value::Integer(x) => Self::write_display(&x, out),
+            Value::Float(x) => Self::write_display(&x, out),
+            #[cfg(feature = "decimal")]
+            Value::Decimal { decimal } => Self::write_display(&decimal, out),
+            Value::Uuid { uuid } => Self::write_display(&uuid, out),
+            Value::Bytes { bytes, encoding } => Ok(encoding.write(&bytes.0, out)?),
+            #[cfg(feature = "phone")]
+            Value::Phone {
+                phone,
+                format,
+                region,
+            } => Self::write_display(
+                &phone::format(
+                    &phone,
+                    format,
+                    region.as_deref(),
+                    ctx.locale("LC_TELEPHONE").as_deref(),
+                ),
+                out,
+            ),
+            Value::Measure {
+                measure,
+                unit,
+                system,
+                precision,
+            } => measure::write(measure, &unit, system, precision, out, ctx),
+            Value::Bool(x) => {
+                Self::write_rendered(Self::translate_word(if x { "yes" } else { "no" }, ctx), out)
+            }
+            Value::Unit(()) => Self::write_rendered(Self::translate_word("n/a", ctx), out),
+            Value::Datetime(x) => Self::write_rendered(x.render(ctx), out),
+            Value::Array(xs) => Self::join(xs, &JoinOptions::default(), out, ctx),
+            Value::Join {
+                join,
+                sort,
+                collate,
+                unique,
+                skip_empty,
+            } => {
+                let options = JoinOptions {
+                    sort,
+                    collate,
+                    unique,
+                    skip_empty,
+                };
+                Self::join(join, &options, out, ctx)
+            }
+            Value::Ref { name } => Self::write_ref(&name, out, ctx),
+            Value::Filtered {
+                value,
+                filters,
+                precision,
+                rounding,
+            } => Self::write_filtered(*value, &filters, precision, rounding, out, ctx),
+            Value::Map { map, entry, sep } => Self::write_map(map, &entry, &sep, out, ctx),
+            Value::Foreach { foreach } => Self::write_foreach(foreach, out, ctx),
+            Value::Table { table, align, sep } => {
+                table::write(table, &align, sep.as_deref(), out, ctx)
+            }
+            Value::FirstOf { first_of } => Self::first_of(first_of, out, ctx),
+            Value::IfTranslated { if_translated } => Self::if_translated(if_translated, out, ctx),
+            Value::Choice { choice, args } => Self::choice(choice, args, out, ctx),
+            Value::Env { env } => Self::write_rendered(environment::var(&env), out),
+            Value::LangInfo { langinfo, index } => {
+                Self::write_rendered(langinfo::langinfo(langinfo, index), out)
+            }
+            Value::Address { address } => address.write_to(out, ctx),
+            Value::PersonName { person_name } => person_name.write_to(out, ctx),
+            Value::Fragment(x) => x.write_to(out, ctx),
+            Value::FormattedText {
+                text,
+                args,
+                defaults,
+            } => Self::format(text.as_ref(), args, defaults, Args::new(), out, ctx),
+            // extracted but not translated yet
+            Value::Noop {
+                noop,
+                args,
+                defaults,
+                ..
+            } => Self::format(&noop, args, defaults, Args::new(), out, ctx),
+            Value::GetText {
+                gettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(&gettext, None, args, defaults, default, out, ctx),
+            Value::NGetText {
+                ngettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(
+                &*ngettext,
+                Some(&ngettext.n),
+                args,
+                defaults,
+                default,
+                out,
+                ctx,
+            ),
+            Value::PGetText {
+                pgettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(&pgettext, None, args, defaults, default, out, ctx),
+            Value::DGetText {
+                dgettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(&dgettext, None, args, defaults, default, out, ctx),
+            Value::DNGetText {
+                dngettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(
+                &*dngettext,
+                Some(&dngettext.n),
+                args,
+                defaults,
+                default,
+                out,
+                ctx,
+            ),
+            Value::NPGetText {
+                npgettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(
+                &*npgettext,
+                Some(&npgettext.n),
+                args,
+                defaults,
+                default,
+                out,
+                ctx,
+            ),
+            Value::DCNGetText {
+                dcngettext,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(
+                &*dcngettext,
+                Some(&dcngettext.n),
+                args,
+                defaults,
+                default,
+                out,
+                ctx,
+            ),
+            Value::SelectPlural {
+                selectplural,
+                args,
+                defaults,
+                default,
+                ..
+            } => Self::write_translated(
+                &*selectplural,
+                Some(&selectplural.n),
+                args,
+                defaults,
+                default,
+                out,
+                ctx,
+            ),
+        }
+    }
+
+    /// Render a scalar, apart from `write_to` to keep its frame small
+    #[inline(never)]
+    fn write_display(x: &dyn fmt::Display, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        Ok(write!(out, "{}", x)?)
+    }
+
+    /// Write the output of a value rendered aside, apart from `write_to` to keep its frame small
+    #[inline(never)]
+    fn write_rendered(
+        rendered: Result<String, Error>,
+        out: &mut dyn fmt::Write,
+    ) -> Result<(), Error> {
+        Ok(out.write_str(&rendered?)?)
+    }
+
+    /// Translate a message and replace its placeholders, the count of the plural messages is
+    /// available as `%(n)s`
+    #[inline(never)]
+    fn write_translated<T: Translatable>(
+        value: &T,
+        n: Option<&Count>,
+        args: Option<Formatter>,
+        defaults: HashMap<String, Value>,
+        default: Option<String>,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut map = Args::new();
+        if let Some(n) = n {
+            map.reserve(match args.as_ref() {
+                Some(Formatter::KeywordArgs(args)) => args.len() + 1,
+                _ => 1,
+            });
+            map.push(("n".to_string(), Arg::leaf(n.to_string())));
+        }
+        let message = Self::translate(value, default, ctx)?;
+
+        Self::format(&message, args, defaults, map, out, ctx)
+    }
+
+    /// Look up the message in the catalog. The observer is notified and the fallback message
+    /// is used when the catalog has no translation.
+    fn translate<T: Translatable>(
+        value: &T,
+        fallback: Option<String>,
+        ctx: &Context,
+    ) -> Result<String, Error> {
+        let lookup = ctx.scoped(value.lookup()?);
+
+        // gettext returns the header of the catalog for an empty msgid
+        if lookup.msgid.is_empty() {
+            return match ctx.empty_msgid {
+                EmptyMsgid::Empty => Ok(String::new()),
+                EmptyMsgid::Error => Err(Error::EmptyMsgid),
+            };
+        }
+
+        let translated = ctx
+            .decode(lookup.msgid, ctx.lookup(&lookup))?
+            .filter(|x| !x.is_empty());
+        if let Some(meta) = ctx.meta {
+            let found = translated.is_some();
+            meta.borrow_mut()
+                .lookups
+                .push(LookupMeta::new(&lookup, ctx.backend, found));
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            domain = lookup.domain,
+            msgid = lookup.msgid,
+            n = lookup.plural.map(|(_, n)| n),
+            plural_form = meta::plural_form(&lookup, ctx.backend, translated.is_some()),
+            found = translated.is_some(),
+            "translation",
+        );
+
+        match translated {
+            Some(translated) => Ok(ctx.localized(translated)),
+            None => {
+                if let Some(missing) = ctx.missing {
+                    missing.set(true);
+                }
+                if let Some(observer) = ctx.observer {
+                    observer.on_missing(
+                        lookup.domain,
+                        lookup.msgid,
+                        ctx.locale("LC_MESSAGES").as_deref(),
+                    );
+                }
+                if let Some(metrics) = ctx.metrics {
+                    metrics.on_missing_translation(
+                        lookup.domain,
+                        lookup.msgid,
+                        ctx.locale("LC_MESSAGES").as_deref(),
+                    );
                 }
 
-                vec.join(&sep)
-            }),
-            Value::FormattedText { text, args } => Self::format(text.as_ref(), args, map, base_map),
-            Value::GetText { gettext, args } => handle_gettext!(gettext, args, map, base_map),
-            Value::NGetText { ngettext, args } => handle_plural!(ngettext, args, map, base_map),
-            Value::PGetText { pgettext, args } => handle_gettext!(pgettext, args, map, base_map),
-            Value::DGetText { dgettext, args } => handle_gettext!(dgettext, args, map, base_map),
-            Value::DNGetText { dngettext, args } => handle_plural!(dngettext, args, map, base_map),
-            Value::NPGetText { npgettext, args } => handle_plural!(npgettext, args, map, base_map),
-            Value::DCNGetText { dcngettext, args } => {
-                handle_plural!(dcngettext, args, map, base_map)
+                Ok(ctx.localized(fallback.unwrap_or_else(|| lookup.untranslated().to_string())))
             }
         }
     }
 
+    /// Translate a message of the current text domain without notifying the observer
+    fn translate_word(msgid: &str, ctx: &Context) -> Result<String, Error> {
+        let translated = ctx.decode(msgid, ctx.lookup(&Lookup::new(msgid)))?;
+
+        Ok(ctx.localized(translated.unwrap_or_else(|| msgid.to_string())))
+    }
+
+    /// Render the value of a variable, the variables can't reference themselves
+    fn write_ref(name: &str, out: &mut dyn fmt::Write, ctx: &Context) -> Result<(), Error> {
+        if ctx.refs.map(|x| x.contains(name)).unwrap_or(false) {
+            return Err(Error::CircularReference(format!(
+                "circular reference to variable: {}",
+                name
+            )));
+        }
+
+        let value = ctx
+            .vars
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::UndefinedVariable(format!("undefined variable: {}", name)))?;
+        let refs = Refs {
+            name,
+            parent: ctx.refs,
+        };
+
+        value.write_to(
+            out,
+            &Context {
+                refs: Some(&refs),
+                ..*ctx
+            },
+        )
+    }
+
+    /// Render a value and apply the filters to its output
+    #[inline(never)]
+    fn write_filtered(
+        value: Value,
+        filters: &[Filter],
+        precision: Option<usize>,
+        rounding: Rounding,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut rendered = value.try_into_string(ctx)?;
+        if let Some(precision) = precision {
+            rendered = filters::round(rendered, precision, rounding)?;
+        }
+        let filtered = filters.iter().try_fold(rendered, |acc, filter| {
+            filter.apply(acc, ctx.locale("LC_CTYPE").as_deref())
+        })?;
+
+        Ok(out.write_str(&filtered)?)
+    }
+
+    /// Render the entries of a map with the `entry` template, separated by `sep`
+    #[inline(never)]
+    fn write_map(
+        map: BTreeMap<String, Value>,
+        entry: &str,
+        sep: &str,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        for (i, (key, value)) in map.into_iter().enumerate() {
+            let entry_map = [
+                ("key".to_string(), Arg::leaf(key)),
+                ("value".to_string(), value.try_into_arg(ctx)?),
+            ];
+
+            if i > 0 {
+                out.write_str(sep)?;
+            }
+            format_into(
+                out,
+                entry,
+                &UnionMap::new(&entry_map, ctx.scope),
+                ctx.format,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Render the template once for every item, with the fields of the item as arguments
+    #[inline(never)]
+    fn write_foreach(
+        foreach: ValueForeach,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let ValueForeach {
+            items,
+            template,
+            sep,
+        } = foreach;
+        for (i, item) in items.into_iter().enumerate() {
+            let mut values = Vec::with_capacity(item.len());
+            for (key, arg) in item.into_iter() {
+                arg.flatten_into(key, &mut values);
+            }
+
+            // the fields of the item take precedence over the base arguments
+            let fields = values
+                .into_iter()
+                .map(|(key, value)| Ok((key, value.try_into_arg(ctx)?)))
+                .collect::<Result<Args, Error>>()?;
+            let scope = Scope::Nested {
+                args: &fields,
+                parent: &ctx.scope,
+            };
+
+            if i > 0 {
+                out.write_str(&sep)?;
+            }
+            (*template).clone().write_to(out, &ctx.with_scope(scope))?;
+        }
+
+        Ok(())
+    }
+
+    /// Render the first candidate that has all its translations, or the last one
+    fn first_of(xs: Vec<Value>, out: &mut dyn fmt::Write, ctx: &Context) -> Result<(), Error> {
+        let mut it = xs.into_iter();
+        let mut candidate = match it.next() {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        // the candidates are rendered aside until one has all its translations, the last one is
+        // rendered even if it is not translated
+        for next in it {
+            let missing = Cell::new(false);
+            let mut res = String::new();
+            let rendered = candidate.write_to(
+                &mut res,
+                &Context {
+                    missing: Some(&missing),
+                    ..*ctx
+                },
+            );
+            if rendered.is_ok() && !missing.get() {
+                return Ok(out.write_str(&res)?);
+            }
+            candidate = next;
+        }
+
+        candidate.write_to(out, ctx)
+    }
+
+    /// Render the branch selected by the translation of the msgid
+    fn if_translated(
+        x: ValueIfTranslated,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let branch = if x.is_translated(ctx)? {
+            x.then
+        } else {
+            x.otherwise
+        };
+
+        match branch {
+            Some(x) => x.write_to(out, ctx),
+            None => Ok(()),
+        }
+    }
+
+    /// Render the case selected by the key formatted with the arguments of the choice, then
+    /// with the keyword arguments in scope. The keyword arguments of the choice are in the scope
+    /// of the cases, like the fields of the items of `foreach`.
+    #[inline(never)]
+    fn choice(
+        x: ValueChoice,
+        args: Option<Formatter>,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let (map, positional) = match args {
+            Some(Formatter::KeywordArgs(kwargs)) => {
+                let mut values = Vec::with_capacity(kwargs.len());
+                for (key, arg) in kwargs.into_iter() {
+                    arg.flatten_into(key, &mut values);
+                }
+                (Self::render_args(values, ctx)?, None)
+            }
+            Some(Formatter::PositionalArgs(args)) => {
+                let mut rendered = Vec::with_capacity(args.len());
+                for value in args.into_iter() {
+                    rendered.push(value.try_into_arg(ctx)?);
+                }
+                (Args::new(), Some(rendered))
+            }
+            None => (Args::new(), None),
+        };
+        let mut key = String::new();
+        match positional {
+            Some(args) => format_into(&mut key, &x.key, args.as_slice(), ctx.format)?,
+            None => format_into(
+                &mut key,
+                &x.key,
+                &UnionMap::new(&map, ctx.scope),
+                ctx.format,
+            )?,
+        }
+        let ValueChoice {
+            mut cases, default, ..
+        } = x;
+
+        let scope = Scope::Nested {
+            args: &map,
+            parent: &ctx.scope,
+        };
+        match cases.remove(&key).or_else(|| default.map(|x| *x)) {
+            Some(case) if map.is_empty() => case.write_to(out, ctx),
+            Some(case) => case.write_to(out, &ctx.with_scope(scope)),
+            None => Err(Error::MissingForm(format!("no case for key: {}", key))),
+        }
+    }
+
+    fn join(
+        xs: Vec<Value>,
+        options: &JoinOptions,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut it = xs.into_iter();
+        let sep: String = match it.next() {
+            Some(x) => x.try_into_string(ctx),
+            None => Err(Error::MissingJoinSeparator),
+        }?;
+
+        // the items are appended as they are rendered, unless they must be sorted or filtered
+        // first
+        if !options.sort && !options.unique && !options.skip_empty {
+            for (i, value) in it.enumerate() {
+                if i > 0 {
+                    out.write_str(&sep)?;
+                }
+                value.write_to(out, ctx)?;
+            }
+
+            return Ok(());
+        }
+
+        let mut vec = it
+            .map(|x| x.try_into_string(ctx))
+            .collect::<Result<Vec<String>, _>>()?;
+        if options.skip_empty {
+            vec.retain(|x| !x.trim().is_empty());
+        }
+        if options.unique {
+            let mut seen = HashSet::new();
+            vec.retain(|x| seen.insert(x.clone()));
+        }
+        match (options.sort, options.collate) {
+            (true, true) => collate::sort(&mut vec, ctx.locale("LC_COLLATE").as_deref()),
+            (true, false) => vec.sort(),
+            (false, _) => {}
+        }
+
+        Ok(out.write_str(&vec.join(&sep))?)
+    }
+
+    /// Check if the value is rendered by formatting a template with its arguments
+    fn is_formatted(&self) -> bool {
+        matches!(
+            self,
+            Value::FormattedText { .. }
+                | Value::Noop { .. }
+                | Value::GetText { .. }
+                | Value::NGetText { .. }
+                | Value::PGetText { .. }
+                | Value::DGetText { .. }
+                | Value::DNGetText { .. }
+                | Value::NPGetText { .. }
+                | Value::DCNGetText { .. }
+                | Value::SelectPlural { .. }
+                | Value::Map { .. }
+                | Value::Foreach { .. }
+                | Value::Address { .. }
+        )
+    }
+
+    fn is_scalar(&self) -> bool {
+        match self {
+            Value::Text(_)
+            | Value::Integer(_)
+            | Value::Float(_)
+            | Value::Uuid { .. }
+            | Value::Bytes { .. }
+            | Value::Bool(_)
+            | Value::Unit(())
+            | Value::Datetime(_) => true,
+            #[cfg(feature = "decimal")]
+            Value::Decimal { .. } => true,
+            #[cfg(feature = "phone")]
+            Value::Phone { .. } => true,
+            _ => false,
+        }
+    }
+
     fn format(
         message: &str,
         formatter: Option<Formatter>,
-        mut map: HashMap<String, String>,
-        base_map: &HashMap<String, String>,
-    ) -> Result<String, Error> {
+        defaults: HashMap<String, Value>,
+        mut map: Args,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        // the arguments are not rendered, only the translation is
+        if ctx.placeholders == PlaceholderMode::Tokens {
+            return format_tokens(out, message);
+        }
+
         match formatter {
             Some(Formatter::KeywordArgs(kwargs)) => {
-                for (key, value) in kwargs.into_iter() {
-                    map.insert(key, value.try_into_string(base_map)?);
-                }
-
-                PythonFormat
-                    .format(message, UnionMap::new(&map, base_map))
-                    .map_err(|err| Error::FormatError(format!("{}", err)))
-                    .map(|x| x.to_string())
+                Self::format_kwargs(message, kwargs, defaults, map, out, ctx)
+            }
+            Some(Formatter::PositionalArgs(args)) => {
+                Self::format_positional(message, args, &map, out, ctx)
             }
-            Some(Formatter::PositionalArgs(args)) => PythonFormat
-                .format(
+            None => {
+                Self::apply_defaults(defaults, &mut map, ctx)?;
+
+                Self::format_args(
+                    out,
                     message,
-                    args.into_iter()
-                        .map(|x| x.try_into_string(base_map))
-                        .collect::<Result<Vec<String>, _>>()?,
+                    &UnionMap::new(&map, ctx.scope).recorded(ctx.meta),
+                    ctx,
                 )
-                .map_err(|err| Error::FormatError(format!("{}", err)))
-                .map(|x| x.to_string()),
-            None => PythonFormat
-                .format(message, UnionMap::new(&map, base_map))
-                .map_err(|err| Error::FormatError(format!("{}", err)))
-                .map(|x| x.to_string()),
+            }
+        }
+    }
+
+    /// Replace the placeholders with the keyword arguments, the scalars are rendered first so
+    /// they can be merged into the nested messages
+    #[inline(never)]
+    fn format_kwargs(
+        message: &str,
+        kwargs: HashMap<String, KeywordArg>,
+        defaults: HashMap<String, Value>,
+        mut map: Args,
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let mut values = Vec::with_capacity(kwargs.len());
+        for (key, arg) in kwargs.into_iter() {
+            arg.flatten_into(key, &mut values);
+        }
+
+        let (scalars, nested): (Vec<_>, Vec<_>) =
+            values.into_iter().partition(|(_, value)| value.is_scalar());
+        let scalars = Self::render_args(scalars, ctx)?;
+        map.extend(scalars);
+
+        // the arguments of the current message take precedence over the base arguments
+        let scope = Scope::Nested {
+            args: &map,
+            parent: &ctx.scope,
+        };
+        let rendered = match ctx.merge {
+            ArgsMerge::Deep if !nested.is_empty() => {
+                Self::render_args(nested, &ctx.with_scope(scope))?
+            }
+            _ => Self::render_args(nested, ctx)?,
+        };
+        map.extend(rendered);
+
+        Self::apply_defaults(defaults, &mut map, ctx)?;
+
+        Self::format_args(
+            out,
+            message,
+            &UnionMap::new(&map, ctx.scope).recorded(ctx.meta),
+            ctx,
+        )
+    }
+
+    /// Render the keyword arguments of a message
+    #[inline(never)]
+    fn render_args(values: Vec<(String, Value)>, ctx: &Context) -> Result<Args, Error> {
+        let mut rendered = Args::with_capacity(values.len());
+        for (key, value) in values.into_iter() {
+            let value = value.try_into_arg(ctx)?;
+            rendered.push((key, value));
+        }
+
+        Ok(rendered)
+    }
+
+    /// Replace the placeholders with the positional arguments
+    #[inline(never)]
+    fn format_positional(
+        message: &str,
+        args: Vec<Value>,
+        map: &[(String, Arg)],
+        out: &mut dyn fmt::Write,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        let scope = Scope::Nested {
+            args: map,
+            parent: &ctx.scope,
+        };
+        let nested_ctx = match ctx.merge {
+            ArgsMerge::Deep if !map.is_empty() => ctx.with_scope(scope),
+            _ => *ctx,
+        };
+
+        let mut rendered = Vec::with_capacity(args.len());
+        for value in args.into_iter() {
+            rendered.push(value.try_into_arg(&nested_ctx)?);
+        }
+
+        Self::format_args(out, message, rendered.as_slice(), ctx)
+    }
+
+    /// Replace the placeholders of the message, in the segments of the rendering if it is the
+    /// top-level message
+    fn format_args<A: FormatArgs + ?Sized>(
+        out: &mut dyn fmt::Write,
+        message: &str,
+        args: &A,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+        let res = match ctx.segments {
+            Some(segments) if ctx.depth == 1 => {
+                format_segments(message, args, ctx.format).map(|x| segments.borrow_mut().extend(x))
+            }
+            _ => format_into(out, message, args, ctx.format),
+        };
+        if let (Some(metrics), Err(err)) = (ctx.metrics, &res) {
+            metrics.on_format_error(err);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            len = message.len(),
+            duration_us = micros(start),
+            ok = res.is_ok(),
+            "format",
+        );
+
+        res
+    }
+
+    /// Render the default values of the keyword arguments that have not been provided.
+    fn apply_defaults(
+        defaults: HashMap<String, Value>,
+        map: &mut Args,
+        ctx: &Context,
+    ) -> Result<(), Error> {
+        for (key, value) in defaults.into_iter() {
+            if get_arg(map, &key).is_none() && ctx.scope.get(&key).is_none() {
+                let value = value.try_into_arg(ctx)?;
+                map.push((key, value));
+            }
         }
+
+        Ok(())
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
-#[serde(untagged)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 enum Formatter {
-    KeywordArgs(HashMap<String, Value>),
+    KeywordArgs(HashMap<String, KeywordArg>),
     PositionalArgs(Vec<Value>),
 }
 
-struct UnionMap<'a>(&'a HashMap<String, String>, &'a HashMap<String, String>);
+/// Keyword argument: the objects can be accessed by dotted path (e.g. `%(user.name)s`)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+enum KeywordArg {
+    Value(Box<Value>),
+    Object(HashMap<String, KeywordArg>),
+}
+
+impl KeywordArg {
+    fn flatten_into(self, key: String, values: &mut Vec<(String, Value)>) {
+        match self {
+            KeywordArg::Value(value) => values.push((key, *value)),
+            KeywordArg::Object(map) => {
+                for (sub_key, arg) in map.into_iter() {
+                    arg.flatten_into(format!("{}.{}", key, sub_key), values);
+                }
+            }
+        }
+    }
+}
+
+/// Microseconds elapsed since an instant, for the durations of the events
+#[cfg(feature = "tracing")]
+fn micros(start: Instant) -> u64 {
+    u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX)
+}
+
+/// Locale of a category from the environment, as used by `setlocale`
+fn env_locale(category: &str) -> Option<String> {
+    ["LC_ALL", category, "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+}
+
+struct UnionMap<'a>(
+    &'a [(String, Arg)],
+    Scope<'a>,
+    Option<&'a RefCell<RenderMeta>>,
+);
 
 impl<'a> UnionMap<'a> {
-    fn new(a: &'a HashMap<String, String>, b: &'a HashMap<String, String>) -> UnionMap<'a> {
-        UnionMap(a, b)
+    fn new(a: &'a [(String, Arg)], b: Scope<'a>) -> UnionMap<'a> {
+        UnionMap(a, b, None)
+    }
+
+    /// Record the keys found in the metadata of the rendering
+    fn recorded(self, meta: Option<&'a RefCell<RenderMeta>>) -> UnionMap<'a> {
+        UnionMap(self.0, self.1, meta)
     }
 }
 
 impl FormatArgs for UnionMap<'_> {
-    fn get_key(&self, key: &str) -> Result<Option<Argument<'_>>, ()> {
-        Ok(self
-            .0
-            .get(key)
-            .or_else(|| self.1.get(key))
-            .map(|x| x as Argument<'_>))
+    fn get_key(&self, key: &str) -> Result<Option<ArgRef<'_>>, ()> {
+        let value = get_arg(self.0, key).or_else(|| self.1.get(key));
+        if let (Some(meta), Some(_)) = (self.2, value) {
+            meta.borrow_mut().args.insert(key.to_string());
+        }
+
+        Ok(value)
+    }
+
+    fn get_index(&self, _index: usize) -> Result<Option<ArgRef<'_>>, ()> {
+        Err(())
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct DatetimeValue {
     strftime: String,
-    epoch: i64,
+    epoch: Option<i64>,
 }
 
-impl ToString for DatetimeValue {
-    fn to_string(&self) -> String {
-        strftime_local(&self.strftime, self.epoch)
+impl DatetimeValue {
+    fn render(&self, ctx: &Context) -> Result<String, Error> {
+        let epoch = self.epoch.unwrap_or_else(|| ctx.now());
+
+        // libc can't represent the dates outside of this range
+        if !(MIN_EPOCH..=MAX_EPOCH).contains(&epoch) {
+            return Err(Error::LimitExceeded(format!(
+                "epoch out of range: {}",
+                epoch
+            )));
+        }
+        if self.strftime.contains('\0') {
+            return Err(Error::FormatError(
+                "strftime format contains a NUL character".to_string(),
+            ));
+        }
+
+        Ok(match ctx.timezone {
+            TimeZone::Local => strftime_local(&self.strftime, epoch),
+            TimeZone::Utc => strftime_gmt(&self.strftime, epoch),
+        })
     }
 }
 
+/// 0001-01-01T00:00:00Z
+const MIN_EPOCH: i64 = -62_135_596_800;
+/// 9999-12-31T23:59:59Z
+const MAX_EPOCH: i64 = 253_402_300_799;
+
+/// Messages looked up in the catalog
+trait Translatable {
+    fn lookup(&self) -> Result<Lookup<'_>, Error>;
+}
+
 #[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct ValueForeach {
+    items: Vec<HashMap<String, KeywordArg>>,
+    template: Box<Value>,
+    #[serde(default)]
+    sep: String,
+}
+
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct ValueIfTranslated {
+    domain: Option<String>,
+    #[serde(alias = "context")]
+    ctx: Option<String>,
+    msgid: String,
+    then: Option<Box<Value>>,
+    #[serde(rename = "else")]
+    otherwise: Option<Box<Value>>,
+}
+
+impl ValueIfTranslated {
+    /// Check if the catalog has a translation of the msgid, without notifying the observer
+    fn is_translated(&self, ctx: &Context) -> Result<bool, Error> {
+        if self.msgid.is_empty() {
+            return Ok(false);
+        }
+        let lookup = self.lookup()?;
+
+        Ok(matches!(ctx.lookup(&lookup), Some(Ok(x)) | Some(Err(x)) if !x.is_empty()))
+    }
+}
+
+impl Translatable for ValueIfTranslated {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup {
+            domain: self.domain.as_deref(),
+            context: self.ctx.as_deref(),
+            ..Lookup::new(&self.msgid)
+        })
+    }
+}
+
+/// Location of a message in the code it is generated from, extracted as a reference (`#:`)
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct ValueSource {
+    file: String,
+    line: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct ValueChoice {
+    key: String,
+    cases: BTreeMap<String, Value>,
+    default: Option<Box<Value>>,
+}
+
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValueGetText(String);
 
-impl ToString for ValueGetText {
-    fn to_string(&self) -> String {
-        gettextrs::gettext(self.0.as_bytes())
+impl Translatable for ValueGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup::new(&self.0))
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValueNGetText {
+    #[serde(alias = "msgid")]
     singular: String,
+    #[serde(alias = "msgid_plural")]
     plural: String,
-    n: u32,
+    #[serde(alias = "count")]
+    n: Count,
+    #[serde(flatten)]
+    counts: ExplicitCounts,
 }
 
-impl ToString for ValueNGetText {
-    fn to_string(&self) -> String {
-        gettextrs::ngettext(self.singular.as_bytes(), self.plural.as_bytes(), self.n)
+impl Translatable for ValueNGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(self.counts.lookup(self.n, &self.singular, &self.plural))
     }
 }
 
 #[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct ValueSelectPlural {
+    select: String,
+    #[serde(alias = "count")]
+    n: Count,
+    forms: HashMap<String, PluralForms>,
+}
+
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct PluralForms {
+    #[serde(alias = "msgid")]
+    singular: String,
+    #[serde(alias = "msgid_plural")]
+    plural: String,
+}
+
+impl ValueSelectPlural {
+    /// Forms of `select` (or `other` if there is none)
+    fn forms(&self) -> Result<&PluralForms, Error> {
+        self.forms
+            .get(&self.select)
+            .or_else(|| self.forms.get("other"))
+            .ok_or_else(|| Error::MissingForm(format!("no forms for: {}", self.select)))
+    }
+}
+
+impl Translatable for ValueSelectPlural {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        let forms = self.forms()?;
+
+        Ok(Lookup {
+            plural: Some((&forms.plural, self.n.selector())),
+            ..Lookup::new(&forms.singular)
+        })
+    }
+}
+
+/// Count of a plural message: any integer or a string containing an integer
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct Count(i128);
+
+impl Count {
+    /// Number given to gettext to select the plural form: the numbers beyond `u64` are reduced
+    /// to a number with the same last 6 digits, which is enough for the plural rules
+    fn selector(self) -> u64 {
+        let n = self.0.unsigned_abs();
+
+        u64::try_from(n).unwrap_or_else(|_| (n % 1_000_000) as u64 + 1_000_000)
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<'de> Deserialize<'de> for Count {
+    fn deserialize<D>(deserializer: D) -> Result<Count, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CountVisitor;
+
+        impl<'de> Visitor<'de> for CountVisitor {
+            type Value = Count;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an integer or a string containing an integer")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Count, E> {
+                Ok(Count(value.into()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Count, E> {
+                Ok(Count(value.into()))
+            }
+
+            // the formats with strict typing (TOML) may give integers as floats (`n = 5.0`)
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Count, E> {
+                if value.fract() == 0.0 && value.abs() < 1e18 {
+                    Ok(Count(value as i128))
+                } else {
+                    Err(E::invalid_value(de::Unexpected::Float(value), &self))
+                }
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Count, E> {
+                value
+                    .trim()
+                    .parse()
+                    .map(Count)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_any(CountVisitor)
+    }
+}
+
+/// Messages used instead of the plural forms of the catalog for specific counts
+#[derive(Deserialize, Clone, Debug, Default, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+struct ExplicitCounts {
+    zero: Option<String>,
+    one: Option<String>,
+    two: Option<String>,
+}
+
+impl ExplicitCounts {
+    fn get(&self, n: Count) -> Option<&str> {
+        match n.0 {
+            0 => self.zero.as_deref(),
+            1 => self.one.as_deref(),
+            2 => self.two.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Look up the explicit message of the count if there is one, the plural forms otherwise
+    fn lookup<'a>(&'a self, n: Count, singular: &'a str, plural: &'a str) -> Lookup<'a> {
+        match self.get(n) {
+            Some(msgid) => Lookup::new(msgid),
+            None => Lookup {
+                plural: Some((plural, n.selector())),
+                ..Lookup::new(singular)
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValuePGetText {
+    #[serde(alias = "context")]
     ctx: String,
     msgid: String,
 }
 
-impl ToString for ValuePGetText {
-    fn to_string(&self) -> String {
-        gettextrs::pgettext(self.ctx.as_bytes(), self.msgid.as_bytes())
+impl Translatable for ValuePGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup {
+            context: Some(&self.ctx),
+            ..Lookup::new(&self.msgid)
+        })
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValueDGetText {
     domain: String,
     msgid: String,
 }
 
-impl ToString for ValueDGetText {
-    fn to_string(&self) -> String {
-        gettextrs::dgettext(self.domain.as_bytes(), self.msgid.as_bytes())
+impl Translatable for ValueDGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup {
+            domain: Some(&self.domain),
+            ..Lookup::new(&self.msgid)
+        })
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValueDNGetText {
     domain: String,
+    #[serde(alias = "msgid")]
     singular: String,
+    #[serde(alias = "msgid_plural")]
     plural: String,
-    n: u32,
+    #[serde(alias = "count")]
+    n: Count,
+    #[serde(flatten)]
+    counts: ExplicitCounts,
 }
 
-impl ToString for ValueDNGetText {
-    fn to_string(&self) -> String {
-        gettextrs::dngettext(
-            self.domain.as_bytes(),
-            self.singular.as_bytes(),
-            self.plural.as_bytes(),
-            self.n,
-        )
+impl Translatable for ValueDNGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup {
+            domain: Some(&self.domain),
+            ..self.counts.lookup(self.n, &self.singular, &self.plural)
+        })
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValueNPGetText {
+    #[serde(alias = "context")]
     ctx: String,
+    #[serde(alias = "msgid")]
     singular: String,
+    #[serde(alias = "msgid_plural")]
     plural: String,
-    n: u32,
+    #[serde(alias = "count")]
+    n: Count,
+    #[serde(flatten)]
+    counts: ExplicitCounts,
 }
 
-impl ToString for ValueNPGetText {
-    fn to_string(&self) -> String {
-        gettextrs::npgettext(
-            self.ctx.as_bytes(),
-            self.singular.as_bytes(),
-            self.plural.as_bytes(),
-            self.n,
-        )
+impl Translatable for ValueNPGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup {
+            context: Some(&self.ctx),
+            ..self.counts.lookup(self.n, &self.singular, &self.plural)
+        })
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 struct ValueDCNGetText {
     domain: String,
+    #[serde(alias = "msgid")]
     singular: String,
+    #[serde(alias = "msgid_plural")]
     plural: String,
-    n: u32,
+    #[serde(alias = "count")]
+    n: Count,
     category: LocaleCategory,
+    #[serde(flatten)]
+    counts: ExplicitCounts,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[allow(clippy::enum_variant_names)]
 enum LocaleCategory {
     #[serde(rename = "ctype")]
@@ -584,14 +3460,12 @@ impl std::convert::From<LocaleCategory> for gettextrs::LocaleCategory {
     }
 }
 
-impl ToString for ValueDCNGetText {
-    fn to_string(&self) -> String {
-        gettextrs::dcngettext(
-            self.domain.as_bytes(),
-            self.singular.as_bytes(),
-            self.plural.as_bytes(),
-            self.n,
-            self.category.into(),
-        )
+impl Translatable for ValueDCNGetText {
+    fn lookup(&self) -> Result<Lookup<'_>, Error> {
+        Ok(Lookup {
+            domain: Some(&self.domain),
+            category: Some(self.category.into()),
+            ..self.counts.lookup(self.n, &self.singular, &self.plural)
+        })
     }
 }
@@ -0,0 +1,99 @@
+//! Locale of the messages rendered by the current thread, scoped by a guard
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::ptr;
+
+thread_local! {
+    static LOCALE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Guard setting the locale of the messages rendered by the current thread until it is dropped,
+/// instead of changing the environment variables of the process (`LC_ALL`, `LANG`, ...)
+///
+/// The locale of a message (`SerdeGetText::locale`) takes precedence, the locale of the
+/// environment is used without guard. The translations are looked up in that locale
+/// (`Lookup::locale`): `GettextBackend` switches the current thread to it during the lookup
+/// (`uselocale`), the locales that aren't installed fall back to the locale of the process. The
+/// guards can be nested: dropping a guard restores the locale that was set when it was entered.
+/// A guard can't be sent to another thread, the asynchronous tasks must enter it in the code
+/// that is polled on the same thread (e.g. between two `await`).
+///
+/// ```rust
+/// use serde_gettext::{LocaleContext, SerdeGetText};
+///
+/// let message: SerdeGetText = serde_json::from_str(r#"{"gettext": "Hello!"}"#)?;
+/// {
+///     let _locale = LocaleContext::enter("de_DE");
+///     let (_, meta) = message.clone().render_with_meta()?;
+///     assert_eq!(meta.locale.as_deref(), Some("de_DE"));
+/// }
+/// assert_eq!(LocaleContext::current(), None);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use = "the locale is restored when the guard is dropped"]
+pub struct LocaleContext {
+    previous: Option<String>,
+    // the locale is set for the current thread only
+    _thread: PhantomData<*const ()>,
+}
+
+impl LocaleContext {
+    /// Set the locale of the messages rendered by the current thread
+    pub fn enter(locale: &str) -> LocaleContext {
+        LocaleContext {
+            previous: LOCALE.with(|x| x.borrow_mut().replace(locale.to_string())),
+            _thread: PhantomData,
+        }
+    }
+
+    /// Locale set on the current thread, or on the current task with the feature `tokio`
+    /// (`task::render_in_locale`)
+    pub fn current() -> Option<String> {
+        let locale = LOCALE.with(|x| x.borrow().clone());
+        #[cfg(feature = "tokio")]
+        let locale = locale.or_else(crate::task::current_locale);
+
+        locale
+    }
+}
+
+impl Drop for LocaleContext {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        LOCALE.with(|x| *x.borrow_mut() = previous);
+    }
+}
+
+/// Categories of a locale used by the current thread (`uselocale`) until the guard is dropped,
+/// the C library functions (`strcoll`, `gettext`, ...) follow it instead of the locale of the
+/// process
+pub(crate) struct ThreadLocale {
+    locale: libc::locale_t,
+    previous: libc::locale_t,
+}
+
+impl ThreadLocale {
+    /// `None` if the locale isn't installed
+    pub(crate) fn enter(mask: libc::c_int, locale: &CString) -> Option<ThreadLocale> {
+        let locale = unsafe { libc::newlocale(mask, locale.as_ptr(), ptr::null_mut()) };
+        if locale.is_null() {
+            return None;
+        }
+
+        Some(ThreadLocale {
+            locale,
+            previous: unsafe { libc::uselocale(locale) },
+        })
+    }
+}
+
+impl Drop for ThreadLocale {
+    fn drop(&mut self) {
+        unsafe {
+            libc::uselocale(self.previous);
+            libc::freelocale(self.locale);
+        }
+    }
+}
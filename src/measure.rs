@@ -0,0 +1,113 @@
+//! Quantities with a unit, converted to the measurement system of their region
+//! (`LC_MEASUREMENT`)
+
+use crate::address::territory;
+use crate::catalog::Lookup;
+use crate::{Context, Error};
+use std::fmt;
+
+/// Context (`msgctxt`) of the unit symbols in the catalogs: `in` is also a preposition
+pub(crate) const UNIT_CONTEXT: &str = "unit";
+
+/// Units of the metric system with their counterpart in the imperial system, the factor and
+/// the offset converting the first to the second (the first counterpart of an imperial unit is
+/// its conversion back)
+const CONVERSIONS: &[(&str, &str, f64, f64)] = &[
+    ("km", "mi", 0.621_371, 0.0),
+    ("m", "ft", 3.280_84, 0.0),
+    ("cm", "in", 0.393_701, 0.0),
+    ("mm", "in", 0.039_370_1, 0.0),
+    ("m", "yd", 1.093_61, 0.0),
+    ("kg", "lb", 2.204_62, 0.0),
+    ("g", "oz", 0.035_274, 0.0),
+    ("l", "gal", 0.264_172, 0.0),
+    ("ml", "fl oz", 0.033_814, 0.0),
+    ("km/h", "mph", 0.621_371, 0.0),
+    ("°C", "°F", 1.8, 32.0),
+];
+
+/// Measurement system of the rendered quantities
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MeasureSystem {
+    /// Kilometers, kilograms, liters, degrees Celsius, ...
+    Metric,
+    /// Miles, pounds, gallons (US), degrees Fahrenheit, ...
+    Imperial,
+}
+
+impl MeasureSystem {
+    /// Measurement system of a region (ISO 3166 code)
+    fn of_region(region: &str) -> MeasureSystem {
+        match region.to_ascii_uppercase().as_str() {
+            "US" | "LR" | "MM" => MeasureSystem::Imperial,
+            _ => MeasureSystem::Metric,
+        }
+    }
+}
+
+/// Render a quantity and its translated unit, converted to `system` (by default the system of
+/// the territory of the locale, not converted without one). The unknown units are not
+/// converted, the converted quantities are rounded to one decimal unless `precision` is given.
+pub(crate) fn write(
+    quantity: f64,
+    unit: &str,
+    system: Option<MeasureSystem>,
+    precision: Option<usize>,
+    out: &mut dyn fmt::Write,
+    ctx: &Context,
+) -> Result<(), Error> {
+    let system = system.or_else(|| {
+        ctx.locale("LC_MEASUREMENT")
+            .as_deref()
+            .and_then(territory)
+            .map(MeasureSystem::of_region)
+    });
+    let converted = system.and_then(|system| convert(quantity, unit, system));
+
+    let number = match (converted, precision) {
+        (Some((x, _)), None) => {
+            let rounded = format!("{:.1}", x);
+            rounded.strip_suffix(".0").unwrap_or(&rounded).to_string()
+        }
+        (Some((x, _)), Some(precision)) => format!("{:.*}", precision, x),
+        (None, Some(precision)) => format!("{:.*}", precision, quantity),
+        (None, None) => quantity.to_string(),
+    };
+    let unit = converted.map(|(_, unit)| unit).unwrap_or(unit);
+
+    let lookup = Lookup {
+        context: Some(UNIT_CONTEXT),
+        ..Lookup::new(unit)
+    };
+    let translated = ctx.decode(unit, ctx.lookup(&lookup))?;
+    let unit = ctx.localized(translated.unwrap_or_else(|| unit.to_string()));
+
+    Ok(write!(out, "{} {}", number, unit)?)
+}
+
+/// Quantity and unit converted to another system, `None` if the unit is unknown or already of
+/// that system
+fn convert(quantity: f64, unit: &str, system: MeasureSystem) -> Option<(f64, &'static str)> {
+    match system {
+        MeasureSystem::Imperial => CONVERSIONS
+            .iter()
+            .find(|(metric, ..)| *metric == unit)
+            .map(|(_, imperial, factor, offset)| (quantity * factor + offset, *imperial)),
+        MeasureSystem::Metric => CONVERSIONS
+            .iter()
+            .find(|(_, imperial, ..)| *imperial == unit)
+            .map(|(metric, _, factor, offset)| ((quantity - offset) / factor, *metric)),
+    }
+}
+
+/// Units a quantity in `unit` can be rendered in, to extract their translations
+pub(crate) fn units(unit: &str) -> Vec<&str> {
+    let converted = [MeasureSystem::Metric, MeasureSystem::Imperial]
+        .iter()
+        .filter_map(|system| convert(0.0, unit, *system))
+        .map(|(_, unit)| unit);
+
+    std::iter::once(unit).chain(converted).collect()
+}
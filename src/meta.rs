@@ -0,0 +1,86 @@
+//! What happened while rendering a message, for debugging and for the analytics of the
+//! translations
+
+use crate::{Backend, Lookup};
+use std::collections::BTreeSet;
+
+/// Locale, lookups and arguments of a rendered message (`SerdeGetText::render_with_meta`)
+///
+/// ```rust
+/// use serde_gettext::{MockCatalog, SerdeGetText};
+/// use std::sync::Arc;
+///
+/// let mut catalog = MockCatalog::new();
+/// catalog.insert_plural("%(n)s file", &["%(n)s fichier", "%(n)s fichiers"]);
+/// let mut message: SerdeGetText = serde_json::from_str(r#"{
+///     "ngettext": {"singular": "%(n)s file", "plural": "%(n)s files", "n": 3}
+/// }"#)?;
+/// message.backend = Some(Arc::new(catalog));
+///
+/// let (rendered, meta) = message.render_with_meta()?;
+/// assert_eq!(rendered, "3 fichiers");
+/// assert_eq!(meta.lookups[0].plural_form, Some(1));
+/// assert!(meta.lookups[0].found);
+/// assert!(meta.args.contains("n"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderMeta {
+    /// Locale of the messages: the locale of the message, or `LC_MESSAGES` of the environment
+    pub locale: Option<String>,
+    /// Messages looked up in the catalog in the order of the rendering (the words of the
+    /// booleans and of null are not included)
+    pub lookups: Vec<LookupMeta>,
+    /// Keyword arguments used by the format strings of the message and of the nested ones
+    pub args: BTreeSet<String>,
+}
+
+impl RenderMeta {
+    /// Check if all the messages have been translated
+    pub fn all_found(&self) -> bool {
+        self.lookups.iter().all(|x| x.found)
+    }
+}
+
+/// Message looked up in the catalog while rendering
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupMeta {
+    /// Text domain, `None` for the current one
+    pub domain: Option<String>,
+    /// Context of the message (`msgctxt`)
+    pub context: Option<String>,
+    /// Message identifier (the singular form for the plural messages)
+    pub msgid: String,
+    /// Plural form of the plural messages (`msgid_plural`)
+    pub plural: Option<String>,
+    /// Index of the plural form selected for the count (`msgstr[i]`), `None` for the messages
+    /// without plural form or if the backend doesn't tell it (the catalogs of the system). The
+    /// untranslated messages use the rule of English.
+    pub plural_form: Option<usize>,
+    /// Whether the catalog has a translation, the fallback message is used otherwise
+    pub found: bool,
+}
+
+impl LookupMeta {
+    pub(crate) fn new(lookup: &Lookup, backend: &dyn Backend, found: bool) -> LookupMeta {
+        LookupMeta {
+            domain: lookup.domain.map(ToString::to_string),
+            context: lookup.context.map(ToString::to_string),
+            msgid: lookup.msgid.to_string(),
+            plural: lookup.plural.map(|(plural, _)| plural.to_string()),
+            plural_form: plural_form(lookup, backend, found),
+            found,
+        }
+    }
+}
+
+/// Index of the plural form selected for a lookup, the untranslated messages use the rule of
+/// English like gettext
+pub(crate) fn plural_form(lookup: &Lookup, backend: &dyn Backend, found: bool) -> Option<usize> {
+    match lookup.plural {
+        Some(_) if found => backend.plural_form(lookup),
+        // gettext returns the msgid for 1 and the plural form otherwise
+        Some((_, n)) => Some(usize::from(n != 1)),
+        None => None,
+    }
+}
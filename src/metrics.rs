@@ -0,0 +1,59 @@
+//! Hooks for the counters and the histograms of the services rendering messages
+
+use crate::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Callbacks invoked while rendering the messages, to update the metrics of a service (e.g.
+/// Prometheus counters and histograms) without wrapping every call site
+///
+/// All the callbacks do nothing by default. The messages rendered by a `RenderCache` are
+/// reported only when they are actually rendered.
+///
+/// ```rust
+/// use serde_gettext::{Metrics, SerdeGetText};
+/// use std::convert::TryFrom;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// #[derive(Default)]
+/// struct Counters {
+///     rendered: AtomicUsize,
+///     missing: AtomicUsize,
+/// }
+///
+/// impl Metrics for Counters {
+///     fn on_render(&self, _locale: Option<&str>, _duration: Duration, _ok: bool) {
+///         self.rendered.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn on_missing_translation(&self, _domain: Option<&str>, _msgid: &str, _locale: Option<&str>) {
+///         self.missing.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counters = Arc::new(Counters::default());
+/// let mut message: SerdeGetText = serde_json::from_str(r#"{"text": "Hello!"}"#)?;
+/// message.metrics = Some(counters.clone());
+/// String::try_from(message)?;
+/// assert_eq!(counters.rendered.load(Ordering::Relaxed), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait Metrics {
+    /// Called when a message has been rendered, with the locale of the messages, the duration
+    /// of the rendering and whether it succeeded
+    fn on_render(&self, _locale: Option<&str>, _duration: Duration, _ok: bool) {}
+
+    /// Called for each message that has no translation, like `MissingTranslationObserver`
+    fn on_missing_translation(&self, _domain: Option<&str>, _msgid: &str, _locale: Option<&str>) {}
+
+    /// Called when a format string can't be formatted (missing argument, width too large, ...)
+    fn on_format_error(&self, _error: &Error) {}
+}
+
+impl fmt::Debug for dyn Metrics + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Metrics")
+    }
+}
@@ -0,0 +1,132 @@
+//! Versions of the format of the messages and upgrade of the older payloads
+//!
+//! A message can give the version of its format in the top-level `version`, the messages without
+//! version are version 1. The older payloads are upgraded to the current format when they are
+//! deserialized, so the templates stored by the applications keep working when the format
+//! evolves. `upgrade` rewrites a payload in the current format to store it back.
+//!
+//!  *  Version 1: the payloads written before the versions
+//!  *  Version 2: `dcngettext` takes its locale category in `category` (the payloads written
+//!     from the documentation of version 1 have `cateogy`)
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_value::Value as Raw;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// Version of the format of the messages
+pub const CURRENT_VERSION: u64 = 2;
+
+/// Migrations upgrading the payloads of a version to the next one, starting at version 1
+const MIGRATIONS: &[fn(&mut Raw)] = &[rename_category];
+
+/// Error that occurs when a payload can't be upgraded
+#[derive(Debug, Error)]
+pub enum MigrateError {
+    /// The version is not a version of the format (0, or newer than the current one)
+    #[error(msg_embedded, no_from, non_std)]
+    UnsupportedVersion(String),
+    /// The payload is not a message or can't be converted back after the upgrade
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidPayload(String),
+}
+
+/// Upgrade a message (e.g. a `serde_json::Value` of a stored template) to the current format,
+/// with `version` set to `CURRENT_VERSION`
+///
+/// ```rust
+/// use serde_gettext::migrate::{upgrade, CURRENT_VERSION};
+/// use serde_json::json;
+///
+/// let payload = json!({
+///     "dcngettext": {
+///         "domain": "app",
+///         "singular": "One file",
+///         "plural": "%(n)s files",
+///         "n": 2,
+///         "cateogy": "messages"
+///     }
+/// });
+/// let payload = upgrade(payload)?;
+///
+/// assert_eq!(payload["dcngettext"]["category"], "messages");
+/// assert_eq!(payload["version"], CURRENT_VERSION);
+/// # Ok::<(), serde_gettext::migrate::MigrateError>(())
+/// ```
+pub fn upgrade<T: Serialize + DeserializeOwned>(payload: T) -> Result<T, MigrateError> {
+    let invalid = |err: &dyn std::fmt::Display| MigrateError::InvalidPayload(err.to_string());
+    let mut map = match serde_value::to_value(payload).map_err(|err| invalid(&err))? {
+        Raw::Map(map) => map,
+        _ => return Err(invalid(&"a message is an object")),
+    };
+    upgrade_map(&mut map)?;
+    map.insert(
+        Raw::String("version".to_string()),
+        Raw::U64(CURRENT_VERSION),
+    );
+
+    Raw::Map(map)
+        .deserialize_into()
+        .map_err(|err| invalid(&err))
+}
+
+/// Upgrade the fields of a message to the current format, removing its `version`
+pub(crate) fn upgrade_map(map: &mut BTreeMap<Raw, Raw>) -> Result<(), MigrateError> {
+    let version = match map.remove(&Raw::String("version".to_string())) {
+        Some(version) => version_number(&version).ok_or_else(|| {
+            MigrateError::UnsupportedVersion(format!("invalid version: {:?}", version))
+        })?,
+        None => 1,
+    };
+    if version == 0 || version > CURRENT_VERSION {
+        return Err(MigrateError::UnsupportedVersion(format!(
+            "unsupported version {}, the current version is {}",
+            version, CURRENT_VERSION
+        )));
+    }
+
+    let mut raw = Raw::Map(std::mem::take(map));
+    for migration in MIGRATIONS.iter().skip((version - 1) as usize) {
+        migration(&mut raw);
+    }
+    if let Raw::Map(upgraded) = raw {
+        *map = upgraded;
+    }
+
+    Ok(())
+}
+
+fn version_number(version: &Raw) -> Option<u64> {
+    match *version {
+        Raw::U8(x) => Some(x.into()),
+        Raw::U16(x) => Some(x.into()),
+        Raw::U32(x) => Some(x.into()),
+        Raw::U64(x) => Some(x),
+        Raw::I8(x) => u64::try_from(x).ok(),
+        Raw::I16(x) => u64::try_from(x).ok(),
+        Raw::I32(x) => u64::try_from(x).ok(),
+        Raw::I64(x) => u64::try_from(x).ok(),
+        _ => None,
+    }
+}
+
+/// Version 1 to 2: `cateogy` of `dcngettext` is `category`
+fn rename_category(raw: &mut Raw) {
+    match raw {
+        Raw::Map(map) => {
+            if let Some(Raw::Map(dcngettext)) = map.get_mut(&Raw::String("dcngettext".to_string()))
+            {
+                let category = Raw::String("category".to_string());
+                if !dcngettext.contains_key(&category) {
+                    if let Some(x) = dcngettext.remove(&Raw::String("cateogy".to_string())) {
+                        dcngettext.insert(category, x);
+                    }
+                }
+            }
+            map.values_mut().for_each(rename_category);
+        }
+        Raw::Seq(items) => items.iter_mut().for_each(rename_category),
+        _ => {}
+    }
+}
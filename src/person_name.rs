@@ -0,0 +1,123 @@
+//! Names of persons ordered following the conventions of their region (`LC_NAME`)
+
+use crate::address::territory;
+use crate::{Context, Error, Value};
+use std::fmt;
+
+/// Parts of the name rendered
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NameForm {
+    /// Title, given names and family name (`Dr Grace Brewster Hopper`)
+    #[default]
+    Full,
+    /// Title and family name, to address the person (`Dr Hopper`)
+    Formal,
+    /// Given name only (`Grace`)
+    Informal,
+}
+
+/// Name of a person, its fields are values (e.g. the title can be translated)
+#[derive(Deserialize, Clone, Debug, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub(crate) struct ValuePersonName {
+    #[serde(alias = "honorific")]
+    pub(crate) title: Option<Box<Value>>,
+    pub(crate) given: Option<Box<Value>>,
+    pub(crate) middle: Option<Box<Value>>,
+    pub(crate) family: Option<Box<Value>>,
+    #[serde(default)]
+    pub(crate) form: NameForm,
+    /// Region whose conventions are followed (e.g. `JP`), the territory of the locale
+    /// (`LC_NAME`) by default
+    pub(crate) region: Option<String>,
+}
+
+impl ValuePersonName {
+    /// Values of the fields of the name
+    pub(crate) fn fields(&self) -> Vec<&Value> {
+        [&self.title, &self.given, &self.middle, &self.family]
+            .iter()
+            .filter_map(|x| x.as_deref())
+            .collect()
+    }
+
+    pub(crate) fn write_to(self, out: &mut dyn fmt::Write, ctx: &Context) -> Result<(), Error> {
+        let region = self
+            .region
+            .or_else(|| {
+                ctx.locale("LC_NAME")
+                    .as_deref()
+                    .and_then(territory)
+                    .map(String::from)
+            })
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        let render = |x: Option<Box<Value>>| match x {
+            Some(x) => x.try_into_string(ctx),
+            None => Ok(String::new()),
+        };
+        let title = render(self.title)?;
+        let given = render(self.given)?;
+        let middle = render(self.middle)?;
+        let family = render(self.family)?;
+
+        let parts = match (self.form, order(&region)) {
+            (NameForm::Informal, _) => vec![given],
+            (NameForm::Formal, Order::HonorificAfter) => vec![family, title],
+            (NameForm::Formal, _) => vec![title, family],
+            (NameForm::Full, Order::GivenFirst) => vec![title, given, middle, family],
+            (NameForm::Full, Order::FamilyFirst) => vec![title, family, given, middle],
+            (NameForm::Full, Order::HonorificAfter) => vec![family, given, middle, title],
+        };
+
+        let mut last: Option<char> = None;
+        for part in parts.iter().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            // the CJK scripts don't separate the parts of the names
+            let first = part.chars().next();
+            match (last, first) {
+                (None, _) => {}
+                (Some(a), Some(b)) if is_cjk(a) && is_cjk(b) => {}
+                _ => out.write_char(' ')?,
+            }
+            out.write_str(part)?;
+            last = part.chars().last();
+        }
+
+        Ok(())
+    }
+}
+
+/// Order of the parts of the names
+enum Order {
+    /// Given names before the family name (`Grace Hopper`)
+    GivenFirst,
+    /// Family name before the given names (`Kovács János`)
+    FamilyFirst,
+    /// Family name before the given names and the honorific at the end (`田中太郎様`)
+    HonorificAfter,
+}
+
+/// Order of the names of a region (ISO 3166 code)
+fn order(region: &str) -> Order {
+    match region {
+        "JP" | "CN" | "TW" | "HK" | "MO" | "KR" => Order::HonorificAfter,
+        "HU" | "VN" | "KH" | "MN" => Order::FamilyFirst,
+        _ => Order::GivenFirst,
+    }
+}
+
+/// Han, kana and hangul characters
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{11ff}'
+            | '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+    )
+}
@@ -0,0 +1,63 @@
+//! Phone numbers formatted following the conventions of their region
+
+use crate::address::territory;
+use phonenumber::country::Id;
+use phonenumber::Mode;
+use std::fmt::Write;
+
+/// Format of a phone number
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PhoneFormat {
+    /// National format for the numbers of the region, international format for the others
+    #[default]
+    Auto,
+    /// National format (`01 23 45 67 89`)
+    National,
+    /// International format (`+33 1 23 45 67 89`)
+    International,
+    /// E.164 format, without spaces (`+33123456789`)
+    E164,
+    /// RFC 3966 URI (`tel:+33-1-23-45-67-89`)
+    Rfc3966,
+}
+
+/// Format a phone number, `region` is the region of the numbers without country code (e.g.
+/// `FR`) and defaults to the territory of `locale` (`LC_TELEPHONE`). The numbers that can't be
+/// parsed are rendered as is.
+pub(crate) fn format(
+    number: &str,
+    format: PhoneFormat,
+    region: Option<&str>,
+    locale: Option<&str>,
+) -> String {
+    let region = region
+        .or_else(|| locale.and_then(territory))
+        .and_then(|x| x.to_ascii_uppercase().parse::<Id>().ok());
+    // the region can't apply to the numbers with a country code: its national prefix (the `1`
+    // of the US) would be stripped from them
+    let default_region = match number.trim_start().starts_with('+') {
+        true => None,
+        false => region,
+    };
+    let parsed = match phonenumber::parse(default_region, number) {
+        Ok(parsed) => parsed,
+        Err(_) => return number.to_string(),
+    };
+
+    let mode = match format {
+        PhoneFormat::Auto if region.is_some() && parsed.country().id() == region => Mode::National,
+        PhoneFormat::Auto | PhoneFormat::International => Mode::International,
+        PhoneFormat::National => Mode::National,
+        PhoneFormat::E164 => Mode::E164,
+        PhoneFormat::Rfc3966 => Mode::Rfc3966,
+    };
+
+    // the formatter fails on the country codes it doesn't know
+    let mut res = String::new();
+    match write!(res, "{}", parsed.format().mode(mode)) {
+        Ok(()) => res,
+        Err(_) => number.to_string(),
+    }
+}
@@ -0,0 +1,298 @@
+//! Evaluation of the plural expressions of the catalogs (`plural=n != 1;` in `Plural-Forms`)
+
+use crate::{Backend, Lookup};
+use std::convert::TryFrom;
+
+/// Rules selecting the plural form of a count: the rules of a language, or the rules of a
+/// catalog (its `Plural-Forms`)
+#[derive(Clone, Copy)]
+pub enum PluralRules<'a> {
+    /// Locale (`ru`, `pt_BR`, `ru_RU.UTF-8`, ...) with the usual `Plural-Forms` of its language
+    Locale(&'a str),
+    /// Backend selecting the plural forms of the current text domain
+    Backend(&'a dyn Backend),
+}
+
+impl<'a> From<&'a str> for PluralRules<'a> {
+    fn from(locale: &'a str) -> PluralRules<'a> {
+        PluralRules::Locale(locale)
+    }
+}
+
+impl<'a, T: Backend> From<&'a T> for PluralRules<'a> {
+    fn from(backend: &'a T) -> PluralRules<'a> {
+        PluralRules::Backend(backend)
+    }
+}
+
+/// Index of the plural form (`msgstr[i]`) selected for a count by the rules of a locale or of a
+/// catalog, e.g. to preview which form a translator should fill. `None` if the language is
+/// unknown or if the backend doesn't tell it (the catalogs of the system).
+///
+/// ```rust
+/// use serde_gettext::{plural_index, MockCatalog};
+///
+/// assert_eq!(plural_index("ru", 21), Some(0));
+/// assert_eq!(plural_index("ru_RU.UTF-8", 22), Some(1));
+/// assert_eq!(plural_index("ru", 25), Some(2));
+/// assert_eq!(plural_index("fr", 0), Some(0));
+/// assert_eq!(plural_index(&MockCatalog::new(), 0), Some(1));
+/// ```
+pub fn plural_index<'a>(rules: impl Into<PluralRules<'a>>, n: u64) -> Option<usize> {
+    match rules.into() {
+        PluralRules::Locale(locale) => {
+            let expr = Expr::parse(locale_rule(locale)?)?;
+            usize::try_from(expr.eval(n)).ok()
+        }
+        PluralRules::Backend(backend) => backend.plural_form(&Lookup {
+            plural: Some(("", n)),
+            ..Lookup::new("")
+        }),
+    }
+}
+
+/// `plural=` expressions of the languages, from the manual of gettext
+const LANGUAGES: &[(&[&str], &str)] = &[
+    (
+        &["ja", "ko", "zh", "vi", "th", "id", "ms", "lo", "km", "my"],
+        "0",
+    ),
+    (
+        &[
+            "en", "de", "nl", "sv", "da", "no", "nb", "nn", "fi", "et", "it", "es", "pt", "el",
+            "hu", "tr", "bg", "he", "ca", "eu", "gl", "eo", "af", "sq", "hi", "bn", "ur", "sw",
+            "is", "fo", "fy", "ka", "az", "kk", "mn",
+        ],
+        "n != 1",
+    ),
+    (&["fr", "pt_BR", "oc", "fil", "ln", "ti", "wa"], "n > 1"),
+    (
+        &["ru", "uk", "be", "sr", "hr", "bs"],
+        "n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2",
+    ),
+    (
+        &["pl"],
+        "n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2",
+    ),
+    (&["cs", "sk"], "n==1 ? 0 : n>=2 && n<=4 ? 1 : 2"),
+    (
+        &["lt"],
+        "n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2",
+    ),
+    (&["lv"], "n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2"),
+    (
+        &["ro"],
+        "n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2",
+    ),
+    (
+        &["sl"],
+        "n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3",
+    ),
+    (&["ga"], "n==1 ? 0 : n==2 ? 1 : 2"),
+    (
+        &["ar"],
+        "n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5",
+    ),
+];
+
+/// `plural=` expression of a locale: the locale with its territory (`pt_BR`), then its language
+fn locale_rule(locale: &str) -> Option<&'static str> {
+    let locale = locale.split(['.', '@']).next().unwrap_or(locale);
+    let language = locale.split(['_', '-']).next().unwrap_or(locale);
+    let territory = locale.replace('-', "_");
+
+    [territory.as_str(), language].iter().find_map(|name| {
+        LANGUAGES
+            .iter()
+            .find(|(names, _)| names.contains(name))
+            .map(|(_, expr)| *expr)
+    })
+}
+
+/// Parsed C expression of the variable `n`
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expr {
+    N,
+    Number(u64),
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Condition(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// Binary operators by precedence, from the lowest
+const PRECEDENCE: &[&[(&str, Op)]] = &[
+    &[("||", Op::Or)],
+    &[("&&", Op::And)],
+    &[("==", Op::Eq), ("!=", Op::Ne)],
+    &[("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)],
+    &[("+", Op::Add), ("-", Op::Sub)],
+    &[("*", Op::Mul), ("/", Op::Div), ("%", Op::Rem)],
+];
+
+/// Expressions can't be nested deeper, like the messages
+const MAX_DEPTH: usize = 128;
+
+/// Longer expressions are rejected: the chains of operators are not nested but still recursive
+const MAX_LENGTH: usize = 1024;
+
+impl Expr {
+    /// Parse the `plural=` expression of the `Plural-Forms` header
+    pub(crate) fn parse(s: &str) -> Option<Expr> {
+        if s.len() > MAX_LENGTH {
+            return None;
+        }
+        let mut parser = Parser {
+            s: s.trim().trim_end_matches(';'),
+            depth: 0,
+        };
+        let expr = parser.condition()?;
+
+        if parser.s.trim().is_empty() {
+            Some(expr)
+        } else {
+            None
+        }
+    }
+
+    /// Index of the plural form of a count (the divisions by zero give 0)
+    pub(crate) fn eval(&self, n: u64) -> u64 {
+        match self {
+            Expr::N => n,
+            Expr::Number(x) => *x,
+            Expr::Not(x) => u64::from(x.eval(n) == 0),
+            Expr::Binary(op, a, b) => {
+                // the evaluation has no side effect: || and && don't need to be lazy
+                let (a, b) = (a.eval(n), b.eval(n));
+                match op {
+                    Op::Or => u64::from(a != 0 || b != 0),
+                    Op::And => u64::from(a != 0 && b != 0),
+                    Op::Eq => u64::from(a == b),
+                    Op::Ne => u64::from(a != b),
+                    Op::Lt => u64::from(a < b),
+                    Op::Le => u64::from(a <= b),
+                    Op::Gt => u64::from(a > b),
+                    Op::Ge => u64::from(a >= b),
+                    Op::Add => a.wrapping_add(b),
+                    Op::Sub => a.wrapping_sub(b),
+                    Op::Mul => a.wrapping_mul(b),
+                    Op::Div => a.checked_div(b).unwrap_or(0),
+                    Op::Rem => a.checked_rem(b).unwrap_or(0),
+                }
+            }
+            Expr::Condition(cond, a, b) => {
+                if cond.eval(n) != 0 {
+                    a.eval(n)
+                } else {
+                    b.eval(n)
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn eat(&mut self, token: &str) -> bool {
+        let s = self.s.trim_start();
+
+        match s.strip_prefix(token) {
+            Some(rest) => {
+                self.s = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `cond ? a : b`, right associative
+    fn condition(&mut self) -> Option<Expr> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return None;
+        }
+
+        let cond = self.binary(0)?;
+        let expr = if self.eat("?") {
+            let a = self.condition()?;
+            if !self.eat(":") {
+                return None;
+            }
+            let b = self.condition()?;
+            Expr::Condition(Box::new(cond), Box::new(a), Box::new(b))
+        } else {
+            cond
+        };
+
+        self.depth -= 1;
+        Some(expr)
+    }
+
+    fn binary(&mut self, level: usize) -> Option<Expr> {
+        let ops = match PRECEDENCE.get(level) {
+            Some(ops) => ops,
+            None => return self.unary(),
+        };
+        let mut expr = self.binary(level + 1)?;
+
+        'outer: loop {
+            for (token, op) in ops.iter() {
+                // the longest tokens are listed first: `<=` is not `<` followed by `=`
+                if self.eat(token) {
+                    let rhs = self.binary(level + 1)?;
+                    expr = Expr::Binary(*op, Box::new(expr), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            break;
+        }
+
+        Some(expr)
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        if self.eat("!") {
+            self.depth += 1;
+            if self.depth > MAX_DEPTH {
+                return None;
+            }
+            let expr = Expr::Not(Box::new(self.unary()?));
+            self.depth -= 1;
+            return Some(expr);
+        }
+        if self.eat("(") {
+            let expr = self.condition()?;
+            return if self.eat(")") { Some(expr) } else { None };
+        }
+        if self.eat("n") {
+            return Some(Expr::N);
+        }
+
+        let s = self.s.trim_start();
+        let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let number = s[..len].parse().ok()?;
+        self.s = &s[len..];
+
+        Some(Expr::Number(number))
+    }
+}
@@ -0,0 +1,250 @@
+//! Writer of POT files (translation templates) from the extracted msgids, and reader of their
+//! msgids
+
+use crate::extract::ExtractedMsgid;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+const HEADER: &str = r#"msgid ""
+msgstr ""
+"Project-Id-Version: PACKAGE VERSION\n"
+"MIME-Version: 1.0\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Content-Transfer-Encoding: 8bit\n"
+"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n"
+"#;
+
+/// Write a POT file with the msgids of a text domain (`None` for the messages without domain)
+/// with their comments for the translators (`#.`), their sources and the files where they are
+/// used (`#:`). The msgids containing `%` are flagged `python-format`.
+pub fn write<W: Write>(
+    out: &mut W,
+    msgids: &BTreeMap<ExtractedMsgid, Vec<PathBuf>>,
+    domain: Option<&str>,
+) -> io::Result<()> {
+    out.write_all(HEADER.as_bytes())?;
+
+    for (msgid, locations) in msgids {
+        if msgid.domain.as_deref() != domain {
+            continue;
+        }
+
+        writeln!(out)?;
+        for line in msgid.comments.iter().flat_map(|x| x.lines()) {
+            writeln!(out, "#. {}", line)?;
+        }
+        // the sources of the messages, then the templates they are in
+        let references: Vec<_> = msgid
+            .references
+            .iter()
+            .cloned()
+            .chain(locations.iter().map(|x| x.display().to_string()))
+            .collect();
+        if !references.is_empty() {
+            writeln!(out, "#: {}", references.join(" "))?;
+        }
+        if msgid.msgid.contains('%') || msgid.plural.as_deref().unwrap_or("").contains('%') {
+            writeln!(out, "#, python-format")?;
+        }
+        if let Some(context) = msgid.context.as_deref() {
+            write_string(out, "msgctxt", context)?;
+        }
+        write_string(out, "msgid", &msgid.msgid)?;
+        match msgid.plural.as_deref() {
+            Some(plural) => {
+                write_string(out, "msgid_plural", plural)?;
+                writeln!(out, "msgstr[0] \"\"")?;
+                writeln!(out, "msgstr[1] \"\"")?;
+            }
+            None => writeln!(out, "msgstr \"\"")?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a keyword and its quoted string, on multiple lines if the string contains new lines
+fn write_string<W: Write>(out: &mut W, keyword: &str, s: &str) -> io::Result<()> {
+    let lines: Vec<&str> = s.split_inclusive('\n').collect();
+
+    if lines.len() > 1 {
+        writeln!(out, "{} \"\"", keyword)?;
+        for line in lines {
+            writeln!(out, "\"{}\"", escape(line))?;
+        }
+        Ok(())
+    } else {
+        writeln!(out, "{} \"{}\"", keyword, escape(s))
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '"' => res.push_str("\\\""),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push(c),
+        }
+    }
+
+    res
+}
+
+/// Error that occurs when a PO or POT file is not valid
+#[derive(Debug, Error)]
+pub enum PotError {
+    /// A line is malformed or out of place
+    #[error(msg_embedded, no_from, non_std)]
+    Parse(String),
+}
+
+/// Msgids of a PO or POT file, in the order of the file, with a text domain (`None` for the
+/// messages without domain). The header, the comments, the references and the obsolete
+/// messages (`#~`) are skipped.
+///
+/// ```rust
+/// use serde_gettext::pot;
+///
+/// let msgids = pot::read("msgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"\"\n", None).unwrap();
+///
+/// assert_eq!(msgids[0].context.as_deref(), Some("menu"));
+/// assert_eq!(msgids[0].msgid, "Open");
+/// ```
+pub fn read(content: &str, domain: Option<&str>) -> Result<Vec<ExtractedMsgid>, PotError> {
+    let mut msgids = Vec::new();
+    let mut entry = Entry::default();
+    let mut field = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| PotError::Parse(format!("line {}: {}", i + 1, message));
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, string) = match line.split_once(|c: char| c.is_whitespace()) {
+            _ if line.starts_with('"') => ("", line),
+            Some((keyword, string)) => (keyword, string.trim_start()),
+            None => return Err(error("missing string")),
+        };
+        let string = unescape(string).ok_or_else(|| error("malformed string"))?;
+
+        match keyword {
+            "" => match field {
+                Some(Field::Context) => entry.context.get_or_insert_with(String::new),
+                Some(Field::Msgid) => entry.msgid.get_or_insert_with(String::new),
+                Some(Field::Plural) => entry.plural.get_or_insert_with(String::new),
+                Some(Field::Msgstr) => continue,
+                None => return Err(error("string without keyword")),
+            }
+            .push_str(&string),
+            "msgctxt" | "msgid" => {
+                if entry.msgstr {
+                    msgids.extend(entry.into_msgid(domain));
+                    entry = Entry::default();
+                }
+                if keyword == "msgctxt" {
+                    if entry.context.is_some() || entry.msgid.is_some() {
+                        return Err(error("unexpected msgctxt"));
+                    }
+                    entry.context = Some(string);
+                    field = Some(Field::Context);
+                } else {
+                    if entry.msgid.is_some() {
+                        return Err(error("unexpected msgid"));
+                    }
+                    entry.msgid = Some(string);
+                    field = Some(Field::Msgid);
+                }
+            }
+            "msgid_plural" => {
+                if entry.msgid.is_none() || entry.plural.is_some() || entry.msgstr {
+                    return Err(error("unexpected msgid_plural"));
+                }
+                entry.plural = Some(string);
+                field = Some(Field::Plural);
+            }
+            _ if keyword == "msgstr" || keyword.starts_with("msgstr[") => {
+                if entry.msgid.is_none() {
+                    return Err(error("msgstr without msgid"));
+                }
+                entry.msgstr = true;
+                field = Some(Field::Msgstr);
+            }
+            _ => return Err(error(&format!("unknown keyword: {}", keyword))),
+        }
+    }
+    msgids.extend(entry.into_msgid(domain));
+
+    Ok(msgids)
+}
+
+#[derive(Default)]
+struct Entry {
+    context: Option<String>,
+    msgid: Option<String>,
+    plural: Option<String>,
+    msgstr: bool,
+}
+
+impl Entry {
+    /// Msgid of the entry, `None` for the header
+    fn into_msgid(self, domain: Option<&str>) -> Option<ExtractedMsgid> {
+        let msgid = self.msgid?;
+        if msgid.is_empty() && self.context.is_none() {
+            return None;
+        }
+
+        Some(ExtractedMsgid {
+            domain: domain.map(ToString::to_string),
+            context: self.context,
+            msgid,
+            plural: self.plural,
+            comments: Vec::new(),
+            references: Vec::new(),
+        })
+    }
+}
+
+/// Field of an entry continued by the strings on the next lines
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Msgid,
+    Plural,
+    Msgstr,
+}
+
+/// Content of a quoted string, `None` if it is malformed
+fn unescape(s: &str) -> Option<String> {
+    let s = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => res.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'a' => '\x07',
+                'b' => '\x08',
+                'f' => '\x0c',
+                'v' => '\x0b',
+                c @ ('\\' | '"' | '\'' | '?') => c,
+                _ => return None,
+            }),
+            '"' => return None,
+            c => res.push(c),
+        }
+    }
+
+    Some(res)
+}
@@ -0,0 +1,68 @@
+/// Pseudo-localize a message: the letters are accented, the text is about 30% longer and it is
+/// put between brackets. The format specifications (e.g. `%(name)s`) are kept unchanged.
+pub(crate) fn pseudo_localize(message: &str) -> String {
+    let mut res = String::with_capacity(message.len() * 2 + 2);
+    let mut letters = 0;
+    let mut chars = message.chars().peekable();
+
+    res.push('[');
+
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            res.push(c);
+
+            if chars.peek() == Some(&'(') {
+                for c in chars.by_ref() {
+                    res.push(c);
+                    if c == ')' {
+                        break;
+                    }
+                }
+            }
+
+            // flags, width, precision and length modifier until the conversion type
+            for c in chars.by_ref() {
+                res.push(c);
+                if c == '%' || (c.is_ascii_alphabetic() && !matches!(c, 'h' | 'l' | 'L')) {
+                    break;
+                }
+            }
+
+            continue;
+        }
+
+        if c.is_alphabetic() {
+            letters += 1;
+        }
+        res.push(accent(c));
+    }
+
+    for _ in 0..(letters + 2) / 3 {
+        res.push('~');
+    }
+    res.push(']');
+
+    res
+}
+
+fn accent(c: char) -> char {
+    match c {
+        'a' => 'á',
+        'c' => 'ç',
+        'e' => 'é',
+        'i' => 'í',
+        'n' => 'ñ',
+        'o' => 'ó',
+        'u' => 'ú',
+        'y' => 'ý',
+        'A' => 'Å',
+        'C' => 'Ç',
+        'E' => 'É',
+        'I' => 'Í',
+        'N' => 'Ñ',
+        'O' => 'Ö',
+        'U' => 'Ü',
+        'Y' => 'Ý',
+        c => c,
+    }
+}
@@ -0,0 +1,200 @@
+//! Messages read from a query string or a form-encoded body, for the translation endpoints
+//! reached with a simple `GET`
+//!
+//! The nested fields are written with brackets (`args[user][name]=Grace`) and the items of the
+//! arrays with empty brackets (`args[]=Grace`) or their index (`args[0]=Grace`). The values are
+//! text, except for the fields that require a boolean (`pseudo`, `sort`, `collate`, `unique`,
+//! `skip_empty`) or an integer (`epoch`, `len`, `width`). The counts of the plural functions are
+//! parsed from text.
+//!
+//! ```rust
+//! use serde_gettext::query;
+//! use std::convert::TryFrom;
+//!
+//! let message = query::from_query("gettext=Hello%20%25(name)s!&args[name]=Grace").unwrap();
+//!
+//! assert_eq!(String::try_from(message).unwrap(), "Hello Grace!");
+//! ```
+
+use crate::SerdeGetText;
+use serde_json::{Map, Value};
+
+/// Error that occurs when the query string is not a valid message
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// A key is malformed or conflicts with another key
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidKey(String),
+    /// The fields don't make a valid message
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidMessage(String),
+}
+
+/// Fields that are booleans wherever they are used
+const BOOLEAN_FIELDS: &[&str] = &["pseudo", "sort", "collate", "unique", "skip_empty"];
+
+/// Fields that are integers wherever they are used
+const INTEGER_FIELDS: &[&str] = &["epoch", "len", "width"];
+
+/// Fields whose entries are named by the user: their values are never converted
+const USER_KEYED_FIELDS: &[&str] = &["args", "defaults", "vars", "map"];
+
+/// Keys can't be nested deeper, like the messages: the nodes are built recursively
+const MAX_DEPTH: usize = 128;
+
+/// Build a message from a query string (without the leading `?`) or a form-encoded body
+pub fn from_query(query: &str) -> Result<SerdeGetText, QueryError> {
+    from_pairs(form_urlencoded::parse(
+        query.trim_start_matches('?').as_bytes(),
+    ))
+}
+
+/// Build a message from the decoded pairs of a query string, e.g. given by a web framework
+pub fn from_pairs<I, K, V>(pairs: I) -> Result<SerdeGetText, QueryError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut root = Value::Object(Map::new());
+
+    for (key, value) in pairs {
+        let key = key.as_ref();
+        let path = parse_key(key)?;
+        insert(&mut root, &path, None, value.as_ref(), key)?;
+    }
+
+    serde_json::from_value(indexed_to_arrays(root))
+        .map_err(|err| QueryError::InvalidMessage(err.to_string()))
+}
+
+/// Split `args[user][name]` into `["args", "user", "name"]`, the empty brackets are kept as empty
+/// segments
+fn parse_key(key: &str) -> Result<Vec<&str>, QueryError> {
+    let invalid = || QueryError::InvalidKey(format!("invalid key: {}", key));
+    let (head, mut rest) = match key.find('[') {
+        Some(i) => key.split_at(i),
+        None => (key, ""),
+    };
+    if head.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut path = vec![head];
+    while !rest.is_empty() {
+        let end = rest.find(']').ok_or_else(invalid)?;
+        if !rest.starts_with('[') || rest[1..end].contains('[') {
+            return Err(invalid());
+        }
+        path.push(&rest[1..end]);
+        rest = &rest[end + 1..];
+        if path.len() > MAX_DEPTH {
+            return Err(QueryError::InvalidKey(format!(
+                "key nested deeper than {} levels: {}",
+                MAX_DEPTH, head
+            )));
+        }
+    }
+
+    Ok(path)
+}
+
+fn insert(
+    node: &mut Value,
+    path: &[&str],
+    parent: Option<&str>,
+    value: &str,
+    key: &str,
+) -> Result<(), QueryError> {
+    let conflict = || QueryError::InvalidKey(format!("conflicting key: {}", key));
+    let (segment, rest) = match path.split_first() {
+        Some(x) => x,
+        None => return Err(conflict()),
+    };
+
+    let child = if segment.is_empty() {
+        let items = node.as_array_mut().ok_or_else(conflict)?;
+        // `a[][b]=1&a[][c]=2` makes a single object, like PHP and Rails do
+        match items.last() {
+            Some(Value::Object(last)) if !rest.is_empty() && !last.contains_key(rest[0]) => {}
+            _ => items.push(empty_node(rest)),
+        }
+        items.last_mut().ok_or_else(conflict)?
+    } else {
+        node.as_object_mut()
+            .ok_or_else(conflict)?
+            .entry(segment.to_string())
+            .or_insert_with(|| empty_node(rest))
+    };
+
+    if rest.is_empty() {
+        if !child.is_null() {
+            return Err(conflict());
+        }
+        *child = leaf(segment, parent, value);
+        Ok(())
+    } else {
+        insert(child, rest, Some(segment), value, key)
+    }
+}
+
+/// Node that will receive the rest of the path
+fn empty_node(rest: &[&str]) -> Value {
+    match rest.first() {
+        None => Value::Null,
+        Some(&"") => Value::Array(Vec::new()),
+        Some(_) => Value::Object(Map::new()),
+    }
+}
+
+fn leaf(field: &str, parent: Option<&str>, value: &str) -> Value {
+    if parent
+        .map(|x| USER_KEYED_FIELDS.contains(&x))
+        .unwrap_or(false)
+    {
+        return Value::String(value.to_string());
+    }
+
+    if BOOLEAN_FIELDS.contains(&field) {
+        match value {
+            "true" | "1" | "" => return Value::Bool(true),
+            "false" | "0" => return Value::Bool(false),
+            _ => {}
+        }
+    } else if INTEGER_FIELDS.contains(&field) {
+        if let Ok(x) = value.trim().parse::<i64>() {
+            return Value::from(x);
+        }
+    }
+
+    Value::String(value.to_string())
+}
+
+/// Turn the objects whose keys are all indexes (`args[0]`, `args[1]`, ...) into arrays
+fn indexed_to_arrays(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let indexed = !map.is_empty()
+                && map
+                    .keys()
+                    .all(|x| !x.is_empty() && x.bytes().all(|x| x.is_ascii_digit()));
+
+            if indexed {
+                let mut items: Vec<(u64, Value)> = map
+                    .into_iter()
+                    .map(|(k, v)| (k.parse().unwrap_or(u64::MAX), indexed_to_arrays(v)))
+                    .collect();
+                items.sort_by_key(|(i, _)| *i);
+                Value::Array(items.into_iter().map(|(_, v)| v).collect())
+            } else {
+                Value::Object(
+                    map.into_iter()
+                        .map(|(k, v)| (k, indexed_to_arrays(v)))
+                        .collect(),
+                )
+            }
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(indexed_to_arrays).collect()),
+        value => value,
+    }
+}
@@ -0,0 +1,212 @@
+//! Catalogs loaded from remote sources, e.g. pulled from an object storage when a serverless
+//! function starts instead of being baked into its image
+//!
+//! `HttpSource` downloads the catalogs over HTTP(S) and keeps them for a time to live. Then they
+//! are revalidated with `If-None-Match` (the `ETag` of the response) and `If-Modified-Since` (its
+//! `Last-Modified`): the catalogs that have not changed are not downloaded again.
+//!
+//! ```rust,no_run
+//! use serde_gettext::remote::{CatalogSource, HttpSource};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> Result<(), serde_gettext::CatalogError> {
+//! let source = HttpSource::new()
+//!     .file(None, "https://example.com/locale/fr/LC_MESSAGES/app.mo")
+//!     .file(Some("errors"), "https://example.com/locale/fr/LC_MESSAGES/errors.mo")
+//!     .ttl(Duration::from_secs(300));
+//! let catalog = source.load().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Catalog, CatalogError};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Future returned by the catalog sources
+pub type LoadFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Arc<Catalog>, CatalogError>> + Send + 'a>>;
+
+/// Source of catalogs loaded asynchronously
+pub trait CatalogSource {
+    /// Current catalog, loaded again from the source if needed
+    fn load(&self) -> LoadFuture<'_>;
+}
+
+/// Catalogs (MO files, or JSON catalogs with the feature `po2json`) downloaded over HTTP(S) and
+/// cached for a time to live
+#[derive(Debug)]
+pub struct HttpSource {
+    client: reqwest::Client,
+    files: Vec<(Option<String>, String)>,
+    ttl: Duration,
+    state: Mutex<State>,
+}
+
+/// Downloaded files and the catalog made of them
+#[derive(Debug, Default)]
+struct State {
+    catalog: Option<Arc<Catalog>>,
+    checked: Option<Instant>,
+    files: Vec<Option<CachedFile>>,
+}
+
+#[derive(Clone, Debug)]
+struct CachedFile {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    data: Arc<Vec<u8>>,
+}
+
+impl Default for HttpSource {
+    fn default() -> HttpSource {
+        HttpSource {
+            client: reqwest::Client::new(),
+            files: Vec::new(),
+            ttl: Duration::from_secs(60),
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl HttpSource {
+    /// Create a source without files, revalidated every minute
+    pub fn new() -> HttpSource {
+        HttpSource::default()
+    }
+
+    /// Add the catalog of a text domain (`None` for the current one). The URLs ending with
+    /// `.json` are JSON catalogs (feature `po2json`), the others MO files.
+    pub fn file(mut self, domain: Option<&str>, url: &str) -> HttpSource {
+        self.files
+            .push((domain.map(ToString::to_string), url.to_string()));
+        self
+    }
+
+    /// Time during which the catalogs are used without being revalidated
+    pub fn ttl(mut self, ttl: Duration) -> HttpSource {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Client used to download the catalogs, e.g. with timeouts or credentials
+    pub fn client(mut self, client: reqwest::Client) -> HttpSource {
+        self.client = client;
+        self
+    }
+
+    async fn load_catalog(&self) -> Result<Arc<Catalog>, CatalogError> {
+        let cached = {
+            let state = self.lock();
+            match (&state.catalog, state.checked) {
+                (Some(catalog), Some(checked)) if checked.elapsed() < self.ttl => {
+                    return Ok(catalog.clone());
+                }
+                _ => state.files.clone(),
+            }
+        };
+
+        let mut changed = cached.len() != self.files.len();
+        let mut files = Vec::with_capacity(self.files.len());
+        for (i, (_, url)) in self.files.iter().enumerate() {
+            let previous = cached.get(i).cloned().flatten();
+            let file = self.fetch(url, previous.as_ref()).await?;
+            changed |= previous
+                .map(|x| !Arc::ptr_eq(&x.data, &file.data))
+                .unwrap_or(true);
+            files.push(Some(file));
+        }
+
+        let mut state = self.lock();
+        let catalog = match state.catalog.clone() {
+            Some(catalog) if !changed => catalog,
+            _ => {
+                let mut catalog = Catalog::new();
+                for ((domain, url), file) in self.files.iter().zip(files.iter().flatten()) {
+                    load_file(&mut catalog, domain.as_deref(), url, &file.data)?;
+                }
+                Arc::new(catalog)
+            }
+        };
+        state.catalog = Some(catalog.clone());
+        state.checked = Some(Instant::now());
+        state.files = files;
+
+        Ok(catalog)
+    }
+
+    /// Download a file, unless the cached version is still valid
+    async fn fetch(
+        &self,
+        url: &str,
+        cached: Option<&CachedFile>,
+    ) -> Result<CachedFile, CatalogError> {
+        let fetch_error = |err: reqwest::Error| CatalogError::Fetch(format!("{}: {}", url, err));
+        let mut request = self.client.get(url);
+        if let Some(cached) = cached {
+            if let Some(etag) = cached.etag.as_deref() {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = cached.last_modified.as_deref() {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await.map_err(fetch_error)?;
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), cached) {
+            return Ok(cached.clone());
+        }
+        let response = response.error_for_status().map_err(fetch_error)?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|x: &reqwest::header::HeaderValue| x.to_str().ok())
+                .map(ToString::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let data = response.bytes().await.map_err(fetch_error)?;
+
+        Ok(CachedFile {
+            etag,
+            last_modified,
+            data: Arc::new(data.to_vec()),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // the state is always consistent, even if a thread panicked while holding it
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl CatalogSource for HttpSource {
+    fn load(&self) -> LoadFuture<'_> {
+        Box::pin(self.load_catalog())
+    }
+}
+
+fn load_file(
+    catalog: &mut Catalog,
+    domain: Option<&str>,
+    url: &str,
+    data: &[u8],
+) -> Result<(), CatalogError> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+
+    if path.ends_with(".json") {
+        #[cfg(feature = "po2json")]
+        return catalog.load_json(domain, data).map(|_| ());
+        #[cfg(not(feature = "po2json"))]
+        return Err(CatalogError::Invalid(format!(
+            "{}: the JSON catalogs require the feature po2json",
+            url
+        )));
+    }
+
+    catalog.load_mo(domain, data).map(|_| ())
+}
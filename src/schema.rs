@@ -0,0 +1,606 @@
+//! JSON Schema of the messages, to validate the payloads before they reach the service (API
+//! gateways, client SDKs, editors)
+//!
+//! The schema follows the deserialization of `SerdeGetText`: a message is an object with one of
+//! the functions (`text`, `gettext`, `ngettext`, ...) and its options, and the arguments are any
+//! value (text, number, boolean, null, date, array, nested message, ...). The unknown properties
+//! are allowed, like the deserialization does. The aliases of the fields (`msgid`, `count`, ...)
+//! and the values of the types registered with `register_fragment` are not described. The same
+//! schemas are available as the components of an OpenAPI document, to document the translation
+//! endpoints.
+//!
+//! ```rust
+//! let schema = serde_gettext::schema::schema();
+//!
+//! assert_eq!(schema["$schema"], "https://json-schema.org/draft/2020-12/schema");
+//! assert!(schema["$defs"]["Value"].is_object());
+//! ```
+
+use serde_json::{json, Map, Value};
+
+/// Dialect of the schema
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema (draft 2020-12) of a `SerdeGetText` document
+pub fn schema() -> Value {
+    json!({
+        "$schema": DIALECT,
+        "title": "SerdeGetText",
+        "$ref": "#/$defs/SerdeGetText",
+        "$defs": definitions(),
+    })
+}
+
+/// Version of the OpenAPI document using the components
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpenApiVersion {
+    /// OpenAPI 3.0, whose schemas are an extended subset of JSON Schema draft 5 (`nullable`)
+    V3_0,
+    /// OpenAPI 3.1, whose schemas are JSON Schema draft 2020-12
+    V3_1,
+}
+
+/// Components (`components` of an OpenAPI document) of the message and of its parts, for the
+/// documentation of the translation endpoints
+///
+/// The request bodies reference the message with `#/components/schemas/SerdeGetText`. The
+/// names of its parts are prefixed with `SerdeGetText` (`SerdeGetTextValue`,
+/// `SerdeGetTextArgs`, ...) to avoid conflicting with the other schemas of the API.
+///
+/// ```rust
+/// use serde_gettext::schema::{openapi_components, OpenApiVersion};
+///
+/// let components = openapi_components(OpenApiVersion::V3_0);
+/// let args = &components["schemas"]["SerdeGetTextKeywordArg"];
+///
+/// assert_eq!(args["anyOf"][1]["additionalProperties"]["$ref"], "#/components/schemas/SerdeGetTextKeywordArg");
+/// ```
+pub fn openapi_components(version: OpenApiVersion) -> Value {
+    let schemas: Map<String, Value> = definitions()
+        .into_iter()
+        .map(|(name, mut schema)| {
+            to_openapi(&mut schema, version);
+            (component_name(&name), schema)
+        })
+        .collect();
+
+    json!({ "schemas": schemas })
+}
+
+fn component_name(name: &str) -> String {
+    if name == "SerdeGetText" {
+        name.to_string()
+    } else {
+        format!("SerdeGetText{}", name)
+    }
+}
+
+/// Point the references to the components and, for OpenAPI 3.0, replace what JSON Schema
+/// draft 5 doesn't have (the `null` type, `const` and `prefixItems`)
+fn to_openapi(schema: &mut Value, version: OpenApiVersion) {
+    match schema {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get_mut("$ref") {
+                if let Some(name) = reference.strip_prefix("#/$defs/") {
+                    *reference = format!("#/components/schemas/{}", component_name(name));
+                }
+            }
+            if version == OpenApiVersion::V3_0 {
+                downgrade(map);
+            }
+            for value in map.values_mut() {
+                to_openapi(value, version);
+            }
+        }
+        Value::Array(values) => {
+            for value in values.iter_mut() {
+                to_openapi(value, version);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn downgrade(map: &mut Map<String, Value>) {
+    let is_null = |x: &Value| x == &json!({"type": "null"});
+
+    // the items of the array forms are only described by `items`
+    map.remove("prefixItems");
+    if let Some(value) = map.remove("const") {
+        map.insert("enum".to_string(), json!([value]));
+    }
+    if let Some(Value::Array(types)) = map.get("type").cloned() {
+        let nullable = types.iter().any(|x| x == "null");
+        let types: Vec<Value> = types.into_iter().filter(|x| x != "null").collect();
+        if let [only] = types.as_slice() {
+            map.insert("type".to_string(), only.clone());
+        } else {
+            map.remove("type");
+            map.insert(
+                "anyOf".to_string(),
+                types.into_iter().map(|x| json!({ "type": x })).collect(),
+            );
+        }
+        if nullable {
+            map.insert("nullable".to_string(), json!(true));
+        }
+    }
+    if let Some(Value::Array(schemas)) = map.get_mut("anyOf") {
+        if schemas.iter().any(is_null) {
+            schemas.retain(|x| !is_null(x));
+            map.insert("nullable".to_string(), json!(true));
+        }
+    }
+}
+
+/// Schemas of the message and of its parts, referencing each other in `#/$defs/`
+fn definitions() -> Map<String, Value> {
+    let r = |name: &str| json!({ "$ref": format!("#/$defs/{}", name) });
+    let string = json!({"type": "string"});
+    let values = json!({"type": "object", "additionalProperties": r("Value")});
+    let counts = |mut properties: Map<String, Value>| {
+        for count in ["zero", "one", "two"] {
+            properties.insert(count.to_string(), json!({"type": ["string", "null"]}));
+        }
+        Value::Object(properties)
+    };
+    // the plural functions: the properties of the function, then the plural forms and counts
+    let plural = |properties: &[(&str, Value)]| {
+        let mut required: Vec<&str> = properties.iter().map(|(name, _)| *name).collect();
+        let mut properties: Map<String, Value> = properties
+            .iter()
+            .map(|(name, schema)| (name.to_string(), schema.clone()))
+            .collect();
+        properties.insert("singular".to_string(), string.clone());
+        properties.insert("plural".to_string(), string.clone());
+        properties.insert("n".to_string(), r("Count"));
+        required.extend(["singular", "plural", "n"]);
+        json!({"type": "object", "properties": counts(properties), "required": required})
+    };
+    let object = |properties: Value, required: &[&str]| json!({"type": "object", "properties": properties, "required": required});
+    let source = object(
+        json!({"file": string, "line": {"type": "integer", "minimum": 0}}),
+        &["file"],
+    );
+    // the options shared by all the functions
+    let function = |name: &str, schema: Value, description: &str| {
+        json!({
+            "description": description,
+            "type": "object",
+            "properties": {
+                name: schema,
+                "args": {"anyOf": [r("Args"), {"type": "null"}]},
+                "defaults": values,
+                "default": {"type": ["string", "null"]},
+                "comment": string,
+                "translators": string,
+                "source": source,
+            },
+            "required": [name],
+        })
+    };
+
+    let messages = vec![
+        json!({
+            "description": "Format string without translation",
+            "type": "object",
+            "properties": {
+                "text": string,
+                "args": {"anyOf": [r("Args"), {"type": "null"}]},
+                "defaults": values,
+            },
+            "required": ["text"],
+        }),
+        function(
+            "gettext",
+            json!({"anyOf": [
+                string,
+                {
+                    "description": "Msgid followed by the positional arguments",
+                    "type": "array",
+                    "prefixItems": [string],
+                    "items": r("Value"),
+                    "minItems": 1,
+                },
+            ]}),
+            "Translated message",
+        ),
+        function(
+            "ngettext",
+            json!({"anyOf": [
+                plural(&[]),
+                {
+                    "description": "Singular form, plural form and n",
+                    "type": "array",
+                    "prefixItems": [string, string, r("Count")],
+                    "items": {"anyOf": [string, r("Count")]},
+                    "minItems": 3,
+                    "maxItems": 3,
+                },
+            ]}),
+            "Translated message with a plural form",
+        ),
+        function(
+            "pgettext",
+            object(json!({"ctx": string, "msgid": string}), &["ctx", "msgid"]),
+            "Translated message with a context",
+        ),
+        function(
+            "dgettext",
+            object(
+                json!({"domain": string, "msgid": string}),
+                &["domain", "msgid"],
+            ),
+            "Translated message of a text domain",
+        ),
+        function(
+            "dngettext",
+            plural(&[("domain", string.clone())]),
+            "Translated message of a text domain with a plural form",
+        ),
+        function(
+            "npgettext",
+            plural(&[("ctx", string.clone())]),
+            "Translated message with a context and a plural form",
+        ),
+        function(
+            "dcngettext",
+            plural(&[
+                ("domain", string.clone()),
+                ("category", r("LocaleCategory")),
+            ]),
+            "Translated message of a text domain and a locale category with a plural form",
+        ),
+        function(
+            "selectplural",
+            object(
+                json!({
+                    "select": string,
+                    "n": r("Count"),
+                    "forms": {
+                        "type": "object",
+                        "additionalProperties": object(
+                            json!({"singular": string, "plural": string}),
+                            &["singular", "plural"],
+                        ),
+                    },
+                }),
+                &["select", "n", "forms"],
+            ),
+            "Translated message whose forms are selected by a keyword (e.g. a gender)",
+        ),
+        json!({
+            "description": "Date formatted with strftime, the current time without epoch",
+            "type": "object",
+            "properties": {
+                "strftime": string,
+                "epoch": {"type": ["integer", "null"]},
+            },
+            "required": ["strftime"],
+        }),
+        json!({
+            "description": "Items joined by the first one, or by `sep` in the object form",
+            "type": "object",
+            "properties": {
+                "join": {
+                    "anyOf": [
+                        {"type": "array", "items": r("Value")},
+                        object(
+                            json!({
+                                "sep": r("Value"),
+                                "items": {"type": "array", "items": r("Value")},
+                            }),
+                            &["sep", "items"],
+                        ),
+                    ],
+                },
+                "sort": {"type": "boolean"},
+                "collate": {"type": "boolean"},
+                "unique": {"type": "boolean"},
+                "skip_empty": {"type": "boolean"},
+            },
+            "required": ["join"],
+        }),
+        object(json!({"ref": string}), &["ref"]),
+        object(
+            json!({
+                "table": {"type": "array", "items": {"type": "array", "items": r("Value")}},
+                "align": {"type": "array", "items": {"enum": ["left", "right", "center"]}},
+                "sep": string,
+            }),
+            &["table"],
+        ),
+        object(json!({"env": string}), &["env"]),
+        object(
+            json!({
+                "langinfo": {
+                    "enum": [
+                        "radix_char", "radixchar", "thousands_sep", "currency_symbol",
+                        "day", "abbreviated_day", "month", "abbreviated_month", "date_format",
+                        "time_format", "date_time_format",
+                    ],
+                },
+                "index": {"type": "integer", "minimum": 1},
+            }),
+            &["langinfo"],
+        ),
+        object(
+            json!({
+                "value": r("Value"),
+                "filters": {"type": "array", "items": r("Filter")},
+                "precision": {"type": "integer", "minimum": 0},
+                "rounding": {
+                    "enum": [
+                        "half_up", "half_even", "half_down", "up", "down", "ceiling", "floor",
+                    ],
+                },
+            }),
+            &["value"],
+        ),
+        object(
+            json!({"map": values, "entry": string, "sep": string}),
+            &["map"],
+        ),
+        object(
+            json!({
+                "foreach": object(
+                    json!({
+                        "items": {
+                            "type": "array",
+                            "items": {"type": "object", "additionalProperties": r("KeywordArg")},
+                        },
+                        "template": r("Value"),
+                        "sep": string,
+                    }),
+                    &["items", "template"],
+                ),
+            }),
+            &["foreach"],
+        ),
+        json!({
+            "description": "First message of the list that has all its translations, the last one otherwise",
+            "type": "object",
+            "properties": {
+                "first_of": {"type": "array", "items": r("Value"), "minItems": 1},
+            },
+            "required": ["first_of"],
+        }),
+        object(
+            json!({
+                "if_translated": object(
+                    json!({
+                        "domain": string,
+                        "ctx": string,
+                        "msgid": string,
+                        "then": r("Value"),
+                        "else": r("Value"),
+                    }),
+                    &["msgid"],
+                ),
+            }),
+            &["if_translated"],
+        ),
+        object(
+            json!({
+                "choice": object(
+                    json!({
+                        "key": string,
+                        "cases": values,
+                        "default": r("Value"),
+                    }),
+                    &["key", "cases"],
+                ),
+                "args": {"anyOf": [r("Args"), {"type": "null"}]},
+            }),
+            &["choice"],
+        ),
+        json!({
+            "description": "Msgid extracted but formatted without translation",
+            "type": "object",
+            "properties": {
+                "noop": string,
+                "args": {"anyOf": [r("Args"), {"type": "null"}]},
+                "defaults": values,
+                "comment": string,
+                "translators": string,
+                "source": source,
+            },
+            "required": ["noop"],
+        }),
+        object(
+            json!({
+                "address": object(
+                    json!({
+                        "name": r("Value"),
+                        "company": r("Value"),
+                        "building": r("Value"),
+                        "street": r("Value"),
+                        "number": r("Value"),
+                        "postcode": r("Value"),
+                        "city": r("Value"),
+                        "state": r("Value"),
+                        "country": r("Value"),
+                        "region": string,
+                        "sep": string,
+                    }),
+                    &[],
+                ),
+            }),
+            &["address"],
+        ),
+        object(
+            json!({
+                "person_name": object(
+                    json!({
+                        "title": r("Value"),
+                        "honorific": r("Value"),
+                        "given": r("Value"),
+                        "middle": r("Value"),
+                        "family": r("Value"),
+                        "form": {"enum": ["full", "formal", "informal"]},
+                        "region": string,
+                    }),
+                    &[],
+                ),
+            }),
+            &["person_name"],
+        ),
+        object(
+            json!({
+                "measure": {"type": "number"},
+                "unit": string,
+                "system": {"enum": ["metric", "imperial"]},
+                "precision": {"type": "integer", "minimum": 0},
+            }),
+            &["measure", "unit"],
+        ),
+    ];
+
+    let mut values_any = vec![
+        json!({"type": ["string", "number", "boolean", "null"]}),
+        json!({"type": "array", "items": r("Value")}),
+        // a unit value in the formats without null (TOML)
+        object(json!({"none": {"const": true}}), &["none"]),
+        object(json!({"uuid": string}), &["uuid"]),
+        object(
+            json!({
+                "bytes": {"type": "array", "items": {"type": "integer", "minimum": 0, "maximum": 255}},
+                "encoding": {"enum": ["hex", "base64", "base64_url"]},
+            }),
+            &["bytes"],
+        ),
+    ];
+    values_any.extend(messages.iter().cloned());
+    #[cfg(feature = "decimal")]
+    values_any.push(object(
+        json!({"decimal": {"type": ["string", "number"]}}),
+        &["decimal"],
+    ));
+    #[cfg(feature = "phone")]
+    values_any.push(object(
+        json!({
+            "phone": string,
+            "format": {"enum": ["auto", "national", "international", "e164", "rfc3966"]},
+            "region": string,
+        }),
+        &["phone"],
+    ));
+
+    let padding = |name: &str| {
+        object(
+            json!({
+                name: object(
+                    json!({
+                        "width": {"type": "integer", "minimum": 0},
+                        "fill": {"type": "string", "minLength": 1, "maxLength": 1},
+                    }),
+                    &["width"],
+                ),
+            }),
+            &[name],
+        )
+    };
+
+    let mut defs = Map::new();
+    defs.insert(
+        "SerdeGetText".to_string(),
+        json!({
+            "description": "Message to translate and format",
+            "anyOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "merge": {"enum": ["leaf", "deep"]},
+                        "vars": values,
+                        "pseudo": {"type": "boolean"},
+                        "escape": {"enum": ["none", "html", "url", "shell", "markdown"]},
+                        "version": {
+                            "description": "Version of the format, 1 if there is none",
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": crate::migrate::CURRENT_VERSION,
+                        },
+                    },
+                    "anyOf": messages,
+                },
+                {
+                    "description": "Bare string, rendered as is or translated (`bare_string`)",
+                    "type": "string",
+                },
+            ],
+        }),
+    );
+    defs.insert(
+        "Value".to_string(),
+        json!({
+            "description": "Argument of a message: a scalar, an array or a nested message",
+            "anyOf": values_any,
+        }),
+    );
+    defs.insert(
+        "Args".to_string(),
+        json!({
+            "description": "Keyword arguments (`%(name)s`) or positional arguments (`%s`)",
+            "anyOf": [
+                {"type": "object", "additionalProperties": r("KeywordArg")},
+                {"type": "array", "items": r("Value")},
+            ],
+        }),
+    );
+    defs.insert(
+        "KeywordArg".to_string(),
+        json!({
+            "description": "Keyword argument, the objects are accessed by dotted path \
+                            (`%(user.name)s`)",
+            "anyOf": [
+                r("Value"),
+                {"type": "object", "additionalProperties": r("KeywordArg")},
+            ],
+        }),
+    );
+    defs.insert(
+        "Count".to_string(),
+        json!({
+            "description": "Count of a plural message",
+            "anyOf": [
+                {"type": "integer"},
+                {"type": "string", "pattern": "^\\s*[+-]?[0-9]+\\s*$"},
+            ],
+        }),
+    );
+    defs.insert(
+        "Filter".to_string(),
+        json!({
+            "description": "Transformation applied on a rendered value",
+            "anyOf": [
+                {"enum": ["upper", "lower", "title", "titlecase", "trim"]},
+                object(
+                    json!({
+                        "truncate": object(
+                            json!({
+                                "len": {"type": "integer", "minimum": 0},
+                                "ellipsis": string,
+                            }),
+                            &["len"],
+                        ),
+                    }),
+                    &["truncate"],
+                ),
+                padding("pad_left"),
+                padding("pad_right"),
+                padding("center"),
+            ],
+        }),
+    );
+    defs.insert(
+        "LocaleCategory".to_string(),
+        json!({
+            "enum": [
+                "ctype", "numeric", "time", "collate", "monetary", "messages", "all", "paper",
+                "name", "address", "telephone", "measurement", "identification",
+            ],
+        }),
+    );
+
+    defs
+}
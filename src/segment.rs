@@ -0,0 +1,67 @@
+//! Rendering of a message into the text and the arguments of its format string, for the
+//! front-ends decorating the arguments (links, bold names, ...)
+
+use crate::{Error, SerdeGetText};
+use std::cell::RefCell;
+
+/// Part of a rendered message (`SerdeGetText::render_segments`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Translated text of the format string
+    Text(String),
+    /// Formatted keyword argument (`%(name)s`), the nested messages are rendered
+    Arg {
+        /// Key of the argument (`user.name` for `%(user.name)s`)
+        key: String,
+        /// Formatted value
+        value: String,
+    },
+    /// Formatted positional argument (`%s`)
+    Positional {
+        /// Index of the argument in `args`
+        index: usize,
+        /// Formatted value
+        value: String,
+    },
+}
+
+impl SerdeGetText {
+    /// Render the message into the text and the formatted arguments of its format string, so
+    /// the arguments can be wrapped in markup without splitting the rendered string
+    ///
+    /// Only the format string of the message is split: the nested messages are rendered in
+    /// their argument, and a message without format string (a date, a `join`, ...) is one
+    /// `Segment::Text`. Concatenating the values of the segments gives the rendered message.
+    ///
+    /// ```rust
+    /// use serde_gettext::{SerdeGetText, Segment};
+    ///
+    /// let message: SerdeGetText = serde_json::from_str(r#"{
+    ///     "text": "Hello %(name)s!",
+    ///     "args": {"name": "Grace"}
+    /// }"#)?;
+    ///
+    /// assert_eq!(
+    ///     message.render_segments()?,
+    ///     vec![
+    ///         Segment::Text("Hello ".to_string()),
+    ///         Segment::Arg { key: "name".to_string(), value: "Grace".to_string() },
+    ///         Segment::Text("!".to_string()),
+    ///     ],
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn render_segments(self) -> Result<Vec<Segment>, Error> {
+        let segments = RefCell::new(Vec::new());
+        let mut res = String::new();
+        self.render_into(&mut res, None, Some(&segments))?;
+
+        let mut segments = segments.into_inner();
+        // the values without format string are written as they are
+        if !res.is_empty() {
+            segments.push(Segment::Text(res));
+        }
+
+        Ok(segments)
+    }
+}
@@ -0,0 +1,337 @@
+//! Ready-made translation service: `POST /translate` renders a message (or a batch of messages)
+//! in the locale negotiated from the `Accept-Language` header
+//!
+//! Every message gets a result, `{"text": "..."}` or `{"error": "..."}`. The `Translator` is
+//! shared by `serve` (feature `server`) and the integrations with the web frameworks:
+//!
+//! ```rust
+//! use serde_gettext::server::Translator;
+//! use serde_gettext::MockCatalog;
+//! use std::sync::Arc;
+//!
+//! let mut fr_catalog = MockCatalog::new();
+//! fr_catalog.insert("Hello %(name)s!", "Bonjour %(name)s !");
+//!
+//! let translator = Translator::new().catalog("fr_FR", Arc::new(fr_catalog));
+//! let body = r#"[
+//!     {"gettext": "Hello %(name)s!", "args": {"name": "Grace"}},
+//!     {"gettext": "Hello %(name)s!"}
+//! ]"#;
+//! let (status, response) = translator.handle(body.as_bytes(), Some("fr-CH, fr;q=0.9"));
+//!
+//! assert_eq!(status, 200);
+//! assert!(response.starts_with(r#"[{"text":"Bonjour Grace !"},{"error":"#));
+//! ```
+
+use crate::{Backend, GettextBackend, Interner, Lookup, Metrics, RenderCache, SerdeGetText};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+#[cfg(feature = "server")]
+use std::error::Error;
+#[cfg(feature = "server")]
+use std::io::Read;
+use std::sync::Arc;
+
+/// Body of a translation request: a batch of messages or a message (a JSON array is always a
+/// batch)
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TranslateRequest {
+    /// Messages rendered in order
+    Batch(Vec<SerdeGetText>),
+    /// Single message
+    Message(Box<SerdeGetText>),
+}
+
+/// Body of the response: a result for every message of the request
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum TranslateResponse {
+    /// Results of a batch, in the order of the request
+    Batch(Vec<TranslateResult>),
+    /// Result of a single message
+    Message(TranslateResult),
+}
+
+/// Result of the rendering of a message: `{"text": "..."}` or `{"error": "..."}`
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslateResult {
+    /// Rendered message
+    Text(String),
+    /// Error that occurred while rendering the message
+    Error(String),
+}
+
+/// Bodies larger than this are rejected by default (1 MiB)
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1 << 20;
+
+/// Renders the messages with the catalog of the locale negotiated from `Accept-Language`
+#[derive(Clone)]
+pub struct Translator {
+    catalogs: Vec<(String, Arc<dyn Backend + Send + Sync>)>,
+    default: Arc<dyn Backend + Send + Sync>,
+    cache: Option<Arc<RenderCache>>,
+    interner: Option<Arc<Interner>>,
+    metrics: Option<Arc<dyn Metrics + Send + Sync>>,
+    max_body_size: usize,
+}
+
+impl Default for Translator {
+    fn default() -> Translator {
+        Translator {
+            catalogs: Vec::new(),
+            default: Arc::new(GettextBackend),
+            cache: None,
+            interner: None,
+            metrics: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
+
+impl Translator {
+    /// Create a translator using the catalogs of the system when no locale matches
+    pub fn new() -> Translator {
+        Translator::default()
+    }
+
+    /// Add the catalog of a locale (e.g. `fr_FR` or `fr`)
+    pub fn catalog(mut self, locale: &str, catalog: Arc<dyn Backend + Send + Sync>) -> Translator {
+        self.catalogs.push((locale.to_string(), catalog));
+        self
+    }
+
+    /// Catalog used when no locale matches, the catalogs of the system by default
+    pub fn default_catalog(mut self, catalog: Arc<dyn Backend + Send + Sync>) -> Translator {
+        self.default = catalog;
+        self
+    }
+
+    /// Cache the rendered messages (a number of them, shared by all the locales and the clones
+    /// of the translator): the identical messages are rendered only once
+    pub fn cache(mut self, capacity: usize) -> Translator {
+        self.cache = Some(Arc::new(RenderCache::new(capacity)));
+        self
+    }
+
+    /// Share the msgids and the translations between all the messages rendered (by all the
+    /// locales and the clones of the translator): the repeated strings are looked up only once
+    pub fn intern(mut self) -> Translator {
+        self.interner = Some(Arc::new(Interner::new()));
+        self
+    }
+
+    /// Report the renderings, the missing translations and the format errors of all the
+    /// messages
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics + Send + Sync>) -> Translator {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Largest body accepted, in bytes (`DEFAULT_MAX_BODY_SIZE` by default): the larger requests
+    /// are answered `413 Payload Too Large` without being read entirely
+    pub fn max_body_size(mut self, bytes: usize) -> Translator {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Locale and catalog that best match an `Accept-Language` header (`None` for the default
+    /// catalog)
+    pub fn negotiate(
+        &self,
+        accept_language: Option<&str>,
+    ) -> (Option<&str>, Arc<dyn Backend + Send + Sync>) {
+        match accept_language.and_then(|x| self.best_match(x)) {
+            Some(i) => (
+                Some(self.catalogs[i].0.as_str()),
+                self.catalogs[i].1.clone(),
+            ),
+            None => (None, self.default.clone()),
+        }
+    }
+
+    fn best_match(&self, accept_language: &str) -> Option<usize> {
+        let mut ranges: Vec<(f32, &str)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|x| x.trim().strip_prefix("q="))
+                    .map(|x| x.trim().parse().unwrap_or(0.0))
+                    .unwrap_or(1.0);
+
+                Some((quality, tag))
+            })
+            .filter(|(quality, tag)| *quality > 0.0 && !tag.is_empty() && *tag != "*")
+            .collect();
+        // the sort is stable: the ranges of the same quality keep the order of the header
+        ranges.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+        ranges.into_iter().find_map(|(_, tag)| {
+            let tag = normalize(tag);
+            self.catalogs
+                .iter()
+                .position(|(locale, _)| normalize(locale) == tag)
+                .or_else(|| {
+                    self.catalogs
+                        .iter()
+                        .position(|(locale, _)| language(&normalize(locale)) == language(&tag))
+                })
+        })
+    }
+
+    /// Locales of the catalogs, in the order they were added
+    pub fn available_locales(&self) -> Vec<&str> {
+        self.catalogs
+            .iter()
+            .map(|(locale, _)| locale.as_str())
+            .collect()
+    }
+
+    /// Check if the catalog of a locale (`fr_FR` and `fr-FR` are the same) has a translation for a
+    /// message (without context) of a text domain (`None` for the current one), `false` if there
+    /// is no catalog for the locale
+    pub fn has_translation(&self, domain: Option<&str>, msgid: &str, locale: &str) -> bool {
+        let locale = normalize(locale);
+
+        self.catalogs
+            .iter()
+            .filter(|(x, _)| normalize(x) == locale)
+            .any(|(_, catalog)| {
+                catalog
+                    .translate(&Lookup {
+                        domain,
+                        ..Lookup::new(msgid)
+                    })
+                    .is_some()
+            })
+    }
+
+    /// Render the messages of a request
+    pub fn translate(
+        &self,
+        request: TranslateRequest,
+        accept_language: Option<&str>,
+    ) -> TranslateResponse {
+        let (locale, catalog) = self.negotiate(accept_language);
+        let render = |mut message: SerdeGetText| {
+            message.backend = Some(catalog.clone());
+            message.locale = locale.map(ToString::to_string);
+            message.interner = self.interner.clone();
+            message.metrics = self.metrics.clone();
+
+            let rendered = match self.cache.as_deref() {
+                Some(cache) => cache.render(message),
+                None => String::try_from(message),
+            };
+
+            match rendered {
+                Ok(text) => TranslateResult::Text(text),
+                Err(err) => TranslateResult::Error(err.to_string()),
+            }
+        };
+
+        match request {
+            TranslateRequest::Batch(messages) => {
+                TranslateResponse::Batch(messages.into_iter().map(render).collect())
+            }
+            TranslateRequest::Message(message) => TranslateResponse::Message(render(*message)),
+        }
+    }
+
+    /// Handle the JSON body of a request: the HTTP status and the JSON body of the response
+    pub fn handle(&self, body: &[u8], accept_language: Option<&str>) -> (u16, String) {
+        if body.len() > self.max_body_size {
+            return payload_too_large();
+        }
+
+        match serde_json::from_slice(body) {
+            Ok(request) => (200, to_json(&self.translate(request, accept_language))),
+            Err(err) => (400, to_json(&TranslateResult::Error(err.to_string()))),
+        }
+    }
+}
+
+fn payload_too_large() -> (u16, String) {
+    (
+        413,
+        to_json(&TranslateResult::Error("payload too large".to_string())),
+    )
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Lower case locale without codeset and with `_` as separator (e.g. `fr_ch`)
+fn normalize(locale: &str) -> String {
+    locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or("")
+        .replace('-', "_")
+        .to_lowercase()
+}
+
+fn language(locale: &str) -> &str {
+    locale.split('_').next().unwrap_or(locale)
+}
+
+/// Serve `POST /translate` on an address (e.g. `127.0.0.1:8080`)
+///
+/// ```rust,no_run
+/// use serde_gettext::server::{self, Translator};
+///
+/// server::serve(Translator::new(), "127.0.0.1:8080")?;
+/// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+/// ```
+#[cfg(feature = "server")]
+pub fn serve(translator: Translator, addr: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let server = tiny_http::Server::http(addr)?;
+    let content_type =
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .map_err(|_| "invalid header")?;
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = if request.url().split('?').next() != Some("/translate") {
+            (
+                404,
+                to_json(&TranslateResult::Error("not found".to_string())),
+            )
+        } else if *request.method() != tiny_http::Method::Post {
+            (
+                405,
+                to_json(&TranslateResult::Error("method not allowed".to_string())),
+            )
+        } else {
+            let accept_language = request
+                .headers()
+                .iter()
+                .find(|x| x.field.equiv("Accept-Language"))
+                .map(|x| x.value.as_str().to_string());
+            let limit = translator.max_body_size;
+            let mut body = Vec::new();
+
+            // one more byte than the limit tells the bodies that are too large
+            match request
+                .as_reader()
+                .take(limit as u64 + 1)
+                .read_to_end(&mut body)
+            {
+                Ok(_) if body.len() > limit => payload_too_large(),
+                Ok(_) => translator.handle(&body, accept_language.as_deref()),
+                Err(err) => (400, to_json(&TranslateResult::Error(err.to_string()))),
+            }
+        };
+
+        let response = tiny_http::Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        // the client may have gone away, the other requests are still served
+        let _ = request.respond(response);
+    }
+
+    Ok(())
+}
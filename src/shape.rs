@@ -0,0 +1,297 @@
+//! Deserialization of the values recognized by their shape
+//!
+//! `#[serde(untagged)]` tries every variant and fails with "data did not match any variant"
+//! when none matches. The values are recognized by the type of the data and by the first field
+//! of the objects instead, and the errors tell which value was recognized and why it failed.
+
+use crate::binary::{BytesEncoding, ValueBytes};
+use crate::config::BareString;
+use crate::fragment::is_registered;
+use crate::migrate::upgrade_map;
+use crate::{ArgsMerge, EscapeMode, Formatter, KeywordArg, SerdeGetText, Value};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use serde_value::{Value as Raw, ValueDeserializer};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Fields recognizing the objects with the variant of `Value` they are deserialized into, in
+/// the order they are tried
+const OBJECTS: &[(&str, &str)] = &[
+    ("none", "unit"),
+    ("strftime", "datetime"),
+    ("join", "join"),
+    ("ref", "ref"),
+    ("table", "table"),
+    ("value", "filtered"),
+    ("map", "map"),
+    ("foreach", "foreach"),
+    ("first_of", "first_of"),
+    ("if_translated", "if_translated"),
+    ("choice", "choice"),
+    ("env", "env"),
+    ("langinfo", "lang_info"),
+    ("address", "address"),
+    ("person_name", "person_name"),
+    ("measure", "measure"),
+    ("uuid", "uuid"),
+    ("bytes", "bytes"),
+    #[cfg(feature = "decimal")]
+    ("decimal", "decimal"),
+    #[cfg(feature = "phone")]
+    ("phone", "phone"),
+    ("text", "formatted_text"),
+    ("noop", "noop"),
+    ("gettext", "get_text"),
+    ("ngettext", "n_get_text"),
+    ("pgettext", "p_get_text"),
+    ("dgettext", "d_get_text"),
+    ("dngettext", "d_n_get_text"),
+    ("npgettext", "n_p_get_text"),
+    ("dcngettext", "d_c_n_get_text"),
+    ("selectplural", "select_plural"),
+];
+
+/// Fields recognizing an object only when they are its only field: they are common keys of the
+/// objects of keyword arguments (`%(user.address.city)s`)
+const SOLE_FIELDS: &[&str] = &["address", "person_name"];
+
+/// Aliases of `gettext` for the payloads of the other gettext tools, recognizing a message only
+/// if the other fields are its options: the objects of keyword arguments often have an `id`
+const GETTEXT_ALIASES: &[&str] = &["msgid", "id"];
+
+/// Options of the messages translated by `gettext`
+const GETTEXT_OPTIONS: &[&str] = &[
+    "args",
+    "defaults",
+    "default",
+    "comment",
+    "translators",
+    "source",
+];
+
+/// Field recognizing an object as a value and the variant of `Value` it is deserialized into,
+/// `None` for the fragments and the objects of keyword arguments
+pub(crate) fn recognize(map: &BTreeMap<Raw, Raw>) -> Option<(&'static str, &'static str)> {
+    let has = |field: &str| map.contains_key(&Raw::String(field.to_string()));
+
+    OBJECTS
+        .iter()
+        .copied()
+        .find(|(field, _)| has(field) && (map.len() == 1 || !SOLE_FIELDS.contains(field)))
+        .or_else(|| {
+            GETTEXT_ALIASES
+                .iter()
+                .find(|alias| {
+                    has(alias)
+                        && map.keys().all(|key| match key {
+                            Raw::String(key) => {
+                                key == *alias || GETTEXT_OPTIONS.contains(&key.as_str())
+                            }
+                            _ => false,
+                        })
+                })
+                .map(|alias| (*alias, "get_text"))
+        })
+}
+
+/// Check if an object is the value of a type registered with `register_fragment`
+pub(crate) fn is_fragment(map: &BTreeMap<Raw, Raw>) -> bool {
+    map.keys().any(|key| match key {
+        Raw::String(key) => is_registered(key),
+        _ => false,
+    })
+}
+
+/// Fields of a message next to the fields of its value
+#[derive(Deserialize)]
+struct Document {
+    #[serde(flatten)]
+    value: Value,
+    #[serde(default)]
+    merge: ArgsMerge,
+    #[serde(default)]
+    vars: HashMap<String, Value>,
+    #[serde(default)]
+    pseudo: bool,
+    #[serde(default)]
+    escape: EscapeMode,
+}
+
+/// Whether the documents that are a bare string are translated by `gettext`
+static GETTEXT_BARE_STRINGS: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_bare_string(mode: BareString) {
+    GETTEXT_BARE_STRINGS.store(mode == BareString::Gettext, Ordering::Relaxed);
+}
+
+impl<'de> Deserialize<'de> for SerdeGetText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SerdeGetText, D::Error> {
+        let document = match Raw::deserialize(deserializer)? {
+            Raw::String(s) if GETTEXT_BARE_STRINGS.load(Ordering::Relaxed) => {
+                return Ok(SerdeGetText::gettext(&s))
+            }
+            Raw::String(s) => return Ok(SerdeGetText::new(Value::Text(s))),
+            Raw::Map(mut map) => {
+                upgrade_map(&mut map).map_err(de::Error::custom)?;
+                Document::deserialize(ValueDeserializer::<D::Error>::new(Raw::Map(map)))?
+            }
+            raw => Document::deserialize(ValueDeserializer::<D::Error>::new(raw))?,
+        };
+
+        Ok(SerdeGetText {
+            merge: document.merge,
+            vars: document.vars,
+            pseudo: document.pseudo,
+            escape: document.escape,
+            ..SerdeGetText::new(document.value)
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        from_raw(Raw::deserialize(deserializer)?)
+    }
+}
+
+fn from_raw<E: de::Error>(mut raw: Raw) -> Result<Value, E> {
+    // the arms that take the value return, the others leave it to the deserialization below
+    let (variant, field) = match raw {
+        // the options and the newtypes are transparent
+        Raw::Option(Some(x)) | Raw::Newtype(x) => return from_raw(*x),
+        Raw::Bool(_) => ("bool", None),
+        // serde-value can't deserialize an i128, the integers are converted here
+        Raw::U8(n) => return Ok(Value::Integer(n.into())),
+        Raw::U16(n) => return Ok(Value::Integer(n.into())),
+        Raw::U32(n) => return Ok(Value::Integer(n.into())),
+        Raw::U64(n) => return Ok(Value::Integer(n.into())),
+        Raw::I8(n) => return Ok(Value::Integer(n.into())),
+        Raw::I16(n) => return Ok(Value::Integer(n.into())),
+        Raw::I32(n) => return Ok(Value::Integer(n.into())),
+        Raw::I64(n) => return Ok(Value::Integer(n.into())),
+        Raw::F32(_) | Raw::F64(_) => ("float", None),
+        Raw::Char(_) | Raw::String(_) => ("text", None),
+        Raw::Bytes(bytes) => {
+            return Ok(Value::Bytes {
+                bytes: ValueBytes(bytes),
+                encoding: BytesEncoding::default(),
+            })
+        }
+        Raw::Unit | Raw::Option(None) => ("unit", None),
+        Raw::Seq(_) => ("array", None),
+        Raw::Map(ref map) => match recognize(map) {
+            Some((field, variant)) => (variant, Some(field)),
+            None if is_fragment(map) => ("fragment", None),
+            None => {
+                let keys: Vec<String> = map.keys().map(key_name).collect();
+                let fields: Vec<String> = OBJECTS.iter().map(|(x, _)| format!("`{}`", x)).collect();
+                return Err(E::custom(format!(
+                    "unrecognized object with the fields {}: expected one of the fields {} or \
+                     the field of a registered fragment",
+                    keys.join(", "),
+                    fields.join(", "),
+                )));
+            }
+        },
+    };
+
+    if let (Some(field), Raw::Map(map)) = (field, &mut raw) {
+        expand_shorthand(map, field, variant)
+            .map_err(|err| E::custom(format!("invalid `{}`: {}", field, err)))?;
+    }
+
+    // the variants of `Value` are deserialized as an externally tagged enum
+    let tagged = Raw::Map(std::iter::once((Raw::String(variant.to_string()), raw)).collect());
+    Value::deserialize(ValueDeserializer::<E>::new(tagged)).map_err(|err| match field {
+        Some(field) => E::custom(format!("invalid `{}`: {}", field, err)),
+        None => err,
+    })
+}
+
+/// Expand the array forms of `gettext` (the msgid followed by the positional arguments) and of
+/// `ngettext` (the singular form, the plural form and the count) into their object forms
+fn expand_shorthand(
+    map: &mut BTreeMap<Raw, Raw>,
+    field: &str,
+    variant: &str,
+) -> Result<(), String> {
+    let key = Raw::String(field.to_string());
+    let mut items = match map.remove(&key) {
+        Some(Raw::Seq(items)) if variant == "get_text" || variant == "n_get_text" => items,
+        Some(x) => {
+            map.insert(key, x);
+            return Ok(());
+        }
+        None => return Ok(()),
+    };
+
+    if variant == "get_text" {
+        let args = Raw::String("args".to_string());
+        if items.is_empty() {
+            return Err("expected the msgid followed by the positional arguments".to_string());
+        }
+        if map.contains_key(&args) {
+            return Err("positional arguments in the array and in `args`".to_string());
+        }
+        map.insert(key, items.remove(0));
+        map.insert(args, Raw::Seq(items));
+    } else {
+        if items.len() != 3 {
+            return Err("expected the singular form, the plural form and n".to_string());
+        }
+        let fields = ["singular", "plural", "n"]
+            .iter()
+            .map(|name| Raw::String(name.to_string()))
+            .zip(items)
+            .collect();
+        map.insert(key, Raw::Map(fields));
+    }
+
+    Ok(())
+}
+
+fn key_name(key: &Raw) -> String {
+    match key {
+        Raw::String(key) => format!("`{}`", key),
+        key => format!("{:?}", key),
+    }
+}
+
+impl<'de> Deserialize<'de> for KeywordArg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeywordArg, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            Raw::Map(map) if recognize(&map).is_none() && !is_fragment(&map) => {
+                HashMap::deserialize(ValueDeserializer::<D::Error>::new(Raw::Map(map)))
+                    .map(KeywordArg::Object)
+            }
+            // the objects that can't be deserialized into their fragment are objects of keyword
+            // arguments
+            Raw::Map(map) if recognize(&map).is_none() => {
+                match from_raw::<D::Error>(Raw::Map(map.clone())) {
+                    Ok(x) => Ok(KeywordArg::Value(Box::new(x))),
+                    Err(_) => {
+                        HashMap::deserialize(ValueDeserializer::<D::Error>::new(Raw::Map(map)))
+                            .map(KeywordArg::Object)
+                    }
+                }
+            }
+            raw => from_raw(raw).map(|x| KeywordArg::Value(Box::new(x))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Formatter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Formatter, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            raw @ Raw::Map(_) => HashMap::deserialize(ValueDeserializer::<D::Error>::new(raw))
+                .map(Formatter::KeywordArgs),
+            raw @ Raw::Seq(_) => Vec::deserialize(ValueDeserializer::<D::Error>::new(raw))
+                .map(Formatter::PositionalArgs),
+            _ => Err(de::Error::custom(
+                "invalid args: expected an object of keyword arguments or an array of positional \
+                 arguments",
+            )),
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Strict deserialization of the messages, failing on the unknown fields
+//!
+//! The values are recognized by their shape and the fields that don't belong to it are ignored:
+//! a typo (`argz` instead of `args`) deserializes fine and the placeholders are rendered without
+//! their arguments. `StrictSerdeGetText` rejects the objects with unknown fields instead, with
+//! the path of the object in the message.
+
+use crate::migrate::{upgrade_map, CURRENT_VERSION};
+use crate::shape::{is_fragment, recognize};
+use crate::SerdeGetText;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use serde_value::Value as Raw;
+use std::collections::BTreeMap;
+
+/// A deserializable struct to translate and format, failing on the unknown fields of its
+/// values and of its nested values
+///
+/// ```rust
+/// use serde_gettext::strict::StrictSerdeGetText;
+///
+/// let err = serde_json::from_str::<StrictSerdeGetText>(r#"{
+///     "gettext": "Hello %(name)s!",
+///     "argz": {"name": "Grace"}
+/// }"#).unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "unknown field `argz` next to `gettext` at $, expected one of `gettext`, `args`, \
+///      `defaults`, `default`, `comment`, `translators`, `source`, `merge`, `vars`, `pseudo`, \
+///      `escape`",
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct StrictSerdeGetText(SerdeGetText);
+
+impl From<StrictSerdeGetText> for SerdeGetText {
+    fn from(x: StrictSerdeGetText) -> SerdeGetText {
+        x.0
+    }
+}
+
+impl<'de> Deserialize<'de> for StrictSerdeGetText {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<StrictSerdeGetText, D::Error> {
+        let mut raw = Raw::deserialize(deserializer)?;
+        // the older payloads are checked in the current format
+        if let Raw::Map(map) = &mut raw {
+            upgrade_map(map).map_err(de::Error::custom)?;
+            check_object(map, "$", MESSAGE).map_err(de::Error::custom)?;
+            map.insert(
+                Raw::String("version".to_string()),
+                Raw::U64(CURRENT_VERSION),
+            );
+        }
+
+        raw.deserialize_into()
+            .map(StrictSerdeGetText)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Fields of the message next to the fields of its value
+const MESSAGE: &[&str] = &["merge", "vars", "pseudo", "escape"];
+
+/// Fields of the plural messages
+const PLURAL: &[&str] = &[
+    "singular",
+    "msgid",
+    "plural",
+    "msgid_plural",
+    "n",
+    "count",
+    "zero",
+    "one",
+    "two",
+];
+
+/// Fields of the object form of `join`
+const JOIN: &[&str] = &["sep", "items"];
+
+/// Shape of an object: the field recognizing the shape, the fields of the object and the fields
+/// of the object in that field if it has one
+type Shape = (
+    &'static str,
+    &'static [&'static str],
+    Option<&'static [&'static str]>,
+);
+
+/// Shapes of the objects
+const SHAPES: &[Shape] = &[
+    ("none", &["none"], None),
+    ("strftime", &["strftime", "epoch"], None),
+    (
+        "join",
+        &["join", "sort", "collate", "unique", "skip_empty"],
+        None,
+    ),
+    ("ref", &["ref"], None),
+    ("table", &["table", "align", "sep"], None),
+    (
+        "value",
+        &["value", "filters", "precision", "rounding"],
+        None,
+    ),
+    ("map", &["map", "entry", "sep"], None),
+    ("foreach", &["foreach"], Some(&["items", "template", "sep"])),
+    ("first_of", &["first_of"], None),
+    (
+        "if_translated",
+        &["if_translated"],
+        Some(&["domain", "ctx", "context", "msgid", "then", "else"]),
+    ),
+    (
+        "choice",
+        &["choice", "args"],
+        Some(&["key", "cases", "default"]),
+    ),
+    ("env", &["env"], None),
+    ("langinfo", &["langinfo", "index"], None),
+    (
+        "address",
+        &["address"],
+        Some(&[
+            "name", "company", "building", "street", "number", "postcode", "city", "state",
+            "country", "region", "sep",
+        ]),
+    ),
+    (
+        "person_name",
+        &["person_name"],
+        Some(&[
+            "title",
+            "honorific",
+            "given",
+            "middle",
+            "family",
+            "form",
+            "region",
+        ]),
+    ),
+    ("measure", &["measure", "unit", "system", "precision"], None),
+    ("uuid", &["uuid"], None),
+    ("bytes", &["bytes", "encoding"], None),
+    #[cfg(feature = "decimal")]
+    ("decimal", &["decimal"], None),
+    #[cfg(feature = "phone")]
+    ("phone", &["phone", "format", "region"], None),
+    ("text", &["text", "args", "defaults"], None),
+    (
+        "noop",
+        &[
+            "noop",
+            "args",
+            "defaults",
+            "comment",
+            "translators",
+            "source",
+        ],
+        None,
+    ),
+    (
+        "gettext",
+        &[
+            "gettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        None,
+    ),
+    (
+        "msgid",
+        &[
+            "msgid",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        None,
+    ),
+    (
+        "id",
+        &[
+            "id",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        None,
+    ),
+    (
+        "ngettext",
+        &[
+            "ngettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(PLURAL),
+    ),
+    (
+        "pgettext",
+        &[
+            "pgettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(&["ctx", "context", "msgid"]),
+    ),
+    (
+        "dgettext",
+        &[
+            "dgettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(&["domain", "msgid"]),
+    ),
+    (
+        "dngettext",
+        &[
+            "dngettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(&[
+            "domain",
+            "singular",
+            "msgid",
+            "plural",
+            "msgid_plural",
+            "n",
+            "count",
+            "zero",
+            "one",
+            "two",
+        ]),
+    ),
+    (
+        "npgettext",
+        &[
+            "npgettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(&[
+            "ctx",
+            "context",
+            "singular",
+            "msgid",
+            "plural",
+            "msgid_plural",
+            "n",
+            "count",
+            "zero",
+            "one",
+            "two",
+        ]),
+    ),
+    (
+        "dcngettext",
+        &[
+            "dcngettext",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(&[
+            "domain",
+            "singular",
+            "msgid",
+            "plural",
+            "msgid_plural",
+            "n",
+            "count",
+            "category",
+            "zero",
+            "one",
+            "two",
+        ]),
+    ),
+    (
+        "selectplural",
+        &[
+            "selectplural",
+            "args",
+            "defaults",
+            "default",
+            "comment",
+            "translators",
+            "source",
+        ],
+        Some(&["select", "n", "count", "forms"]),
+    ),
+];
+
+/// Fields of the objects of `forms` in `selectplural`
+const FORMS: &[&str] = &["singular", "msgid", "plural", "msgid_plural"];
+
+/// Check a value and its nested values
+fn check_value(raw: &Raw, path: &str) -> Result<(), String> {
+    match raw {
+        Raw::Seq(items) => items
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, x)| check_value(x, &format!("{}[{}]", path, i))),
+        Raw::Map(map) => check_object(map, path, &[]),
+        _ => Ok(()),
+    }
+}
+
+/// Check a keyword argument: a value or an object of keyword arguments
+fn check_arg(raw: &Raw, path: &str) -> Result<(), String> {
+    match raw {
+        Raw::Map(map) if shape(map).is_none() && !is_fragment(map) => {
+            entries(map).try_for_each(|(key, x)| check_arg(x, &format!("{}.{}", path, key)))
+        }
+        _ => check_value(raw, path),
+    }
+}
+
+fn check_object(map: &BTreeMap<Raw, Raw>, path: &str, extra: &[&str]) -> Result<(), String> {
+    let (name, fields, inner) = match shape(map) {
+        Some(x) => x,
+        None if is_fragment(map) => return Ok(()),
+        None => {
+            let keys: Vec<String> = entries(map).map(|(key, _)| format!("`{}`", key)).collect();
+            return Err(format!(
+                "unrecognized value at {} with the fields {}",
+                path,
+                keys.join(", "),
+            ));
+        }
+    };
+    check_fields(
+        map,
+        path,
+        &format!("next to `{}`", name),
+        &[fields, extra].concat(),
+    )?;
+
+    for (key, value) in entries(map) {
+        let path = format!("{}.{}", path, key);
+        match (key, value) {
+            ("join", Raw::Map(object)) => {
+                check_fields(object, &path, "in `join`", JOIN)?;
+                for (key, x) in entries(object) {
+                    check_value(x, &format!("{}.{}", path, key))?;
+                }
+            }
+            ("join", _) | ("table", _) | ("value", _) | ("first_of", _) => {
+                check_value(value, &path)?
+            }
+            ("args", Raw::Map(args)) => {
+                for (key, x) in entries(args) {
+                    check_arg(x, &format!("{}.{}", path, key))?;
+                }
+            }
+            ("args", _) => check_value(value, &path)?,
+            ("map", Raw::Map(values))
+            | ("defaults", Raw::Map(values))
+            | ("vars", Raw::Map(values)) => {
+                for (key, x) in entries(values) {
+                    check_value(x, &format!("{}.{}", path, key))?;
+                }
+            }
+            // the msgid followed by the positional arguments
+            ("gettext", Raw::Seq(items)) | ("msgid", Raw::Seq(items)) | ("id", Raw::Seq(items))
+                if key == name =>
+            {
+                for (i, x) in items.iter().enumerate().skip(1) {
+                    check_value(x, &format!("{}[{}]", path, i))?;
+                }
+            }
+            (key, Raw::Map(object)) if key == name => {
+                if let Some(inner) = inner {
+                    check_fields(object, &path, &format!("in `{}`", key), inner)?;
+                    check_inner(object, &path)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the nested values of the objects of `foreach`, `if_translated`, `choice` and
+/// `selectplural`
+fn check_inner(object: &BTreeMap<Raw, Raw>, path: &str) -> Result<(), String> {
+    for (key, value) in entries(object) {
+        let path = format!("{}.{}", path, key);
+        match (key, value) {
+            ("template", _) | ("then", _) | ("else", _) | ("default", _) => {
+                check_value(value, &path)?
+            }
+            ("cases", Raw::Map(cases)) => {
+                for (key, x) in entries(cases) {
+                    check_value(x, &format!("{}.{}", path, key))?;
+                }
+            }
+            ("items", Raw::Seq(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Raw::Map(args) = item {
+                        for (key, x) in entries(args) {
+                            check_arg(x, &format!("{}[{}].{}", path, i, key))?;
+                        }
+                    }
+                }
+            }
+            ("forms", Raw::Map(forms)) => {
+                for (key, form) in entries(forms) {
+                    if let Raw::Map(form) = form {
+                        check_fields(form, &format!("{}.{}", path, key), "in `forms`", FORMS)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Fail on the first field of an object that is not expected
+fn check_fields(
+    map: &BTreeMap<Raw, Raw>,
+    path: &str,
+    place: &str,
+    fields: &[&str],
+) -> Result<(), String> {
+    match entries(map).find(|(key, _)| !fields.contains(key)) {
+        Some((key, _)) => {
+            let expected: Vec<String> = fields.iter().map(|x| format!("`{}`", x)).collect();
+            Err(format!(
+                "unknown field `{}` {} at {}, expected one of {}",
+                key,
+                place,
+                path,
+                expected.join(", "),
+            ))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Shape of an object, from the field recognizing its value
+fn shape(map: &BTreeMap<Raw, Raw>) -> Option<Shape> {
+    let (field, _) = recognize(map)?;
+
+    SHAPES.iter().find(|(name, _, _)| *name == field).copied()
+}
+
+/// Entries of an object with a string key
+fn entries(map: &BTreeMap<Raw, Raw>) -> impl Iterator<Item = (&str, &Raw)> {
+    map.iter().filter_map(|(key, value)| match key {
+        Raw::String(key) => Some((key.as_str(), value)),
+        _ => None,
+    })
+}
@@ -0,0 +1,133 @@
+//! Rows of values laid out in aligned columns of plain text (CLI output, email reports)
+
+use crate::filters::pad;
+use crate::{Context, Error, Value};
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Alignment of the cells of a column
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Render the rows with the columns separated by `sep` (two spaces by default), the spaces at
+/// the end of the lines are left out
+pub(crate) fn write(
+    rows: Vec<Vec<Value>>,
+    align: &[Align],
+    sep: Option<&str>,
+    out: &mut dyn fmt::Write,
+    ctx: &Context,
+) -> Result<(), Error> {
+    let sep = sep.unwrap_or("  ");
+    let wide_ambiguous = is_east_asian(ctx.locale("LC_CTYPE").as_deref());
+
+    let mut cells = Vec::with_capacity(rows.len());
+    let mut widths = Vec::new();
+    for row in rows {
+        let mut rendered = Vec::with_capacity(row.len());
+        for (i, value) in row.into_iter().enumerate() {
+            let cell = value.try_into_string(ctx)?;
+            let w = width(&cell, wide_ambiguous);
+            match widths.get_mut(i) {
+                Some(max) if *max < w => *max = w,
+                Some(_) => {}
+                None => widths.push(w),
+            }
+            rendered.push((cell, w));
+        }
+        cells.push(rendered);
+    }
+
+    for (i, row) in cells.into_iter().enumerate() {
+        if i > 0 {
+            out.write_char('\n')?;
+        }
+
+        let mut line = String::new();
+        for (j, (cell, w)) in row.iter().enumerate() {
+            let missing = widths[j] - w;
+            let (left, right) = match align.get(j).copied().unwrap_or_default() {
+                Align::Left => (0, missing),
+                Align::Right => (missing, 0),
+                Align::Center => (missing / 2, missing - missing / 2),
+            };
+
+            if j > 0 {
+                line.push_str(sep);
+            }
+            line.push_str(&pad(cell, left, right, ' '));
+        }
+        out.write_str(line.trim_end_matches(' '))?;
+    }
+
+    Ok(())
+}
+
+/// Number of columns taken by a string in a terminal: the East Asian wide characters take two,
+/// and the ambiguous ones too in the East Asian locales
+fn width(s: &str, wide_ambiguous: bool) -> usize {
+    s.graphemes(true)
+        .map(|x| match x.chars().next() {
+            Some(c) if is_wide(c) || (wide_ambiguous && is_ambiguous(c)) => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Chinese, Japanese and Korean locales
+fn is_east_asian(locale: Option<&str>) -> bool {
+    matches!(
+        locale.and_then(|x| x.split(['_', '.', '@']).next()),
+        Some("zh") | Some("ja") | Some("ko")
+    )
+}
+
+/// Characters of the East Asian scripts and full width forms
+fn is_wide(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{115f}'
+            | '\u{2e80}'..='\u{303e}'
+            | '\u{3041}'..='\u{33ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{a960}'..='\u{a97f}'
+            | '\u{ac00}'..='\u{d7a3}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{fe30}'..='\u{fe4f}'
+            | '\u{ff00}'..='\u{ff60}'
+            | '\u{ffe0}'..='\u{ffe6}'
+            | '\u{1f300}'..='\u{1f64f}'
+            | '\u{1f900}'..='\u{1f9ff}'
+            | '\u{20000}'..='\u{3fffd}'
+    )
+}
+
+/// Characters whose width depends on the locale: Greek, Cyrillic, punctuation, box drawing, ...
+fn is_ambiguous(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00a1}'
+            | '\u{00a7}'
+            | '\u{00b0}'
+            | '\u{00b1}'
+            | '\u{00d7}'
+            | '\u{00f7}'
+            | '\u{0391}'..='\u{03c9}'
+            | '\u{0401}'..='\u{0451}'
+            | '\u{2010}'..='\u{2026}'
+            | '\u{2103}'
+            | '\u{2190}'..='\u{21ff}'
+            | '\u{2460}'..='\u{24ff}'
+            | '\u{2500}'..='\u{257f}'
+            | '\u{25a0}'..='\u{25ff}'
+            | '\u{2605}'..='\u{2606}'
+    )
+}
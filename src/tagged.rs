@@ -0,0 +1,565 @@
+//! Tagged representation of the messages for the formats that are not self-describing (Bincode,
+//! Postcard, ...) or to get compact MessagePack and CBOR payloads
+//!
+//! Every value is an externally tagged enum (`{"gettext": "Hello"}`, `{"integer": 42}`, ...)
+//! instead of being recognized by its shape, the messages are converted to `SerdeGetText` to be
+//! rendered.
+
+use crate::address::ValueAddress;
+use crate::binary::{BytesEncoding, ValueBytes, ValueUuid};
+use crate::filters::Filter;
+use crate::langinfo::LangInfoItem;
+use crate::measure::MeasureSystem;
+use crate::person_name::{NameForm, ValuePersonName};
+use crate::table::Align;
+use crate::{
+    ArgsMerge, Count, DatetimeValue, ExplicitCounts, Formatter, KeywordArg, LocaleCategory,
+    PluralForms, SerdeGetText, Value, ValueChoice, ValueDCNGetText, ValueDGetText, ValueDNGetText,
+    ValueForeach, ValueGetText, ValueIfTranslated, ValueNGetText, ValueNPGetText, ValuePGetText,
+    ValueSelectPlural,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// A deserializable struct to translate and format, in the tagged representation
+#[derive(Deserialize, Clone, Debug)]
+pub struct TaggedSerdeGetText {
+    value: TaggedValue,
+    #[serde(default)]
+    merge: ArgsMerge,
+    #[serde(default)]
+    vars: HashMap<String, TaggedValue>,
+    #[serde(default)]
+    pseudo: bool,
+}
+
+impl From<TaggedSerdeGetText> for SerdeGetText {
+    fn from(x: TaggedSerdeGetText) -> SerdeGetText {
+        SerdeGetText {
+            merge: x.merge,
+            vars: x.vars.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            pseudo: x.pseudo,
+            ..SerdeGetText::new(x.value.into())
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+enum TaggedValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Unit,
+    Datetime(DatetimeValue),
+    Array(Vec<TaggedValue>),
+    Join {
+        sep: Option<Box<TaggedValue>>,
+        items: Vec<TaggedValue>,
+        #[serde(default)]
+        sort: bool,
+        #[serde(default)]
+        collate: bool,
+        #[serde(default)]
+        unique: bool,
+        #[serde(default)]
+        skip_empty: bool,
+    },
+    Ref(String),
+    Filtered {
+        value: Box<TaggedValue>,
+        filters: Vec<Filter>,
+    },
+    Map {
+        map: BTreeMap<String, TaggedValue>,
+        #[serde(default = "crate::default_map_entry")]
+        entry: String,
+        #[serde(default = "crate::default_map_sep")]
+        sep: String,
+    },
+    Foreach {
+        items: Vec<HashMap<String, TaggedArg>>,
+        template: Box<TaggedValue>,
+        #[serde(default)]
+        sep: String,
+    },
+    Message(Box<TaggedMessage>),
+    // last: the formats that are not self-describing encode the index of the variant
+    FirstOf(Vec<TaggedValue>),
+    IfTranslated {
+        domain: Option<String>,
+        ctx: Option<String>,
+        msgid: String,
+        then: Option<Box<TaggedValue>>,
+        #[serde(rename = "else")]
+        otherwise: Option<Box<TaggedValue>>,
+    },
+    Choice {
+        key: String,
+        cases: BTreeMap<String, TaggedValue>,
+        default: Option<Box<TaggedValue>>,
+    },
+    Env(String),
+    #[serde(rename = "langinfo")]
+    LangInfo {
+        item: LangInfoItem,
+        index: Option<usize>,
+    },
+    Integer128(i128),
+    #[cfg(feature = "decimal")]
+    Decimal(#[serde(with = "rust_decimal::serde::str")] rust_decimal::Decimal),
+    Uuid(ValueUuid),
+    Bytes {
+        bytes: ValueBytes,
+        encoding: BytesEncoding,
+    },
+    Measure {
+        measure: f64,
+        unit: String,
+        system: Option<MeasureSystem>,
+        precision: Option<usize>,
+    },
+    #[cfg(feature = "phone")]
+    Phone {
+        phone: String,
+        format: crate::phone::PhoneFormat,
+        region: Option<String>,
+    },
+    Address {
+        name: Option<Box<TaggedValue>>,
+        company: Option<Box<TaggedValue>>,
+        building: Option<Box<TaggedValue>>,
+        street: Option<Box<TaggedValue>>,
+        number: Option<Box<TaggedValue>>,
+        postcode: Option<Box<TaggedValue>>,
+        city: Option<Box<TaggedValue>>,
+        state: Option<Box<TaggedValue>>,
+        country: Option<Box<TaggedValue>>,
+        region: Option<String>,
+        sep: Option<String>,
+    },
+    PersonName {
+        #[serde(alias = "honorific")]
+        title: Option<Box<TaggedValue>>,
+        given: Option<Box<TaggedValue>>,
+        middle: Option<Box<TaggedValue>>,
+        family: Option<Box<TaggedValue>>,
+        #[serde(default)]
+        form: NameForm,
+        region: Option<String>,
+    },
+    Table {
+        rows: Vec<Vec<TaggedValue>>,
+        #[serde(default)]
+        align: Vec<Align>,
+        sep: Option<String>,
+    },
+}
+
+/// Text or gettext function formatted with its arguments
+#[derive(Deserialize, Clone, Debug)]
+struct TaggedMessage {
+    message: TaggedText,
+    #[serde(default)]
+    args: TaggedArgs,
+    #[serde(default)]
+    defaults: HashMap<String, TaggedValue>,
+    #[serde(default)]
+    default: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+enum TaggedText {
+    Text(String),
+    Gettext(String),
+    Ngettext(TaggedPlural),
+    Pgettext {
+        ctx: String,
+        msgid: String,
+    },
+    Dgettext {
+        domain: String,
+        msgid: String,
+    },
+    Dngettext {
+        domain: String,
+        forms: TaggedPlural,
+    },
+    Npgettext {
+        ctx: String,
+        forms: TaggedPlural,
+    },
+    Dcngettext {
+        domain: String,
+        category: LocaleCategory,
+        forms: TaggedPlural,
+    },
+    Selectplural {
+        select: String,
+        n: i64,
+        forms: HashMap<String, PluralForms>,
+    },
+    // last: the formats that are not self-describing encode the index of the variant
+    Noop(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct TaggedPlural {
+    singular: String,
+    plural: String,
+    n: i64,
+    #[serde(default)]
+    zero: Option<String>,
+    #[serde(default)]
+    one: Option<String>,
+    #[serde(default)]
+    two: Option<String>,
+}
+
+impl TaggedPlural {
+    fn counts(&self) -> ExplicitCounts {
+        ExplicitCounts {
+            zero: self.zero.clone(),
+            one: self.one.clone(),
+            two: self.two.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+enum TaggedArgs {
+    #[default]
+    None,
+    Keyword(HashMap<String, TaggedArg>),
+    Positional(Vec<TaggedValue>),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+enum TaggedArg {
+    Value(TaggedValue),
+    Object(HashMap<String, TaggedArg>),
+}
+
+fn convert_map<T: Into<U>, U>(map: HashMap<String, T>) -> HashMap<String, U> {
+    map.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+impl From<TaggedArg> for KeywordArg {
+    fn from(x: TaggedArg) -> KeywordArg {
+        match x {
+            TaggedArg::Value(value) => KeywordArg::Value(Box::new(value.into())),
+            TaggedArg::Object(map) => KeywordArg::Object(convert_map(map)),
+        }
+    }
+}
+
+impl From<TaggedArgs> for Option<Formatter> {
+    fn from(x: TaggedArgs) -> Option<Formatter> {
+        match x {
+            TaggedArgs::None => None,
+            TaggedArgs::Keyword(args) => Some(Formatter::KeywordArgs(convert_map(args))),
+            TaggedArgs::Positional(args) => Some(Formatter::PositionalArgs(
+                args.into_iter().map(Into::into).collect(),
+            )),
+        }
+    }
+}
+
+impl From<TaggedValue> for Value {
+    fn from(x: TaggedValue) -> Value {
+        match x {
+            TaggedValue::Text(s) => Value::Text(s),
+            TaggedValue::Integer(i) => Value::Integer(i.into()),
+            TaggedValue::Integer128(i) => Value::Integer(i),
+            #[cfg(feature = "decimal")]
+            TaggedValue::Decimal(decimal) => Value::Decimal { decimal },
+            TaggedValue::Uuid(uuid) => Value::Uuid { uuid },
+            TaggedValue::Bytes { bytes, encoding } => Value::Bytes { bytes, encoding },
+            TaggedValue::Address {
+                name,
+                company,
+                building,
+                street,
+                number,
+                postcode,
+                city,
+                state,
+                country,
+                region,
+                sep,
+            } => {
+                let value = |x: Option<Box<TaggedValue>>| x.map(|x| Box::new(Value::from(*x)));
+                Value::Address {
+                    address: Box::new(ValueAddress {
+                        name: value(name),
+                        company: value(company),
+                        building: value(building),
+                        street: value(street),
+                        number: value(number),
+                        postcode: value(postcode),
+                        city: value(city),
+                        state: value(state),
+                        country: value(country),
+                        region,
+                        sep,
+                    }),
+                }
+            }
+            TaggedValue::PersonName {
+                title,
+                given,
+                middle,
+                family,
+                form,
+                region,
+            } => {
+                let value = |x: Option<Box<TaggedValue>>| x.map(|x| Box::new(Value::from(*x)));
+                Value::PersonName {
+                    person_name: Box::new(ValuePersonName {
+                        title: value(title),
+                        given: value(given),
+                        middle: value(middle),
+                        family: value(family),
+                        form,
+                        region,
+                    }),
+                }
+            }
+            TaggedValue::Measure {
+                measure,
+                unit,
+                system,
+                precision,
+            } => Value::Measure {
+                measure,
+                unit,
+                system,
+                precision,
+            },
+            #[cfg(feature = "phone")]
+            TaggedValue::Phone {
+                phone,
+                format,
+                region,
+            } => Value::Phone {
+                phone,
+                format,
+                region,
+            },
+            TaggedValue::Float(f) => Value::Float(f),
+            TaggedValue::Bool(b) => Value::Bool(b),
+            TaggedValue::Unit => Value::Unit(()),
+            TaggedValue::Datetime(datetime) => Value::Datetime(datetime),
+            TaggedValue::Array(xs) => Value::Array(xs.into_iter().map(Into::into).collect()),
+            TaggedValue::Join {
+                sep,
+                items,
+                sort,
+                collate,
+                unique,
+                skip_empty,
+            } => Value::Join {
+                join: sep
+                    .map(|x| *x)
+                    .into_iter()
+                    .chain(items)
+                    .map(Into::into)
+                    .collect(),
+                sort,
+                collate,
+                unique,
+                skip_empty,
+            },
+            TaggedValue::Ref(name) => Value::Ref { name },
+            TaggedValue::Table { rows, align, sep } => Value::Table {
+                table: rows
+                    .into_iter()
+                    .map(|row| row.into_iter().map(Into::into).collect())
+                    .collect(),
+                align,
+                sep,
+            },
+            TaggedValue::Filtered { value, filters } => Value::Filtered {
+                value: Box::new((*value).into()),
+                filters,
+                precision: None,
+                rounding: Default::default(),
+            },
+            TaggedValue::Map { map, entry, sep } => Value::Map {
+                map: map.into_iter().map(|(k, v)| (k, v.into())).collect(),
+                entry,
+                sep,
+            },
+            TaggedValue::Foreach {
+                items,
+                template,
+                sep,
+            } => Value::Foreach {
+                foreach: ValueForeach {
+                    items: items.into_iter().map(convert_map).collect(),
+                    template: Box::new((*template).into()),
+                    sep,
+                },
+            },
+            TaggedValue::FirstOf(xs) => Value::FirstOf {
+                first_of: xs.into_iter().map(Into::into).collect(),
+            },
+            TaggedValue::IfTranslated {
+                domain,
+                ctx,
+                msgid,
+                then,
+                otherwise,
+            } => Value::IfTranslated {
+                if_translated: ValueIfTranslated {
+                    domain,
+                    ctx,
+                    msgid,
+                    then: then.map(|x| Box::new((*x).into())),
+                    otherwise: otherwise.map(|x| Box::new((*x).into())),
+                },
+            },
+            TaggedValue::Choice {
+                key,
+                cases,
+                default,
+            } => Value::Choice {
+                choice: ValueChoice {
+                    key,
+                    cases: cases.into_iter().map(|(k, v)| (k, v.into())).collect(),
+                    default: default.map(|x| Box::new((*x).into())),
+                },
+                args: None,
+            },
+            TaggedValue::Env(env) => Value::Env { env },
+            TaggedValue::LangInfo { item, index } => Value::LangInfo {
+                langinfo: item,
+                index,
+            },
+            TaggedValue::Message(message) => (*message).into(),
+        }
+    }
+}
+
+impl From<TaggedMessage> for Value {
+    fn from(x: TaggedMessage) -> Value {
+        let args = x.args.into();
+        let defaults = convert_map(x.defaults);
+        let default = x.default;
+
+        match x.message {
+            TaggedText::Text(text) => Value::FormattedText {
+                text,
+                args,
+                defaults,
+            },
+            TaggedText::Noop(noop) => Value::Noop {
+                noop,
+                args,
+                defaults,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Gettext(msgid) => Value::GetText {
+                gettext: ValueGetText(msgid),
+                args,
+                defaults,
+                default,
+                // the comments are only read by the extractor
+                comment: None,
+                source: None,
+            },
+            TaggedText::Ngettext(forms) => Value::NGetText {
+                ngettext: Box::new(ValueNGetText {
+                    counts: forms.counts(),
+                    singular: forms.singular,
+                    plural: forms.plural,
+                    n: Count(forms.n.into()),
+                }),
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Pgettext { ctx, msgid } => Value::PGetText {
+                pgettext: ValuePGetText { ctx, msgid },
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Dgettext { domain, msgid } => Value::DGetText {
+                dgettext: ValueDGetText { domain, msgid },
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Dngettext { domain, forms } => Value::DNGetText {
+                dngettext: Box::new(ValueDNGetText {
+                    domain,
+                    counts: forms.counts(),
+                    singular: forms.singular,
+                    plural: forms.plural,
+                    n: Count(forms.n.into()),
+                }),
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Npgettext { ctx, forms } => Value::NPGetText {
+                npgettext: Box::new(ValueNPGetText {
+                    ctx,
+                    counts: forms.counts(),
+                    singular: forms.singular,
+                    plural: forms.plural,
+                    n: Count(forms.n.into()),
+                }),
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Dcngettext {
+                domain,
+                category,
+                forms,
+            } => Value::DCNGetText {
+                dcngettext: Box::new(ValueDCNGetText {
+                    domain,
+                    category,
+                    counts: forms.counts(),
+                    singular: forms.singular,
+                    plural: forms.plural,
+                    n: Count(forms.n.into()),
+                }),
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+            TaggedText::Selectplural { select, n, forms } => Value::SelectPlural {
+                selectplural: Box::new(ValueSelectPlural {
+                    select,
+                    n: Count(n.into()),
+                    forms,
+                }),
+                args,
+                defaults,
+                default,
+                comment: None,
+                source: None,
+            },
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Locale of the messages rendered by a Tokio task, carried across the `.await` points
+//!
+//! `LocaleContext` is bound to a thread: a task can be resumed on another thread of the runtime
+//! after an `.await`. `render_in_locale` sets the locale of a future instead, for the handlers
+//! of the asynchronous web frameworks.
+//!
+//! ```rust
+//! use serde_gettext::task::render_in_locale;
+//! use serde_gettext::SerdeGetText;
+//!
+//! async fn handler() -> Option<String> {
+//!     tokio::task::yield_now().await;
+//!     let message: SerdeGetText = serde_json::from_str(r#"{"gettext": "Hello!"}"#).ok()?;
+//!     message.render_with_meta().ok()?.1.locale
+//! }
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! assert_eq!(render_in_locale("de_DE", handler()).await.as_deref(), Some("de_DE"));
+//! # });
+//! ```
+
+use std::future::Future;
+
+tokio::task_local! {
+    static LOCALE: String;
+}
+
+/// Run a future with the locale of the messages it renders, the locale of a message
+/// (`SerdeGetText::locale`) and the guards of `LocaleContext` take precedence. The translations
+/// are looked up in that locale (`Lookup::locale`). The tasks spawned by the future don't inherit
+/// it.
+pub async fn render_in_locale<F: Future>(locale: &str, future: F) -> F::Output {
+    LOCALE.scope(locale.to_string(), future).await
+}
+
+/// Locale of the current task, if any
+pub fn current_locale() -> Option<String> {
+    LOCALE.try_with(Clone::clone).ok()
+}
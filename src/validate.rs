@@ -0,0 +1,531 @@
+//! Validation of the catalogs against the messages, e.g. as a pre-deploy check
+
+use crate::default_args::default_args;
+use crate::extract::{CollectMsgids, ExtractedMsgid, Msgids};
+use crate::{ArgsMerge, Backend, Formatter, KeywordArg, Lookup, SerdeGetText, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// Coverage of the messages by the catalogs of the target locales
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CoverageReport {
+    /// Number of distinct msgids used by the messages
+    pub msgids: usize,
+    /// Msgids without translation, by locale (the locales with a complete catalog are absent)
+    pub missing: BTreeMap<String, Vec<ExtractedMsgid>>,
+}
+
+impl CoverageReport {
+    /// All the msgids are translated in all the locales
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Check that every msgid used by the messages has a translation in the catalog of each
+/// target locale
+pub fn verify(messages: &[SerdeGetText], catalogs: &[(&str, &dyn Backend)]) -> CoverageReport {
+    let msgids: BTreeSet<ExtractedMsgid> = messages
+        .iter()
+        .flat_map(SerdeGetText::extract_msgids)
+        .collect();
+    let mut report = CoverageReport {
+        msgids: msgids.len(),
+        missing: BTreeMap::new(),
+    };
+
+    for (locale, catalog) in catalogs {
+        let missing: Vec<ExtractedMsgid> = msgids
+            .iter()
+            .filter(|msgid| catalog.translate(&lookup(msgid, 1)).is_none())
+            .cloned()
+            .collect();
+
+        if !missing.is_empty() {
+            report.missing.insert(locale.to_string(), missing);
+        }
+    }
+
+    report
+}
+
+fn lookup(msgid: &ExtractedMsgid, n: u64) -> Lookup<'_> {
+    Lookup {
+        domain: msgid.domain.as_deref(),
+        context: msgid.context.as_deref(),
+        plural: msgid.plural.as_deref().map(|plural| (plural, n)),
+        ..Lookup::new(&msgid.msgid)
+    }
+}
+
+/// Inconsistency between the placeholders of a message, its translations and its arguments
+#[derive(Clone, Debug, PartialEq)]
+pub enum LintWarning {
+    /// Placeholder of the plural form missing in the singular form or the opposite (`n` may be
+    /// missing from the singular form)
+    PluralMismatch {
+        /// Singular form
+        msgid: String,
+        /// Name of the placeholder
+        placeholder: String,
+    },
+    /// Placeholder of a translation missing in the msgid: formatting it would fail
+    TranslationMismatch {
+        /// Msgid of the message
+        msgid: String,
+        /// Translation of the message
+        translation: String,
+        /// Name of the placeholder
+        placeholder: String,
+    },
+    /// Argument never used by the message (the positional arguments are named by their index)
+    UnusedArg {
+        /// Msgid of the message
+        msgid: String,
+        /// Name (or dotted path) of the argument
+        arg: String,
+    },
+    /// Placeholder without argument nor default (the positional placeholders are named by
+    /// their index)
+    MissingArg {
+        /// Msgid of the message
+        msgid: String,
+        /// Name of the placeholder
+        placeholder: String,
+    },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintWarning::PluralMismatch { msgid, placeholder } => write!(
+                f,
+                "{:?}: placeholder {:?} not in both singular and plural forms",
+                msgid, placeholder
+            ),
+            LintWarning::TranslationMismatch {
+                msgid,
+                translation,
+                placeholder,
+            } => write!(
+                f,
+                "{:?}: placeholder {:?} of the translation {:?} not in the msgid",
+                msgid, placeholder, translation
+            ),
+            LintWarning::UnusedArg { msgid, arg } => {
+                write!(f, "{:?}: argument {:?} never used", msgid, arg)
+            }
+            LintWarning::MissingArg { msgid, placeholder } => write!(
+                f,
+                "{:?}: no argument for placeholder {:?}",
+                msgid, placeholder
+            ),
+        }
+    }
+}
+
+/// Check the placeholders of the message and all its nested messages against their plural
+/// forms, their arguments and, if a catalog is provided, their translations
+pub fn lint(message: &SerdeGetText, catalog: Option<&dyn Backend>) -> Vec<LintWarning> {
+    let mut linter = Linter {
+        catalog,
+        merge: message.merge,
+        warnings: Vec::new(),
+    };
+    let scope: BTreeSet<String> = message
+        .args
+        .keys()
+        .chain(default_args().keys())
+        .cloned()
+        .collect();
+
+    linter.value(&message.value, &scope);
+    for value in message.vars.values() {
+        linter.value(value, &scope);
+    }
+
+    linter.warnings
+}
+
+struct Linter<'a> {
+    catalog: Option<&'a dyn Backend>,
+    merge: ArgsMerge,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter<'_> {
+    /// Lint a value, `scope` contains the names of the arguments provided by the outer messages
+    fn value(&mut self, value: &Value, scope: &BTreeSet<String>) {
+        match value {
+            Value::Text(_)
+            | Value::Integer(_)
+            | Value::Float(_)
+            | Value::Bool(_)
+            | Value::Unit(())
+            | Value::Datetime(_)
+            | Value::Ref { .. }
+            | Value::Env { .. }
+            | Value::LangInfo { .. }
+            | Value::Uuid { .. }
+            | Value::Bytes { .. }
+            | Value::Fragment(_) => {}
+            #[cfg(feature = "decimal")]
+            Value::Decimal { .. } => {}
+            #[cfg(feature = "phone")]
+            Value::Phone { .. } => {}
+            Value::Measure { .. } => {}
+            Value::Array(xs) | Value::Join { join: xs, .. } | Value::FirstOf { first_of: xs } => {
+                for x in xs {
+                    self.value(x, scope);
+                }
+            }
+            Value::Table { table, .. } => {
+                for x in table.iter().flatten() {
+                    self.value(x, scope);
+                }
+            }
+            Value::Filtered { value, .. } => self.value(value, scope),
+            Value::IfTranslated { if_translated } => {
+                let branches = if_translated.then.iter().chain(&if_translated.otherwise);
+                for x in branches {
+                    self.value(x, scope);
+                }
+            }
+            Value::Choice { choice, args } => {
+                let mut case_scope = scope.clone();
+                match args {
+                    Some(Formatter::KeywordArgs(args)) => {
+                        for (key, arg) in args {
+                            arg_paths(key, arg, &mut case_scope);
+                            self.keyword_arg(arg, scope);
+                        }
+                    }
+                    Some(Formatter::PositionalArgs(args)) => {
+                        for x in args {
+                            self.value(x, scope);
+                        }
+                    }
+                    None => {}
+                }
+                let cases = choice.cases.values().chain(choice.default.as_deref());
+                for x in cases {
+                    self.value(x, &case_scope);
+                }
+            }
+            Value::Address { address } => {
+                for value in address.fields() {
+                    self.value(value, scope);
+                }
+            }
+            Value::PersonName { person_name } => {
+                for value in person_name.fields() {
+                    self.value(value, scope);
+                }
+            }
+            Value::Map { map, .. } => {
+                for value in map.values() {
+                    self.value(value, scope);
+                }
+            }
+            Value::Foreach { foreach } => {
+                let mut template_scope = scope.clone();
+                for item in foreach.items.iter() {
+                    for (key, arg) in item {
+                        arg_paths(key, arg, &mut template_scope);
+                        self.keyword_arg(arg, scope);
+                    }
+                }
+                self.value(&foreach.template, &template_scope);
+            }
+            Value::FormattedText {
+                text,
+                args,
+                defaults,
+            } => {
+                let msgids = vec![ExtractedMsgid::new(text)];
+                self.message(&msgids, false, args, defaults, scope);
+            }
+            Value::Noop {
+                noop,
+                args,
+                defaults,
+                ..
+            } => {
+                let msgids = vec![ExtractedMsgid::new(noop)];
+                self.message(&msgids, false, args, defaults, scope);
+            }
+            Value::GetText {
+                gettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(gettext, false, args, defaults, scope),
+            Value::NGetText {
+                ngettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(&**ngettext, true, args, defaults, scope),
+            Value::PGetText {
+                pgettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(pgettext, false, args, defaults, scope),
+            Value::DGetText {
+                dgettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(dgettext, false, args, defaults, scope),
+            Value::DNGetText {
+                dngettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(&**dngettext, true, args, defaults, scope),
+            Value::NPGetText {
+                npgettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(&**npgettext, true, args, defaults, scope),
+            Value::DCNGetText {
+                dcngettext,
+                args,
+                defaults,
+                ..
+            } => self.translatable(&**dcngettext, true, args, defaults, scope),
+            Value::SelectPlural {
+                selectplural,
+                args,
+                defaults,
+                ..
+            } => self.translatable(&**selectplural, true, args, defaults, scope),
+        }
+    }
+
+    fn keyword_arg(&mut self, arg: &KeywordArg, scope: &BTreeSet<String>) {
+        match arg {
+            KeywordArg::Value(value) => self.value(value, scope),
+            KeywordArg::Object(map) => {
+                for arg in map.values() {
+                    self.keyword_arg(arg, scope);
+                }
+            }
+        }
+    }
+
+    fn translatable<T: CollectMsgids>(
+        &mut self,
+        value: &T,
+        plural: bool,
+        args: &Option<Formatter>,
+        defaults: &HashMap<String, Value>,
+        scope: &BTreeSet<String>,
+    ) {
+        let mut msgids = Msgids::new();
+        value.collect_msgids(&mut msgids);
+        let msgids: Vec<ExtractedMsgid> = msgids.into_iter().collect();
+
+        for msgid in msgids.iter() {
+            self.translations(msgid, plural);
+        }
+        self.message(&msgids, plural, args, defaults, scope);
+    }
+
+    /// Check the placeholders of the translations against the placeholders of the msgid
+    fn translations(&mut self, msgid: &ExtractedMsgid, plural: bool) {
+        let catalog = match self.catalog {
+            Some(catalog) => catalog,
+            None => return,
+        };
+        let mut expected = placeholders(&msgid.msgid).named;
+        if let Some(plural) = msgid.plural.as_deref() {
+            expected.extend(placeholders(plural).named);
+        }
+        if plural {
+            expected.insert("n".to_string());
+        }
+
+        let counts: &[u64] = if msgid.plural.is_some() {
+            &[1, 2]
+        } else {
+            &[1]
+        };
+        for n in counts {
+            if let Some(translation) = catalog.translate(&lookup(msgid, *n)) {
+                for placeholder in placeholders(&translation).named.difference(&expected) {
+                    self.warnings.push(LintWarning::TranslationMismatch {
+                        msgid: msgid.msgid.clone(),
+                        translation: translation.clone(),
+                        placeholder: placeholder.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Check the placeholders of the forms of a message against each other and against the
+    /// arguments, then lint the arguments
+    fn message(
+        &mut self,
+        msgids: &[ExtractedMsgid],
+        plural: bool,
+        args: &Option<Formatter>,
+        defaults: &HashMap<String, Value>,
+        scope: &BTreeSet<String>,
+    ) {
+        let main = match msgids.first() {
+            Some(msgid) => msgid.msgid.clone(),
+            None => return,
+        };
+        let mut used = Placeholders::default();
+
+        for msgid in msgids {
+            let singular = placeholders(&msgid.msgid);
+
+            if let Some(plural) = msgid.plural.as_deref() {
+                let plural = placeholders(plural);
+                for placeholder in plural.named.symmetric_difference(&singular.named) {
+                    if placeholder != "n" || singular.named.contains("n") {
+                        self.warnings.push(LintWarning::PluralMismatch {
+                            msgid: msgid.msgid.clone(),
+                            placeholder: placeholder.clone(),
+                        });
+                    }
+                }
+                used.extend(plural);
+            }
+            used.extend(singular);
+        }
+
+        let mut provided = BTreeSet::new();
+        let mut positional = 0;
+        let mut nested_scope = scope.clone();
+        match args {
+            Some(Formatter::KeywordArgs(args)) => {
+                for (key, arg) in args {
+                    arg_paths(key, arg, &mut provided);
+                }
+            }
+            Some(Formatter::PositionalArgs(args)) => positional = args.len(),
+            None => {}
+        }
+        if self.merge == ArgsMerge::Deep {
+            nested_scope.extend(provided.iter().cloned());
+            nested_scope.extend(defaults.keys().cloned());
+        }
+
+        for arg in provided.difference(&used.named) {
+            self.warnings.push(LintWarning::UnusedArg {
+                msgid: main.clone(),
+                arg: arg.clone(),
+            });
+        }
+        for placeholder in used.named.iter() {
+            if !provided.contains(placeholder)
+                && !defaults.contains_key(placeholder)
+                && !scope.contains(placeholder)
+                && (!plural || placeholder != "n")
+            {
+                self.warnings.push(LintWarning::MissingArg {
+                    msgid: main.clone(),
+                    placeholder: placeholder.clone(),
+                });
+            }
+        }
+        for i in positional..used.positional {
+            self.warnings.push(LintWarning::MissingArg {
+                msgid: main.clone(),
+                placeholder: i.to_string(),
+            });
+        }
+        for i in used.positional..positional {
+            self.warnings.push(LintWarning::UnusedArg {
+                msgid: main.clone(),
+                arg: i.to_string(),
+            });
+        }
+
+        match args {
+            Some(Formatter::KeywordArgs(args)) => {
+                for arg in args.values() {
+                    self.keyword_arg(arg, &nested_scope);
+                }
+            }
+            Some(Formatter::PositionalArgs(args)) => {
+                for arg in args {
+                    self.value(arg, &nested_scope);
+                }
+            }
+            None => {}
+        }
+        for value in defaults.values() {
+            self.value(value, &nested_scope);
+        }
+    }
+}
+
+/// Dotted paths of a keyword argument (e.g. `user.name`)
+fn arg_paths(key: &str, arg: &KeywordArg, paths: &mut BTreeSet<String>) {
+    match arg {
+        KeywordArg::Value(_) => {
+            paths.insert(key.to_string());
+        }
+        KeywordArg::Object(map) => {
+            for (sub_key, arg) in map {
+                arg_paths(&format!("{}.{}", key, sub_key), arg, paths);
+            }
+        }
+    }
+}
+
+/// Placeholders of a format string: the names of the keyword placeholders and the number of
+/// positional placeholders
+#[derive(Default)]
+pub(crate) struct Placeholders {
+    pub(crate) named: BTreeSet<String>,
+    pub(crate) positional: usize,
+}
+
+impl Placeholders {
+    pub(crate) fn extend(&mut self, other: Placeholders) {
+        self.named.extend(other.named);
+        self.positional = self.positional.max(other.positional);
+    }
+}
+
+pub(crate) fn placeholders(s: &str) -> Placeholders {
+    let mut res = Placeholders::default();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+
+        match chars.peek() {
+            Some('%') => {
+                chars.next();
+                continue;
+            }
+            Some('(') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != ')').collect();
+                res.named.insert(name);
+            }
+            Some(_) => res.positional += 1,
+            None => break,
+        }
+
+        // flags, width, precision and length modifier until the conversion type
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() && !matches!(c, 'h' | 'l' | 'L') {
+                break;
+            }
+        }
+    }
+
+    res
+}
@@ -0,0 +1,60 @@
+//! JavaScript API for [wasm-bindgen](https://docs.rs/wasm-bindgen/): the messages are rendered
+//! in the browsers with the catalogs loaded from `ArrayBuffer`s
+//!
+//! ```js
+//! const response = await fetch("/locale/fr/LC_MESSAGES/app.mo");
+//! loadCatalog(await response.arrayBuffer());
+//! translate({gettext: "Hello %(name)s!", args: {name: "Grace"}});
+//! ```
+
+use crate::{Catalog, SerdeGetText};
+use ::wasm_bindgen::prelude::*;
+use js_sys::{ArrayBuffer, Uint8Array, JSON};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+thread_local! {
+    static CATALOG: RefCell<Arc<Catalog>> = RefCell::new(Arc::new(Catalog::new()));
+    static LOCALE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn to_js<E: ToString>(err: E) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}
+
+/// Add the messages of a compiled catalog (MO file) of a text domain (`undefined` for the
+/// messages without domain)
+#[wasm_bindgen(js_name = loadCatalog)]
+pub fn load_catalog(buffer: &ArrayBuffer, domain: Option<String>) -> Result<(), JsValue> {
+    let data = Uint8Array::new(buffer).to_vec();
+
+    CATALOG.with(|catalog| {
+        // the messages being rendered keep the previous catalog
+        let mut updated = Catalog::clone(&catalog.borrow());
+        updated.load_mo(domain.as_deref(), &data).map_err(to_js)?;
+        *catalog.borrow_mut() = Arc::new(updated);
+
+        Ok(())
+    })
+}
+
+/// Set the locale of the filters (e.g. `tr_TR`)
+#[wasm_bindgen(js_name = setLocale)]
+pub fn set_locale(locale: Option<String>) {
+    LOCALE.with(|x| *x.borrow_mut() = locale);
+}
+
+/// Render a message (an object or its JSON) with the loaded catalogs
+#[wasm_bindgen]
+pub fn translate(json: JsValue) -> Result<String, JsValue> {
+    let json = match json.as_string() {
+        Some(json) => json,
+        None => String::from(JSON::stringify(&json)?),
+    };
+    let mut message: SerdeGetText = serde_json::from_str(&json).map_err(to_js)?;
+    message.backend = Some(CATALOG.with(|x| x.borrow().clone()));
+    message.locale = LOCALE.with(|x| x.borrow().clone());
+
+    String::try_from(message).map_err(to_js)
+}
@@ -0,0 +1,167 @@
+//! Catalogs reloaded when their MO files change on disk, so the long-running services pick up
+//! the new translations without restarting
+//!
+//! The directories of the files are watched: the files replaced by a rename (e.g. by a
+//! deployment) are reloaded too. The new catalog replaces the previous one atomically, the
+//! previous one is kept if the files can't be loaded.
+//!
+//! ```rust,no_run
+//! use serde_gettext::{Catalog, SerdeGetText};
+//! use std::convert::TryFrom;
+//!
+//! let catalog = Catalog::watch("locale/fr/LC_MESSAGES/app.mo")?
+//!     .domain("errors", "locale/fr/LC_MESSAGES/errors.mo")?;
+//!
+//! let mut message: SerdeGetText = serde_json::from_str(r#"{"gettext": "Hello!"}"#)?;
+//! // all the messages of the rendering are translated with the same catalog
+//! message.backend = Some(catalog.current());
+//! String::try_from(message)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{Backend, Catalog, CatalogError, Lookup};
+use notify::{RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Catalog reloaded when its MO files change, see `Catalog::watch`
+#[derive(Debug)]
+pub struct WatchedCatalog {
+    shared: Arc<Shared>,
+    watcher: notify::RecommendedWatcher,
+}
+
+#[derive(Debug)]
+struct Shared {
+    /// MO files by text domain (`None` for the current one)
+    files: Mutex<Vec<(Option<String>, PathBuf)>>,
+    catalog: RwLock<Arc<Catalog>>,
+}
+
+impl Catalog {
+    /// Load the MO file of the current text domain and reload it whenever it changes on disk
+    pub fn watch<P: AsRef<Path>>(path: P) -> Result<WatchedCatalog, CatalogError> {
+        let shared = Arc::new(Shared {
+            files: Mutex::new(Vec::new()),
+            catalog: RwLock::new(Arc::new(Catalog::new())),
+        });
+        let watched = Arc::downgrade(&shared);
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let (shared, event) = match (watched.upgrade(), event) {
+                (Some(shared), Ok(event)) => (shared, event),
+                _ => return,
+            };
+            if event.kind.is_access() {
+                return;
+            }
+            let files = shared.files();
+            if event
+                .paths
+                .iter()
+                .any(|x| files.iter().any(|(_, path)| same_file(x, path)))
+            {
+                // the previous catalog is kept until the files are valid again
+                let _ = shared.reload();
+            }
+        })
+        .map_err(io_error)?;
+
+        WatchedCatalog { shared, watcher }.add(None, path.as_ref())
+    }
+}
+
+impl WatchedCatalog {
+    /// Add the MO file of a text domain, reloaded whenever it changes on disk
+    pub fn domain<P: AsRef<Path>>(
+        self,
+        domain: &str,
+        path: P,
+    ) -> Result<WatchedCatalog, CatalogError> {
+        self.add(Some(domain), path.as_ref())
+    }
+
+    fn add(mut self, domain: Option<&str>, path: &Path) -> Result<WatchedCatalog, CatalogError> {
+        self.shared
+            .lock_files()
+            .push((domain.map(ToString::to_string), path.to_path_buf()));
+        self.shared.reload()?;
+        self.watcher
+            .watch(dir(path), RecursiveMode::NonRecursive)
+            .map_err(io_error)?;
+
+        Ok(self)
+    }
+
+    /// Catalog loaded from the current files
+    pub fn current(&self) -> Arc<Catalog> {
+        self.shared.current()
+    }
+
+    /// Load the files again, e.g. when the changes can't be watched (some network file systems).
+    /// The previous catalog is kept if they can't be loaded.
+    pub fn reload(&self) -> Result<(), CatalogError> {
+        self.shared.reload()
+    }
+}
+
+impl Shared {
+    fn lock_files(&self) -> std::sync::MutexGuard<'_, Vec<(Option<String>, PathBuf)>> {
+        self.files.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn files(&self) -> Vec<(Option<String>, PathBuf)> {
+        self.lock_files().clone()
+    }
+
+    fn current(&self) -> Arc<Catalog> {
+        self.catalog
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    fn reload(&self) -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new();
+        for (domain, path) in self.files() {
+            let data = fs::read(&path)
+                .map_err(|err| CatalogError::Io(format!("{}: {}", path.display(), err)))?;
+            catalog
+                .load_mo(domain.as_deref(), &data)
+                .map_err(|err| CatalogError::Invalid(format!("{}: {}", path.display(), err)))?;
+        }
+        *self.catalog.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(catalog);
+
+        Ok(())
+    }
+}
+
+impl Backend for WatchedCatalog {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        self.current().translate(lookup)
+    }
+
+    fn plural_form(&self, lookup: &Lookup) -> Option<usize> {
+        self.current().plural_form(lookup)
+    }
+}
+
+/// The watcher gives absolute paths, the files may have been given relative to the current
+/// directory, and a replaced file may not exist anymore
+fn same_file(event: &Path, file: &Path) -> bool {
+    event == file
+        || (event.file_name() == file.file_name()
+            && fs::canonicalize(dir(event)).ok() == fs::canonicalize(dir(file)).ok())
+}
+
+/// Directory of a file, the current directory for the relative paths without directory
+fn dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+fn io_error(err: notify::Error) -> CatalogError {
+    CatalogError::Io(err.to_string())
+}
@@ -0,0 +1,149 @@
+use serde_gettext::codegen;
+use serde_gettext::pot::{self, PotError};
+use serde_gettext::SerdeGetText;
+use std::convert::TryFrom;
+
+const POT: &str = r#"msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#: templates/hello.json
+#, python-format
+msgid "Hello %(name)s!"
+msgstr ""
+
+msgid "One item has been deleted"
+msgid_plural "%(n)s items have been deleted from %(folder.name)s"
+msgstr[0] ""
+msgstr[1] ""
+
+msgctxt "menu_open"
+msgid "Open"
+msgstr ""
+
+msgid "type"
+msgstr ""
+
+msgid "Hello %(name)s!"
+msgstr "Bonjour %(name)s !"
+
+msgid "%s and %s"
+msgstr ""
+
+#~ msgid "Obsolete"
+#~ msgstr ""
+"#;
+
+#[test]
+fn read_pot() {
+    let msgids = pot::read(POT, Some("app")).unwrap();
+
+    assert_eq!(msgids.len(), 6);
+    assert_eq!(msgids[0].domain.as_deref(), Some("app"));
+    assert_eq!(
+        msgids[1].plural.as_deref(),
+        Some("%(n)s items have been deleted from %(folder.name)s")
+    );
+    assert_eq!(msgids[2].context.as_deref(), Some("menu_open"));
+
+    for (pot, error) in [
+        ("msgstr \"\"", "line 1: msgstr without msgid"),
+        ("msgid \"a\"\nmsgid \"b\"", "line 2: unexpected msgid"),
+        ("msgid \"a\\q\"", "line 1: malformed string"),
+        ("\"a\"", "line 1: string without keyword"),
+        (
+            "msgid \"a\"\nmsgtxt \"b\"",
+            "line 2: unknown keyword: msgtxt",
+        ),
+    ] {
+        match pot::read(pot, None) {
+            Err(PotError::Parse(message)) => assert_eq!(message, error),
+            x => panic!("{:?}", x),
+        }
+    }
+}
+
+#[test]
+fn generate() {
+    let mut out = Vec::new();
+    codegen::generate(&mut out, &pot::read(POT, None).unwrap()).unwrap();
+
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"// Generated by serde_gettext::codegen, do not edit
+
+/// Hello %(name)s!
+pub fn hello_name(name: impl ::std::fmt::Display) -> ::serde_gettext::SerdeGetText {
+    ::serde_gettext::SerdeGetText::gettext("Hello %(name)s!")
+        .arg("name", name)
+}
+
+/// One item has been deleted
+pub fn one_item_has_been_deleted(n: u64, folder_name: impl ::std::fmt::Display) -> ::serde_gettext::SerdeGetText {
+    ::serde_gettext::SerdeGetText::ngettext("One item has been deleted", "%(n)s items have been deleted from %(folder.name)s", n)
+        .arg("folder.name", folder_name)
+}
+
+/// Open
+pub fn menu_open() -> ::serde_gettext::SerdeGetText {
+    ::serde_gettext::SerdeGetText::pgettext("menu_open", "Open")
+}
+
+/// type
+pub fn type_() -> ::serde_gettext::SerdeGetText {
+    ::serde_gettext::SerdeGetText::gettext("type")
+}
+
+/// Hello %(name)s!
+pub fn hello_name_2(name: impl ::std::fmt::Display) -> ::serde_gettext::SerdeGetText {
+    ::serde_gettext::SerdeGetText::gettext("Hello %(name)s!")
+        .arg("name", name)
+}
+
+// No constructor: "%s and %s"
+"#
+    );
+}
+
+/// Constructors as generated for the POT file above
+mod messages {
+    pub fn hello_name(name: impl ::std::fmt::Display) -> ::serde_gettext::SerdeGetText {
+        ::serde_gettext::SerdeGetText::gettext("Hello %(name)s!").arg("name", name)
+    }
+
+    pub fn one_item_has_been_deleted(
+        n: u64,
+        folder_name: impl ::std::fmt::Display,
+    ) -> ::serde_gettext::SerdeGetText {
+        ::serde_gettext::SerdeGetText::ngettext(
+            "One item has been deleted",
+            "%(n)s items have been deleted from %(folder.name)s",
+            n,
+        )
+        .arg("folder.name", folder_name)
+    }
+}
+
+#[test]
+fn constructors() {
+    let render = |message: SerdeGetText| String::try_from(message).unwrap();
+
+    assert_eq!(render(messages::hello_name("Grace")), "Hello Grace!");
+    assert_eq!(
+        render(messages::one_item_has_been_deleted(1, "Inbox")),
+        "One item has been deleted"
+    );
+    assert_eq!(
+        render(messages::one_item_has_been_deleted(3, "Inbox")),
+        "3 items have been deleted from Inbox"
+    );
+    assert_eq!(
+        render(SerdeGetText::npgettext(
+            "menu",
+            "%(n)s file",
+            "%(n)s files",
+            2
+        )),
+        "2 files"
+    );
+}
@@ -0,0 +1,170 @@
+use serde_gettext::config::{BareString, ConfigError, Configure};
+use serde_gettext::{Error, MockCatalog, RenderCache, SerdeGetText};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+#[test]
+fn configure() {
+    let configure: Configure = serde_json::from_str(
+        r#"{"configure": {
+            "textdomain": "myapp",
+            "bindtextdomain": {"domain": "myapp", "dir": "/usr/share/locale"},
+            "codeset": "UTF-8"
+        }}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        configure.configure,
+        serde_gettext::init()
+            .textdomain("myapp")
+            .bindtextdomain("myapp", "/usr/share/locale")
+            .codeset("UTF-8")
+    );
+    configure.apply().unwrap();
+
+    let configure: Configure = serde_yaml::from_str(
+        r#"---
+        configure:
+            bindtextdomain:
+                - domain: app
+                  dir: locale
+                - domain: errors
+                  dir: locale
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        configure.configure,
+        serde_gettext::init()
+            .bindtextdomain("app", "locale")
+            .bindtextdomain("errors", "locale")
+    );
+    assert!(serde_json::from_str::<Configure>(r#"{"configure": {"domain": "app"}}"#).is_err());
+}
+
+#[test]
+fn invalid_values() {
+    assert!(matches!(
+        serde_gettext::init().textdomain("my\0app").apply(),
+        Err(ConfigError::InvalidValue(_))
+    ));
+    assert!(matches!(
+        serde_gettext::init().bindtextdomain("", "locale").apply(),
+        Err(ConfigError::InvalidValue(_))
+    ));
+    assert!(matches!(
+        serde_gettext::init().codeset("").apply(),
+        Err(ConfigError::InvalidValue(_))
+    ));
+}
+
+#[test]
+fn bare_string() {
+    let render = |s: &str| {
+        let mut catalog = MockCatalog::new();
+        catalog.insert("Hello!", "Bonjour !");
+        let mut message: SerdeGetText = serde_json::from_str(s).unwrap();
+        message.backend = Some(Arc::new(catalog));
+        String::try_from(message).unwrap()
+    };
+
+    let configure: Configure =
+        serde_json::from_str(r#"{"configure": {"bare_string": "gettext"}}"#).unwrap();
+    assert_eq!(
+        configure.configure,
+        serde_gettext::init().bare_string(BareString::Gettext)
+    );
+    configure.apply().unwrap();
+    assert_eq!(render(r#""Hello!""#), "Bonjour !");
+
+    serde_gettext::init()
+        .bare_string(BareString::Text)
+        .apply()
+        .unwrap();
+    assert_eq!(render(r#""Hello!""#), "Hello!");
+}
+
+#[test]
+fn env() {
+    let render = |s: &str| String::try_from(serde_json::from_str::<SerdeGetText>(s).unwrap());
+    std::env::set_var("SERDE_GETTEXT_APP_NAME", "Acme");
+    std::env::set_var("SERDE_GETTEXT_SECRET", "hunter2");
+    let message = r#"{
+        "text": "Welcome to %(product)s!",
+        "args": {"product": {"env": "SERDE_GETTEXT_APP_NAME"}}
+    }"#;
+
+    let configure: Configure = serde_json::from_str(
+        r#"{"configure": {"env": ["SERDE_GETTEXT_APP_NAME", "SERDE_GETTEXT_UNSET"]}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        configure.configure,
+        serde_gettext::init()
+            .allow_env("SERDE_GETTEXT_APP_NAME")
+            .allow_env("SERDE_GETTEXT_UNSET")
+    );
+    configure.apply().unwrap();
+    assert_eq!(render(message).unwrap(), "Welcome to Acme!");
+
+    // the cache renders the current value of the variable
+    let cache = RenderCache::new(10);
+    let cached = || {
+        cache
+            .render(serde_json::from_str(message).unwrap())
+            .unwrap()
+    };
+    assert_eq!(cached(), "Welcome to Acme!");
+    std::env::set_var("SERDE_GETTEXT_APP_NAME", "Initech");
+    assert_eq!(cached(), "Welcome to Initech!");
+    assert_eq!(cache.len(), 2);
+    std::env::set_var("SERDE_GETTEXT_APP_NAME", "Acme");
+    let err = render(r#"{"env": "SERDE_GETTEXT_SECRET"}"#).unwrap_err();
+    assert!(matches!(err, Error::EnvNotAllowed(_)));
+    assert_eq!(err.code(), "env_not_allowed");
+    assert_eq!(
+        err.to_string(),
+        "environment variable not allowed: SERDE_GETTEXT_SECRET"
+    );
+    let err = render(r#"{"env": "SERDE_GETTEXT_UNSET"}"#).unwrap_err();
+    assert!(matches!(err, Error::UndefinedVariable(_)));
+    assert_eq!(
+        err.to_string(),
+        "undefined environment variable: SERDE_GETTEXT_UNSET"
+    );
+
+    assert!(matches!(
+        serde_gettext::init().allow_env("APP=NAME").apply(),
+        Err(ConfigError::InvalidValue(_))
+    ));
+}
+
+#[test]
+fn default_args() {
+    let render = |s: &str| String::try_from(serde_json::from_str::<SerdeGetText>(s).unwrap());
+
+    let configure: Configure = serde_json::from_str(
+        r#"{"configure": {"default_args": {"test_product": "Acme", "test_url": "https://acme.test"}}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        configure.configure,
+        serde_gettext::init()
+            .default_arg("test_product", "Acme")
+            .default_arg("test_url", "https://acme.test")
+    );
+    configure.apply().unwrap();
+    assert_eq!(
+        render(r#"{"text": "%(test_product)s: %(test_url)s"}"#).unwrap(),
+        "Acme: https://acme.test"
+    );
+    // the arguments of the message take precedence
+    let message = serde_json::from_str::<SerdeGetText>(r#"{"text": "%(test_product)s"}"#)
+        .unwrap()
+        .arg("test_product", "Widget");
+    assert_eq!(String::try_from(message).unwrap(), "Widget");
+
+    assert!(serde_gettext::remove_default_arg("test_product"));
+    assert!(!serde_gettext::remove_default_arg("test_product"));
+    assert!(render(r#"{"text": "%(test_product)s"}"#).is_err());
+}
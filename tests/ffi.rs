@@ -0,0 +1,53 @@
+#![cfg(feature = "ffi")]
+
+use serde_gettext::ffi::*;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+fn translate(json: &str, locale: Option<&str>) -> Option<String> {
+    let json = CString::new(json).unwrap();
+    let locale = locale.map(|x| CString::new(x).unwrap());
+    let locale = locale.as_ref().map_or(ptr::null(), |x| x.as_ptr());
+
+    unsafe {
+        let text = sg_translate(json.as_ptr(), locale);
+        if text.is_null() {
+            return None;
+        }
+        let res = CStr::from_ptr(text).to_str().unwrap().to_string();
+        sg_free(text);
+        Some(res)
+    }
+}
+
+#[test]
+fn translate_and_errors() {
+    assert_eq!(
+        translate(
+            r#"{"text": "Hello %(name)s!", "args": {"name": "Grace"}}"#,
+            None
+        )
+        .as_deref(),
+        Some("Hello Grace!")
+    );
+    assert_eq!(sg_last_error(), SG_OK);
+
+    assert_eq!(translate("{", Some("fr_FR")), None);
+    assert_eq!(sg_last_error(), SG_INVALID_MESSAGE);
+    assert_eq!(translate(r#"{"text": "%(missing)s"}"#, None), None);
+    assert_eq!(sg_last_error(), SG_RENDER_ERROR);
+    let message = unsafe { CStr::from_ptr(sg_last_error_message()) };
+    assert!(!message.to_bytes().is_empty());
+
+    assert_eq!(
+        unsafe { sg_translate(ptr::null(), ptr::null()) },
+        ptr::null_mut()
+    );
+    assert_eq!(sg_last_error(), SG_NULL_POINTER);
+    let locale = CString::new("fr").unwrap();
+    let data = b"not a catalog";
+    assert_eq!(
+        unsafe { sg_load_catalog(locale.as_ptr(), ptr::null(), data.as_ptr(), data.len()) },
+        SG_INVALID_CATALOG
+    );
+}
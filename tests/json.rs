@@ -1,12 +1,12 @@
 #[macro_use]
 extern crate serde_json;
-#[macro_use]
-extern crate serde_derive;
 
 use libc_strftime::{set_locale, tz_set};
 use serde::Deserialize;
 use serde_gettext::*;
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
 
 #[derive(Deserialize, Clone)]
 #[serde(untagged)]
@@ -45,6 +45,71 @@ fn custom_message() {
     assert_eq!(message.into_string(), "Custom: true");
 }
 
+#[derive(Deserialize, Debug)]
+struct Price {
+    price: i64,
+    currency: String,
+}
+
+impl RenderFragment for Price {
+    fn render(&self, out: &mut dyn std::fmt::Write, ctx: &FragmentContext) -> Result<(), Error> {
+        let currency = ctx.gettext(&self.currency)?;
+        let sep = ctx.arg("sep").unwrap_or(".");
+        write!(
+            out,
+            "{}{}{:02} {}",
+            self.price / 100,
+            sep,
+            self.price % 100,
+            currency
+        )?;
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_fragment() {
+    register_fragment::<Price>("price");
+
+    let j = json!({
+        "text": "Total: %(total)s (%(items)s)",
+        "args": {
+            "total": {"price": 1250, "currency": "EUR"},
+            "items": [", ", {"price": 1000, "currency": "EUR"}, {"price": 250, "currency": "EUR"}],
+        },
+    });
+    let mut catalog = MockCatalog::new();
+    catalog.insert("EUR", "euros");
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(Arc::new(catalog));
+    assert_eq!(
+        String::try_from(message.clone()).unwrap(),
+        "Total: 12.50 euros (10.00 euros, 2.50 euros)"
+    );
+    assert_eq!(
+        String::try_from(message.arg("sep", ",")).unwrap(),
+        "Total: 12,50 euros (10,00 euros, 2,50 euros)"
+    );
+
+    // the objects of the built-in values and the other objects are not fragments
+    let j = json!({
+        "text": "%(total)s %(user.price)s",
+        "args": {"total": {"text": "free"}, "user": {"price": "none"}},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "free none");
+
+    let j = json!({
+        "text": "%(total)s",
+        "args": {"total": {"price": "invalid", "currency": "EUR"}},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(String::try_from(message).is_err());
+
+    assert!(unregister_fragment("price"));
+    assert!(!unregister_fragment("price"));
+}
+
 #[test]
 fn not_translated_text() {
     let j = json!({
@@ -74,6 +139,53 @@ fn not_translated_text_with_kwargs() {
     assert_eq!(String::try_from(message).unwrap(), "Hello Grace!");
 }
 
+#[test]
+fn conversion_specs() {
+    let j = json!({
+        "text": "%05d|%+d|%-5s|%5s|%.2f|%08.3f|%x|%#X|%#o|%e|%g|%g|%g|%g|%.3s|%5.1f%%",
+        "args": [
+            42, 42, "ab", "ab", 1.23456, -1.23456, 255, 255, 8, 12345.678, 0.0001, 1e-5,
+            123456789, 100, "abcdef", 99.5,
+        ],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "00042|+42|ab   |   ab|1.23|-001.235|ff|0XFF|0o10|1.234568e+04|0.0001|1e-05|1.23457e+08|100|abc| 99.5%"
+    );
+
+    let j = json!({
+        "text": "%*d %(user.name)-6s| %d 100% %z",
+        "args": [5, 42, "n/a"],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap_err().to_string(),
+        "format requires an argument map"
+    );
+
+    let j = json!({
+        "text": "%(n)4d %(user.name)-6s| %(x)d 100%! %z",
+        "args": {"n": 7, "user": {"name": "Grace"}, "x": true},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "   7 Grace | yes 100%! %z"
+    );
+
+    let j = json!({"text": "%*d", "args": [5, 42]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "   42");
+
+    let j = json!({"text": "%5000s", "args": ["x"]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
 #[test]
 fn datetime() {
     tz_set();
@@ -267,3 +379,2830 @@ fn base_args_union_order() {
     message.args.insert("name".to_string(), "Grace".to_string());
     assert_eq!(String::try_from(message).unwrap(), "Hello Marie!");
 }
+
+#[test]
+fn merge_leaf() {
+    let j = json!({
+        "gettext": "%(greetings)s",
+        "args": {
+            "user_name": "Grace",
+            "greetings": {
+                "gettext": "Hello %(user_name)s!",
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(String::try_from(message).is_err());
+}
+
+#[test]
+fn merge_deep() {
+    let j = json!({
+        "gettext": "%(greetings)s %(farewell)s",
+        "merge": "deep",
+        "args": {
+            "user_name": "Grace",
+            "greetings": {
+                "gettext": "Hello %(user_name)s!",
+            },
+            "farewell": {
+                "gettext": "%(bye)s",
+                "args": {
+                    "user_name": "Marie",
+                    "bye": {
+                        "gettext": "Bye %(user_name)s!",
+                    },
+                },
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "Hello Grace! Bye Marie!"
+    );
+}
+
+#[test]
+fn vars_ref() {
+    let j = json!({
+        "gettext": "%(summary)s (copy: %(copy)s)",
+        "vars": {
+            "order_summary": {
+                "ngettext": {
+                    "singular": "%(n)s item ordered",
+                    "plural": "%(n)s items ordered",
+                    "n": 3,
+                },
+            },
+        },
+        "args": {
+            "summary": {"ref": "order_summary"},
+            "copy": {"ref": "order_summary"},
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "3 items ordered (copy: 3 items ordered)"
+    );
+}
+
+#[test]
+fn vars_undefined() {
+    let j = json!({
+        "ref": "missing",
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::UndefinedVariable(_))
+    ));
+}
+
+#[test]
+fn vars_circular_reference() {
+    let j = json!({
+        "ref": "a",
+        "vars": {
+            "a": {"text": "%s", "args": [{"ref": "b"}]},
+            "b": {"ref": "a"},
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::CircularReference(_))
+    ));
+}
+
+#[test]
+fn defaults() {
+    let j = json!({
+        "gettext": "Hello %(name)s!",
+        "defaults": {"name": "stranger"},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello stranger!");
+
+    let j = json!({
+        "gettext": "Hello %(name)s!",
+        "args": {"name": "Grace"},
+        "defaults": {"name": "stranger"},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello Grace!");
+}
+
+#[test]
+fn defaults_base_args() {
+    let j = json!({
+        "text": "Hello %(name)s!",
+        "defaults": {"name": "stranger"},
+    });
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.args.insert("name".to_string(), "Grace".to_string());
+    assert_eq!(String::try_from(message).unwrap(), "Hello Grace!");
+}
+
+#[test]
+fn filters() {
+    let j = json!({
+        "gettext": "Status: %(status)s",
+        "args": {
+            "status": {
+                "value": {"gettext": "  shipped "},
+                "filters": ["trim", "upper"],
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Status: SHIPPED");
+
+    let j = json!({
+        "value": {"text": "hello wORLD"},
+        "filters": ["title", {"truncate": {"len": 7}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello W");
+}
+
+#[test]
+fn filters_unicode_case_mapping() {
+    let j = json!({
+        "value": {"text": "straße"},
+        "filters": ["upper"],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "STRASSE");
+
+    let j = json!({
+        "value": {"text": "ǆemal ΟΔΟΣ"},
+        "filters": ["titlecase"],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "ǅemal Οδος");
+}
+
+#[test]
+fn filters_truncate() {
+    let j = json!({
+        "value": {"text": "Hello World!"},
+        "filters": [{"truncate": {"len": 8, "ellipsis": "…"}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello W…");
+
+    let j = json!({
+        "value": {"text": "Hello World!"},
+        "filters": [{"truncate": {"len": 12, "ellipsis": "…"}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello World!");
+
+    let j = json!({
+        "value": {"text": "ne\u{301}e\u{301}"},
+        "filters": [{"truncate": {"len": 2}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "ne\u{301}");
+
+    let j = json!({
+        "value": {"text": "Hello World!"},
+        "filters": [{"truncate": {"len": 0, "ellipsis": "…"}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "");
+
+    let j = json!({
+        "value": {"text": "Hello World!"},
+        "filters": [{"truncate": {"len": 3, "ellipsis": "……………"}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hel");
+
+    let j = json!({
+        "value": {"text": "Hello World!"},
+        "filters": [{"truncate": {"len": 3, "ellipsis": "..."}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "...");
+}
+
+#[test]
+fn filters_padding() {
+    let j = json!({
+        "text": "[%s|%s|%s]",
+        "args": [
+            {"value": "ab", "filters": [{"pad_left": {"width": 5}}]},
+            {"value": "ab", "filters": [{"pad_right": {"width": 5, "fill": "."}}]},
+            {"value": "ab", "filters": [{"center": {"width": 5, "fill": "-"}}]},
+        ],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "[   ab|ab...|-ab--]");
+}
+
+#[test]
+fn filters_precision() {
+    let round = |value: serde_json::Value, precision: usize, rounding: &str| {
+        let j = json!({"value": value, "precision": precision, "rounding": rounding});
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap()
+    };
+    assert_eq!(round(json!(1.23456), 2, "half_up"), "1.23");
+    assert_eq!(round(json!(2.675), 2, "half_up"), "2.68");
+    assert_eq!(round(json!(2.5), 0, "half_even"), "2");
+    assert_eq!(round(json!(3.5), 0, "half_even"), "4");
+    assert_eq!(round(json!(-2.5), 0, "half_down"), "-2");
+    assert_eq!(round(json!(-2.5), 0, "half_up"), "-3");
+    assert_eq!(round(json!(1.01), 1, "up"), "1.1");
+    assert_eq!(round(json!(1.99), 1, "down"), "1.9");
+    assert_eq!(round(json!(-1.01), 1, "ceiling"), "-1.0");
+    assert_eq!(round(json!(-1.01), 1, "floor"), "-1.1");
+    assert_eq!(round(json!(9.995), 2, "half_up"), "10.00");
+    assert_eq!(round(json!(-0.001), 2, "half_up"), "0.00");
+    assert_eq!(round(json!(7), 2, "half_up"), "7.00");
+    assert_eq!(round(json!(1e21), 1, "half_up"), "1000000000000000000000.0");
+    assert_eq!(round(json!("n/a"), 2, "half_up"), "n/a");
+
+    let j = json!({
+        "text": "%(total)s",
+        "args": {"total": {"value": 19.999, "precision": 2, "filters": [{"pad_left": {"width": 7}}]}},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "  20.00");
+
+    let j = json!({"value": 1, "precision": 5000});
+    assert!(matches!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn gettext_with_args_map() {
+    let j = json!({
+        "gettext": "Totals: %(totals)s",
+        "args": {
+            "totals": {
+                "map": {"pears": 5, "apples": 3},
+                "entry": "%(value)s %(key)s",
+                "sep": " and ",
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "Totals: 3 apples and 5 pears"
+    );
+
+    let j = json!({
+        "map": {"b": true, "a": {"gettext": "Hello!"}},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "a: Hello!, b: yes");
+}
+
+#[test]
+fn gettext_with_args_dotted_path() {
+    let j = json!({
+        "gettext": "Hello %(user.name)s (%(user.address.city)s)!",
+        "args": {
+            "user": {
+                "name": "Grace",
+                "address": {"city": "Arlington"},
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "Hello Grace (Arlington)!"
+    );
+}
+
+#[test]
+fn gettext_with_args_dotted_path_reserved_keys() {
+    let args = json!({"user": {"name": "Ada", "text": "Ada Lovelace"}});
+
+    let j = json!({"gettext": "%(user)s", "args": args});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Ada Lovelace");
+
+    let j = json!({"gettext": "%(user.name)s", "args": args});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(String::try_from(message).is_err());
+}
+
+#[test]
+fn foreach() {
+    let j = json!({
+        "gettext": "Guests:\n%(guests)s",
+        "args": {
+            "guests": {
+                "foreach": {
+                    "items": [
+                        {"name": "Grace", "vip": true},
+                        {"name": "Marie", "vip": false},
+                    ],
+                    "template": {
+                        "gettext": "- %(name)s (VIP: %(vip)s)",
+                    },
+                    "sep": "\n",
+                },
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "Guests:\n- Grace (VIP: yes)\n- Marie (VIP: no)"
+    );
+}
+
+#[test]
+fn gettext_with_args_sorted_array() {
+    let j = json!({
+        "gettext": "%(fruits)s",
+        "args": {
+            "fruits": {
+                "join": [", ", "pear", "banana", "apple"],
+                "sort": true,
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "apple, banana, pear");
+
+    let j = json!({
+        "join": [", ", "pear", "banana", "apple"],
+        "sort": true,
+        "collate": true,
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "apple, banana, pear");
+
+    let j = json!({
+        "join": {"sep": ", ", "items": ["pear", "banana", "apple"]},
+        "sort": true,
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "apple, banana, pear");
+
+    // the locales that aren't installed fall back to the locale of the process
+    assert_eq!(
+        collate("xx_XX.UTF-8", &["pear", "banana", "apple", "banana"]),
+        vec![2, 1, 3, 0]
+    );
+    assert_eq!(collate("C", &["b\0", "a"]), vec![1, 0]);
+}
+
+#[test]
+fn ngettext_explicit_counts() {
+    let j = json!({
+        "ngettext": {
+            "singular": "%(n)s item has been deleted",
+            "plural": "%(n)s items have been deleted",
+            "zero": "No item has been deleted",
+            "n": 0,
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "No item has been deleted"
+    );
+
+    let j = json!({
+        "npgettext": {
+            "ctx": "files",
+            "singular": "%(n)s item has been deleted",
+            "plural": "%(n)s items have been deleted",
+            "zero": "No item has been deleted",
+            "n": 3,
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "3 items have been deleted"
+    );
+}
+
+#[test]
+fn selectplural() {
+    let j = json!({
+        "selectplural": {
+            "select": "female",
+            "n": 5,
+            "forms": {
+                "female": {
+                    "singular": "She added %(n)s photo",
+                    "plural": "She added %(n)s photos",
+                },
+                "other": {
+                    "singular": "They added %(n)s photo",
+                    "plural": "They added %(n)s photos",
+                },
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "She added 5 photos");
+
+    let j = json!({
+        "selectplural": {
+            "select": "unknown",
+            "n": 1,
+            "forms": {
+                "other": {
+                    "singular": "They added %(n)s photo",
+                    "plural": "They added %(n)s photos",
+                },
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "They added 1 photo");
+
+    let j = json!({
+        "selectplural": {
+            "select": "unknown",
+            "n": 1,
+            "forms": {},
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::MissingForm(_))
+    ));
+}
+
+#[test]
+fn ngettext_big_numbers() {
+    let j = json!({
+        "ngettext": {
+            "singular": "%(n)s element",
+            "plural": "%(n)s elements",
+            "n": 18_446_744_073_709_551_615u64,
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "18446744073709551615 elements"
+    );
+
+    let j = json!({
+        "ngettext": {
+            "singular": "%(n)s element",
+            "plural": "%(n)s elements",
+            "n": "-1000000000000000000000",
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "-1000000000000000000000 elements"
+    );
+
+    // the counts beyond u32 reach the backend unchanged
+    let mut catalog = MockCatalog::new();
+    catalog
+        .insert_plural("%(n)s element", &["small", "huge"])
+        .plural_rule(|n| usize::from(n > u64::from(u32::MAX)));
+    let mut message = SerdeGetText::deserialize(&json!({
+        "ngettext": {"singular": "%(n)s element", "plural": "%(n)s elements", "n": 5_000_000_000u64},
+    }))
+    .unwrap();
+    message.backend = Some(Arc::new(catalog));
+    assert_eq!(String::try_from(message).unwrap(), "huge");
+}
+
+#[test]
+fn default_fallback() {
+    let j = json!({
+        "gettext": "Welcome to the new dashboard %(name)s",
+        "default": "Welcome %(name)s!",
+        "args": {"name": "Grace"},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Welcome Grace!");
+
+    let j = json!({
+        "ngettext": {
+            "singular": "%(n)s element",
+            "plural": "%(n)s elements",
+            "n": 2,
+        },
+        "default": "Some elements",
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Some elements");
+}
+
+#[derive(Default)]
+struct Missing(Mutex<Vec<(Option<String>, String)>>);
+
+impl MissingTranslationObserver for Missing {
+    fn on_missing(&self, domain: Option<&str>, msgid: &str, _locale: Option<&str>) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((domain.map(String::from), msgid.to_string()));
+    }
+}
+
+#[test]
+fn missing_translation_observer() {
+    let j = json!({
+        "gettext": "%(a)s %(b)s",
+        "args": {
+            "a": {"dgettext": {"domain": "some_domain", "msgid": "Hello"}},
+            "b": {"text": "World"},
+        },
+    });
+    let observer = Arc::new(Missing::default());
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.observer = Some(observer.clone());
+    assert_eq!(String::try_from(message).unwrap(), "Hello World");
+
+    let mut missing = observer.0.lock().unwrap().clone();
+    missing.sort();
+    assert_eq!(
+        missing,
+        vec![
+            (None, "%(a)s %(b)s".to_string()),
+            (Some("some_domain".to_string()), "Hello".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn missing_translation_identical_to_msgid() {
+    let mut catalog = Catalog::new();
+    catalog
+        .load_mo(None, &mo_file(&[("OK", "OK"), ("Untranslated", "")]))
+        .unwrap();
+
+    let j = json!({
+        "text": "%(a)s %(b)s",
+        "args": {"a": {"gettext": "OK"}, "b": {"gettext": "Untranslated"}},
+    });
+    let observer = Arc::new(Missing::default());
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.observer = Some(observer.clone());
+    message.backend = Some(Arc::new(catalog));
+    assert_eq!(String::try_from(message).unwrap(), "OK Untranslated");
+
+    // the catalog has a translation of `OK`, even if it is the msgid itself
+    assert_eq!(
+        *observer.0.lock().unwrap(),
+        vec![(None, "Untranslated".to_string())]
+    );
+}
+
+#[test]
+fn empty_msgid() {
+    let j = json!({
+        "gettext": "",
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "");
+
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.empty_msgid = EmptyMsgid::Error;
+    assert!(matches!(String::try_from(message), Err(Error::EmptyMsgid)));
+}
+
+#[test]
+fn pseudo_localization() {
+    let j = json!({
+        "gettext": "Hello %(name)s! %(answer)s",
+        "pseudo": true,
+        "args": {"name": "Grace", "answer": true},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "[Hélló Grace! [ýés~]~~]"
+    );
+}
+
+#[test]
+fn mock_catalog() {
+    let mut catalog = MockCatalog::new();
+    catalog
+        .insert("Hello %(name)s!", "Bonjour %(name)s !")
+        .insert("yes", "oui")
+        .insert_plural("%(n)s apple", &["%(n)s pomme", "%(n)s pommes"])
+        .insert_with(None, Some("menu"), "File", &["Fichier"]);
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(
+        render(json!({
+            "gettext": "Hello %(name)s!",
+            "args": {"name": "Grace"},
+        })),
+        "Bonjour Grace !"
+    );
+    assert_eq!(
+        render(json!({
+            "text": "%(fruits)s %(ok)s",
+            "args": {
+                "fruits": {"ngettext": {"singular": "%(n)s apple", "plural": "%(n)s apples", "n": 3}},
+                "ok": true,
+            },
+        })),
+        "3 pommes oui"
+    );
+    assert_eq!(
+        render(json!({"pgettext": {"ctx": "menu", "msgid": "File"}})),
+        "Fichier"
+    );
+    assert_eq!(render(json!({"gettext": "File"})), "File");
+}
+
+/// Compiled catalog (MO file) of the entries, the plural forms are separated by NUL characters
+fn mo_file(entries: &[(&str, &str)]) -> Vec<u8> {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(original, translation)| (original.as_bytes(), translation.as_bytes()))
+        .collect();
+    mo_file_bytes(&entries)
+}
+
+/// Compiled catalog (MO file) of entries in any charset
+fn mo_file_bytes(entries: &[(&[u8], &[u8])]) -> Vec<u8> {
+    let mut entries = entries.to_vec();
+    entries.sort();
+    let originals = 28;
+    let translations = originals + entries.len() * 8;
+    let mut strings = translations + entries.len() * 8;
+    let mut header: Vec<u32> = vec![0x9504_12de, 0, entries.len() as u32];
+    header.extend(&[originals as u32, translations as u32, 0, 0]);
+    let mut tables = Vec::new();
+    let mut data = Vec::new();
+
+    for s in entries
+        .iter()
+        .map(|x| x.0)
+        .chain(entries.iter().map(|x| x.1))
+    {
+        tables.extend(&[s.len() as u32, strings as u32]);
+        data.extend(s);
+        data.push(0);
+        strings += s.len() + 1;
+    }
+
+    let mut mo: Vec<u8> = header
+        .iter()
+        .chain(tables.iter())
+        .flat_map(|x| x.to_le_bytes().to_vec())
+        .collect();
+    mo.extend(data);
+    mo
+}
+
+#[test]
+fn catalog_mo() {
+    let mut catalog = Catalog::new();
+    catalog
+        .load_mo(
+            None,
+            &mo_file(&[
+                (
+                    "",
+                    "Content-Type: text/plain; charset=UTF-8\n\
+                     Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && \
+                     n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);\n",
+                ),
+                (
+                    "%(n)s file\0%(n)s files",
+                    "%(n)s plik\0%(n)s pliki\0%(n)s plików",
+                ),
+                ("menu\u{4}File", "Plik"),
+                ("Untranslated", ""),
+            ]),
+        )
+        .unwrap()
+        .load_mo(
+            Some("errors"),
+            &mo_file(&[
+                // the fields of the header may come in any order
+                ("", "Plural-Forms: plural=n > 1; nplurals=2;\n"),
+                ("Oops", "Ups"),
+                ("%(n)s error\0%(n)s errors", "%(n)s erreur\0%(n)s erreurs"),
+            ]),
+        )
+        .unwrap();
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        String::try_from(message).unwrap()
+    };
+    let files = |n: u32| {
+        render(json!({"ngettext": {"singular": "%(n)s file", "plural": "%(n)s files", "n": n}}))
+    };
+
+    assert_eq!(files(1), "1 plik");
+    assert_eq!(files(3), "3 pliki");
+    assert_eq!(files(5), "5 plików");
+    assert_eq!(files(22), "22 pliki");
+    assert_eq!(
+        render(json!({"pgettext": {"ctx": "menu", "msgid": "File"}})),
+        "Plik"
+    );
+    assert_eq!(render(json!({"gettext": "Untranslated"})), "Untranslated");
+    assert_eq!(
+        render(json!({"dgettext": {"domain": "errors", "msgid": "Oops"}})),
+        "Ups"
+    );
+    // every text domain has its own plural rule
+    let errors = |n: u32| {
+        render(json!({"dngettext": {
+            "domain": "errors",
+            "singular": "%(n)s error",
+            "plural": "%(n)s errors",
+            "n": n,
+        }}))
+    };
+    assert_eq!(errors(0), "0 erreur");
+    assert_eq!(errors(5), "5 erreurs");
+    assert!(Catalog::new().load_mo(None, b"not a catalog").is_err());
+    assert!(Catalog::new()
+        .load_mo(
+            None,
+            &mo_file(&[("", "Plural-Forms: nplurals=2; plural=n +;\n")])
+        )
+        .is_err());
+}
+
+#[test]
+fn catalog_charset() {
+    let mut catalog = Catalog::new();
+    catalog
+        .load_mo(
+            None,
+            &mo_file_bytes(&[
+                (b"", b"Content-Type: text/plain; charset=ISO-8859-1\n"),
+                (b"Coffee", b"Caf\xe9"),
+            ]),
+        )
+        .unwrap();
+    assert!(catalog.has_translation(None, "Coffee"));
+    let mut message = SerdeGetText::deserialize(&json!({"gettext": "Coffee"})).unwrap();
+    message.backend = Some(Arc::new(catalog));
+    assert_eq!(String::try_from(message).unwrap(), "Café");
+
+    assert!(Catalog::new()
+        .load_mo(None, &mo_file_bytes(&[(b"Coffee", b"Caf\xe9")]))
+        .is_err());
+    assert!(Catalog::new()
+        .load_mo(
+            None,
+            &mo_file(&[("", "Content-Type: text/plain; charset=KOI8-R\n")])
+        )
+        .is_err());
+}
+
+/// Backend of a legacy catalog returning invalid UTF-8
+struct Latin1Backend;
+
+impl Backend for Latin1Backend {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        self.translate_checked(lookup)
+            .map(|x| x.unwrap_or_else(|x| x))
+    }
+
+    fn translate_checked(&self, lookup: &Lookup) -> Option<Result<String, String>> {
+        match lookup.msgid {
+            "Coffee" => Some(Err(String::from_utf8_lossy(b"Caf\xe9").into_owned())),
+            "yes" => Some(Ok("oui".to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn decoding() {
+    let j =
+        json!({"text": "%(coffee)s %(ok)s", "args": {"coffee": {"gettext": "Coffee"}, "ok": true}});
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(Arc::new(Latin1Backend));
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::InvalidEncoding(_))
+    ));
+
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(Arc::new(Latin1Backend));
+    message.decoding = Decoding::Lossy;
+    assert_eq!(String::try_from(message).unwrap(), "Caf\u{fffd} oui");
+}
+
+#[test]
+fn catalog_introspection() {
+    let mut catalog = Catalog::new();
+    catalog
+        .load_mo(
+            None,
+            &mo_file(&[("Hello", "Bonjour"), ("Untranslated", "")]),
+        )
+        .unwrap()
+        .load_mo(Some("errors"), &mo_file(&[("Oops", "Ups")]))
+        .unwrap();
+    assert_eq!(catalog.available_domains(), vec![None, Some("errors")]);
+    assert!(catalog.has_translation(None, "Hello"));
+    assert!(!catalog.has_translation(None, "Untranslated"));
+    assert!(!catalog.has_translation(None, "Oops"));
+    assert!(catalog.has_translation(Some("errors"), "Oops"));
+
+    let dir = std::env::temp_dir().join(format!("serde-gettext-locales-{}", std::process::id()));
+    for (locale, domain) in &[("fr", "app"), ("fr", "errors"), ("de_DE", "app")] {
+        let messages = dir.join(locale).join("LC_MESSAGES");
+        std::fs::create_dir_all(&messages).unwrap();
+        std::fs::write(
+            messages.join(format!("{}.mo", domain)),
+            mo_file(&[("Hello", "Bonjour")]),
+        )
+        .unwrap();
+    }
+    std::fs::create_dir_all(dir.join("it").join("LC_MESSAGES")).unwrap();
+    std::fs::write(dir.join("locale.alias"), "").unwrap();
+    let locales = LocaleDir::new(&dir);
+    assert_eq!(locales.available_locales().unwrap(), vec!["de_DE", "fr"]);
+    assert_eq!(
+        locales.available_domains("fr").unwrap(),
+        vec!["app", "errors"]
+    );
+    assert!(locales.available_domains("it").unwrap().is_empty());
+    assert!(locales.has_translation("app", "Hello", "fr"));
+    assert!(!locales.has_translation("app", "Bye", "fr"));
+    assert!(!locales.has_translation("app", "Hello", "it"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn catalog_watch() {
+    use std::time::{Duration, Instant};
+
+    let dir = std::env::temp_dir().join(format!("serde-gettext-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.mo");
+    std::fs::write(&path, mo_file(&[("Hello", "Bonjour")])).unwrap();
+    let catalog = Catalog::watch(&path).unwrap();
+    let hello = |catalog: &watch::WatchedCatalog| {
+        let mut message = SerdeGetText::deserialize(&json!({"gettext": "Hello"})).unwrap();
+        message.backend = Some(catalog.current());
+        String::try_from(message).unwrap()
+    };
+    assert_eq!(hello(&catalog), "Bonjour");
+
+    // replaced like a deployment does
+    let new_path = dir.join("app.mo.new");
+    std::fs::write(&new_path, mo_file(&[("Hello", "Salut")])).unwrap();
+    std::fs::rename(&new_path, &path).unwrap();
+    let start = Instant::now();
+    while hello(&catalog) != "Salut" && start.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(hello(&catalog), "Salut");
+
+    // the previous catalog is kept while the file is invalid
+    std::fs::write(&path, b"not a catalog").unwrap();
+    assert!(catalog.reload().is_err());
+    assert_eq!(hello(&catalog), "Salut");
+    assert!(Catalog::watch(dir.join("missing.mo")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn decimal() {
+    let j = json!({
+        "text": "%(price)s %(total).2f %(tax).2f %(big)f %(float).1f",
+        "args": {
+            "price": {"decimal": "19.90"},
+            "total": "1.005",
+            "tax": {"decimal": "-0.125"},
+            "big": "12345678901234567890.123456789",
+            "float": 0.25,
+        },
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "19.90 1.01 -0.13 12345678901234567890.123457 0.3"
+    );
+}
+
+#[cfg(feature = "phone")]
+#[test]
+fn phone() {
+    let render = |phone: serde_json::Value, locale: Option<&str>| {
+        let mut message = SerdeGetText::deserialize(&phone).unwrap();
+        message.locale = locale.map(ToString::to_string);
+        String::try_from(message).unwrap()
+    };
+    assert_eq!(
+        render(json!({"phone": "0123456789"}), Some("fr_FR.UTF-8")),
+        "01 23 45 67 89"
+    );
+    assert_eq!(
+        render(json!({"phone": "+33123456789"}), Some("en_US.UTF-8")),
+        "+33 1 23 45 67 89"
+    );
+    assert_eq!(
+        render(json!({"phone": "01 23 45 67 89", "region": "fr"}), None),
+        "01 23 45 67 89"
+    );
+    assert_eq!(
+        render(
+            json!({"phone": "01 23 45 67 89", "region": "FR", "format": "e164"}),
+            None
+        ),
+        "+33123456789"
+    );
+    assert_eq!(
+        render(json!({"phone": "not a number"}), None),
+        "not a number"
+    );
+}
+
+#[cfg(feature = "po2json")]
+#[test]
+fn catalog_po2json() {
+    let mut catalog = Catalog::new();
+    // raw format of po2json: the arrays start with the plural form of the msgid
+    catalog
+        .load_json(
+            None,
+            br#"{
+                "": {"language": "fr", "plural-forms": "nplurals=2; plural=n>1;"},
+                "Hello %(name)s!": [null, "Bonjour %(name)s !"],
+                "%(n)s apple": ["%(n)s apples", "%(n)s pomme", "%(n)s pommes"],
+                "menu\u0004File": [null, "Fichier"]
+            }"#,
+        )
+        .unwrap()
+        // Jed 1.x: the arrays only have the translations
+        .load_json(
+            Some("app"),
+            br#"{
+                "domain": "messages",
+                "locale_data": {
+                    "messages": {
+                        "": {"domain": "messages", "plural_forms": "nplurals=2; plural=n != 1;"},
+                        "Oops": ["Ups"],
+                        "%(n)s error": ["%(n)s erreur", "%(n)s erreurs"]
+                    },
+                    "other": {"Bye": ["Salut"]}
+                }
+            }"#,
+        )
+        .unwrap()
+        // gettext.js
+        .load_json(
+            Some("js"),
+            br#"{"": {"plural-forms": "plural=0; nplurals=1;"}, "%(n)s day": "%(n)s jour"}"#,
+        )
+        .unwrap();
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(
+        render(json!({"gettext": "Hello %(name)s!", "args": {"name": "Grace"}})),
+        "Bonjour Grace !"
+    );
+    assert_eq!(
+        render(json!({"ngettext": {"singular": "%(n)s apple", "plural": "%(n)s apples", "n": 0}})),
+        "0 pomme"
+    );
+    assert_eq!(
+        render(json!({"pgettext": {"ctx": "menu", "msgid": "File"}})),
+        "Fichier"
+    );
+    assert_eq!(
+        render(json!({"dgettext": {"domain": "app", "msgid": "Oops"}})),
+        "Ups"
+    );
+    assert_eq!(
+        render(json!({"dngettext": {
+            "domain": "app",
+            "singular": "%(n)s error",
+            "plural": "%(n)s errors",
+            "n": 0,
+        }})),
+        "0 erreurs"
+    );
+    assert_eq!(
+        render(json!({"dgettext": {"domain": "other", "msgid": "Bye"}})),
+        "Salut"
+    );
+    assert_eq!(
+        render(json!({"dngettext": {
+            "domain": "js",
+            "singular": "%(n)s day",
+            "plural": "%(n)s days",
+            "n": 3,
+        }})),
+        "3 jour"
+    );
+    assert!(Catalog::new().load_json(None, b"[]").is_err());
+    assert!(Catalog::new()
+        .load_json(None, br#"{"Hello": [null, 1]}"#)
+        .is_err());
+}
+
+#[test]
+fn fixed_clock_and_locale() {
+    let j = json!({
+        "text": "%(date)s %(name)s",
+        "args": {
+            "date": {"strftime": "%Y-%m-%d %H:%M"},
+            "name": {"value": "istanbul", "filters": ["upper"]},
+        },
+    });
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.now = Some(1_565_854_615);
+    message.timezone = TimeZone::Utc;
+    message.locale = Some("tr_TR.UTF-8".to_string());
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "2019-08-15 07:36 \u{130}STANBUL"
+    );
+}
+
+#[test]
+fn limits() {
+    let mut vars = serde_json::Map::new();
+    for i in 0..200 {
+        vars.insert(format!("v{}", i), json!({"ref": format!("v{}", i + 1)}));
+    }
+    vars.insert("v200".to_string(), json!("end"));
+    let j = json!({
+        "ref": "v0",
+        "vars": vars,
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::LimitExceeded(_))
+    ));
+
+    // the messages nested in the arguments take the deepest path of the rendering
+    let mut vars = serde_json::Map::new();
+    for i in 0..200 {
+        vars.insert(
+            format!("v{}", i),
+            json!({
+                "ngettext": {"singular": "%(x)s", "plural": "%(x)s", "n": 2},
+                "args": {"x": {"ref": format!("v{}", i + 1)}},
+            }),
+        );
+    }
+    vars.insert("v200".to_string(), json!("end"));
+    let j = json!({
+        "ref": "v0",
+        "vars": vars,
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::LimitExceeded(_))
+    ));
+
+    let j = json!({
+        "value": "ab",
+        "filters": [{"pad_left": {"width": 1_000_000_000}}],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::LimitExceeded(_))
+    ));
+
+    let j = json!({
+        "strftime": "%Y\u{0}",
+        "epoch": 0,
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        String::try_from(message),
+        Err(Error::FormatError(_))
+    ));
+
+    let j = json!({
+        "gettext": "Hello\u{0}World",
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello\u{0}World");
+}
+
+#[test]
+fn extract_msgids() {
+    let j = json!({
+        "gettext": "Hello %(name)s! %(items)s",
+        "args": {
+            "name": {"pgettext": {"ctx": "user", "msgid": "Anonymous"}},
+            "items": {"ref": "items"},
+        },
+        "vars": {
+            "items": {
+                "dngettext": {
+                    "domain": "shop",
+                    "singular": "%(n)s item",
+                    "plural": "%(n)s items",
+                    "n": 2,
+                    "zero": "No items",
+                },
+            },
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let msgid = |domain: Option<&str>, context: Option<&str>, msgid: &str, plural: Option<&str>| {
+        extract::ExtractedMsgid {
+            domain: domain.map(ToString::to_string),
+            context: context.map(ToString::to_string),
+            msgid: msgid.to_string(),
+            plural: plural.map(ToString::to_string),
+            comments: Vec::new(),
+            references: Vec::new(),
+        }
+    };
+    assert_eq!(
+        message.extract_msgids(),
+        vec![
+            msgid(None, None, "Hello %(name)s! %(items)s", None),
+            msgid(None, Some("user"), "Anonymous", None),
+            msgid(Some("shop"), None, "%(n)s item", Some("%(n)s items")),
+            msgid(Some("shop"), None, "No items", None),
+        ]
+    );
+}
+
+#[cfg(feature = "extract")]
+#[test]
+fn extract_dir() {
+    use std::path::{Path, PathBuf};
+
+    let dir = std::env::temp_dir().join(format!("serde-gettext-extract-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("emails")).unwrap();
+    std::fs::write(dir.join("title.yaml"), "gettext: Welcome!\n").unwrap();
+    std::fs::write(
+        dir.join("emails").join("list.json"),
+        r#"[{"gettext": "Welcome!"}, {"gettext": "Bye!"}]"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("emails").join("named.yml"),
+        "greeting:\n    gettext: Hello!\nfarewell:\n    gettext: Bye!\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("README.md"), "not a template").unwrap();
+    let msgids = extract::extract_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let files = |msgid: &str| {
+        msgids[&extract::ExtractedMsgid {
+            domain: None,
+            context: None,
+            msgid: msgid.to_string(),
+            plural: None,
+            comments: Vec::new(),
+            references: Vec::new(),
+        }]
+            .iter()
+            .map(|path| path.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(msgids.len(), 3);
+    assert_eq!(
+        files("Bye!"),
+        vec![
+            Path::new("emails").join("list.json"),
+            Path::new("emails").join("named.yml"),
+        ]
+    );
+    assert_eq!(files("Hello!"), vec![Path::new("emails").join("named.yml")]);
+    assert_eq!(
+        files("Welcome!"),
+        vec![
+            Path::new("emails").join("list.json"),
+            PathBuf::from("title.yaml"),
+        ]
+    );
+}
+
+#[test]
+fn pot() {
+    let j = json!({
+        "gettext": "Say \"%(greeting)s\"\nto everyone",
+        "args": {
+            "greeting": {"npgettext": {"ctx": "greeting", "singular": "Hello", "plural": "Hellos", "n": 1}},
+            "ignored": {"dgettext": {"domain": "other", "msgid": "Other domain"}},
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let msgids = message
+        .extract_msgids()
+        .into_iter()
+        .map(|x| (x, vec!["templates/hello.json".into()]))
+        .collect();
+    let mut out = Vec::new();
+    pot::write(&mut out, &msgids, None).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    // the msgids read back are the ones written, without the header
+    assert_eq!(
+        pot::read(&out, None).unwrap(),
+        msgids
+            .keys()
+            .filter(|x| x.domain.is_none())
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        out,
+        r#"msgid ""
+msgstr ""
+"Project-Id-Version: PACKAGE VERSION\n"
+"MIME-Version: 1.0\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Content-Transfer-Encoding: 8bit\n"
+"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n"
+
+#: templates/hello.json
+#, python-format
+msgid ""
+"Say \"%(greeting)s\"\n"
+"to everyone"
+msgstr ""
+
+#: templates/hello.json
+msgctxt "greeting"
+msgid "Hello"
+msgid_plural "Hellos"
+msgstr[0] ""
+msgstr[1] ""
+"#
+    );
+}
+
+#[test]
+fn translator_comments() {
+    let j = json!({"join": [
+        " ",
+        {"gettext": "Open", "comment": "Verb: the button opening a file"},
+        {
+            "ngettext": {"singular": "%(n)s file", "plural": "%(n)s files", "n": 2, "zero": "No files"},
+            "translators": "Files in the trash\nshown in the sidebar",
+        },
+        {"pgettext": {"ctx": "menu", "msgid": "Open"}, "translators": "Menu item"},
+        {"noop": "Open", "comment": "Tooltip"},
+        {"gettext": "Open", "comment": "Verb: the button opening a file"},
+    ]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let comments = |msgid: &str, context: Option<&str>| {
+        message
+            .extract_msgids()
+            .into_iter()
+            .find(|x| x.msgid == msgid && x.context.as_deref() == context)
+            .unwrap()
+            .comments
+    };
+
+    // the comments of the uses of a msgid are merged
+    assert_eq!(
+        comments("Open", None),
+        vec!["Verb: the button opening a file", "Tooltip"]
+    );
+    assert_eq!(comments("Open", Some("menu")), vec!["Menu item"]);
+    assert_eq!(
+        comments("No files", None),
+        vec!["Files in the trash\nshown in the sidebar"]
+    );
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "Open 2 files Open Open Open"
+    );
+
+    let msgids = SerdeGetText::deserialize(&j["join"][2])
+        .unwrap()
+        .extract_msgids()
+        .into_iter()
+        .map(|x| (x, Vec::new()))
+        .collect();
+    let mut out = Vec::new();
+    pot::write(&mut out, &msgids, None).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(
+        r#"
+#. Files in the trash
+#. shown in the sidebar
+#, python-format
+msgid "%(n)s file"
+msgid_plural "%(n)s files"
+msgstr[0] ""
+msgstr[1] ""
+"#
+    ));
+}
+
+#[test]
+fn source_references() {
+    let j = json!({"join": [
+        " ",
+        {"gettext": "Save", "source": {"file": "src/editor.rs", "line": 42}},
+        {"gettext": "Save", "source": {"file": "src/toolbar.rs"}},
+        {"noop": "Saved", "source": {"file": "src/editor.rs", "line": 57}},
+    ]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let msgids = message
+        .extract_msgids()
+        .into_iter()
+        .map(|x| (x, vec!["templates/editor.json".into()]))
+        .collect();
+    let mut out = Vec::new();
+    pot::write(&mut out, &msgids, None).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(out.ends_with(
+        r#"
+#: src/editor.rs:42 src/toolbar.rs templates/editor.json
+msgid "Save"
+msgstr ""
+
+#: src/editor.rs:57 templates/editor.json
+msgid "Saved"
+msgstr ""
+"#
+    ));
+    assert_eq!(String::try_from(message).unwrap(), "Save Save Saved");
+}
+
+#[test]
+fn verify_catalogs() {
+    let messages: Vec<SerdeGetText> = [
+        json!({"gettext": "Hello"}),
+        json!({"ngettext": {"singular": "%(n)s item", "plural": "%(n)s items", "n": 2}}),
+    ]
+    .iter()
+    .map(|j| SerdeGetText::deserialize(j).unwrap())
+    .collect();
+    let mut fr = MockCatalog::new();
+    fr.insert("Hello", "Bonjour")
+        .insert_plural("%(n)s item", &["%(n)s article", "%(n)s articles"]);
+    let mut de = MockCatalog::new();
+    de.insert("Hello", "Hallo");
+
+    let report = validate::verify(&messages, &[("fr", &fr), ("de", &de)]);
+    assert!(!report.is_complete());
+    assert_eq!(report.msgids, 2);
+    assert_eq!(report.missing.keys().collect::<Vec<_>>(), vec!["de"]);
+    assert_eq!(report.missing["de"][0].msgid, "%(n)s item");
+}
+
+#[test]
+fn lint_placeholders() {
+    let j = json!({
+        "ngettext": {
+            "singular": "One file in %(dir)s",
+            "plural": "%(n)s files",
+            "n": 1,
+        },
+        "args": {"dir": "/tmp", "unused": "x"},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let mut catalog = MockCatalog::new();
+    catalog.insert_plural(
+        "One file in %(dir)s",
+        &["Un fichier dans %(dossier)s", "%(n)s fichiers"],
+    );
+    assert_eq!(
+        validate::lint(&message, Some(&catalog)),
+        vec![
+            validate::LintWarning::TranslationMismatch {
+                msgid: "One file in %(dir)s".to_string(),
+                translation: "Un fichier dans %(dossier)s".to_string(),
+                placeholder: "dossier".to_string(),
+            },
+            validate::LintWarning::PluralMismatch {
+                msgid: "One file in %(dir)s".to_string(),
+                placeholder: "dir".to_string(),
+            },
+            validate::LintWarning::UnusedArg {
+                msgid: "One file in %(dir)s".to_string(),
+                arg: "unused".to_string(),
+            },
+        ]
+    );
+}
+
+/// Backend counting its lookups
+#[derive(Default)]
+struct CountingBackend(Mutex<usize>);
+
+impl Backend for CountingBackend {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        *self.0.lock().unwrap() += 1;
+        Some(format!("T:{}", lookup.msgid))
+    }
+}
+
+#[test]
+fn render_cache() {
+    let backend = Arc::new(CountingBackend::default());
+    let cache = RenderCache::new(2);
+    let render = |j: serde_json::Value, locale: &str| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        message.locale = Some(locale.to_string());
+        cache.render(message).unwrap()
+    };
+    let hello = json!({"gettext": "Hello %(name)s!", "args": {"name": "Grace", "n": 1}});
+    let hello_reordered = json!({"gettext": "Hello %(name)s!", "args": {"n": 1, "name": "Grace"}});
+
+    assert_eq!(render(hello.clone(), "fr_FR"), "T:Hello Grace!");
+    assert_eq!(render(hello_reordered, "fr_FR"), "T:Hello Grace!");
+    assert_eq!(*backend.0.lock().unwrap(), 1);
+
+    // the locale is part of the key
+    render(hello.clone(), "de_DE");
+    assert_eq!(*backend.0.lock().unwrap(), 2);
+    assert_eq!(cache.len(), 2);
+
+    // the least recently used message is evicted
+    render(hello.clone(), "fr_FR");
+    render(json!({"gettext": "Bye!"}), "fr_FR");
+    assert_eq!(cache.len(), 2);
+    render(hello.clone(), "fr_FR");
+    assert_eq!(*backend.0.lock().unwrap(), 3);
+    render(hello.clone(), "de_DE");
+    assert_eq!(*backend.0.lock().unwrap(), 4);
+
+    // the dates at the current time are never cached
+    let now = json!({"gettext": "%(now)s", "args": {"now": {"strftime": "%s"}}});
+    render(now.clone(), "fr_FR");
+    render(now, "fr_FR");
+    assert_eq!(*backend.0.lock().unwrap(), 6);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn lookups_memoized() {
+    let j = json!({
+        "foreach": {
+            "items": [{"name": "Grace"}, {"name": "Marie"}, {"name": "Ada"}],
+            "template": {"gettext": "Hello %(name)s!"},
+            "sep": " ",
+        },
+    });
+    let backend = Arc::new(CountingBackend::default());
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(backend.clone());
+
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "T:Hello Grace! T:Hello Marie! T:Hello Ada!"
+    );
+    assert_eq!(*backend.0.lock().unwrap(), 1);
+}
+
+#[test]
+fn interner() {
+    let backend = Arc::new(CountingBackend::default());
+    let other_backend = Arc::new(CountingBackend::default());
+    let interner = Arc::new(Interner::new());
+    let render = |j: serde_json::Value, backend: &Arc<CountingBackend>| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        message.interner = Some(interner.clone());
+        String::try_from(message).unwrap()
+    };
+
+    // the arguments change but the translation is looked up only once
+    for name in ["Grace", "Marie", "Ada"] {
+        let j = json!({"gettext": "Hello %(name)s!", "args": {"name": name}});
+        assert_eq!(render(j, &backend), format!("T:Hello {}!", name));
+    }
+    assert_eq!(*backend.0.lock().unwrap(), 1);
+
+    // the backend is part of the key
+    render(
+        json!({"gettext": "Hello %(name)s!", "args": {"name": "Grace"}}),
+        &other_backend,
+    );
+    assert_eq!(*other_backend.0.lock().unwrap(), 1);
+    assert_eq!(interner.len(), 2);
+
+    interner.clear();
+    assert!(interner.is_empty());
+    render(
+        json!({"gettext": "Hello %(name)s!", "args": {"name": "Grace"}}),
+        &backend,
+    );
+    assert_eq!(*backend.0.lock().unwrap(), 2);
+}
+
+#[test]
+fn render_to() {
+    let j = json!({
+        "text": "%(items)s",
+        "args": {"items": [", ", "apple", {"gettext": "pear"}, 3]},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let mut body = String::from("Items: ");
+    message.render_to(&mut body).unwrap();
+    assert_eq!(body, "Items: apple, pear, 3");
+
+    /// Writer refusing any output
+    struct Closed;
+
+    impl std::fmt::Write for Closed {
+        fn write_str(&mut self, _: &str) -> std::fmt::Result {
+            Err(std::fmt::Error)
+        }
+    }
+
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert!(matches!(
+        message.render_to(&mut Closed),
+        Err(Error::WriteError)
+    ));
+}
+
+#[test]
+fn render_with_meta() {
+    let mut catalog = MockCatalog::new();
+    catalog
+        .insert("Hello %(name)s!", "Bonjour %(name)s !")
+        .insert_plural("%(n)s file", &["%(n)s fichier", "%(n)s fichiers"])
+        .plural_rule(|n| usize::from(n > 1));
+    let catalog = Arc::new(catalog);
+    let j = json!({
+        "gettext": "Hello %(name)s!",
+        "args": {
+            "name": {
+                "dngettext": {
+                    "domain": "files",
+                    "singular": "%(n)s file",
+                    "plural": "%(n)s files",
+                    "n": 0,
+                },
+            },
+        },
+    });
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(catalog.clone());
+    message.locale = Some("fr_FR".to_string());
+    message.args.insert("unused".to_string(), "x".to_string());
+
+    let (rendered, meta) = message.render_with_meta().unwrap();
+    assert_eq!(rendered, "Bonjour 0 files !");
+    assert_eq!(meta.locale.as_deref(), Some("fr_FR"));
+    assert_eq!(
+        meta.lookups,
+        vec![
+            LookupMeta {
+                domain: None,
+                context: None,
+                msgid: "Hello %(name)s!".to_string(),
+                plural: None,
+                plural_form: None,
+                found: true,
+            },
+            LookupMeta {
+                domain: Some("files".to_string()),
+                context: None,
+                msgid: "%(n)s file".to_string(),
+                plural: Some("%(n)s files".to_string()),
+                plural_form: Some(1),
+                found: false,
+            },
+        ]
+    );
+    assert!(!meta.all_found());
+    assert_eq!(
+        meta.args.iter().map(String::as_str).collect::<Vec<_>>(),
+        vec!["n", "name"]
+    );
+
+    // the plural form selected by the catalog
+    let j = json!({"ngettext": {"singular": "%(n)s file", "plural": "%(n)s files", "n": 0}});
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(catalog);
+    let (rendered, meta) = message.render_with_meta().unwrap();
+    assert_eq!(rendered, "0 fichier");
+    assert_eq!(meta.lookups[0].plural_form, Some(0));
+    assert!(meta.all_found());
+}
+
+#[test]
+fn render_segments() {
+    let text = |x: &str| Segment::Text(x.to_string());
+    let arg = |key: &str, value: &str| Segment::Arg {
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+    let j = json!({
+        "gettext": "%(user.name)s has 100%% of %(files)s",
+        "args": {
+            "user": {"name": "Grace"},
+            "files": {"ngettext": {"singular": "one file", "plural": "%(n)s files", "n": 3}},
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let segments = message.clone().render_segments().unwrap();
+    assert_eq!(
+        segments,
+        vec![
+            arg("user.name", "Grace"),
+            text(" has 100% of "),
+            arg("files", "3 files"),
+        ]
+    );
+    let concatenated: String = segments
+        .iter()
+        .map(|x| match x {
+            Segment::Text(value)
+            | Segment::Arg { value, .. }
+            | Segment::Positional { value, .. } => value.as_str(),
+        })
+        .collect();
+    assert_eq!(concatenated, String::try_from(message).unwrap());
+
+    let j = json!({"text": "%-6s|%*d|", "args": ["ab", 4, 42]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        message.render_segments().unwrap(),
+        vec![
+            Segment::Positional {
+                index: 0,
+                value: "ab    ".to_string(),
+            },
+            text("|"),
+            Segment::Positional {
+                index: 2,
+                value: "  42".to_string(),
+            },
+            text("|"),
+        ]
+    );
+
+    // a message without format string is one segment
+    let j = json!({"join": [", ", "a", {"text": "%(x)s", "args": {"x": "b"}}]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(message.render_segments().unwrap(), vec![text("a, b")]);
+}
+
+#[test]
+fn placeholder_tokens() {
+    let mut catalog = MockCatalog::new();
+    catalog
+        .insert("Hello %(user.name)s!", "{Bonjour} %(user.name)s !")
+        .insert_plural(
+            "%(n)s file in %(folder)s",
+            &[
+                "%(n)s fichier dans %(folder)s",
+                "%(n)s fichiers dans %(folder)s",
+            ],
+        );
+    let catalog = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(catalog.clone());
+        message.placeholders = PlaceholderMode::Tokens;
+        String::try_from(message)
+    };
+
+    // the arguments are not rendered, even if they would fail
+    assert_eq!(
+        render(json!({
+            "gettext": "Hello %(user.name)s!",
+            "args": {"user": {"name": {"ref": "undefined"}}},
+        }))
+        .unwrap(),
+        "{{Bonjour}} {user.name} !"
+    );
+    // the count still selects the plural form
+    assert_eq!(
+        render(json!({
+            "ngettext": {
+                "singular": "%(n)s file in %(folder)s",
+                "plural": "%(n)s files in %(folder)s",
+                "n": 2,
+            },
+        }))
+        .unwrap(),
+        "{n} fichiers dans {folder}"
+    );
+    assert_eq!(
+        render(json!({"text": "%s: %*.*f%% (%-5s)", "args": ["a", 5, 2, 1.5, "b"]})).unwrap(),
+        "{0}: {3}% ({4})"
+    );
+}
+
+#[test]
+fn serialize_error() {
+    let j = json!({"ref": "missing"});
+    let err = String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap_err();
+    assert_eq!(err.code(), "undefined_variable");
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({"code": "undefined_variable", "detail": "undefined variable: missing"})
+    );
+
+    let j = json!({"join": []});
+    let err = String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap_err();
+    assert_eq!(
+        serde_json::to_string(&err).unwrap(),
+        r#"{"code":"missing_join_separator","detail":"Missing join separator"}"#
+    );
+}
+
+#[test]
+fn deserialize_errors() {
+    let error = |j: serde_json::Value| SerdeGetText::deserialize(&j).unwrap_err().to_string();
+
+    assert_eq!(
+        error(json!({"ngettext": {"singular": "One file", "plural": "%(n)s files"}})),
+        "invalid `ngettext`: missing field `n`"
+    );
+    assert_eq!(
+        error(json!({
+            "text": "%(count)s",
+            "args": {"count": {"pgettext": {"ctx": "files", "msgid": 42}}},
+        })),
+        "invalid `text`: invalid `pgettext`: invalid type: integer `42`, expected a string"
+    );
+    assert!(error(json!({"join": [{"gettxt": "Hello"}]}))
+        .starts_with("invalid `join`: unrecognized object with the fields `gettxt`: expected"));
+    assert_eq!(
+        error(json!({"text": "%s", "args": "Grace"})),
+        "invalid `text`: invalid args: expected an object of keyword arguments or an array of \
+         positional arguments"
+    );
+}
+
+#[test]
+fn field_aliases() {
+    let mut catalog = MockCatalog::new();
+    catalog.insert("Hello %(name)s!", "Bonjour %(name)s !");
+    catalog.insert_plural("One file", &["Un fichier", "%(n)s fichiers"]);
+    let backend: Arc<MockCatalog> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(
+        render(json!({"msgid": "Hello %(name)s!", "args": {"name": "Grace"}})),
+        "Bonjour Grace !"
+    );
+    assert_eq!(
+        render(json!({"id": "Hello %(name)s!", "args": {"name": "Grace"}, "merge": "deep"})),
+        "Bonjour Grace !"
+    );
+    assert_eq!(
+        render(json!({
+            "ngettext": {"msgid": "One file", "msgid_plural": "%(n)s files", "count": 3},
+        })),
+        "3 fichiers"
+    );
+    assert_eq!(
+        render(json!({"npgettext": {
+            "context": "files",
+            "msgid": "One file",
+            "msgid_plural": "%(n)s files",
+            "count": 1,
+        }})),
+        "One file"
+    );
+    assert_eq!(
+        render(json!({"dngettext": {
+            "domain": "app",
+            "msgid": "One file",
+            "msgid_plural": "%(n)s files",
+            "count": 3,
+        }})),
+        "3 files"
+    );
+    assert_eq!(
+        render(json!({"dcngettext": {
+            "domain": "app",
+            "msgid": "One file",
+            "msgid_plural": "%(n)s files",
+            "count": 1,
+            "category": "messages",
+        }})),
+        "One file"
+    );
+    assert_eq!(
+        render(json!({"selectplural": {
+            "select": "other",
+            "count": 3,
+            "forms": {"other": {"msgid": "One file", "msgid_plural": "%(n)s files"}},
+        }})),
+        "3 fichiers"
+    );
+
+    // the objects of keyword arguments with an `id` are not messages
+    assert_eq!(
+        render(json!({
+            "text": "%(user.id)s: %(user.name)s",
+            "args": {"user": {"id": 42, "name": "Grace"}},
+        })),
+        "42: Grace"
+    );
+}
+
+#[test]
+fn shorthand_arrays() {
+    let j = json!({"gettext": ["Hello %s!", "Grace"]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello Grace!");
+
+    let j = json!({"gettext": ["Hello!"]});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Hello!");
+
+    let j = json!({
+        "text": "%(files)s by %(user)s",
+        "args": {
+            "files": {"ngettext": ["%(n)s item", "%(n)s items", 5]},
+            "user": {"gettext": ["%s %s", "Grace", {"text": "Hopper"}]},
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap(),
+        "5 items by Grace Hopper"
+    );
+
+    let error = |j: serde_json::Value| SerdeGetText::deserialize(&j).unwrap_err().to_string();
+    assert_eq!(
+        error(json!({"ngettext": ["%(n)s item", "%(n)s items"]})),
+        "invalid `ngettext`: expected the singular form, the plural form and n"
+    );
+    assert_eq!(
+        error(json!({"gettext": ["Hello %s!", "Grace"], "args": ["Marie"]})),
+        "invalid `gettext`: positional arguments in the array and in `args`"
+    );
+}
+
+#[test]
+fn versions() {
+    let j = json!({
+        "version": 1,
+        "dcngettext": {
+            "domain": "app",
+            "singular": "One file",
+            "plural": "%(n)s files",
+            "n": 2,
+            "cateogy": "messages",
+        },
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "2 files");
+
+    // the messages without version are version 1, nested messages included
+    let j = json!({
+        "text": "%(files)s",
+        "args": {"files": {"dcngettext": {
+            "domain": "app",
+            "singular": "One file",
+            "plural": "%(n)s files",
+            "n": 1,
+            "cateogy": "messages",
+        }}},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "One file");
+
+    let upgraded = serde_gettext::migrate::upgrade(j).unwrap();
+    assert_eq!(upgraded["version"], 2);
+    assert_eq!(
+        upgraded["args"]["files"]["dcngettext"]["category"],
+        "messages"
+    );
+
+    // the current version isn't upgraded
+    let j = json!({"version": 2, "dcngettext": {
+        "domain": "app",
+        "singular": "One file",
+        "plural": "%(n)s files",
+        "n": 1,
+        "cateogy": "messages",
+    }});
+    assert!(SerdeGetText::deserialize(&j).is_err());
+
+    let error = |j: serde_json::Value| SerdeGetText::deserialize(&j).unwrap_err().to_string();
+    assert_eq!(
+        error(json!({"version": 3, "gettext": "Hello!"})),
+        "unsupported version 3, the current version is 2"
+    );
+    assert_eq!(
+        error(json!({"version": 0, "gettext": "Hello!"})),
+        "unsupported version 0, the current version is 2"
+    );
+}
+
+#[test]
+fn first_of() {
+    let mut catalog = MockCatalog::new();
+    catalog.insert("Confirm", "Confirmer").insert_with(
+        None,
+        Some("checkout"),
+        "Confirm",
+        &["Valider"],
+    );
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        String::try_from(message)
+    };
+
+    assert_eq!(
+        render(json!({"first_of": [
+            {"pgettext": {"ctx": "checkout", "msgid": "Confirm"}},
+            {"gettext": "Confirm"},
+        ]}))
+        .unwrap(),
+        "Valider"
+    );
+    assert_eq!(
+        render(json!({"first_of": [
+            {"pgettext": {"ctx": "cart", "msgid": "Confirm"}},
+            {"gettext": "Confirm"},
+        ]}))
+        .unwrap(),
+        "Confirmer"
+    );
+    // the messages whose arguments can't be formatted are skipped
+    assert_eq!(
+        render(json!({"first_of": [
+            {"gettext": "Confirm %(name)s"},
+            {"gettext": "Confirm"},
+        ]}))
+        .unwrap(),
+        "Confirmer"
+    );
+    // the last message is rendered even if it is not translated
+    assert_eq!(
+        render(json!({"first_of": [
+            {"pgettext": {"ctx": "cart", "msgid": "Confirm"}},
+            {"gettext": "Cancel"},
+        ]}))
+        .unwrap(),
+        "Cancel"
+    );
+    assert!(render(json!({"first_of": [{"gettext": "Hello %(name)s!"}]})).is_err());
+    assert!(SerdeGetText::deserialize(&json!({"first_of": []})).is_err());
+}
+
+#[test]
+fn if_translated() {
+    let mut catalog = MockCatalog::new();
+    catalog
+        .insert("Start your free trial", "Commencez votre essai gratuit")
+        .insert("Sign up", "Inscrivez-vous");
+    let observer = Arc::new(Missing::default());
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        message.observer = Some(observer.clone());
+        String::try_from(message).unwrap()
+    };
+    let message = |msgid: &str| {
+        json!({"if_translated": {
+            "msgid": msgid,
+            "then": {"gettext": msgid},
+            "else": {"gettext": "Sign up"},
+        }})
+    };
+
+    assert_eq!(
+        render(message("Start your free trial")),
+        "Commencez votre essai gratuit"
+    );
+    assert_eq!(render(message("Try it for free")), "Inscrivez-vous");
+    assert_eq!(
+        render(json!({"if_translated": {"msgid": "Try it for free", "then": "New"}})),
+        ""
+    );
+    assert!(observer.0.lock().unwrap().is_empty());
+
+    let message = SerdeGetText::deserialize(&message("Try it for free")).unwrap();
+    assert_eq!(
+        message
+            .extract_msgids()
+            .into_iter()
+            .map(|x| x.msgid)
+            .collect::<Vec<_>>(),
+        vec!["Sign up", "Try it for free"]
+    );
+}
+
+#[test]
+fn choice() {
+    let render = |status: &str| {
+        let j = json!({
+            "choice": {
+                "key": "%(status)s",
+                "cases": {
+                    "shipped": {"gettext": "Your order is on its way"},
+                    "pending": {"gettext": "Your order is being prepared"},
+                },
+                "default": {"gettext": "Your order has been received"},
+            },
+            "args": {"status": status},
+        });
+        let message = SerdeGetText::deserialize(&j).unwrap();
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(render("shipped"), "Your order is on its way");
+    assert_eq!(render("pending"), "Your order is being prepared");
+    assert_eq!(render("cancelled"), "Your order has been received");
+
+    // the key is formatted with the arguments of the message it is nested in
+    let j = json!({
+        "text": "%(order)s",
+        "args": {
+            "status": "pending",
+            "order": {"choice": {"key": "%(status)s", "cases": {"pending": "Pending"}}},
+        },
+    });
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.merge = ArgsMerge::Deep;
+    assert_eq!(String::try_from(message).unwrap(), "Pending");
+
+    // the cases see the arguments of the choice, the key can use positional arguments
+    let j = json!({
+        "choice": {
+            "key": "%(status)s",
+            "cases": {"shipped": {"text": "Shipped on %(date)s"}},
+        },
+        "args": {"status": "shipped", "date": "2024-03-01"},
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "Shipped on 2024-03-01");
+    let j = json!({
+        "choice": {"key": "%s-%s", "cases": {"a-1": "First"}},
+        "args": ["a", 1],
+    });
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "First");
+
+    // the base arguments are in scope too
+    let j = json!({"choice": {"key": "%(status)s", "cases": {"pending": "Pending"}}});
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message
+        .args
+        .insert("status".to_string(), "pending".to_string());
+    assert_eq!(String::try_from(message).unwrap(), "Pending");
+
+    let j = json!({"choice": {"key": "unknown", "cases": {"pending": "Pending"}}});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(message).unwrap_err().to_string(),
+        "no case for key: unknown"
+    );
+}
+
+#[test]
+fn scoped_domain() {
+    let mut catalog = MockCatalog::new();
+    catalog
+        .insert("Pay", "Payer")
+        .insert_with(Some("billing"), None, "Pay", &["Régler"])
+        .insert_with(Some("billing"), None, "yes", &["oui"])
+        .insert_with(Some("shop"), None, "Pay", &["Acheter"]);
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let render = |j: serde_json::Value| {
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.backend = Some(backend.clone());
+        String::try_from(message).unwrap()
+    };
+    let pay = json!({"text": "%(pay)s", "args": {"pay": {"gettext": "Pay"}}});
+
+    assert_eq!(render(pay.clone()), "Payer");
+    assert_eq!(with_domain("billing", || render(pay.clone())), "Régler");
+    assert_eq!(
+        with_domain("billing", || render(
+            json!({"text": "%(ok)s", "args": {"ok": true}})
+        )),
+        "oui"
+    );
+    // the messages with a text domain keep it, the calls can be nested
+    assert_eq!(
+        with_domain("billing", || {
+            let dgettext = json!({"dgettext": {"domain": "shop", "msgid": "Pay"}});
+            let nested = with_domain("shop", || render(pay.clone()));
+            (render(dgettext), nested, render(pay.clone()))
+        }),
+        (
+            "Acheter".to_string(),
+            "Acheter".to_string(),
+            "Régler".to_string()
+        )
+    );
+    assert_eq!(render(pay), "Payer");
+}
+
+#[test]
+fn locale_context() {
+    let locale = || {
+        let message = SerdeGetText::deserialize(&json!({"text": "Hello!"})).unwrap();
+        message.render_with_meta().unwrap().1.locale
+    };
+
+    let outer = LocaleContext::enter("de_DE");
+    assert_eq!(locale().as_deref(), Some("de_DE"));
+    {
+        let _inner = LocaleContext::enter("fr_FR");
+        assert_eq!(locale().as_deref(), Some("fr_FR"));
+
+        // the locale of the message takes precedence
+        let mut message = SerdeGetText::deserialize(&json!({"text": "Hello!"})).unwrap();
+        message.locale = Some("en_GB".to_string());
+        assert_eq!(
+            message.render_with_meta().unwrap().1.locale.as_deref(),
+            Some("en_GB")
+        );
+    }
+    assert_eq!(locale().as_deref(), Some("de_DE"));
+
+    // the other threads don't use it
+    let other = std::thread::spawn(LocaleContext::current).join().unwrap();
+    assert_eq!(other, None);
+    drop(outer);
+    assert_eq!(LocaleContext::current(), None);
+}
+
+/// Backend translating to the locale of the lookup
+struct LocaleBackend;
+
+impl Backend for LocaleBackend {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        match (lookup.msgid, lookup.locale) {
+            ("Hello!", Some("de_DE")) => Some("Hallo!".to_string()),
+            ("Hello!", Some("fr_FR")) => Some("Bonjour !".to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn locale_context_translations() {
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(LocaleBackend);
+    let interner = Arc::new(Interner::new());
+    let render = || {
+        let mut message = SerdeGetText::deserialize(&json!({
+            "text": "%(greeting)s",
+            "args": {"greeting": {"gettext": "Hello!"}},
+        }))
+        .unwrap();
+        message.backend = Some(backend.clone());
+        message.interner = Some(interner.clone());
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(render(), "Hello!");
+    {
+        let _locale = LocaleContext::enter("de_DE");
+        assert_eq!(render(), "Hallo!");
+        let _locale = LocaleContext::enter("fr_FR");
+        assert_eq!(render(), "Bonjour !");
+    }
+    // the interner keeps the translations by locale
+    assert_eq!(render(), "Hello!");
+    assert_eq!(interner.len(), 3);
+}
+
+#[test]
+fn plural_index_rules() {
+    assert_eq!(plural_index("en_US", 1), Some(0));
+    assert_eq!(plural_index("en_US", 0), Some(1));
+    assert_eq!(plural_index("fr_FR.UTF-8", 1), Some(0));
+    assert_eq!(plural_index("pt_BR", 0), Some(0));
+    assert_eq!(plural_index("pt-PT", 0), Some(1));
+    assert_eq!(
+        [1, 2, 5, 11, 21, 22, 111]
+            .iter()
+            .map(|n| plural_index("ru", *n))
+            .collect::<Vec<_>>(),
+        vec![
+            Some(0),
+            Some(1),
+            Some(2),
+            Some(2),
+            Some(0),
+            Some(1),
+            Some(2)
+        ]
+    );
+    assert_eq!(plural_index("ja", 5), Some(0));
+    assert_eq!(plural_index("tlh", 5), None);
+
+    let mut catalog = Catalog::new();
+    catalog
+        .load_mo(
+            None,
+            &mo_file(&[(
+                "",
+                "Plural-Forms: nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;\n",
+            )]),
+        )
+        .unwrap();
+    assert_eq!(plural_index(&catalog, 2), Some(1));
+    assert_eq!(plural_index(&GettextBackend, 2), None);
+}
+
+#[test]
+fn percent_modes() {
+    let render = |text: &str, percent: PercentMode| {
+        let j = json!({"text": text, "args": {"name": "Grace"}});
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.percent = percent;
+        String::try_from(message)
+    };
+
+    assert_eq!(
+        render("100%, %(name)s: 50%%", PercentMode::Lenient).unwrap(),
+        "100%, Grace: 50%"
+    );
+    assert!(render("50%d off", PercentMode::Lenient).is_err());
+    // `% %` is a percent in the printf syntax
+    assert_eq!(
+        render("100% %(name)s", PercentMode::Lenient).unwrap(),
+        "100%(name)s"
+    );
+
+    assert!(matches!(
+        render("100%, sure", PercentMode::Strict),
+        Err(Error::FormatError(_))
+    ));
+    assert_eq!(
+        render("100%% sure, %(name)s", PercentMode::Strict).unwrap(),
+        "100% sure, Grace"
+    );
+
+    assert_eq!(
+        render("50%d off for %(name)s, %(other)s", PercentMode::Escape).unwrap(),
+        "50%d off for Grace, %(other)s"
+    );
+    assert_eq!(
+        render("100% %(name)s, 100%%", PercentMode::Escape).unwrap(),
+        "100% Grace, 100%"
+    );
+
+    let j = json!({"text": "%s: 50%d off %s", "args": ["Grace"]});
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.percent = PercentMode::Escape;
+    assert_eq!(
+        message.render_segments().unwrap(),
+        vec![
+            Segment::Positional {
+                index: 0,
+                value: "Grace".to_string()
+            },
+            Segment::Text(": 50%d off %s".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn control_chars() {
+    let render = |control: ControlChars| {
+        let j = json!({
+            "text": "user %(name)s logged in\n",
+            "args": {"name": "Grace\n\u{1b}[31mroot\u{202e}"},
+        });
+        let mut message = SerdeGetText::deserialize(&j).unwrap();
+        message.control = control;
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(
+        render(ControlChars::Keep),
+        "user Grace\n\u{1b}[31mroot\u{202e} logged in\n"
+    );
+    // the text of the message is kept as is
+    assert_eq!(
+        render(ControlChars::Strip),
+        "user Grace[31mroot logged in\n"
+    );
+    assert_eq!(
+        render(ControlChars::Escape),
+        "user Grace\\u{a}\\u{1b}[31mroot\\u{202e} logged in\n"
+    );
+
+    let j = json!({"text": "%s: %5s|", "args": ["\u{2066}Grace", "a\tb"]});
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.control = ControlChars::Strip;
+    assert_eq!(String::try_from(message).unwrap(), "Grace:    ab|");
+}
+
+#[test]
+fn escape_modes() {
+    let render = |escape: &str| {
+        let j = json!({
+            "text": "<a href=\"/?q=%(query)s\">%(query)s</a>",
+            "escape": escape,
+            "args": {"query": "R&D <it's>"},
+        });
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap()
+    };
+
+    assert_eq!(render("none"), "<a href=\"/?q=R&D <it's>\">R&D <it's></a>");
+    assert_eq!(
+        render("html"),
+        "<a href=\"/?q=R&amp;D &lt;it&#39;s&gt;\">R&amp;D &lt;it&#39;s&gt;</a>"
+    );
+    assert_eq!(
+        render("url"),
+        "<a href=\"/?q=R%26D%20%3Cit%27s%3E\">R%26D%20%3Cit%27s%3E</a>"
+    );
+
+    let j = json!({
+        "text": "git commit -m %s %s %s",
+        "escape": "shell",
+        "args": ["it's done", "--amend", ""],
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "git commit -m 'it'\"'\"'s done' --amend ''"
+    );
+
+    let j = json!({"text": "café", "escape": "url"});
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "café"
+    );
+    let j = json!({"text": "%s", "escape": "url", "args": ["café"]});
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "caf%C3%A9"
+    );
+    assert!(SerdeGetText::deserialize(&json!({"text": "x", "escape": "sql"})).is_err());
+}
+
+#[test]
+fn escape_nested() {
+    let j = json!({
+        "text": "<p>%(greeting)s %(tags)s</p>",
+        "escape": "html",
+        "args": {
+            "greeting": {"text": "<b>%(name)s</b>", "args": {"name": "R&D <it's>"}},
+            "tags": {"foreach": {
+                "items": [{"tag": "a&b"}],
+                "template": {"text": "<i>%(tag)s</i>"},
+            }},
+        },
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "<p><b>R&amp;D &lt;it&#39;s&gt;</b> <i>a&amp;b</i></p>"
+    );
+
+    // the other values are escaped by the message
+    let j = json!({
+        "text": "%s %s",
+        "escape": "html",
+        "args": [{"join": {"sep": ", ", "items": ["<a>", "<b>"]}}, {"filters": ["upper"], "value": "a&b"}],
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "&lt;a&gt;, &lt;b&gt; A&amp;B"
+    );
+}
+
+#[test]
+fn escape_nested_url() {
+    let j = json!({
+        "text": "https://example.com/search?%(query)s&page=%(page)s",
+        "escape": "url",
+        "args": {
+            "query": {"text": "q=%(q)s&lang=%(lang)s", "args": {"q": "R&D labs", "lang": "fr"}},
+            "page": 2,
+        },
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "https://example.com/search?q=R%26D%20labs&lang=fr&page=2"
+    );
+
+    let j = json!({
+        "text": "/users/%s/%s",
+        "escape": "url",
+        "args": ["a b", {"text": "posts?tag=%s", "args": ["c d"]}],
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "/users/a%20b/posts?tag=c%20d"
+    );
+}
+
+#[test]
+fn escape_markdown() {
+    let j = json!({
+        "text": "**%(name)s** joined [the channel](%(url)s)",
+        "escape": "markdown",
+        "args": {"name": "*admin*_[1]", "url": "https://example.com/a_b"},
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "**\\*admin\\*\\_\\[1\\]** joined [the channel](https://example\\.com/a\\_b)"
+    );
+}
+
+#[test]
+fn noop() {
+    let mut catalog = MockCatalog::new();
+    catalog.insert("Hello %(name)s!", "Bonjour %(name)s !");
+    let j = json!({
+        "noop": "Hello %(name)s!",
+        "args": {"name": {"gettext": "stranger"}},
+    });
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(Arc::new(catalog));
+
+    assert_eq!(
+        message
+            .extract_msgids()
+            .into_iter()
+            .map(|x| x.msgid)
+            .collect::<Vec<_>>(),
+        vec!["Hello %(name)s!", "stranger"]
+    );
+    assert_eq!(String::try_from(message).unwrap(), "Hello stranger!");
+}
+
+#[test]
+fn big_integers() {
+    let j = json!({
+        "text": "%s %d %d %05d",
+        "args": [
+            u64::MAX,
+            "123456789012345678901234567890123456789012",
+            "-000123456789012345678901234567890",
+            "-42",
+        ],
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "18446744073709551615 123456789012345678901234567890123456789012 \
+         -123456789012345678901234567890 -0042"
+    );
+}
+
+#[test]
+fn uuids_and_bytes() {
+    let j = json!({
+        "text": "%s %s %s %s %s %s",
+        "args": [
+            {"uuid": "{67E55044-10B1-426F-9247-BB680E5FE0C8}"},
+            {"uuid": "urn:uuid:67e5504410b1426f9247bb680e5fe0c8"},
+            {"uuid": [103, 229, 80, 68, 16, 177, 66, 111, 146, 71, 187, 104, 14, 95, 224, 200]},
+            {"bytes": [222, 173, 190, 239]},
+            {"bytes": [222, 173, 190, 239], "encoding": "base64"},
+            {"bytes": [251, 255], "encoding": "base64_url"},
+        ],
+    });
+    assert_eq!(
+        String::try_from(SerdeGetText::deserialize(&j).unwrap()).unwrap(),
+        "67e55044-10b1-426f-9247-bb680e5fe0c8 67e55044-10b1-426f-9247-bb680e5fe0c8 \
+         67e55044-10b1-426f-9247-bb680e5fe0c8 deadbeef 3q2+7w== -_8"
+    );
+
+    assert!(SerdeGetText::deserialize(&json!({"uuid": "67e55044-10b1-426f"})).is_err());
+    assert!(
+        SerdeGetText::deserialize(&json!({"uuid": "+7e55044-10b1-426f-9247-bb680e5fe0c8"}))
+            .is_err()
+    );
+
+    // the binary data of the self-describing formats
+    let raw = serde_value::Value::Map(
+        vec![
+            (
+                serde_value::Value::String("text".to_string()),
+                serde_value::Value::String("%s".to_string()),
+            ),
+            (
+                serde_value::Value::String("args".to_string()),
+                serde_value::Value::Seq(vec![serde_value::Value::Bytes(vec![0, 1, 254])]),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    let message = SerdeGetText::deserialize(serde_value::ValueDeserializer::<
+        serde_value::DeserializerError,
+    >::new(raw))
+    .unwrap();
+    assert_eq!(String::try_from(message).unwrap(), "0001fe");
+}
+
+#[test]
+fn address() {
+    let address = json!({
+        "name": "Grace Hopper",
+        "company": "ACME",
+        "street": "Main Street",
+        "number": "5",
+        "postcode": "12345",
+        "city": "Springfield",
+        "state": "IL",
+        "country": {"gettext": "United States"},
+    });
+    let render = |address: serde_json::Value, locale: Option<&str>| {
+        let mut message = SerdeGetText::deserialize(&json!({"address": address})).unwrap();
+        message.locale = locale.map(ToString::to_string);
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(
+        render(address.clone(), Some("en_US.UTF-8")),
+        "Grace Hopper\nACME\n5 Main Street\nSpringfield, IL 12345\nUnited States"
+    );
+    let mut german = address.clone();
+    german["region"] = json!("de");
+    german["sep"] = json!(", ");
+    assert_eq!(
+        render(german, None),
+        "Grace Hopper, ACME, Main Street 5, 12345 Springfield, IL, United States"
+    );
+    assert_eq!(
+        render(address.clone(), Some("ja_JP.UTF-8")),
+        "United States\n12345\nILSpringfieldMain Street5\nACME\nGrace Hopper"
+    );
+
+    // the separators of the missing fields are left out
+    assert_eq!(
+        render(
+            json!({"street": "Main Street", "city": "Springfield", "postcode": "12345"}),
+            Some("en_US.UTF-8"),
+        ),
+        "Main Street\nSpringfield, 12345"
+    );
+    assert_eq!(
+        render(
+            json!({"city": "Paris", "postcode": "75001"}),
+            Some("fr_FR.UTF-8")
+        ),
+        "75001 Paris"
+    );
+}
+
+#[test]
+fn person_name() {
+    let render = |name: serde_json::Value, locale: Option<&str>| {
+        let mut message = SerdeGetText::deserialize(&json!({"person_name": name})).unwrap();
+        message.locale = locale.map(ToString::to_string);
+        String::try_from(message).unwrap()
+    };
+    let grace = json!({"title": "Dr", "given": "Grace", "middle": "Brewster", "family": "Hopper"});
+
+    assert_eq!(
+        render(grace.clone(), Some("en_US.UTF-8")),
+        "Dr Grace Brewster Hopper"
+    );
+    assert_eq!(
+        render(grace.clone(), Some("hu_HU.UTF-8")),
+        "Dr Hopper Grace Brewster"
+    );
+    let mut formal = grace.clone();
+    formal["form"] = json!("formal");
+    assert_eq!(render(formal, None), "Dr Hopper");
+    let mut informal = grace;
+    informal["form"] = json!("informal");
+    assert_eq!(render(informal, None), "Grace");
+
+    let taro = json!({"honorific": "様", "given": "太郎", "family": "田中"});
+    assert_eq!(render(taro.clone(), Some("ja_JP.UTF-8")), "田中太郎様");
+    let mut formal = taro;
+    formal["form"] = json!("formal");
+    assert_eq!(render(formal, Some("ja_JP.UTF-8")), "田中様");
+    assert_eq!(
+        render(
+            json!({"title": "san", "given": "Taro", "family": "Tanaka", "region": "jp"}),
+            None,
+        ),
+        "Tanaka Taro san"
+    );
+}
+
+#[test]
+fn measure() {
+    let render = |measure: serde_json::Value, locale: Option<&str>| {
+        let mut message = SerdeGetText::deserialize(&measure).unwrap();
+        message.locale = locale.map(ToString::to_string);
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(render(json!({"measure": 5, "unit": "km"}), None), "5 km");
+    assert_eq!(
+        render(json!({"measure": 5, "unit": "km"}), Some("en_US.UTF-8")),
+        "3.1 mi"
+    );
+    assert_eq!(
+        render(json!({"measure": 5, "unit": "km"}), Some("fr_FR.UTF-8")),
+        "5 km"
+    );
+    assert_eq!(
+        render(
+            json!({"measure": 50, "unit": "°F", "system": "metric"}),
+            None
+        ),
+        "10 °C"
+    );
+    assert_eq!(
+        render(
+            json!({"measure": 2, "unit": "lb", "system": "metric", "precision": 2}),
+            None
+        ),
+        "0.91 kg"
+    );
+    assert_eq!(
+        render(
+            json!({"measure": 3, "unit": "cups", "system": "metric"}),
+            None
+        ),
+        "3 cups"
+    );
+
+    let mut catalog = MockCatalog::new();
+    catalog.insert_with(None, Some("unit"), "km", &["км"]);
+    let mut message = SerdeGetText::deserialize(&json!({"measure": 5, "unit": "km"})).unwrap();
+    message.backend = Some(Arc::new(catalog));
+    assert_eq!(String::try_from(message).unwrap(), "5 км");
+}
+
+#[test]
+fn langinfo() {
+    let render = |langinfo: serde_json::Value| {
+        String::try_from(SerdeGetText::deserialize(&langinfo).unwrap())
+    };
+
+    assert_eq!(
+        render(json!({"langinfo": "month", "index": 3})).unwrap(),
+        "March"
+    );
+    assert_eq!(
+        render(json!({"langinfo": "abbreviated_day", "index": 1})).unwrap(),
+        "Sun"
+    );
+    assert_eq!(render(json!({"langinfo": "radix_char"})).unwrap(), ".");
+    assert_eq!(render(json!({"langinfo": "radixchar"})).unwrap(), ".");
+    assert_eq!(
+        render(json!({"langinfo": "month", "index": 13}))
+            .unwrap_err()
+            .to_string(),
+        "langinfo index must be between 1 and 12"
+    );
+}
+
+#[test]
+fn translated_map() {
+    let j = json!({
+        "title": {"gettext": "Settings"},
+        "body": {"text": "Version %(version)s", "args": {"version": "1.2"}},
+        "footer": "Thanks!",
+    });
+    let map = TranslatedMap::deserialize(&j).unwrap();
+    let mut catalog = MockCatalog::new();
+    catalog.insert("Settings", "Paramètres");
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(catalog);
+    let map = TranslatedMap(
+        map.0
+            .into_iter()
+            .map(|(key, mut message)| {
+                message.backend = Some(backend.clone());
+                (key, message)
+            })
+            .collect(),
+    );
+
+    let strings = HashMap::try_from(map).unwrap();
+    assert_eq!(strings.len(), 3);
+    assert_eq!(strings["title"], "Paramètres");
+    assert_eq!(strings["body"], "Version 1.2");
+    assert_eq!(strings["footer"], "Thanks!");
+
+    let map = TranslatedMap::deserialize(&json!({"title": {"ref": "missing"}})).unwrap();
+    assert!(HashMap::try_from(map).is_err());
+}
+
+#[test]
+fn translated_list() {
+    let j = json!([
+        {"gettext": "Yes"},
+        {"text": "%(n)s items", "args": {"n": 3}},
+        {"text": "%(list)s", "args": {"list": [", ", "a", "b"]}},
+    ]);
+    let list = TranslatedList::deserialize(&j).unwrap();
+    assert_eq!(Vec::try_from(list).unwrap(), vec!["Yes", "3 items", "a, b"]);
+    assert_eq!(
+        Vec::try_from(TranslatedList::default()).unwrap(),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn join_object() {
+    let render = |j: serde_json::Value| String::try_from(SerdeGetText::deserialize(&j).unwrap());
+
+    assert_eq!(
+        render(json!({"join": {"sep": {"gettext": " and "}, "items": [1, 2, 3]}})).unwrap(),
+        "1 and 2 and 3"
+    );
+    // the first item is data, not the separator
+    assert_eq!(
+        render(json!({"join": {"sep": " / ", "items": ["Home", "Settings"]}})).unwrap(),
+        "Home / Settings"
+    );
+    assert_eq!(
+        render(json!({"join": {"sep": ", ", "items": []}})).unwrap(),
+        ""
+    );
+    assert!(SerdeGetText::deserialize(&json!({"join": {"items": ["a"]}})).is_err());
+}
+
+#[test]
+fn join_unique_skip_empty() {
+    let render = |j: serde_json::Value| String::try_from(SerdeGetText::deserialize(&j).unwrap());
+    let items = json!({"sep": ", ", "items": ["a", "", "a", " ", "b"]});
+
+    assert_eq!(render(json!({"join": items})).unwrap(), "a, , a,  , b");
+    assert_eq!(
+        render(json!({"join": items, "skip_empty": true})).unwrap(),
+        "a, a, b"
+    );
+    assert_eq!(
+        render(json!({"join": items, "unique": true})).unwrap(),
+        "a, ,  , b"
+    );
+    assert_eq!(
+        render(json!({"join": items, "unique": true, "skip_empty": true})).unwrap(),
+        "a, b"
+    );
+    assert_eq!(
+        render(
+            json!({"join": [", ", "b", {"gettext": "a"}, "b", "a"], "unique": true, "sort": true})
+        )
+        .unwrap(),
+        "a, b"
+    );
+}
+
+#[test]
+fn table() {
+    let render = |table: serde_json::Value, locale: Option<&str>| {
+        let mut message = SerdeGetText::deserialize(&table).unwrap();
+        message.locale = locale.map(ToString::to_string);
+        String::try_from(message).unwrap()
+    };
+
+    assert_eq!(
+        render(
+            json!({
+                "table": [["Item", "Qty"], [{"gettext": "Apples"}, 3], [{"gettext": "Pears"}, 12]],
+                "align": ["left", "right"],
+            }),
+            None,
+        ),
+        "Item    Qty\nApples    3\nPears    12"
+    );
+    assert_eq!(
+        render(
+            json!({"table": [["a", "b", "c"], ["long", "x"]], "sep": " | ", "align": ["center"]}),
+            None,
+        ),
+        " a   | b | c\nlong | x"
+    );
+
+    // the CJK characters take two columns, the ambiguous ones only in the CJK locales
+    let cjk = json!({"table": [["名前", "1"], ["Name", "2"], ["α", "3"]]});
+    assert_eq!(render(cjk.clone(), None), "名前  1\nName  2\nα     3");
+    assert_eq!(render(cjk, Some("ja_JP.UTF-8")), "名前  1\nName  2\nα    3");
+}
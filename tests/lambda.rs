@@ -0,0 +1,35 @@
+#![cfg(feature = "lambda")]
+
+use serde_gettext::lambda::handle_event;
+use serde_gettext::server::Translator;
+use serde_gettext::MockCatalog;
+use serde_json::json;
+use std::sync::Arc;
+
+#[test]
+fn api_gateway_and_direct_events() {
+    let mut fr = MockCatalog::new();
+    fr.insert("Hello %(name)s!", "Bonjour %(name)s !");
+    let translator = Translator::new()
+        .catalog("fr_FR", Arc::new(fr))
+        .default_catalog(Arc::new(MockCatalog::new()));
+
+    let response = handle_event(
+        &translator,
+        json!({
+            "headers": {"Accept-Language": "fr"},
+            "body": r#"{"gettext": "Hello %(name)s!", "args": {"name": "Grace"}}"#,
+            "isBase64Encoded": false,
+        }),
+    );
+    assert_eq!(response["statusCode"], 200);
+    assert_eq!(response["body"], r#"{"text":"Bonjour Grace !"}"#);
+    let response = handle_event(&translator, json!({"headers": {}, "body": "{"}));
+    assert_eq!(response["statusCode"], 400);
+
+    assert_eq!(
+        handle_event(&translator, json!([{"text": "ok"}, {"gettext": "Hello"}])),
+        json!([{"text": "ok"}, {"text": "Hello"}])
+    );
+    assert!(handle_event(&translator, json!(42))["error"].is_string());
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "query")]
+
+use serde_gettext::query::{self, QueryError};
+use std::convert::TryFrom;
+
+fn render(s: &str) -> String {
+    String::try_from(query::from_query(s).unwrap()).unwrap()
+}
+
+#[test]
+fn keyword_and_positional_args() {
+    assert_eq!(
+        render("gettext=Hello%20%25(name)s!&args[name]=Grace"),
+        "Hello Grace!"
+    );
+    assert_eq!(
+        render("?text=Hello+%25(user.name)s+(%25(user.city)s)&args[user][name]=Grace&args[user][city]=Arlington"),
+        "Hello Grace (Arlington)"
+    );
+    assert_eq!(render("text=%25s+%25s&args[]=a&args[]=b"), "a b");
+    assert_eq!(render("text=%25s+%25s&args[1]=b&args[0]=a"), "a b");
+}
+
+#[test]
+fn typed_fields() {
+    assert_eq!(
+        render("ngettext[singular]=One+item&ngettext[plural]=%25(n)s+items&ngettext[n]=5"),
+        "5 items"
+    );
+    assert_eq!(
+        render("text=%25(fruits)s&args[fruits][join][]=,+&args[fruits][join][]=Pear&args[fruits][join][]=Apple&args[fruits][sort]=true"),
+        "Apple, Pear"
+    );
+    assert_eq!(render("strftime=%25Y&epoch=100000000"), "1973");
+    // the values of the keyword arguments are never converted
+    assert_eq!(render("text=%25(sort)s&args[sort]=true"), "true");
+}
+
+#[test]
+fn invalid() {
+    assert!(matches!(
+        query::from_query("text=a&text=b"),
+        Err(QueryError::InvalidKey(_))
+    ));
+    assert!(matches!(
+        query::from_query("text=a&text[x]=b"),
+        Err(QueryError::InvalidKey(_))
+    ));
+    assert!(matches!(
+        query::from_query("args[name=Grace"),
+        Err(QueryError::InvalidKey(_))
+    ));
+    assert!(matches!(
+        query::from_query("[name]=Grace"),
+        Err(QueryError::InvalidKey(_))
+    ));
+    assert!(matches!(
+        query::from_query(&format!("text=a&args{}=b", "[a]".repeat(10_000))),
+        Err(QueryError::InvalidKey(_))
+    ));
+    assert!(matches!(
+        query::from_query("ngettext[singular]=One"),
+        Err(QueryError::InvalidMessage(_))
+    ));
+}
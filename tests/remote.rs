@@ -0,0 +1,103 @@
+#![cfg(feature = "remote")]
+
+use serde_gettext::remote::{CatalogSource, HttpSource};
+use serde_gettext::{Backend, Lookup};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Compiled catalog (MO file) of a single translation
+fn mo_file(msgid: &str, msgstr: &str) -> Vec<u8> {
+    let (originals, translations) = (28u32, 36u32);
+    let strings = 44 + msgid.len() as u32 + 1;
+    let mut mo: Vec<u8> = [0x9504_12de, 0, 1, originals, translations, 0, 0]
+        .iter()
+        .chain(&[msgid.len() as u32, 44, msgstr.len() as u32, strings])
+        .flat_map(|x: &u32| x.to_le_bytes().to_vec())
+        .collect();
+    mo.extend(msgid.as_bytes());
+    mo.push(0);
+    mo.extend(msgstr.as_bytes());
+    mo.push(0);
+    mo
+}
+
+/// Serve the catalog with an `ETag`, the revalidations get `304 Not Modified`
+fn serve(requests: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut revalidated = false;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                revalidated |= line.to_lowercase() == "if-none-match: \"v1\"\r\n";
+            }
+            requests.fetch_add(1, Ordering::SeqCst);
+
+            if revalidated {
+                write!(
+                    stream,
+                    "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            } else {
+                let body = mo_file("Hello", "Bonjour");
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        }
+    });
+
+    format!("http://{}/locale/fr/LC_MESSAGES/app.mo", addr)
+}
+
+#[tokio::test]
+async fn http_source() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let url = serve(requests.clone());
+    let source = HttpSource::new().file(None, &url).ttl(Duration::ZERO);
+
+    let catalog = source.load().await.unwrap();
+    assert_eq!(
+        catalog.translate(&Lookup {
+            domain: None,
+            context: None,
+            msgid: "Hello",
+            plural: None,
+            category: None,
+            locale: None,
+        }),
+        Some("Bonjour".to_string())
+    );
+    // not modified: the same catalog is kept
+    let revalidated = source.load().await.unwrap();
+    assert!(Arc::ptr_eq(&catalog, &revalidated));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    // the catalog is not revalidated before the end of its time to live
+    let source = HttpSource::new()
+        .file(None, &url)
+        .ttl(Duration::from_secs(3600));
+    source.load().await.unwrap();
+    source.load().await.unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    let missing = HttpSource::new().file(None, "http://127.0.0.1:1/app.mo");
+    assert!(missing.load().await.is_err());
+}
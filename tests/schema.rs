@@ -0,0 +1,154 @@
+#![cfg(feature = "schema")]
+
+use jsonschema::JSONSchema;
+use serde_gettext::schema::{self, OpenApiVersion};
+use serde_gettext::SerdeGetText;
+use serde_json::json;
+
+fn validator() -> JSONSchema {
+    JSONSchema::compile(&schema::schema()).unwrap()
+}
+
+/// Schema of a message referencing the OpenAPI components in the same document
+fn openapi_validator() -> JSONSchema {
+    JSONSchema::compile(&json!({
+        "$schema": schema::DIALECT,
+        "$ref": "#/components/schemas/SerdeGetText",
+        "components": schema::openapi_components(OpenApiVersion::V3_1),
+    }))
+    .unwrap()
+}
+
+fn valid_messages() -> Vec<serde_json::Value> {
+    vec![
+        json!({"text": "Hello %(name)s!", "args": {"name": "Grace"}}),
+        json!("Hello!"),
+        json!({"gettext": "Hello!", "version": 1}),
+        json!({"gettext": "Hello %s!", "args": ["Grace"]}),
+        json!({"gettext": ["Hello %s!", "Grace"]}),
+        json!({"ngettext": ["One item", "%(n)s items", 3]}),
+        json!({
+            "ngettext": {"singular": "One item", "plural": "%(n)s items", "n": "3", "zero": "No items"},
+        }),
+        json!({"pgettext": {"ctx": "menu", "msgid": "Open"}, "default": "Open"}),
+        json!({"dgettext": {"domain": "app", "msgid": "Hello!"}}),
+        json!({"dngettext": {"domain": "app", "singular": "One", "plural": "Many", "n": 2}}),
+        json!({"npgettext": {"ctx": "menu", "singular": "One", "plural": "Many", "n": 2}}),
+        json!({
+            "dcngettext": {
+                "domain": "app",
+                "singular": "One",
+                "plural": "Many",
+                "n": 2,
+                "category": "messages",
+            },
+        }),
+        json!({
+            "selectplural": {
+                "select": "female",
+                "n": 2,
+                "forms": {"other": {"singular": "One", "plural": "Many"}},
+            },
+        }),
+        json!({
+            "text": "%(user.name)s: %(items)s (%(date)s)",
+            "args": {
+                "user": {"name": {"value": "grace", "filters": ["title", {"pad_left": {"width": 8}}]}},
+                "items": [", ", "apple", {"gettext": "pear"}, 3, 1.5, true, null],
+                "date": {"strftime": "%Y", "epoch": 0},
+            },
+            "defaults": {"items": {"join": [", ", "a", "b"], "sort": true}},
+        }),
+        json!({
+            "text": "%(list)s %(map)s %(x)s",
+            "args": {
+                "list": {
+                    "foreach": {"items": [{"i": 1}], "template": {"text": "%(i)s"}, "sep": " "},
+                },
+                "map": {"map": {"a": 1}, "entry": "%(key)s=%(value)s"},
+                "x": {"ref": "y"},
+            },
+            "vars": {"y": {"gettext": "Hello!"}},
+            "merge": "deep",
+            "pseudo": true,
+        }),
+    ]
+}
+
+fn invalid_messages() -> Vec<serde_json::Value> {
+    vec![
+        json!(42),
+        json!({"args": {"name": "Grace"}}),
+        json!({"gettext": 42}),
+        json!({"gettext": []}),
+        json!({"ngettext": ["One item", "%(n)s items"]}),
+        json!({"ngettext": {"singular": "One", "plural": "Many", "n": "many"}}),
+        json!({"ngettext": {"singular": "One", "n": 1}}),
+        json!({"value": "x", "filters": ["reverse"]}),
+        json!({"value": "x", "filters": [{"center": {"width": -1}}]}),
+        json!({"dcngettext": {"domain": "app", "singular": "One", "plural": "Many", "n": 2, "category": "x"}}),
+        json!({"gettext": "Hello!", "merge": "shallow"}),
+        json!({"gettext": "Hello!", "version": 99}),
+    ]
+}
+
+#[test]
+fn valid() {
+    let validator = validator();
+
+    for message in valid_messages().iter() {
+        assert!(validator.is_valid(message), "{}", message);
+        // the schema agrees with the deserialization
+        serde_json::from_value::<SerdeGetText>(message.clone()).unwrap();
+    }
+}
+
+#[test]
+fn invalid() {
+    let validator = validator();
+
+    for message in invalid_messages().iter() {
+        assert!(!validator.is_valid(message), "{}", message);
+        assert!(
+            serde_json::from_value::<SerdeGetText>(message.clone()).is_err(),
+            "{}",
+            message
+        );
+    }
+}
+
+#[test]
+fn openapi_3_1() {
+    let validator = openapi_validator();
+
+    for message in valid_messages().iter() {
+        assert!(validator.is_valid(message), "{}", message);
+    }
+    for message in invalid_messages().iter() {
+        assert!(!validator.is_valid(message), "{}", message);
+    }
+}
+
+#[test]
+fn openapi_3_0() {
+    let components = schema::openapi_components(OpenApiVersion::V3_0);
+    let json = components.to_string();
+
+    // no null type, type array nor const in OpenAPI 3.0
+    assert!(!json.contains(r#""null""#));
+    assert!(!json.contains(r#""type":["#));
+    assert!(!json.contains(r#""const""#));
+    assert!(!json.contains("prefixItems"));
+    assert!(!json.contains("#/$defs/"));
+    assert_eq!(
+        components["schemas"]["SerdeGetTextValue"]["anyOf"][0],
+        json!({
+            "anyOf": [{"type": "string"}, {"type": "number"}, {"type": "boolean"}],
+            "nullable": true,
+        })
+    );
+    assert_eq!(
+        components["schemas"]["SerdeGetText"]["anyOf"][0]["anyOf"][1]["properties"]["default"],
+        json!({"type": "string", "nullable": true})
+    );
+}
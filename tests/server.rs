@@ -0,0 +1,108 @@
+#![cfg(feature = "server")]
+
+use serde_gettext::server::Translator;
+use serde_gettext::{Error, Metrics, MockCatalog};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[test]
+fn translate() {
+    let mut fr = MockCatalog::new();
+    fr.insert("Hello %(name)s!", "Bonjour %(name)s !");
+    let mut de = MockCatalog::new();
+    de.insert("Hello %(name)s!", "Hallo %(name)s!");
+    let translator = Translator::new()
+        .catalog("fr_FR", Arc::new(fr))
+        .catalog("de_DE", Arc::new(de))
+        .default_catalog(Arc::new(MockCatalog::new()));
+    let body = br#"{"gettext": "Hello %(name)s!", "args": {"name": "Grace"}}"#;
+
+    assert_eq!(
+        translator.handle(body, Some("de-CH, fr;q=0.9")),
+        (200, r#"{"text":"Hallo Grace!"}"#.to_string())
+    );
+    assert_eq!(
+        translator.handle(body, Some("it, fr-BE;q=0.5")),
+        (200, r#"{"text":"Bonjour Grace !"}"#.to_string())
+    );
+    assert_eq!(
+        translator.handle(body, None),
+        (200, r#"{"text":"Hello Grace!"}"#.to_string())
+    );
+    let (status, body) = translator.handle(
+        br#"[{"gettext": "Hello %(name)s!"}, {"text": "ok"}]"#,
+        Some("fr"),
+    );
+    let results: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(status, 200);
+    assert!(results[0]["error"].is_string());
+    assert_eq!(results[1], serde_json::json!({"text": "ok"}));
+    assert_eq!(translator.handle(b"{", None).0, 400);
+    let translator = translator.max_body_size(16);
+    assert_eq!(translator.handle(br#"{"text": "ok"}"#, None).0, 200);
+    assert_eq!(translator.handle(br#"{"text": "too long"}"#, None).0, 413);
+}
+
+#[test]
+fn introspection() {
+    let mut fr = MockCatalog::new();
+    fr.insert("Hello", "Bonjour")
+        .insert_with(Some("errors"), None, "Oops", &["Ups"]);
+    let translator = Translator::new()
+        .catalog("fr_FR", Arc::new(fr))
+        .catalog("de_DE", Arc::new(MockCatalog::new()));
+
+    assert_eq!(translator.available_locales(), vec!["fr_FR", "de_DE"]);
+    assert!(translator.has_translation(None, "Hello", "fr-FR"));
+    assert!(translator.has_translation(Some("errors"), "Oops", "fr_FR"));
+    assert!(!translator.has_translation(None, "Hello", "de_DE"));
+    assert!(!translator.has_translation(None, "Hello", "it"));
+}
+
+/// Metrics recording the callbacks
+#[derive(Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Metrics for Recorder {
+    fn on_render(&self, locale: Option<&str>, _duration: Duration, ok: bool) {
+        let event = format!("render {:?} {}", locale, ok);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_missing_translation(&self, domain: Option<&str>, msgid: &str, locale: Option<&str>) {
+        let event = format!("missing {:?} {} {:?}", domain, msgid, locale);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_format_error(&self, error: &Error) {
+        let event = format!("format error {}", error);
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+#[test]
+fn metrics() {
+    let mut fr = MockCatalog::new();
+    fr.insert("Hello %(name)s!", "Bonjour %(name)s !");
+    let recorder = Arc::new(Recorder::default());
+    let translator = Translator::new()
+        .catalog("fr_FR", Arc::new(fr))
+        .metrics(recorder.clone());
+
+    let body = br#"[
+        {"gettext": "Hello %(name)s!", "args": {"name": "Grace"}},
+        {"dgettext": {"domain": "errors", "msgid": "Oops"}},
+        {"gettext": "Hello %(name)s!"}
+    ]"#;
+    assert_eq!(translator.handle(body, Some("fr")).0, 200);
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        vec![
+            r#"render Some("fr_FR") true"#,
+            r#"missing Some("errors") Oops Some("fr_FR")"#,
+            r#"render Some("fr_FR") true"#,
+            "format error missing argument: name",
+            r#"render Some("fr_FR") false"#,
+        ]
+    );
+}
@@ -0,0 +1,81 @@
+#[macro_use]
+extern crate serde_json;
+
+use serde::Deserialize;
+use serde_gettext::strict::StrictSerdeGetText;
+use serde_gettext::SerdeGetText;
+use std::convert::TryFrom;
+
+fn error(j: serde_json::Value) -> String {
+    StrictSerdeGetText::deserialize(&j).unwrap_err().to_string()
+}
+
+#[test]
+fn strict_valid() {
+    let j = json!({
+        "ngettext": {"singular": "One file", "plural": "%(n)s files", "n": 2},
+        "args": {
+            "user": {"name": "Grace", "role": {"gettext": "admin"}},
+            "items": {"join": [{"text": ", "}, 1, {"ref": "two"}], "sort": true},
+        },
+        "defaults": {"user.name": "someone"},
+        "vars": {"two": {"value": "two", "filters": ["upper"]}},
+        "merge": "deep",
+    });
+    let message = StrictSerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(
+        String::try_from(SerdeGetText::from(message)).unwrap(),
+        "2 files"
+    );
+}
+
+#[test]
+fn strict_unknown_fields() {
+    assert_eq!(
+        error(json!({"gettext": "Hello %(name)s!", "argz": {"name": "Grace"}})),
+        "unknown field `argz` next to `gettext` at $, expected one of `gettext`, `args`, \
+         `defaults`, `default`, `comment`, `translators`, `source`, `merge`, `vars`, `pseudo`, \
+         `escape`"
+    );
+    assert_eq!(
+        error(json!({
+            "text": "%(count)s",
+            "args": {"count": {"ngettext": {"singular": "One", "plurl": "Many", "n": 1}}},
+        })),
+        "unknown field `plurl` in `ngettext` at $.args.count.ngettext, expected one of \
+         `singular`, `msgid`, `plural`, `msgid_plural`, `n`, `count`, `zero`, `one`, `two`"
+    );
+    assert_eq!(
+        error(json!({
+            "gettext": "%(a)s",
+            "vars": {"a": {"join": [{"text": "x", "sep": ", "}]}},
+        })),
+        "unknown field `sep` next to `text` at $.vars.a.join[0], expected one of `text`, \
+         `args`, `defaults`"
+    );
+    assert_eq!(
+        error(json!({"join": [{"gettxt": "Hello"}]})),
+        "unrecognized value at $.join[0] with the fields `gettxt`"
+    );
+    assert_eq!(
+        error(json!({"join": {"separator": ", ", "items": ["a"]}})),
+        "unknown field `separator` in `join` at $.join, expected one of `sep`, `items`"
+    );
+    assert_eq!(
+        error(json!({"join": {"sep": ", ", "items": [{"gettxt": "Hello"}]}})),
+        "unrecognized value at $.join.items[0] with the fields `gettxt`"
+    );
+    // the fields of the message are only allowed at the top-level
+    assert_eq!(
+        error(json!({"join": [{"text": "Hello", "merge": "deep"}]})),
+        "unknown field `merge` next to `text` at $.join[0], expected one of `text`, `args`, \
+         `defaults`"
+    );
+}
+
+#[test]
+fn strict_lenient() {
+    // the same message is accepted by `SerdeGetText`, without its arguments
+    let j = json!({"gettext": "Hello %(name)s!", "argz": {"name": "Grace"}});
+    assert!(SerdeGetText::deserialize(&j).is_ok());
+}
@@ -0,0 +1,130 @@
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use serde::Deserialize;
+use serde_gettext::tagged::TaggedSerdeGetText;
+use serde_gettext::SerdeGetText;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+fn render(message: TaggedSerdeGetText) -> String {
+    String::try_from(SerdeGetText::from(message)).unwrap()
+}
+
+#[test]
+fn tagged_json() {
+    let j = json!({
+        "value": {"message": {
+            "message": {"ngettext": {
+                "singular": "%(n)s item deleted by %(user)s",
+                "plural": "%(n)s items deleted by %(user)s",
+                "n": 5,
+            }},
+            "args": {"keyword": {"user": {"value": {"text": "Grace"}}}},
+        }},
+    });
+    let message = TaggedSerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(render(message), "5 items deleted by Grace");
+
+    let j = json!({
+        "value": {"join": {"items": [{"text": ", "}, {"integer": 1}, "unit", {"bool": true}]}},
+    });
+    let message = TaggedSerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(render(message), "1, n/a, yes");
+
+    let j = json!({
+        "value": {"join": {"sep": {"text": " / "}, "items": [{"text": "a"}, {"text": "b"}]}},
+    });
+    let message = TaggedSerdeGetText::deserialize(&j).unwrap();
+    assert_eq!(render(message), "a / b");
+}
+
+// Bincode encodes the variants by index: the mirror lists them in the same order
+#[derive(Serialize)]
+struct Message {
+    value: Value,
+    merge: Merge,
+    vars: HashMap<String, Value>,
+    pseudo: bool,
+}
+
+#[derive(Serialize)]
+enum Merge {
+    #[allow(dead_code)]
+    Leaf,
+}
+
+#[allow(dead_code)]
+#[derive(Serialize)]
+enum Value {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Unit,
+    Datetime,
+    Array(Vec<Value>),
+    Join,
+    Ref(String),
+    Filtered,
+    Map,
+    Foreach,
+    Message(Box<Inner>),
+}
+
+#[derive(Serialize)]
+struct Inner {
+    message: Text,
+    args: Args,
+    defaults: HashMap<String, Value>,
+    default: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Serialize)]
+enum Text {
+    Text(String),
+    Gettext(String),
+}
+
+#[allow(dead_code)]
+#[derive(Serialize)]
+enum Args {
+    None,
+    Keyword(HashMap<String, Arg>),
+    Positional(Vec<Value>),
+}
+
+#[allow(dead_code)]
+#[derive(Serialize)]
+enum Arg {
+    Value(Value),
+}
+
+#[test]
+fn tagged_bincode() {
+    let mut vars = HashMap::new();
+    vars.insert("name".to_string(), Value::Text("Grace".to_string()));
+    let message = Message {
+        value: Value::Array(vec![
+            Value::Text(" ".to_string()),
+            Value::Message(Box::new(Inner {
+                message: Text::Gettext("Hello %s!".to_string()),
+                args: Args::Positional(vec![Value::Ref("name".to_string())]),
+                defaults: HashMap::new(),
+                default: None,
+            })),
+            Value::Integer(42),
+            Value::Unit,
+        ]),
+        merge: Merge::Leaf,
+        vars,
+        pseudo: false,
+    };
+    let bytes = bincode::serialize(&message).unwrap();
+    let message: TaggedSerdeGetText = bincode::deserialize(&bytes).unwrap();
+
+    assert_eq!(render(message), "Hello Grace! 42 n/a");
+}
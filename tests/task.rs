@@ -0,0 +1,77 @@
+#![cfg(feature = "tokio")]
+
+#[macro_use]
+extern crate serde_json;
+
+use serde::Deserialize;
+use serde_gettext::task::{current_locale, render_in_locale};
+use serde_gettext::*;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+fn locale() -> Option<String> {
+    let message = SerdeGetText::deserialize(&json!({"text": "Hello!"})).unwrap();
+    message.render_with_meta().unwrap().1.locale
+}
+
+#[tokio::test]
+async fn task_locale() {
+    let res = render_in_locale("de_DE", async {
+        tokio::task::yield_now().await;
+        let de = locale();
+        let fr = render_in_locale("fr_FR", async { locale() }).await;
+        let guard = {
+            let _locale = LocaleContext::enter("it_IT");
+            locale()
+        };
+
+        (de, fr, guard, locale())
+    })
+    .await;
+
+    assert_eq!(
+        res,
+        (
+            Some("de_DE".to_string()),
+            Some("fr_FR".to_string()),
+            Some("it_IT".to_string()),
+            Some("de_DE".to_string()),
+        )
+    );
+    assert_eq!(current_locale(), None);
+}
+
+/// Backend translating to the locale of the lookup
+struct LocaleBackend;
+
+impl Backend for LocaleBackend {
+    fn translate(&self, lookup: &Lookup) -> Option<String> {
+        match (lookup.msgid, lookup.locale) {
+            ("Hello!", Some("de_DE")) => Some("Hallo!".to_string()),
+            ("Hello!", Some("fr_FR")) => Some("Bonjour !".to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn task_translations() {
+    let backend: Arc<dyn Backend + Send + Sync> = Arc::new(LocaleBackend);
+    let greet = |backend: Arc<dyn Backend + Send + Sync>| async move {
+        let mut greetings = Vec::new();
+        for _ in 0..3 {
+            let mut message = SerdeGetText::deserialize(&json!({"gettext": "Hello!"})).unwrap();
+            message.backend = Some(backend.clone());
+            greetings.push(String::try_from(message).unwrap());
+            // the other task renders its messages in between
+            tokio::task::yield_now().await;
+        }
+        greetings
+    };
+
+    let de = tokio::spawn(render_in_locale("de_DE", greet(backend.clone())));
+    let fr = tokio::spawn(render_in_locale("fr_FR", greet(backend.clone())));
+
+    assert_eq!(de.await.unwrap(), vec!["Hallo!"; 3]);
+    assert_eq!(fr.await.unwrap(), vec!["Bonjour !"; 3]);
+}
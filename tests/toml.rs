@@ -0,0 +1,85 @@
+use serde_gettext::SerdeGetText;
+use std::convert::TryFrom;
+
+fn render(s: &str) -> String {
+    let message: SerdeGetText = toml::from_str(s).unwrap();
+    String::try_from(message).unwrap()
+}
+
+#[test]
+fn scalars_and_unit() {
+    assert_eq!(
+        render(
+            r#"
+            text = "%s %s %s %s"
+            args = [true, 3.5, 42, { none = true }]
+            "#
+        ),
+        "yes 3.5 42 n/a"
+    );
+    assert_eq!(
+        render(
+            r#"
+            text = "%(status)s"
+            [args]
+            status = { none = true }
+            "#
+        ),
+        "n/a"
+    );
+    assert!(toml::from_str::<SerdeGetText>(
+        r#"
+        text = "%s"
+        args = [{ none = false }]
+        "#
+    )
+    .is_err());
+}
+
+#[test]
+fn plural_counts() {
+    let message = |n: &str| {
+        render(&format!(
+            r#"
+            [ngettext]
+            singular = "One item has been deleted"
+            plural = "%(n)s items have been deleted"
+            n = {}
+            "#,
+            n
+        ))
+    };
+
+    assert_eq!(message("1"), "One item has been deleted");
+    assert_eq!(message("5"), "5 items have been deleted");
+    assert_eq!(message("5.0"), "5 items have been deleted");
+    assert_eq!(message(r#""5""#), "5 items have been deleted");
+    assert!(toml::from_str::<SerdeGetText>(
+        r#"
+        [ngettext]
+        singular = "One item"
+        plural = "%(n)s items"
+        n = 1.5
+        "#
+    )
+    .is_err());
+}
+
+#[test]
+fn nested_tables() {
+    assert_eq!(
+        render(
+            r#"
+            gettext = "Hello %(user.name)s (%(fruits)s)!"
+
+            [args.user]
+            name = "Grace"
+
+            [args.fruits]
+            join = [", ", "Pear", "Apple"]
+            sort = true
+            "#
+        ),
+        "Hello Grace (Apple, Pear)!"
+    );
+}
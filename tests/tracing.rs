@@ -0,0 +1,74 @@
+#![cfg(feature = "tracing")]
+
+#[macro_use]
+extern crate serde_json;
+
+use serde::Deserialize;
+use serde_gettext::*;
+use std::convert::TryFrom;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// Output of the subscriber shared with the test
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn traced<F: FnOnce()>(f: F) -> String {
+    let output = Output::default();
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+
+    let output = output.0.lock().unwrap().clone();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn events() {
+    let mut catalog = MockCatalog::new();
+    catalog.insert_plural("%(n)s file", &["%(n)s fichier", "%(n)s fichiers"]);
+    let j = json!({
+        "ngettext": {"singular": "%(n)s file", "plural": "%(n)s files", "n": 2},
+    });
+    let mut message = SerdeGetText::deserialize(&j).unwrap();
+    message.backend = Some(Arc::new(catalog));
+    message.locale = Some("fr_FR".to_string());
+
+    let output = traced(|| assert_eq!(String::try_from(message).unwrap(), "2 fichiers"));
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", output);
+    assert!(lines[0].contains("catalog lookup"), "{}", output);
+    assert!(lines[0].contains(r#"msgid="%(n)s file" found=true duration_us="#));
+    assert!(lines[1].contains("translation"));
+    assert!(lines[1].contains("n=2 plural_form=1 found=true"));
+    assert!(lines[2].contains("format"));
+    assert!(lines[3].contains("rendered"));
+    for line in lines {
+        assert!(line.contains("render{hash="), "{}", line);
+        assert!(line.contains(r#"locale="fr_FR""#), "{}", line);
+    }
+
+    let j = json!({"text": "%(missing)s"});
+    let message = SerdeGetText::deserialize(&j).unwrap();
+    let output = traced(|| assert!(String::try_from(message).is_err()));
+    assert!(
+        output.contains("rendering failed") && output.contains("missing argument: missing"),
+        "{}",
+        output
+    );
+}
@@ -1,8 +1,4 @@
-#[macro_use]
-extern crate serde_derive;
-
 use libc_strftime::{set_locale, tz_set};
-#[allow(unused_imports)]
 use serde::Deserialize;
 use serde_gettext::*;
 use std::convert::TryFrom;
@@ -69,6 +65,18 @@ text: Hello!
     );
 }
 
+#[test]
+fn bare_string() {
+    assert_eq!(
+        to_string(
+            r#"---
+Hello %s!
+"#
+        ),
+        "Hello %s!"
+    );
+}
+
 #[test]
 fn not_translated_text_with_args() {
     assert_eq!(